@@ -0,0 +1,2 @@
+DROP TABLE IF EXISTS search_evaluation_queries;
+DROP TABLE IF EXISTS search_evaluations;
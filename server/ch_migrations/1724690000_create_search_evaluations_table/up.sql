@@ -0,0 +1,35 @@
+CREATE TABLE IF NOT EXISTS search_evaluations (
+    id UUID,
+    dataset_id UUID,
+    judgment_list_id UUID,
+    request_params String,
+    k UInt32,
+    query_count UInt32,
+    ndcg Float64,
+    mrr Float64,
+    recall Float64,
+    avg_latency Float32,
+    p95_latency Float32,
+    created_at DateTime
+) ENGINE = MergeTree()
+ORDER BY (dataset_id, judgment_list_id, created_at, id)
+PARTITION BY
+    (toYYYYMM(created_at),
+    dataset_id);
+
+CREATE TABLE IF NOT EXISTS search_evaluation_queries (
+    id UUID,
+    evaluation_id UUID,
+    dataset_id UUID,
+    query String,
+    ndcg Float64,
+    reciprocal_rank Float64,
+    recall Float64,
+    latency Float32,
+    results Array(String),
+    created_at DateTime
+) ENGINE = MergeTree()
+ORDER BY (dataset_id, evaluation_id, created_at, id)
+PARTITION BY
+    (toYYYYMM(created_at),
+    dataset_id);
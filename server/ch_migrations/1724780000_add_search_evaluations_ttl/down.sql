@@ -0,0 +1,2 @@
+ALTER TABLE search_evaluations REMOVE TTL;
+ALTER TABLE search_evaluation_queries REMOVE TTL;
@@ -0,0 +1,2 @@
+ALTER TABLE search_evaluations MODIFY TTL created_at + INTERVAL 30 DAY;
+ALTER TABLE search_evaluation_queries MODIFY TTL created_at + INTERVAL 30 DAY;
@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE files ADD COLUMN pending BOOLEAN NOT NULL DEFAULT false;
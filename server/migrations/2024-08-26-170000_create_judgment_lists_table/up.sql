@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE judgment_lists (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    dataset_id UUID NOT NULL,
+    name TEXT NOT NULL,
+    queries JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (dataset_id) REFERENCES datasets(id) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS judgment_lists_dataset_id_idx ON judgment_lists(dataset_id);
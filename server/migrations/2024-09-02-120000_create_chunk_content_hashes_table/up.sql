@@ -0,0 +1,14 @@
+-- Your SQL goes here
+CREATE TABLE chunk_content_hashes (
+    chunk_id UUID PRIMARY KEY,
+    dataset_id UUID NOT NULL,
+    content_hash TEXT NOT NULL,
+    simhash BIGINT NOT NULL,
+    simhash_bands INTEGER[] NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (chunk_id) REFERENCES chunk_metadata(id) ON UPDATE CASCADE ON DELETE CASCADE,
+    FOREIGN KEY (dataset_id) REFERENCES datasets(id) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX idx_chunk_content_hashes_dataset_id_content_hash ON chunk_content_hashes (dataset_id, content_hash);
+CREATE INDEX idx_chunk_content_hashes_simhash_bands ON chunk_content_hashes USING GIN (simhash_bands);
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE synonym_sets (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    dataset_id UUID NOT NULL,
+    root TEXT,
+    synonyms TEXT[] NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    FOREIGN KEY (dataset_id) REFERENCES datasets(id) ON UPDATE CASCADE ON DELETE CASCADE
+);
+
+CREATE INDEX idx_synonym_sets_dataset_id ON synonym_sets (dataset_id);
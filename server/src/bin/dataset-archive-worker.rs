@@ -0,0 +1,367 @@
+use diesel_async::pooled_connection::{AsyncDieselConnectionManager, ManagerConfig};
+use sentry::{Hub, SentryFutureExt};
+use signal_hook::consts::SIGTERM;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+use trieve_server::{
+    data::models::{
+        self, DatasetArchiveJobState, DatasetArchiveJobType, DatasetArchiveMessage,
+        DatasetArchiveStatus,
+    },
+    errors::ServiceError,
+    establish_connection, get_env,
+    operators::{
+        clickhouse_operator::{ClickHouseEvent, EventQueue},
+        dataset_archive_operator::{
+            clone_dataset_query, export_dataset_query, fail_dataset_archive_job_query,
+            import_dataset_query, set_dataset_archive_status_query,
+        },
+    },
+};
+
+fn main() {
+    dotenvy::dotenv().ok();
+    let sentry_url = std::env::var("SENTRY_URL");
+    let _guard = if let Ok(sentry_url) = sentry_url {
+        let guard = sentry::init((
+            sentry_url,
+            sentry::ClientOptions {
+                release: sentry::release_name!(),
+                traces_sample_rate: 1.0,
+                ..Default::default()
+            },
+        ));
+
+        tracing_subscriber::Registry::default()
+            .with(sentry::integrations::tracing::layer())
+            .with(
+                tracing_subscriber::fmt::layer().with_filter(
+                    EnvFilter::from_default_env()
+                        .add_directive(tracing_subscriber::filter::LevelFilter::INFO.into()),
+                ),
+            )
+            .init();
+
+        log::info!("Sentry monitoring enabled");
+        Some(guard)
+    } else {
+        tracing_subscriber::Registry::default()
+            .with(
+                tracing_subscriber::fmt::layer().with_filter(
+                    EnvFilter::from_default_env()
+                        .add_directive(tracing_subscriber::filter::LevelFilter::INFO.into()),
+                ),
+            )
+            .init();
+
+        None
+    };
+
+    let database_url = get_env!("DATABASE_URL", "DATABASE_URL is not set");
+
+    let mut config = ManagerConfig::default();
+    config.custom_setup = Box::new(establish_connection);
+
+    let mgr = AsyncDieselConnectionManager::<diesel_async::AsyncPgConnection>::new_with_config(
+        database_url,
+        config,
+    );
+
+    let pool = diesel_async::pooled_connection::deadpool::Pool::builder(mgr)
+        .max_size(3)
+        .build()
+        .expect("Failed to create diesel_async pool");
+
+    let web_pool = actix_web::web::Data::new(pool.clone());
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to create tokio runtime")
+        .block_on(
+            async move {
+                let redis_url = get_env!("REDIS_URL", "REDIS_URL is not set");
+                let redis_connections: u32 = std::env::var("REDIS_CONNECTIONS")
+                    .unwrap_or("2".to_string())
+                    .parse()
+                    .unwrap_or(2);
+
+                let redis_manager = bb8_redis::RedisConnectionManager::new(redis_url)
+                    .expect("Failed to connect to redis");
+
+                let redis_pool = bb8_redis::bb8::Pool::builder()
+                    .max_size(redis_connections)
+                    .connection_timeout(std::time::Duration::from_secs(2))
+                    .build(redis_manager)
+                    .await
+                    .expect("Failed to create redis pool");
+
+                let web_redis_pool = actix_web::web::Data::new(redis_pool);
+
+                let should_terminate = Arc::new(AtomicBool::new(false));
+                signal_hook::flag::register(SIGTERM, Arc::clone(&should_terminate))
+                    .expect("Failed to register shutdown hook");
+
+                let mut event_queue = if std::env::var("USE_ANALYTICS")
+                    .unwrap_or("false".to_string())
+                    .parse()
+                    .unwrap_or(false)
+                {
+                    log::info!("Analytics enabled");
+
+                    let clickhouse_client = clickhouse::Client::default()
+                        .with_url(
+                            std::env::var("CLICKHOUSE_URL")
+                                .unwrap_or("http://localhost:8123".to_string()),
+                        )
+                        .with_user(
+                            std::env::var("CLICKHOUSE_USER").unwrap_or("default".to_string()),
+                        )
+                        .with_password(
+                            std::env::var("CLICKHOUSE_PASSWORD").unwrap_or("".to_string()),
+                        )
+                        .with_database(
+                            std::env::var("CLICKHOUSE_DATABASE").unwrap_or("default".to_string()),
+                        )
+                        .with_option("async_insert", "1")
+                        .with_option("wait_for_async_insert", "0");
+
+                    let mut event_queue = EventQueue::new(clickhouse_client);
+                    event_queue.start_service();
+                    event_queue
+                } else {
+                    log::info!("Analytics disabled");
+                    EventQueue::default()
+                };
+
+                event_queue.enable_webhooks(web_redis_pool.as_ref().clone());
+
+                let web_event_queue = actix_web::web::Data::new(event_queue);
+
+                dataset_archive_worker(should_terminate, web_redis_pool, web_pool, web_event_queue)
+                    .await
+            }
+            .bind_hub(Hub::new_from_top(Hub::current())),
+        );
+}
+
+async fn dataset_archive_worker(
+    should_terminate: Arc<AtomicBool>,
+    redis_pool: actix_web::web::Data<models::RedisPool>,
+    web_pool: actix_web::web::Data<models::Pool>,
+    event_queue: actix_web::web::Data<EventQueue>,
+) {
+    log::info!("Starting dataset archive worker service thread");
+
+    let mut redis_conn_sleep = std::time::Duration::from_secs(1);
+
+    #[allow(unused_assignments)]
+    let mut opt_redis_connection = None;
+
+    loop {
+        let borrowed_redis_connection = match redis_pool.get().await {
+            Ok(redis_connection) => Some(redis_connection),
+            Err(err) => {
+                log::error!("Failed to get redis connection outside of loop: {:?}", err);
+                None
+            }
+        };
+
+        if borrowed_redis_connection.is_some() {
+            opt_redis_connection = borrowed_redis_connection;
+            break;
+        }
+
+        tokio::time::sleep(redis_conn_sleep).await;
+        redis_conn_sleep = std::cmp::min(redis_conn_sleep * 2, std::time::Duration::from_secs(300));
+    }
+
+    let mut redis_connection =
+        opt_redis_connection.expect("Failed to get redis connection outside of loop");
+
+    let mut broken_pipe_sleep = std::time::Duration::from_secs(10);
+
+    loop {
+        if should_terminate.load(Ordering::Relaxed) {
+            log::info!("Shutting down");
+            break;
+        }
+
+        let payload_result: Result<Vec<String>, redis::RedisError> = redis::cmd("brpop")
+            .arg("dataset_archive")
+            .arg(1.0)
+            .query_async(&mut *redis_connection)
+            .await;
+
+        let serialized_message = if let Ok(payload) = payload_result {
+            broken_pipe_sleep = std::time::Duration::from_secs(10);
+
+            // brpop returns the name of the list followed by the popped element.
+            match payload.get(1) {
+                Some(serialized_message) => serialized_message.clone(),
+                None => continue,
+            }
+        } else {
+            log::error!("Unable to process {:?}", payload_result);
+
+            if payload_result.is_err_and(|err| err.is_io_error()) {
+                tokio::time::sleep(broken_pipe_sleep).await;
+                broken_pipe_sleep =
+                    std::cmp::min(broken_pipe_sleep * 2, std::time::Duration::from_secs(300));
+            }
+
+            continue;
+        };
+
+        let message: DatasetArchiveMessage = match serde_json::from_str(&serialized_message) {
+            Ok(message) => message,
+            Err(err) => {
+                log::error!("Failed to parse dataset archive message: {:?}", err);
+                continue;
+            }
+        };
+
+        let job_id = message.job_id();
+
+        // Partially exported or imported archives can not be resumed, so failed jobs are not retried.
+        if let Err(err) = process_archive_message(
+            message,
+            web_pool.clone(),
+            redis_pool.clone(),
+            event_queue.clone(),
+        )
+        .await
+        {
+            log::error!("Dataset archive job {:?} failed: {:?}", job_id, err);
+
+            let _ = redis::cmd("lpush")
+                .arg("dead_letters_dataset_archive")
+                .arg(&serialized_message)
+                .query_async::<redis::aio::MultiplexedConnection, usize>(&mut *redis_connection)
+                .await;
+        }
+    }
+}
+
+async fn process_archive_message(
+    message: DatasetArchiveMessage,
+    web_pool: actix_web::web::Data<models::Pool>,
+    redis_pool: actix_web::web::Data<models::RedisPool>,
+    event_queue: actix_web::web::Data<EventQueue>,
+) -> Result<(), ServiceError> {
+    let (job_type, dataset_id) = match message {
+        DatasetArchiveMessage::Export { dataset_id, .. } => {
+            (DatasetArchiveJobType::Export, dataset_id)
+        }
+        DatasetArchiveMessage::Import { dataset_id, .. } => {
+            (DatasetArchiveJobType::Import, dataset_id)
+        }
+        DatasetArchiveMessage::Clone { dataset_id, .. } => {
+            (DatasetArchiveJobType::Clone, dataset_id)
+        }
+    };
+    let source_dataset_id = match message {
+        DatasetArchiveMessage::Clone {
+            source_dataset_id, ..
+        } => Some(source_dataset_id),
+        _ => None,
+    };
+
+    let mut status = DatasetArchiveStatus::from_details(
+        message.job_id(),
+        message.archive_id(),
+        job_type,
+        dataset_id,
+        DatasetArchiveJobState::Running,
+    );
+    set_dataset_archive_status_query(&status, redis_pool.clone()).await?;
+
+    let result = match message {
+        DatasetArchiveMessage::Export {
+            archive_id,
+            dataset_id,
+            include_vectors,
+            include_files,
+        } => export_dataset_query(
+            archive_id,
+            dataset_id,
+            include_vectors,
+            include_files,
+            &mut status,
+            web_pool,
+            redis_pool.clone(),
+        )
+        .await
+        .map(|manifest| manifest.chunk_count),
+        DatasetArchiveMessage::Import {
+            archive_id,
+            dataset_id,
+            ..
+        } => {
+            import_dataset_query(
+                archive_id,
+                dataset_id,
+                &mut status,
+                web_pool,
+                redis_pool.clone(),
+            )
+            .await
+        }
+        DatasetArchiveMessage::Clone {
+            source_dataset_id,
+            dataset_id,
+            ..
+        } => {
+            clone_dataset_query(
+                source_dataset_id,
+                dataset_id,
+                &mut status,
+                web_pool,
+                redis_pool.clone(),
+                event_queue.clone(),
+            )
+            .await
+        }
+    };
+
+    if let Some(source_dataset_id) = source_dataset_id {
+        let event_type = match &result {
+            Ok(chunk_count) => models::EventType::DatasetCloned {
+                source_dataset_id,
+                chunk_count: *chunk_count,
+            },
+            Err(err) => models::EventType::DatasetCloneFailed {
+                source_dataset_id,
+                error: err.to_string(),
+            },
+        };
+
+        event_queue
+            .send(ClickHouseEvent::WorkerEvent(
+                models::WorkerEvent::from_details(dataset_id, event_type).into(),
+            ))
+            .await;
+    }
+
+    match result {
+        Ok(chunk_count) => {
+            log::info!(
+                "Dataset archive job {:?} completed with {} chunks",
+                status.job_id,
+                chunk_count
+            );
+
+            status.state = DatasetArchiveJobState::Completed;
+            status.chunk_count = chunk_count;
+            status.updated_at = chrono::Utc::now().naive_local();
+            set_dataset_archive_status_query(&status, redis_pool).await
+        }
+        Err(err) => {
+            fail_dataset_archive_job_query(status, &err, redis_pool).await?;
+            Err(err)
+        }
+    }
+}
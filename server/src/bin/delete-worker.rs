@@ -14,12 +14,13 @@ use trieve_server::{
     operators::{
         clickhouse_operator::{ClickHouseEvent, EventQueue},
         dataset_operator::{
-            clear_dataset_query, delete_dataset_by_id_query,
+            clear_bm25_corpus_stats_query, clear_dataset_query, delete_dataset_by_id_query,
             get_deleted_dataset_by_unifiedid_query, DeleteMessage,
         },
         organization_operator::{
             delete_actual_organization_query, get_soft_deleted_datasets_for_organization,
         },
+        spelling_operator::clear_vocabulary_query,
     },
 };
 
@@ -106,7 +107,7 @@ fn main() {
                 signal_hook::flag::register(SIGTERM, Arc::clone(&should_terminate))
                     .expect("Failed to register shutdown hook");
 
-                let (clickhouse_client, event_queue) = if std::env::var("USE_ANALYTICS")
+                let (clickhouse_client, mut event_queue) = if std::env::var("USE_ANALYTICS")
                     .unwrap_or("false".to_string())
                     .parse()
                     .unwrap_or(false)
@@ -138,6 +139,8 @@ fn main() {
                     (clickhouse::Client::default(), EventQueue::default())
                 };
 
+                event_queue.enable_webhooks(web_redis_pool.as_ref().clone());
+
                 let web_event_queue = actix_web::web::Data::new(event_queue);
                 let web_clickhouse_client = actix_web::web::Data::new(clickhouse_client);
 
@@ -271,6 +274,16 @@ async fn delete_worker(
                         "Cleared all chunks for dataset: {:?}",
                         delete_worker_message.dataset_id
                     );
+                    let _ = clear_bm25_corpus_stats_query(
+                        delete_worker_message.dataset_id,
+                        redis_pool.clone(),
+                    )
+                    .await;
+                    let _ = clear_vocabulary_query(
+                        delete_worker_message.dataset_id,
+                        redis_pool.clone(),
+                    )
+                    .await;
                     let _ = redis::cmd("LREM")
                         .arg("delete_dataset_processing")
                         .arg(1)
@@ -311,6 +324,14 @@ async fn delete_worker(
         {
             Ok(dataset) => {
                 log::info!("Deleted Dataset: {:?}", delete_worker_message.dataset_id);
+                let _ = clear_bm25_corpus_stats_query(
+                    delete_worker_message.dataset_id,
+                    redis_pool.clone(),
+                )
+                .await;
+                let _ =
+                    clear_vocabulary_query(delete_worker_message.dataset_id, redis_pool.clone())
+                        .await;
 
                 let _ = redis::cmd("LREM")
                     .arg("delete_dataset_processing")
@@ -8,13 +8,21 @@ use std::sync::{
 };
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
 use trieve_server::{
-    data::models::{self, FileWorkerMessage},
+    data::models::{self, DatasetConfiguration, FileWorkerMessage},
     errors::ServiceError,
     establish_connection, get_env,
     operators::{
+        chunk_operator::delete_chunk_metadata_query,
         clickhouse_operator::{ClickHouseEvent, EventQueue},
         dataset_operator::get_dataset_and_organization_from_dataset_id_query,
-        file_operator::{create_file_chunks, create_file_query, get_aws_bucket},
+        file_operator::{
+            clear_chunk_tracking_ids_query, create_file_chunks, create_file_query,
+            create_file_row_chunks, get_aws_bucket,
+        },
+        parse_operator::{
+            convert_markdown_to_html, convert_plain_text_to_html, get_native_file_format,
+            parse_csv_rows, parse_jsonl_rows, NativeFileFormat,
+        },
     },
 };
 
@@ -97,7 +105,7 @@ fn main() {
 
                 let web_redis_pool = actix_web::web::Data::new(redis_pool);
 
-                let event_queue = if std::env::var("USE_ANALYTICS")
+                let mut event_queue = if std::env::var("USE_ANALYTICS")
                     .unwrap_or("false".to_string())
                     .parse()
                     .unwrap_or(false)
@@ -129,6 +137,8 @@ fn main() {
                     EventQueue::default()
                 };
 
+                event_queue.enable_webhooks(web_redis_pool.as_ref().clone());
+
                 let web_event_queue = actix_web::web::Data::new(event_queue);
 
                 let should_terminate = Arc::new(AtomicBool::new(false));
@@ -226,6 +236,7 @@ async fn file_worker(
             web_pool.clone(),
             event_queue.clone(),
             redis_connection.clone(),
+            redis_pool.clone(),
         )
         .await
         {
@@ -267,6 +278,7 @@ async fn upload_file(
     web_pool: actix_web::web::Data<models::Pool>,
     event_queue: actix_web::web::Data<EventQueue>,
     redis_conn: MultiplexedConnection,
+    redis_pool: actix_web::web::Data<models::RedisPool>,
 ) -> Result<Option<uuid::Uuid>, ServiceError> {
     let file_id = file_worker_message.file_id;
 
@@ -290,40 +302,25 @@ async fn upload_file(
 
     get_file_span.finish();
 
-    let tika_url = std::env::var("TIKA_URL")
-        .expect("TIKA_URL must be set")
-        .to_string();
-
-    let tika_client = reqwest::Client::new();
-    let tika_html_parse_span = transaction.start_child("tika_html_parse", "Parse tika html");
-
-    let tika_response = tika_client
-        .put(&format!("{}/tika", tika_url))
-        .header("Accept", "text/html")
-        .body(file_data.clone())
-        .send()
-        .await
-        .map_err(|err| {
-            log::error!("Could not send file to tika {:?}", err);
-            ServiceError::BadRequest("Could not send file to tika".to_string())
-        })?;
-
-    let tike_html_converted_file_bytes = tika_response
-        .bytes()
-        .await
-        .map_err(|err| {
-            log::error!("Could not get tika response bytes {:?}", err);
-            ServiceError::BadRequest("Could not get tika response bytes".to_string())
-        })?
-        .to_vec();
-    tika_html_parse_span.finish();
-
-    let html_content = String::from_utf8_lossy(&tike_html_converted_file_bytes).to_string();
-    if html_content.is_empty() {
-        return Err(ServiceError::BadRequest(
-            "Could not parse file with tika".to_string(),
-        ));
-    }
+    let parsed_file = match get_native_file_format(&file_worker_message.upload_file_data.file_name)
+    {
+        Some(NativeFileFormat::PlainText) => ParsedFile::Html(convert_plain_text_to_html(
+            &String::from_utf8_lossy(&file_data),
+        )),
+        Some(NativeFileFormat::Markdown) => ParsedFile::Html(convert_markdown_to_html(
+            &String::from_utf8_lossy(&file_data),
+        )),
+        Some(NativeFileFormat::Csv) => ParsedFile::Rows(parse_csv_rows(&file_data)?),
+        Some(NativeFileFormat::Jsonl) => ParsedFile::Rows(parse_jsonl_rows(&file_data)?),
+        None => {
+            let tika_html_parse_span =
+                transaction.start_child("tika_html_parse", "Parse tika html");
+            let html_content = parse_file_with_tika(file_data.clone()).await?;
+            tika_html_parse_span.finish();
+
+            ParsedFile::Html(html_content)
+        }
+    };
 
     let file_size_mb = (file_data.len() as f64 / 1024.0 / 1024.0).round() as i64;
 
@@ -356,22 +353,109 @@ async fn upload_file(
     )
     .await?;
 
-    create_file_chunks(
-        created_file.id,
-        file_worker_message.upload_file_data,
-        html_content,
-        dataset_org_plan_sub,
-        web_pool.clone(),
-        event_queue.clone(),
-        redis_conn,
-    )
-    .await?;
+    let replaced_chunk_ids = file_worker_message.replaced_chunk_ids.clone();
+    let dataset = dataset_org_plan_sub.dataset.clone();
+
+    if !replaced_chunk_ids.is_empty() {
+        clear_chunk_tracking_ids_query(replaced_chunk_ids.clone(), web_pool.clone()).await?;
+    }
+
+    match parsed_file {
+        ParsedFile::Html(html_content) => {
+            create_file_chunks(
+                created_file.id,
+                file_worker_message.upload_file_data,
+                html_content,
+                dataset_org_plan_sub,
+                web_pool.clone(),
+                event_queue.clone(),
+                redis_conn,
+            )
+            .await?;
+        }
+        ParsedFile::Rows(rows) => {
+            create_file_row_chunks(
+                created_file.id,
+                file_worker_message.upload_file_data,
+                rows,
+                dataset_org_plan_sub,
+                web_pool.clone(),
+                event_queue.clone(),
+                redis_conn,
+            )
+            .await?;
+        }
+    }
 
     create_file_chunks_span.finish();
 
+    if !replaced_chunk_ids.is_empty() {
+        let dataset_config = DatasetConfiguration::from_json(dataset.server_configuration.clone());
+
+        // The new chunks are already queued, so failing here would queue them a second time on retry
+        if let Err(err) = delete_chunk_metadata_query(
+            replaced_chunk_ids,
+            chrono::Utc::now().naive_utc(),
+            dataset,
+            web_pool.clone(),
+            redis_pool,
+            dataset_config,
+        )
+        .await
+        {
+            log::error!(
+                "Could not delete replaced chunks of file {}: {:?}",
+                file_id,
+                err
+            );
+        }
+    }
+
     Ok(Some(file_id))
 }
 
+enum ParsedFile {
+    Html(String),
+    Rows(Vec<serde_json::Map<String, serde_json::Value>>),
+}
+
+async fn parse_file_with_tika(file_data: Vec<u8>) -> Result<String, ServiceError> {
+    let tika_url = std::env::var("TIKA_URL")
+        .expect("TIKA_URL must be set")
+        .to_string();
+
+    let tika_client = reqwest::Client::new();
+
+    let tika_response = tika_client
+        .put(&format!("{}/tika", tika_url))
+        .header("Accept", "text/html")
+        .body(file_data)
+        .send()
+        .await
+        .map_err(|err| {
+            log::error!("Could not send file to tika {:?}", err);
+            ServiceError::BadRequest("Could not send file to tika".to_string())
+        })?;
+
+    let tike_html_converted_file_bytes = tika_response
+        .bytes()
+        .await
+        .map_err(|err| {
+            log::error!("Could not get tika response bytes {:?}", err);
+            ServiceError::BadRequest("Could not get tika response bytes".to_string())
+        })?
+        .to_vec();
+
+    let html_content = String::from_utf8_lossy(&tike_html_converted_file_bytes).to_string();
+    if html_content.is_empty() {
+        return Err(ServiceError::BadRequest(
+            "Could not parse file with tika".to_string(),
+        ));
+    }
+
+    Ok(html_content)
+}
+
 #[tracing::instrument(skip(redis_pool, event_queue))]
 pub async fn readd_error_to_queue(
     mut payload: FileWorkerMessage,
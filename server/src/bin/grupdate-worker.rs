@@ -99,7 +99,7 @@ fn main() {
 
                 let web_redis_pool = actix_web::web::Data::new(redis_pool);
 
-                let event_queue = if std::env::var("USE_ANALYTICS")
+                let mut event_queue = if std::env::var("USE_ANALYTICS")
                     .unwrap_or("false".to_string())
                     .parse()
                     .unwrap_or(false)
@@ -131,6 +131,8 @@ fn main() {
                     EventQueue::default()
                 };
 
+                event_queue.enable_webhooks(web_redis_pool.as_ref().clone());
+
                 let web_event_queue = actix_web::web::Data::new(event_queue);
 
                 let should_terminate = Arc::new(AtomicBool::new(false));
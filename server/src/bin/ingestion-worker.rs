@@ -10,7 +10,8 @@ use std::collections::HashMap;
 use std::sync::{atomic::AtomicBool, atomic::Ordering, Arc};
 use tracing_subscriber::{prelude::*, EnvFilter, Layer};
 use trieve_server::data::models::{
-    self, ChunkMetadata, DatasetConfiguration, QdrantPayload, UnifiedId, WorkerEvent,
+    self, ChunkContentHash, ChunkGroupBookmark, ChunkMetadata, DatasetConfiguration, DedupPolicy,
+    EmbeddingMigration, EmbeddingMigrationState, QdrantPayload, UnifiedId, WorkerEvent,
 };
 use trieve_server::errors::ServiceError;
 use trieve_server::handlers::chunk_handler::{
@@ -20,20 +21,35 @@ use trieve_server::handlers::chunk_handler::{
 use trieve_server::handlers::group_handler::dataset_owns_group;
 use trieve_server::operators::chunk_operator::{
     bulk_insert_chunk_metadata_query, bulk_revert_insert_chunk_metadata_query,
-    insert_chunk_metadata_query, update_chunk_metadata_query,
+    get_metadata_from_id_query, get_metadata_from_tracking_ids_query, insert_chunk_metadata_query,
+    update_chunk_metadata_query,
 };
 use trieve_server::operators::clickhouse_operator::{ClickHouseEvent, EventQueue};
-use trieve_server::operators::dataset_operator::get_dataset_by_id_query;
-use trieve_server::operators::group_operator::get_groups_from_group_ids_query;
+use trieve_server::operators::dataset_operator::{
+    get_dataset_by_id_query, increment_bm25_corpus_stats_query,
+    remove_chunks_from_bm25_corpus_stats,
+};
+use trieve_server::operators::dedup_operator::{
+    get_duplicate_chunks_query, upsert_chunk_content_hashes_query,
+};
+use trieve_server::operators::group_operator::{
+    create_chunk_bookmark_query, get_groups_from_group_ids_query,
+};
+use trieve_server::operators::migration_operator::{
+    finish_embedding_migration_batch_query, hold_embedding_migration_query,
+    queue_new_points_for_embedding_migration_query,
+};
 use trieve_server::operators::model_operator::{
-    get_bm25_embeddings, get_dense_vector, get_dense_vectors, get_sparse_vectors,
+    get_bm25_doc_lengths, get_bm25_embeddings, get_dense_vector, get_dense_vectors,
+    get_sparse_vectors, get_vocabulary_terms,
 };
 use trieve_server::operators::parse_operator::{
     average_embeddings, coarse_doc_chunker, convert_html_to_text,
 };
 use trieve_server::operators::qdrant_operator::{
-    bulk_upsert_qdrant_points_query, update_qdrant_point_query,
+    add_bookmark_to_qdrant_query, bulk_upsert_qdrant_points_query, update_qdrant_point_query,
 };
+use trieve_server::operators::spelling_operator::increment_vocabulary_query;
 use trieve_server::{establish_connection, get_env};
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -122,7 +138,7 @@ fn main() {
 
                 let web_redis_pool = actix_web::web::Data::new(redis_pool);
 
-                let event_queue = if std::env::var("USE_ANALYTICS")
+                let mut event_queue = if std::env::var("USE_ANALYTICS")
                     .unwrap_or("false".to_string())
                     .parse()
                     .unwrap_or(false)
@@ -153,6 +169,8 @@ fn main() {
                     log::info!("Analytics disabled");
                     EventQueue::default()
                 };
+                event_queue.enable_webhooks(web_redis_pool.as_ref().clone());
+
                 let web_event_queue = actix_web::web::Data::new(event_queue);
 
                 let should_terminate = Arc::new(AtomicBool::new(false));
@@ -259,17 +277,52 @@ async fn ingestion_worker(
             }
         };
 
-        let dataset_result: Result<models::Dataset, ServiceError> = match ingestion_message.clone()
-        {
-            IngestionMessage::Update(payload) => {
-                get_dataset_by_id_query(UnifiedId::TrieveUuid(payload.dataset_id), web_pool.clone())
-                    .await
-            }
-            IngestionMessage::BulkUpload(payload) => {
-                get_dataset_by_id_query(UnifiedId::TrieveUuid(payload.dataset_id), web_pool.clone())
-                    .await
-            }
+        let dataset_id = match &ingestion_message {
+            IngestionMessage::Update(payload) => payload.dataset_id,
+            IngestionMessage::BulkUpload(payload) => payload.dataset_id,
         };
+
+        // Writes wait while an embedding migration switches the dataset, they would land in the collection it is switching away from
+        let held_migration =
+            match hold_embedding_migration_query(dataset_id, web_pool.clone(), redis_pool.clone())
+                .await
+            {
+                Ok(Some(migration)) if migration.state == EmbeddingMigrationState::Switching => {
+                    log::info!(
+                        "Dataset {:?} is switching embedding models, requeueing ingestion",
+                        dataset_id
+                    );
+                    let _ = redis::pipe()
+                        .cmd("LREM")
+                        .arg("processing")
+                        .arg(1)
+                        .arg(&serialized_message)
+                        .ignore()
+                        .cmd("lpush")
+                        .arg("ingestion")
+                        .arg(&serialized_message)
+                        .ignore()
+                        .query_async::<redis::aio::MultiplexedConnection, ()>(
+                            &mut *redis_connection,
+                        )
+                        .await;
+                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                    transaction.finish();
+                    continue;
+                }
+                Ok(migration) => migration,
+                Err(err) => {
+                    log::error!(
+                        "Failed to hold embedding migration of dataset {:?}: {:?}",
+                        dataset_id,
+                        err
+                    );
+                    None
+                }
+            };
+
+        let dataset_result: Result<models::Dataset, ServiceError> =
+            get_dataset_by_id_query(UnifiedId::TrieveUuid(dataset_id), web_pool.clone()).await;
         let dataset = match dataset_result {
             Ok(dataset) => dataset,
             Err(err) => {
@@ -281,6 +334,13 @@ async fn ingestion_worker(
                 )
                 .await;
                 log::error!("Failed to get dataset; likely does not exist: {:?}", err);
+                release_embedding_migration(
+                    dataset_id,
+                    held_migration,
+                    web_pool.clone(),
+                    redis_pool.clone(),
+                )
+                .await;
                 transaction.finish();
                 continue;
             }
@@ -293,6 +353,8 @@ async fn ingestion_worker(
                     payload.clone(),
                     dataset_config.clone(),
                     web_pool.clone(),
+                    redis_pool.clone(),
+                    event_queue.clone(),
                     reqwest_client.clone(),
                 )
                 .await
@@ -334,7 +396,14 @@ async fn ingestion_worker(
             }
 
             IngestionMessage::Update(payload) => {
-                match update_chunk(payload.clone(), web_pool.clone(), dataset_config).await {
+                match update_chunk(
+                    payload.clone(),
+                    web_pool.clone(),
+                    redis_pool.clone(),
+                    dataset_config,
+                )
+                .await
+                {
                     Ok(_) => {
                         log::info!("Updated chunk: {:?}", payload.chunk_metadata.id);
                         event_queue
@@ -370,10 +439,46 @@ async fn ingestion_worker(
                 }
             }
         }
+
+        release_embedding_migration(
+            dataset_id,
+            held_migration,
+            web_pool.clone(),
+            redis_pool.clone(),
+        )
+        .await;
         transaction.finish();
     }
 }
 
+/// Releases the hold on an embedding migration taken before processing a message, which lets the migration switch once its last batch is done.
+async fn release_embedding_migration(
+    dataset_id: uuid::Uuid,
+    held_migration: Option<EmbeddingMigration>,
+    web_pool: actix_web::web::Data<models::Pool>,
+    redis_pool: actix_web::web::Data<models::RedisPool>,
+) {
+    let Some(migration) = held_migration else {
+        return;
+    };
+
+    if let Err(err) = finish_embedding_migration_batch_query(
+        dataset_id,
+        migration.migration_id,
+        0,
+        web_pool,
+        redis_pool,
+    )
+    .await
+    {
+        log::error!(
+            "Failed to release embedding migration of dataset {:?}: {:?}",
+            dataset_id,
+            err
+        );
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ChunkDataWithEmbeddingText {
     pub chunk_metadata: ChunkMetadata,
@@ -398,11 +503,13 @@ impl From<ChunkDataWithEmbeddingText> for models::ChunkData {
     }
 }
 
-#[tracing::instrument(skip(payload, web_pool))]
+#[tracing::instrument(skip(payload, web_pool, redis_pool, event_queue))]
 pub async fn bulk_upload_chunks(
     payload: BulkUploadIngestionMessage,
     dataset_config: DatasetConfiguration,
     web_pool: actix_web::web::Data<models::Pool>,
+    redis_pool: actix_web::web::Data<models::RedisPool>,
+    event_queue: actix_web::web::Data<EventQueue>,
     reqwest_client: reqwest::Client,
 ) -> Result<Vec<uuid::Uuid>, ServiceError> {
     let tx_ctx = sentry::TransactionContext::new(
@@ -507,24 +614,100 @@ pub async fn bulk_upload_chunks(
         .filter(|data| !data.content.is_empty())
         .collect();
 
+    let (ingestion_data, duplicate_merges) = dedup_ingestion_data(
+        payload.dataset_id,
+        ingestion_data,
+        &dataset_config,
+        web_pool.clone(),
+        event_queue,
+    )
+    .await?;
+
+    let replaced_chunk_htmls = if upsert_by_tracking_id_being_used {
+        get_replaced_chunk_htmls(
+            payload.dataset_id,
+            ingestion_data
+                .iter()
+                .filter_map(|data| data.chunk_metadata.tracking_id.clone())
+                .collect(),
+            web_pool.clone(),
+        )
+        .await
+    } else {
+        HashMap::new()
+    };
+
     if split_average_being_used {
         let mut chunk_ids = vec![];
+        let mut uploaded_contents = vec![];
+        // Keyed by tracking id so a chunk upserted more than once in the batch is counted with its final content
+        let mut written_contents: HashMap<String, String> = HashMap::new();
+        let ingestion_messages = payload
+            .ingestion_messages
+            .into_iter()
+            .filter(|message| {
+                ingestion_data
+                    .iter()
+                    .any(|data| data.chunk_metadata.id == message.ingest_specific_chunk_metadata.id)
+            })
+            .collect_vec();
         // Split average or Collisions
-        for (message, ingestion_data) in izip!(payload.ingestion_messages, ingestion_data) {
+        for (message, ingestion_data) in izip!(ingestion_messages, ingestion_data) {
+            let content = ingestion_data.content.clone();
+            let tracking_id = ingestion_data.chunk_metadata.tracking_id.clone();
             let upload_chunk_result = upload_chunk(
                 message,
                 dataset_config.clone(),
                 ingestion_data,
                 web_pool.clone(),
+                redis_pool.clone(),
                 reqwest_client.clone(),
             )
             .await;
 
             if let Ok(chunk_uuid) = upload_chunk_result {
                 chunk_ids.push(chunk_uuid);
+                written_contents.insert(
+                    tracking_id.unwrap_or(chunk_uuid.to_string()),
+                    content.clone(),
+                );
+                uploaded_contents.push(content);
             }
         }
 
+        record_chunk_content_hashes(
+            payload.dataset_id,
+            chunk_ids
+                .iter()
+                .copied()
+                .zip(uploaded_contents.iter().map(String::as_str))
+                .collect(),
+            &dataset_config,
+            web_pool.clone(),
+        )
+        .await;
+
+        merge_duplicate_chunks(duplicate_merges, &dataset_config, web_pool.clone()).await;
+
+        remove_chunks_from_bm25_corpus_stats(
+            payload.dataset_id,
+            written_contents
+                .keys()
+                .filter_map(|tracking_id| replaced_chunk_htmls.get(tracking_id).cloned())
+                .collect(),
+            &dataset_config,
+            redis_pool.clone(),
+        )
+        .await;
+
+        record_bm25_corpus_stats(
+            payload.dataset_id,
+            written_contents.into_values().collect(),
+            &dataset_config,
+            redis_pool,
+        )
+        .await;
+
         transaction.finish();
         return Ok(chunk_ids);
     }
@@ -550,6 +733,19 @@ pub async fn bulk_upload_chunks(
 
     insert_tx.finish();
 
+    record_chunk_content_hashes(
+        payload.dataset_id,
+        inserted_chunk_metadatas
+            .iter()
+            .map(|chunk_data| (chunk_data.chunk_metadata.id, chunk_data.content.as_str()))
+            .collect(),
+        &dataset_config,
+        web_pool.clone(),
+    )
+    .await;
+
+    merge_duplicate_chunks(duplicate_merges, &dataset_config, web_pool.clone()).await;
+
     if inserted_chunk_metadatas.is_empty() {
         // All collisions
         return Ok(vec![]);
@@ -673,6 +869,7 @@ pub async fn bulk_upload_chunks(
             dataset_config.BM25_AVG_LEN,
             dataset_config.BM25_B,
             dataset_config.BM25_K,
+            &dataset_config.bm25_analyzer_options(),
         )
         .into_iter()
         .map(Some)
@@ -788,15 +985,270 @@ pub async fn bulk_upload_chunks(
         return Err(err);
     }
 
+    record_embedding_migration_points(
+        payload.dataset_id,
+        inserted_chunk_metadatas
+            .iter()
+            .map(|chunk_data| chunk_data.chunk_metadata.qdrant_point_id)
+            .collect(),
+        redis_pool.clone(),
+    )
+    .await;
+
+    // Chunks sharing a tracking id within the batch were written once, and upserted ones replace content that is already counted
+    let written_chunks = inserted_chunk_metadatas
+        .iter()
+        .unique_by(|chunk_data| {
+            chunk_data
+                .chunk_metadata
+                .tracking_id
+                .clone()
+                .unwrap_or(chunk_data.chunk_metadata.id.to_string())
+        })
+        .collect_vec();
+
+    remove_chunks_from_bm25_corpus_stats(
+        payload.dataset_id,
+        written_chunks
+            .iter()
+            .filter_map(|chunk_data| chunk_data.chunk_metadata.tracking_id.as_ref())
+            .filter_map(|tracking_id| replaced_chunk_htmls.get(tracking_id).cloned())
+            .collect(),
+        &dataset_config,
+        redis_pool.clone(),
+    )
+    .await;
+
+    record_bm25_corpus_stats(
+        payload.dataset_id,
+        written_chunks
+            .into_iter()
+            .map(|chunk_data| chunk_data.content.clone())
+            .collect(),
+        &dataset_config,
+        redis_pool,
+    )
+    .await;
+
     Ok(inserted_chunk_metadata_ids)
 }
 
-#[tracing::instrument(skip(payload, web_pool))]
+/// Checks the chunks of a bulk upload against the content hashes of the dataset when a dedup policy is set. Emits a chunk_duplicate_detected event for every duplicate and drops the duplicates unless the policy is "flag". Returns the remaining chunks and, for the "merge" policy, the groups each original chunk has to be added to once the upload is inserted.
+async fn dedup_ingestion_data(
+    dataset_id: uuid::Uuid,
+    mut ingestion_data: Vec<ChunkDataWithEmbeddingText>,
+    dataset_config: &DatasetConfiguration,
+    web_pool: actix_web::web::Data<models::Pool>,
+    event_queue: actix_web::web::Data<EventQueue>,
+) -> Result<
+    (
+        Vec<ChunkDataWithEmbeddingText>,
+        Vec<(uuid::Uuid, Vec<uuid::Uuid>)>,
+    ),
+    ServiceError,
+> {
+    if dataset_config.DEDUP_POLICY == DedupPolicy::Off {
+        return Ok((ingestion_data, vec![]));
+    }
+
+    let duplicate_chunks = get_duplicate_chunks_query(
+        ingestion_data
+            .iter()
+            .map(|data| {
+                (
+                    ChunkContentHash::from_details(
+                        data.chunk_metadata.id,
+                        dataset_id,
+                        &data.content,
+                    ),
+                    data.chunk_metadata.tracking_id.clone(),
+                )
+            })
+            .collect(),
+        dataset_id,
+        dataset_config,
+        web_pool,
+    )
+    .await?;
+
+    let mut duplicate_merges = vec![];
+    for duplicate_chunk in duplicate_chunks.iter() {
+        event_queue
+            .send(ClickHouseEvent::WorkerEvent(
+                WorkerEvent::from_details(
+                    dataset_id,
+                    models::EventType::ChunkDuplicateDetected {
+                        chunk_id: duplicate_chunk.chunk_id,
+                        duplicate_of_chunk_id: duplicate_chunk.duplicate_of_chunk_id,
+                        distance: duplicate_chunk.distance,
+                        action: dataset_config.DEDUP_POLICY,
+                    },
+                )
+                .into(),
+            ))
+            .await;
+
+        if dataset_config.DEDUP_POLICY == DedupPolicy::Merge {
+            let group_ids = ingestion_data
+                .iter()
+                .find(|data| data.chunk_metadata.id == duplicate_chunk.chunk_id)
+                .and_then(|data| data.group_ids.clone())
+                .unwrap_or_default();
+
+            if !group_ids.is_empty() {
+                duplicate_merges.push((duplicate_chunk.duplicate_of_chunk_id, group_ids));
+            }
+        }
+    }
+
+    if dataset_config.DEDUP_POLICY != DedupPolicy::Flag {
+        ingestion_data.retain(|data| {
+            !duplicate_chunks
+                .iter()
+                .any(|duplicate_chunk| duplicate_chunk.chunk_id == data.chunk_metadata.id)
+        });
+    }
+
+    Ok((ingestion_data, duplicate_merges))
+}
+
+/// Stores the content hashes of uploaded chunks so later uploads can be checked against them. Hashes are only kept while a dedup policy is set.
+async fn record_chunk_content_hashes(
+    dataset_id: uuid::Uuid,
+    chunk_contents: Vec<(uuid::Uuid, &str)>,
+    dataset_config: &DatasetConfiguration,
+    web_pool: actix_web::web::Data<models::Pool>,
+) {
+    if dataset_config.DEDUP_POLICY == DedupPolicy::Off || chunk_contents.is_empty() {
+        return;
+    }
+
+    let chunk_content_hashes = chunk_contents
+        .into_iter()
+        .map(|(chunk_id, content)| ChunkContentHash::from_details(chunk_id, dataset_id, content))
+        .collect();
+
+    if let Err(err) = upsert_chunk_content_hashes_query(chunk_content_hashes, web_pool).await {
+        log::error!("Failed to record chunk content hashes: {:?}", err);
+    }
+}
+
+/// Adds the original chunks of merged duplicates to the groups of the duplicates.
+async fn merge_duplicate_chunks(
+    duplicate_merges: Vec<(uuid::Uuid, Vec<uuid::Uuid>)>,
+    dataset_config: &DatasetConfiguration,
+    web_pool: actix_web::web::Data<models::Pool>,
+) {
+    for (chunk_id, group_ids) in duplicate_merges {
+        for group_id in group_ids {
+            let merge_result = async {
+                let qdrant_point_id = create_chunk_bookmark_query(
+                    web_pool.clone(),
+                    ChunkGroupBookmark::from_details(group_id, chunk_id),
+                )
+                .await?;
+
+                add_bookmark_to_qdrant_query(qdrant_point_id, group_id, dataset_config.clone())
+                    .await
+            }
+            .await;
+
+            if let Err(err) = merge_result {
+                log::error!(
+                    "Failed to add duplicate chunk {} to group {}: {:?}",
+                    chunk_id,
+                    group_id,
+                    err
+                );
+            }
+        }
+    }
+}
+
+/// Fetches the current html of the chunks an upsert by tracking id is about to overwrite, keyed by tracking id, so their content can be taken out of the BM25 corpus statistics.
+async fn get_replaced_chunk_htmls(
+    dataset_id: uuid::Uuid,
+    tracking_ids: Vec<String>,
+    web_pool: actix_web::web::Data<models::Pool>,
+) -> HashMap<String, String> {
+    if tracking_ids.is_empty() {
+        return HashMap::new();
+    }
+
+    match get_metadata_from_tracking_ids_query(tracking_ids, dataset_id, web_pool).await {
+        Ok(chunks) => chunks
+            .into_iter()
+            .filter_map(|chunk| Some((chunk.tracking_id?, chunk.chunk_html?)))
+            .collect(),
+        Err(err) => {
+            log::error!("Failed to get chunks replaced by upsert: {:?}", err);
+            HashMap::new()
+        }
+    }
+}
+
+/// Adds the BM25 document lengths and vocabulary terms of newly uploaded chunks to the dataset's corpus statistics.
+/// Failing to record them only makes the derived BM25_AVG_LEN and spelling corrections less accurate, so errors are logged rather than returned.
+async fn record_bm25_corpus_stats(
+    dataset_id: uuid::Uuid,
+    contents: Vec<String>,
+    dataset_config: &DatasetConfiguration,
+    redis_pool: actix_web::web::Data<models::RedisPool>,
+) {
+    if !dataset_config.BM25_ENABLED
+        || std::env::var("BM25_ACTIVE").unwrap_or("false".to_string()) != "true"
+    {
+        return;
+    }
+
+    let analyzer = dataset_config.bm25_analyzer_options();
+    let vocabulary_terms = get_vocabulary_terms(contents.clone(), &analyzer);
+    let doc_lengths = get_bm25_doc_lengths(contents, &analyzer);
+
+    if let Err(err) =
+        increment_bm25_corpus_stats_query(dataset_id, doc_lengths, redis_pool.clone()).await
+    {
+        log::error!("Failed to record BM25 corpus stats: {:?}", err);
+    }
+
+    if let Err(err) = increment_vocabulary_query(
+        dataset_id,
+        vocabulary_terms,
+        analyzer.ascii_folding,
+        redis_pool,
+    )
+    .await
+    {
+        log::error!("Failed to record vocabulary terms: {:?}", err);
+    }
+}
+
+/// Hands newly written points to the dataset's running embedding model migration, if there is one.
+/// A failure here leaves the points out of the migrated collection, so it is logged loudly but does not fail the ingest.
+async fn record_embedding_migration_points(
+    dataset_id: uuid::Uuid,
+    qdrant_point_ids: Vec<uuid::Uuid>,
+    redis_pool: actix_web::web::Data<models::RedisPool>,
+) {
+    if let Err(err) =
+        queue_new_points_for_embedding_migration_query(dataset_id, qdrant_point_ids, redis_pool)
+            .await
+    {
+        log::error!(
+            "Failed to queue points for embedding migration of dataset {:?}: {:?}",
+            dataset_id,
+            err
+        );
+    }
+}
+
+#[tracing::instrument(skip(payload, web_pool, redis_pool))]
 async fn upload_chunk(
     mut payload: UploadIngestionMessage,
     dataset_config: DatasetConfiguration,
     ingestion_data: ChunkDataWithEmbeddingText,
     web_pool: actix_web::web::Data<models::Pool>,
+    redis_pool: actix_web::web::Data<models::RedisPool>,
     reqwest_client: reqwest::Client,
 ) -> Result<uuid::Uuid, ServiceError> {
     let tx_ctx = sentry::TransactionContext::new(
@@ -962,6 +1414,7 @@ async fn upload_chunk(
                 dataset_config.BM25_AVG_LEN,
                 dataset_config.BM25_B,
                 dataset_config.BM25_K,
+                &dataset_config.bm25_analyzer_options(),
             )
             .first()
             .expect("Vector Must exist")
@@ -1068,6 +1521,9 @@ async fn upload_chunk(
 
         insert_tx.finish();
 
+        record_embedding_migration_points(payload.dataset_id, vec![qdrant_point_id], redis_pool)
+            .await;
+
         inserted_chunk.id
     };
 
@@ -1075,10 +1531,11 @@ async fn upload_chunk(
     Ok(chunk_metadata_id)
 }
 
-#[tracing::instrument(skip(web_pool))]
+#[tracing::instrument(skip(web_pool, redis_pool))]
 async fn update_chunk(
     payload: UpdateIngestionMessage,
     web_pool: actix_web::web::Data<models::Pool>,
+    redis_pool: actix_web::web::Data<models::RedisPool>,
     dataset_config: DatasetConfiguration,
 ) -> Result<(), ServiceError> {
     let content = match payload.convert_html_to_text.unwrap_or(true) {
@@ -1104,6 +1561,11 @@ async fn update_chunk(
 
     let chunk_metadata = payload.chunk_metadata.clone();
 
+    let previous_chunk_html =
+        get_metadata_from_id_query(chunk_metadata.id, payload.dataset_id, web_pool.clone())
+            .await?
+            .chunk_html;
+
     let embedding_vector = match dataset_config.SEMANTIC_ENABLED {
         true => {
             let embedding = get_dense_vector(
@@ -1140,10 +1602,11 @@ async fn update_chunk(
         && std::env::var("BM25_ACTIVE").unwrap_or("false".to_string()) == "true"
     {
         let vecs = get_bm25_embeddings(
-            vec![(content, payload.fulltext_boost)],
+            vec![(content.clone(), payload.fulltext_boost)],
             dataset_config.BM25_AVG_LEN,
             dataset_config.BM25_B,
             dataset_config.BM25_K,
+            &dataset_config.bm25_analyzer_options(),
         );
 
         vecs.first().cloned()
@@ -1176,7 +1639,7 @@ async fn update_chunk(
             payload.dataset_id,
             splade_vector,
             bm25_vector,
-            dataset_config,
+            dataset_config.clone(),
             web_pool.clone(),
         )
         .await
@@ -1198,13 +1661,45 @@ async fn update_chunk(
             payload.dataset_id,
             splade_vector,
             bm25_vector,
-            dataset_config,
+            dataset_config.clone(),
             web_pool.clone(),
         )
         .await
         .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
     }
 
+    record_embedding_migration_points(
+        payload.dataset_id,
+        vec![payload.chunk_metadata.qdrant_point_id],
+        redis_pool.clone(),
+    )
+    .await;
+
+    remove_chunks_from_bm25_corpus_stats(
+        payload.dataset_id,
+        previous_chunk_html.into_iter().collect(),
+        &dataset_config,
+        redis_pool.clone(),
+    )
+    .await;
+
+    // Later uploads are compared against the chunk's new content rather than the one it replaced
+    record_chunk_content_hashes(
+        payload.dataset_id,
+        vec![(payload.chunk_metadata.id, content.as_str())],
+        &dataset_config,
+        web_pool.clone(),
+    )
+    .await;
+
+    record_bm25_corpus_stats(
+        payload.dataset_id,
+        vec![content],
+        &dataset_config,
+        redis_pool,
+    )
+    .await;
+
     Ok(())
 }
 
@@ -0,0 +1,216 @@
+use diesel_async::pooled_connection::{AsyncDieselConnectionManager, ManagerConfig};
+use itertools::Itertools;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+use trieve_server::{
+    data::models::{DatasetConfiguration, DistanceMetric, MigrationMode, UnifiedId},
+    errors::ServiceError,
+    establish_connection, get_env,
+    operators::{
+        dataset_operator::{
+            get_all_datasets_query, get_dataset_by_id_query, queue_dataset_migration_query,
+        },
+        migration_operator::start_embedding_migration_query,
+        qdrant_operator::{
+            create_new_qdrant_collection_query, get_qdrant_collection_from_dataset_config,
+        },
+    },
+};
+
+/// Queues the points of one dataset (DATASET_ID), one organization (ORGANIZATION_ID) or every dataset for the reindex-worker.
+///
+/// MIGRATION_MODE is one of `bm25`, `dense`, `splade`, `move_collection` or `payload_refresh`. Points are rewritten in place and modes which recompute vectors use each dataset's current configuration.
+///
+/// `move_collection` starts a migration of each dataset to the collection for TO_DISTANCE_METRIC, which is created first with QUANTIZE_VECTORS and REPLICATION_FACTOR if it does not exist yet. Collections are shared by every dataset with the same embedding size and distance metric, so quantization only applies to a newly created collection. The datasets keep searching their current collection until their points are copied, at which point their configuration is switched like after an embedding model migration.
+#[allow(clippy::print_stdout)]
+#[tokio::main]
+async fn main() -> Result<(), ServiceError> {
+    dotenvy::dotenv().ok();
+    tracing_subscriber::Registry::default()
+        .with(
+            tracing_subscriber::fmt::layer().with_filter(
+                EnvFilter::from_default_env()
+                    .add_directive(tracing_subscriber::filter::LevelFilter::INFO.into()),
+            ),
+        )
+        .init();
+
+    let migration_mode = get_env!("MIGRATION_MODE", "MIGRATION_MODE is not set").to_lowercase();
+    let to_distance_metric = if migration_mode == "move_collection" {
+        let to_distance_metric = std::env::var("TO_DISTANCE_METRIC").map_err(|_| {
+            ServiceError::BadRequest(
+                "TO_DISTANCE_METRIC must be set for move_collection".to_string(),
+            )
+        })?;
+
+        Some(
+            serde_json::from_value::<DistanceMetric>(serde_json::Value::String(
+                to_distance_metric.to_lowercase(),
+            ))
+            .map_err(|_| {
+                ServiceError::BadRequest(
+                    "TO_DISTANCE_METRIC must be one of cosine, euclidean, manhattan or dot"
+                        .to_string(),
+                )
+            })?,
+        )
+    } else {
+        None
+    };
+
+    let dataset_id = std::env::var("DATASET_ID")
+        .ok()
+        .map(|id| uuid::Uuid::parse_str(&id))
+        .transpose()
+        .map_err(|_| ServiceError::BadRequest("DATASET_ID must be a uuid".to_string()))?;
+    let organization_id = std::env::var("ORGANIZATION_ID")
+        .ok()
+        .map(|id| uuid::Uuid::parse_str(&id))
+        .transpose()
+        .map_err(|_| ServiceError::BadRequest("ORGANIZATION_ID must be a uuid".to_string()))?;
+
+    let database_url = get_env!("DATABASE_URL", "DATABASE_URL is not set");
+
+    let mut config = ManagerConfig::default();
+    config.custom_setup = Box::new(establish_connection);
+
+    let mgr = AsyncDieselConnectionManager::<diesel_async::AsyncPgConnection>::new_with_config(
+        database_url,
+        config,
+    );
+
+    let pool = diesel_async::pooled_connection::deadpool::Pool::builder(mgr)
+        .max_size(3)
+        .build()
+        .expect("Failed to create diesel_async pool");
+
+    let web_pool = actix_web::web::Data::new(pool.clone());
+
+    let redis_url = get_env!("REDIS_URL", "REDIS_URL is not set");
+    let redis_connections: u32 = std::env::var("REDIS_CONNECTIONS")
+        .unwrap_or("2".to_string())
+        .parse()
+        .unwrap_or(2);
+
+    let redis_manager =
+        bb8_redis::RedisConnectionManager::new(redis_url).expect("Failed to connect to redis");
+
+    let redis_pool = bb8_redis::bb8::Pool::builder()
+        .max_size(redis_connections)
+        .connection_timeout(std::time::Duration::from_secs(2))
+        .build(redis_manager)
+        .await
+        .expect("Failed to create redis pool");
+
+    let web_redis_pool = actix_web::web::Data::new(redis_pool);
+
+    let datasets = match dataset_id {
+        Some(dataset_id) => vec![
+            get_dataset_by_id_query(UnifiedId::TrieveUuid(dataset_id), web_pool.clone()).await?,
+        ],
+        None => get_all_datasets_query(organization_id, web_pool.clone()).await?,
+    };
+
+    log::info!(
+        "queue'ing {} migration for {} datasets",
+        migration_mode,
+        datasets.len()
+    );
+
+    if to_distance_metric.is_some() {
+        let replication_factor: u32 = std::env::var("REPLICATION_FACTOR")
+            .unwrap_or("2".to_string())
+            .parse()
+            .unwrap_or(2);
+
+        let quantize_vectors = std::env::var("QUANTIZE_VECTORS")
+            .unwrap_or("false".to_string())
+            .parse()
+            .unwrap_or(false);
+
+        let vector_sizes = datasets
+            .iter()
+            .map(|dataset| {
+                DatasetConfiguration::from_json(dataset.server_configuration.clone()).EMBEDDING_SIZE
+                    as u64
+            })
+            .unique()
+            .collect_vec();
+
+        create_new_qdrant_collection_query(
+            None,
+            None,
+            quantize_vectors,
+            false,
+            replication_factor,
+            vector_sizes,
+        )
+        .await?;
+    }
+
+    for dataset in datasets {
+        let dataset_config = DatasetConfiguration::from_json(dataset.server_configuration);
+        let collection = get_qdrant_collection_from_dataset_config(&dataset_config);
+
+        if let Some(to_distance_metric) = to_distance_metric.clone() {
+            // Datasets already in the target collection or in the middle of another migration are skipped
+            match start_embedding_migration_query(
+                dataset.id,
+                dataset_config.clone(),
+                dataset_config.embedding_model_options(),
+                to_distance_metric,
+                web_redis_pool.clone(),
+            )
+            .await
+            {
+                Ok(migration) => log::info!(
+                    "Started migration {:?} of dataset {:?} from {:?} to {:?}",
+                    migration.migration_id,
+                    dataset.id,
+                    migration.from_collection,
+                    migration.to_collection
+                ),
+                Err(err) => log::info!("Skipped dataset {:?}: {:?}", dataset.id, err),
+            }
+            continue;
+        }
+
+        let mode = match migration_mode.as_str() {
+            "bm25" => MigrationMode::BM25 {
+                average_len: dataset_config.BM25_AVG_LEN,
+                k: dataset_config.BM25_K,
+                b: dataset_config.BM25_B,
+                analyzer: dataset_config.bm25_analyzer_options(),
+                corpus_stats_dataset_id: None,
+            },
+            "dense" => MigrationMode::Dense {
+                model: dataset_config.embedding_model_options(),
+            },
+            "splade" => MigrationMode::Splade,
+            "payload_refresh" => MigrationMode::PayloadRefresh,
+            _ => {
+                return Err(ServiceError::BadRequest(format!(
+                    "Unknown MIGRATION_MODE {:?}, expected one of bm25, dense, splade, move_collection or payload_refresh",
+                    migration_mode
+                )))
+            }
+        };
+
+        let queued_points = queue_dataset_migration_query(
+            dataset.id,
+            collection.clone(),
+            collection.clone(),
+            mode,
+            web_redis_pool.clone(),
+        )
+        .await?;
+
+        log::info!(
+            "Queued {:?} points of dataset {:?} in {:?}",
+            queued_points,
+            dataset.id,
+            collection
+        );
+    }
+
+    Ok(())
+}
@@ -1,15 +1,32 @@
+use diesel_async::pooled_connection::{AsyncDieselConnectionManager, ManagerConfig};
 use itertools::Itertools;
 #[allow(deprecated)]
 use qdrant_client::{
     qdrant::{self, GetPointsBuilder, PointId, RetrievedPoint, UpsertPointsBuilder},
-    Qdrant,
+    Payload, Qdrant,
 };
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
 use trieve_server::{
-    data::models::{MigratePointMessage, MigrationMode},
+    data::models::{
+        self, Bm25AnalyzerOptions, DatasetConfiguration, EmbeddingMigrationState,
+        EmbeddingModelOptions, MigrateDatasetMessage, MigratePointMessage, MigrationMode,
+    },
     errors::ServiceError,
-    get_env,
-    operators::{model_operator::get_bm25_embeddings, qdrant_operator::get_qdrant_connection},
+    establish_connection, get_env,
+    operators::{
+        dataset_operator::{increment_bm25_corpus_stats_query, queue_dataset_migration_query},
+        migration_operator::{
+            fail_embedding_migration_query, finish_embedding_migration_batch_query,
+            get_embedding_migration_query, get_migrated_points_query, get_refreshed_payloads_query,
+            queue_embedding_migration_query,
+        },
+        model_operator::{
+            get_bm25_doc_lengths, get_bm25_embeddings, get_dense_vectors, get_sparse_vectors,
+            get_vocabulary_terms,
+        },
+        qdrant_operator::get_qdrant_connection,
+        spelling_operator::increment_vocabulary_query,
+    },
 };
 
 #[allow(clippy::print_stdout)]
@@ -25,6 +42,23 @@ async fn main() -> Result<(), ServiceError> {
         )
         .init();
 
+    let database_url = get_env!("DATABASE_URL", "DATABASE_URL is not set");
+
+    let mut config = ManagerConfig::default();
+    config.custom_setup = Box::new(establish_connection);
+
+    let mgr = AsyncDieselConnectionManager::<diesel_async::AsyncPgConnection>::new_with_config(
+        database_url,
+        config,
+    );
+
+    let pool = diesel_async::pooled_connection::deadpool::Pool::builder(mgr)
+        .max_size(3)
+        .build()
+        .expect("Failed to create diesel_async pool");
+
+    let web_pool = actix_web::web::Data::new(pool);
+
     let redis_url = get_env!("REDIS_URL", "REDIS_URL is not set");
 
     let redis_manager =
@@ -46,13 +80,15 @@ async fn main() -> Result<(), ServiceError> {
     let mut broken_pipe_sleep = std::time::Duration::from_secs(10);
 
     loop {
+        // Point batches come first so a dataset being queued doesn't hold up migrations already in flight
         let payload_result: Result<Vec<String>, redis::RedisError> = redis::cmd("brpop")
             .arg("collection_migration")
+            .arg("dataset_migration")
             .arg(1)
             .query_async(&mut *connection)
             .await;
 
-        let serialized_message = match payload_result {
+        let (queue, serialized_message) = match payload_result {
             Ok(payload) => {
                 broken_pipe_sleep = std::time::Duration::from_secs(10);
 
@@ -61,10 +97,16 @@ async fn main() -> Result<(), ServiceError> {
                     continue;
                 }
 
-                payload
-                    .get(1)
-                    .expect("Payload must have a first element")
-                    .clone()
+                (
+                    payload
+                        .first()
+                        .expect("Payload must have a queue name")
+                        .clone(),
+                    payload
+                        .get(1)
+                        .expect("Payload must have a first element")
+                        .clone(),
+                )
             }
             Err(err) => {
                 log::error!("Unable to process {:?}", err);
@@ -79,6 +121,16 @@ async fn main() -> Result<(), ServiceError> {
             }
         };
 
+        if queue == "dataset_migration" {
+            queue_dataset_points(
+                &serialized_message,
+                web_pool.clone(),
+                web_redis_pool.clone(),
+            )
+            .await;
+            continue;
+        }
+
         let migration_message: MigratePointMessage = match serde_json::from_str(&serialized_message)
         {
             Ok(message) => message,
@@ -88,10 +140,11 @@ async fn main() -> Result<(), ServiceError> {
             }
         };
         log::info!(
-            "Migrating {} points from {} to {}",
+            "Migrating {} points from {} to {} ({})",
             migration_message.qdrant_point_ids.len(),
             migration_message.from_collection,
-            migration_message.to_collection
+            migration_message.to_collection,
+            migration_message.mode.name()
         );
 
         if migration_message.qdrant_point_ids.is_empty() {
@@ -127,7 +180,13 @@ async fn main() -> Result<(), ServiceError> {
             .result;
 
         let result = match migration_message.mode {
-            MigrationMode::BM25 { average_len, k, b } => {
+            MigrationMode::BM25 {
+                average_len,
+                k,
+                b,
+                analyzer,
+                corpus_stats_dataset_id,
+            } => {
                 migrate_bm25(
                     qdrant_client,
                     points,
@@ -135,6 +194,72 @@ async fn main() -> Result<(), ServiceError> {
                     average_len,
                     b,
                     k,
+                    &analyzer,
+                    corpus_stats_dataset_id,
+                    web_redis_pool.clone(),
+                )
+                .await
+            }
+            MigrationMode::EmbeddingModel {
+                dataset_id,
+                migration_id,
+                ..
+            }
+            | MigrationMode::MoveCollection {
+                dataset_id,
+                migration_id,
+            } => {
+                let migration_result = migrate_embedding_model(
+                    qdrant_client,
+                    points,
+                    dataset_id,
+                    migration_id,
+                    web_redis_pool.clone(),
+                )
+                .await;
+
+                match migration_result {
+                    Ok(Some(migrated_points)) => {
+                        finish_embedding_migration_batch_query(
+                            dataset_id,
+                            migration_id,
+                            migrated_points,
+                            web_pool.clone(),
+                            web_redis_pool.clone(),
+                        )
+                        .await
+                    }
+                    Ok(None) => Ok(()),
+                    Err(err) => {
+                        fail_embedding_migration_query(
+                            dataset_id,
+                            migration_id,
+                            err.to_string(),
+                            web_redis_pool.clone(),
+                        )
+                        .await?;
+                        Err(err)
+                    }
+                }
+            }
+            MigrationMode::Dense { model } => {
+                migrate_dense(
+                    qdrant_client,
+                    points,
+                    migration_message.to_collection,
+                    model,
+                )
+                .await
+            }
+            MigrationMode::Splade => {
+                migrate_splade(qdrant_client, points, migration_message.to_collection).await
+            }
+            MigrationMode::PayloadRefresh => {
+                migrate_payload_refresh(
+                    qdrant_client,
+                    points,
+                    migration_message.to_collection,
+                    web_pool.clone(),
                 )
                 .await
             }
@@ -161,7 +286,99 @@ async fn main() -> Result<(), ServiceError> {
     }
 }
 
-#[tracing::instrument(skip(qdrant_client, points))]
+/// Scrolls the dataset of a `MigrateDatasetMessage` and queues its points as `MigratePointMessage`s for this worker.
+async fn queue_dataset_points(
+    serialized_message: &str,
+    pool: actix_web::web::Data<models::Pool>,
+    redis_pool: actix_web::web::Data<models::RedisPool>,
+) {
+    let dataset_message: MigrateDatasetMessage = match serde_json::from_str(serialized_message) {
+        Ok(message) => message,
+        Err(_) => {
+            log::error!("Failed to deserialize message {:?}", serialized_message);
+            return;
+        }
+    };
+
+    let queue_result = match dataset_message.mode {
+        // Embedding migrations count their batches so they know when to switch the dataset over
+        MigrationMode::EmbeddingModel {
+            dataset_id,
+            migration_id,
+            ..
+        }
+        | MigrationMode::MoveCollection {
+            dataset_id,
+            migration_id,
+        } => queue_embedding_migration_query(dataset_id, migration_id, pool, redis_pool)
+            .await
+            .map(|queued_points| queued_points as usize),
+        mode => {
+            queue_dataset_migration_query(
+                dataset_message.dataset_id,
+                dataset_message.from_collection,
+                dataset_message.to_collection,
+                mode,
+                redis_pool,
+            )
+            .await
+        }
+    };
+
+    match queue_result {
+        Ok(queued_points) => log::info!(
+            "Queued {} points of dataset {} for migration",
+            queued_points,
+            dataset_message.dataset_id
+        ),
+        Err(err) => log::error!(
+            "Failed to queue points of dataset {} for migration {:?}",
+            dataset_message.dataset_id,
+            err
+        ),
+    }
+}
+
+/// Embeds `points` with the migration's target model, or copies them if only the collection changes, and writes them to its target collection. Returns `None` if the migration is no longer running.
+#[tracing::instrument(skip(qdrant_client, points, redis_pool))]
+pub async fn migrate_embedding_model(
+    qdrant_client: Qdrant,
+    points: Vec<RetrievedPoint>,
+    dataset_id: uuid::Uuid,
+    migration_id: uuid::Uuid,
+    redis_pool: actix_web::web::Data<models::RedisPool>,
+) -> Result<Option<usize>, ServiceError> {
+    let migration = match get_embedding_migration_query(dataset_id, redis_pool).await? {
+        Some(migration)
+            if migration.migration_id == migration_id
+                && migration.state != EmbeddingMigrationState::Failed =>
+        {
+            migration
+        }
+        _ => {
+            log::info!(
+                "Embedding migration {} is not running, skipping",
+                migration_id
+            );
+            return Ok(None);
+        }
+    };
+
+    let new_points = get_migrated_points_query(&migration, points).await?;
+    let migrated_points = new_points.len();
+
+    if !new_points.is_empty() {
+        qdrant_client
+            .upsert_points(UpsertPointsBuilder::new(migration.to_collection, new_points).wait(true))
+            .await
+            .map_err(|e| ServiceError::BadRequest(format!("Failed to upsert points {:?}", e)))?;
+    }
+
+    Ok(Some(migrated_points))
+}
+
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(skip(qdrant_client, points, redis_pool))]
 pub async fn migrate_bm25(
     qdrant_client: Qdrant,
     points: Vec<RetrievedPoint>,
@@ -169,6 +386,9 @@ pub async fn migrate_bm25(
     average_len: f32,
     b: f32,
     k: f32,
+    analyzer: &Bm25AnalyzerOptions,
+    corpus_stats_dataset_id: Option<uuid::Uuid>,
+    redis_pool: actix_web::web::Data<models::RedisPool>,
 ) -> Result<(), ServiceError> {
     // Insert points into new collection
     let new_points = points
@@ -184,7 +404,8 @@ pub async fn migrate_bm25(
             };
 
             // calculate bm25
-            let bm25_embeddings = get_bm25_embeddings(vec![(content, None)], average_len, b, k);
+            let bm25_embeddings =
+                get_bm25_embeddings(vec![(content, None)], average_len, b, k, analyzer);
 
             let bm25_embedding = bm25_embeddings.first().expect("BM25 Vectors");
 
@@ -217,6 +438,86 @@ pub async fn migrate_bm25(
         })
         .collect_vec();
 
+    qdrant_client
+        .upsert_points(UpsertPointsBuilder::new(to_collection, new_points))
+        .await
+        .map_err(|e| ServiceError::BadRequest(format!("Failed to upsert points {:?}", e)))?;
+
+    if let Some(dataset_id) = corpus_stats_dataset_id {
+        let contents = get_point_contents(points)
+            .into_iter()
+            .map(|(content, _)| content)
+            .collect::<Vec<String>>();
+
+        increment_bm25_corpus_stats_query(
+            dataset_id,
+            get_bm25_doc_lengths(contents.clone(), analyzer),
+            redis_pool.clone(),
+        )
+        .await?;
+        increment_vocabulary_query(
+            dataset_id,
+            get_vocabulary_terms(contents, analyzer),
+            analyzer.ascii_folding,
+            redis_pool,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Pairs every point with its chunk's text, leaving out points without content.
+fn get_point_contents(points: Vec<RetrievedPoint>) -> Vec<(String, RetrievedPoint)> {
+    points
+        .into_iter()
+        .filter_map(|point| match point.payload.get("content") {
+            Some(qdrant::Value {
+                kind: Some(qdrant::value::Kind::StringValue(content)),
+            }) if !content.is_empty() => Some((content.clone(), point)),
+            _ => None,
+        })
+        .collect_vec()
+}
+
+fn get_point_uuid(point: &RetrievedPoint) -> Option<uuid::Uuid> {
+    match point.id.clone()?.point_id_options? {
+        qdrant::point_id::PointIdOptions::Uuid(id) => uuid::Uuid::parse_str(&id).ok(),
+        qdrant::point_id::PointIdOptions::Num(_) => None,
+    }
+}
+
+/// Returns the point with the named vector set to `vector`, keeping its other vectors and payload.
+fn with_named_vector(
+    point: RetrievedPoint,
+    vector_name: &str,
+    vector: qdrant::Vector,
+) -> qdrant::PointStruct {
+    let mut vectors = match point.vectors {
+        Some(qdrant::Vectors {
+            vectors_options:
+                Some(qdrant::vectors::VectorsOptions::Vectors(qdrant::NamedVectors { vectors })),
+        }) => vectors,
+        _ => Default::default(),
+    };
+    vectors.insert(vector_name.to_string(), vector);
+
+    qdrant::PointStruct {
+        id: point.id,
+        payload: point.payload,
+        vectors: Some(vectors.into()),
+    }
+}
+
+async fn upsert_migrated_points(
+    qdrant_client: Qdrant,
+    to_collection: String,
+    new_points: Vec<qdrant::PointStruct>,
+) -> Result<(), ServiceError> {
+    if new_points.is_empty() {
+        return Ok(());
+    }
+
     qdrant_client
         .upsert_points(UpsertPointsBuilder::new(to_collection, new_points))
         .await
@@ -224,3 +525,97 @@ pub async fn migrate_bm25(
 
     Ok(())
 }
+
+#[tracing::instrument(skip(qdrant_client, points))]
+pub async fn migrate_dense(
+    qdrant_client: Qdrant,
+    points: Vec<RetrievedPoint>,
+    to_collection: String,
+    model: EmbeddingModelOptions,
+) -> Result<(), ServiceError> {
+    let points = get_point_contents(points);
+    if points.is_empty() {
+        return Ok(());
+    }
+
+    let vector_name = format!("{}_vectors", model.size);
+    let dense_vectors = get_dense_vectors(
+        points
+            .iter()
+            .map(|(content, _)| (content.clone(), None))
+            .collect(),
+        "doc",
+        DatasetConfiguration::default().with_embedding_model_options(model),
+        reqwest::Client::new(),
+    )
+    .await?;
+
+    let new_points = points
+        .into_iter()
+        .zip(dense_vectors)
+        .map(|((_, point), dense_vector)| {
+            with_named_vector(point, &vector_name, qdrant::Vector::from(dense_vector))
+        })
+        .collect_vec();
+
+    upsert_migrated_points(qdrant_client, to_collection, new_points).await
+}
+
+#[tracing::instrument(skip(qdrant_client, points))]
+pub async fn migrate_splade(
+    qdrant_client: Qdrant,
+    points: Vec<RetrievedPoint>,
+    to_collection: String,
+) -> Result<(), ServiceError> {
+    let points = get_point_contents(points);
+    if points.is_empty() {
+        return Ok(());
+    }
+
+    let sparse_vectors = get_sparse_vectors(
+        points
+            .iter()
+            .map(|(content, _)| (content.clone(), None))
+            .collect(),
+        "doc",
+        reqwest::Client::new(),
+    )
+    .await?;
+
+    let new_points = points
+        .into_iter()
+        .zip(sparse_vectors)
+        .map(|((_, point), sparse_vector)| {
+            with_named_vector(point, "sparse_vectors", qdrant::Vector::from(sparse_vector))
+        })
+        .collect_vec();
+
+    upsert_migrated_points(qdrant_client, to_collection, new_points).await
+}
+
+#[tracing::instrument(skip(qdrant_client, points, pool))]
+pub async fn migrate_payload_refresh(
+    qdrant_client: Qdrant,
+    points: Vec<RetrievedPoint>,
+    to_collection: String,
+    pool: actix_web::web::Data<models::Pool>,
+) -> Result<(), ServiceError> {
+    let point_ids = points.iter().filter_map(get_point_uuid).collect_vec();
+
+    let mut payloads = get_refreshed_payloads_query(point_ids, pool).await?;
+
+    let new_points = points
+        .into_iter()
+        .filter_map(|point| {
+            let payload = payloads.remove(&get_point_uuid(&point)?)?;
+
+            Some(qdrant::PointStruct {
+                id: point.id,
+                payload: Payload::from(payload).into(),
+                vectors: point.vectors,
+            })
+        })
+        .collect_vec();
+
+    upsert_migrated_points(qdrant_client, to_collection, new_points).await
+}
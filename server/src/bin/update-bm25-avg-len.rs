@@ -0,0 +1,189 @@
+use diesel_async::pooled_connection::{AsyncDieselConnectionManager, ManagerConfig};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+use trieve_server::{
+    data::models::{DatasetConfiguration, UnifiedId},
+    errors::ServiceError,
+    establish_connection, get_env,
+    operators::dataset_operator::{
+        get_bm25_corpus_stats_query, get_dataset_by_id_query, queue_dataset_bm25_reindex_query,
+        update_dataset_query,
+    },
+};
+
+#[allow(clippy::print_stdout)]
+#[tokio::main]
+async fn main() -> Result<(), ServiceError> {
+    dotenvy::dotenv().ok();
+    tracing_subscriber::Registry::default()
+        .with(
+            tracing_subscriber::fmt::layer().with_filter(
+                EnvFilter::from_default_env()
+                    .add_directive(tracing_subscriber::filter::LevelFilter::INFO.into()),
+            ),
+        )
+        .init();
+
+    let database_url = get_env!("DATABASE_URL", "DATABASE_URL is not set");
+
+    let mut config = ManagerConfig::default();
+    config.custom_setup = Box::new(establish_connection);
+
+    let mgr = AsyncDieselConnectionManager::<diesel_async::AsyncPgConnection>::new_with_config(
+        database_url,
+        config,
+    );
+
+    let pool = diesel_async::pooled_connection::deadpool::Pool::builder(mgr)
+        .max_size(3)
+        .build()
+        .expect("Failed to create diesel_async pool");
+
+    let web_pool = actix_web::web::Data::new(pool.clone());
+
+    let redis_url = get_env!("REDIS_URL", "REDIS_URL is not set");
+    let redis_connections: u32 = std::env::var("REDIS_CONNECTIONS")
+        .unwrap_or("2".to_string())
+        .parse()
+        .unwrap_or(2);
+
+    let redis_manager =
+        bb8_redis::RedisConnectionManager::new(redis_url).expect("Failed to connect to redis");
+
+    let redis_pool = bb8_redis::bb8::Pool::builder()
+        .max_size(redis_connections)
+        .connection_timeout(std::time::Duration::from_secs(2))
+        .build(redis_manager)
+        .await
+        .expect("Failed to create redis pool");
+
+    let web_redis_pool = actix_web::web::Data::new(redis_pool);
+
+    // Relative difference between the derived and configured average length before a reindex is queued.
+    let drift_threshold: f32 = std::env::var("BM25_AVG_LEN_DRIFT_THRESHOLD")
+        .unwrap_or("0.2".to_string())
+        .parse()
+        .unwrap_or(0.2);
+    let min_docs: u64 = std::env::var("BM25_AVG_LEN_MIN_DOCS")
+        .unwrap_or("100".to_string())
+        .parse()
+        .unwrap_or(100);
+
+    let mut dataset_ids: Vec<uuid::Uuid> = vec![];
+    let mut cursor: u64 = 0;
+
+    loop {
+        let mut conn = web_redis_pool
+            .get()
+            .await
+            .expect("Failed to connect to redis");
+
+        let (next_cursor, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("MATCH")
+            .arg("bm25_stats:*")
+            .arg("COUNT")
+            .arg(1000)
+            .query_async(&mut *conn)
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+        dataset_ids.extend(keys.iter().filter_map(|key| {
+            key.strip_prefix("bm25_stats:")
+                .and_then(|id| uuid::Uuid::parse_str(id).ok())
+        }));
+
+        cursor = next_cursor;
+        if cursor == 0 {
+            break;
+        }
+    }
+
+    for dataset_id in dataset_ids {
+        let dataset = match get_dataset_by_id_query(
+            UnifiedId::TrieveUuid(dataset_id),
+            web_pool.clone(),
+        )
+        .await
+        {
+            Ok(dataset) => dataset,
+            Err(err) => {
+                log::error!("Failed to get dataset {:?}: {:?}", dataset_id, err);
+                continue;
+            }
+        };
+
+        let mut dataset_config =
+            DatasetConfiguration::from_json(dataset.server_configuration.clone());
+
+        if !dataset_config.BM25_ENABLED {
+            continue;
+        }
+
+        let stats = match get_bm25_corpus_stats_query(dataset_id, web_redis_pool.clone()).await {
+            Ok(stats) => stats,
+            Err(err) => {
+                log::error!(
+                    "Failed to get BM25 corpus stats of dataset {:?}: {:?}",
+                    dataset_id,
+                    err
+                );
+                continue;
+            }
+        };
+
+        let derived_avg_len = match stats.avg_len() {
+            Some(avg_len) if stats.doc_count >= min_docs => avg_len,
+            _ => continue,
+        };
+
+        let current_avg_len = dataset_config.BM25_AVG_LEN;
+        let drift = (derived_avg_len - current_avg_len).abs() / current_avg_len.max(1.0);
+
+        if drift <= drift_threshold {
+            continue;
+        }
+
+        log::info!(
+            "BM25_AVG_LEN for dataset {:?} drifted from {:?} to {:?}, queueing reindex",
+            dataset_id,
+            current_avg_len,
+            derived_avg_len
+        );
+
+        dataset_config.BM25_AVG_LEN = derived_avg_len;
+
+        if let Err(err) = update_dataset_query(
+            dataset_id,
+            dataset.name.clone(),
+            dataset_config.clone(),
+            None,
+            web_pool.clone(),
+        )
+        .await
+        {
+            log::error!(
+                "Failed to update BM25_AVG_LEN of dataset {:?}: {:?}",
+                dataset_id,
+                err
+            );
+            continue;
+        }
+
+        if let Err(err) = queue_dataset_bm25_reindex_query(
+            dataset_id,
+            dataset_config,
+            false,
+            web_redis_pool.clone(),
+        )
+        .await
+        {
+            log::error!(
+                "Failed to queue BM25 reindex of dataset {:?}: {:?}",
+                dataset_id,
+                err
+            );
+        }
+    }
+
+    Ok(())
+}
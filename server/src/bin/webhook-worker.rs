@@ -0,0 +1,454 @@
+use diesel_async::pooled_connection::{AsyncDieselConnectionManager, ManagerConfig};
+use sentry::{Hub, SentryFutureExt};
+use signal_hook::consts::SIGTERM;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+use trieve_server::{
+    data::models::{self, WebhookDeliveryMessage, WorkerEvent},
+    errors::ServiceError,
+    establish_connection, get_env,
+    operators::webhook_operator::{
+        get_webhook_by_id_query, get_webhooks_for_dataset_query, send_webhook_query,
+    },
+};
+
+fn main() {
+    dotenvy::dotenv().ok();
+    let sentry_url = std::env::var("SENTRY_URL");
+    let _guard = if let Ok(sentry_url) = sentry_url {
+        let guard = sentry::init((
+            sentry_url,
+            sentry::ClientOptions {
+                release: sentry::release_name!(),
+                traces_sample_rate: 1.0,
+                ..Default::default()
+            },
+        ));
+
+        tracing_subscriber::Registry::default()
+            .with(sentry::integrations::tracing::layer())
+            .with(
+                tracing_subscriber::fmt::layer().with_filter(
+                    EnvFilter::from_default_env()
+                        .add_directive(tracing_subscriber::filter::LevelFilter::INFO.into()),
+                ),
+            )
+            .init();
+
+        log::info!("Sentry monitoring enabled");
+        Some(guard)
+    } else {
+        tracing_subscriber::Registry::default()
+            .with(
+                tracing_subscriber::fmt::layer().with_filter(
+                    EnvFilter::from_default_env()
+                        .add_directive(tracing_subscriber::filter::LevelFilter::INFO.into()),
+                ),
+            )
+            .init();
+
+        None
+    };
+
+    let database_url = get_env!("DATABASE_URL", "DATABASE_URL is not set");
+
+    let mut config = ManagerConfig::default();
+    config.custom_setup = Box::new(establish_connection);
+
+    let mgr = AsyncDieselConnectionManager::<diesel_async::AsyncPgConnection>::new_with_config(
+        database_url,
+        config,
+    );
+
+    let pool = diesel_async::pooled_connection::deadpool::Pool::builder(mgr)
+        .max_size(3)
+        .build()
+        .expect("Failed to create diesel_async pool");
+
+    let web_pool = actix_web::web::Data::new(pool.clone());
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("Failed to create tokio runtime")
+        .block_on(
+            async move {
+                let redis_url = get_env!("REDIS_URL", "REDIS_URL is not set");
+                let redis_connections: u32 = std::env::var("REDIS_CONNECTIONS")
+                    .unwrap_or("2".to_string())
+                    .parse()
+                    .unwrap_or(2);
+
+                let redis_manager = bb8_redis::RedisConnectionManager::new(redis_url)
+                    .expect("Failed to connect to redis");
+
+                let redis_pool = bb8_redis::bb8::Pool::builder()
+                    .max_size(redis_connections)
+                    .connection_timeout(std::time::Duration::from_secs(2))
+                    .build(redis_manager)
+                    .await
+                    .expect("Failed to create redis pool");
+
+                let web_redis_pool = actix_web::web::Data::new(redis_pool);
+
+                let should_terminate = Arc::new(AtomicBool::new(false));
+                signal_hook::flag::register(SIGTERM, Arc::clone(&should_terminate))
+                    .expect("Failed to register shutdown hook");
+
+                webhook_worker(should_terminate, web_redis_pool, web_pool).await
+            }
+            .bind_hub(Hub::new_from_top(Hub::current())),
+        );
+}
+
+struct RetryPolicy {
+    max_attempts: usize,
+    base_delay_secs: u64,
+    max_delay_secs: u64,
+}
+
+impl RetryPolicy {
+    fn from_env() -> Self {
+        RetryPolicy {
+            max_attempts: std::env::var("WEBHOOK_MAX_ATTEMPTS")
+                .unwrap_or("5".to_string())
+                .parse()
+                .unwrap_or(5),
+            base_delay_secs: std::env::var("WEBHOOK_RETRY_BASE_DELAY_SECS")
+                .unwrap_or("10".to_string())
+                .parse()
+                .unwrap_or(10),
+            max_delay_secs: std::env::var("WEBHOOK_RETRY_MAX_DELAY_SECS")
+                .unwrap_or("3600".to_string())
+                .parse()
+                .unwrap_or(3600),
+        }
+    }
+
+    /// Delay before the given attempt, doubling after every failed one.
+    fn delay_secs(&self, attempt_number: usize) -> u64 {
+        let exponent = attempt_number.saturating_sub(1).min(32) as u32;
+        self.base_delay_secs
+            .saturating_mul(2u64.saturating_pow(exponent))
+            .min(self.max_delay_secs)
+    }
+}
+
+async fn webhook_worker(
+    should_terminate: Arc<AtomicBool>,
+    redis_pool: actix_web::web::Data<models::RedisPool>,
+    web_pool: actix_web::web::Data<models::Pool>,
+) {
+    log::info!("Starting webhook worker service thread");
+
+    let retry_policy = RetryPolicy::from_env();
+
+    let mut redis_conn_sleep = std::time::Duration::from_secs(1);
+
+    #[allow(unused_assignments)]
+    let mut opt_redis_connection = None;
+
+    loop {
+        let borrowed_redis_connection = match redis_pool.get().await {
+            Ok(redis_connection) => Some(redis_connection),
+            Err(err) => {
+                log::error!("Failed to get redis connection outside of loop: {:?}", err);
+                None
+            }
+        };
+
+        if borrowed_redis_connection.is_some() {
+            opt_redis_connection = borrowed_redis_connection;
+            break;
+        }
+
+        tokio::time::sleep(redis_conn_sleep).await;
+        redis_conn_sleep = std::cmp::min(redis_conn_sleep * 2, std::time::Duration::from_secs(300));
+    }
+
+    let mut redis_connection =
+        opt_redis_connection.expect("Failed to get redis connection outside of loop");
+
+    // Events left in webhook_processing by a worker which stopped before delivering them go back in line.
+    let mut requeued_events = 0;
+    loop {
+        let requeued_event: Result<Option<String>, redis::RedisError> = redis::cmd("rpoplpush")
+            .arg("webhook_processing")
+            .arg("webhook_events")
+            .query_async(&mut *redis_connection)
+            .await;
+
+        match requeued_event {
+            Ok(Some(_)) => requeued_events += 1,
+            Ok(None) => break,
+            Err(err) => {
+                log::error!(
+                    "Failed to requeue webhook events left in processing: {:?}",
+                    err
+                );
+                break;
+            }
+        }
+    }
+    if requeued_events > 0 {
+        log::info!(
+            "Requeued {} webhook events left in processing",
+            requeued_events
+        );
+    }
+
+    let mut broken_pipe_sleep = std::time::Duration::from_secs(10);
+
+    loop {
+        if should_terminate.load(Ordering::Relaxed) {
+            log::info!("Shutting down");
+            break;
+        }
+
+        if let Err(err) =
+            deliver_due_retries(&retry_policy, web_pool.clone(), redis_pool.clone()).await
+        {
+            log::error!("Failed to deliver webhook retries: {:?}", err);
+        }
+
+        // Events stay in webhook_processing until their deliveries were attempted, so a crash does not drop them.
+        let payload_result: Result<Vec<String>, redis::RedisError> = redis::cmd("brpoplpush")
+            .arg("webhook_events")
+            .arg("webhook_processing")
+            .arg(1.0)
+            .query_async(&mut *redis_connection)
+            .await;
+
+        let serialized_event = if let Ok(payload) = payload_result {
+            broken_pipe_sleep = std::time::Duration::from_secs(10);
+
+            if payload.is_empty() {
+                continue;
+            }
+
+            payload
+                .first()
+                .expect("Payload must have a first element")
+                .clone()
+        } else {
+            log::error!("Unable to process {:?}", payload_result);
+
+            if payload_result.is_err_and(|err| err.is_io_error()) {
+                tokio::time::sleep(broken_pipe_sleep).await;
+                broken_pipe_sleep =
+                    std::cmp::min(broken_pipe_sleep * 2, std::time::Duration::from_secs(300));
+            }
+
+            continue;
+        };
+
+        let event: WorkerEvent = match serde_json::from_str(&serialized_event) {
+            Ok(event) => event,
+            Err(err) => {
+                log::error!("Failed to parse webhook event: {:?}", err);
+
+                let _ = redis::cmd("LREM")
+                    .arg("webhook_processing")
+                    .arg(1)
+                    .arg(&serialized_event)
+                    .query_async::<redis::aio::MultiplexedConnection, usize>(&mut *redis_connection)
+                    .await;
+                continue;
+            }
+        };
+
+        let webhooks = match get_webhooks_for_dataset_query(event.dataset_id, web_pool.clone())
+            .await
+        {
+            Ok(webhooks) => webhooks,
+            Err(err) => {
+                log::error!("Failed to get webhooks for event {:?}: {:?}", event.id, err);
+
+                let _ = redis::pipe()
+                    .atomic()
+                    .cmd("LREM")
+                    .arg("webhook_processing")
+                    .arg(1)
+                    .arg(&serialized_event)
+                    .ignore()
+                    .cmd("lpush")
+                    .arg("webhook_events")
+                    .arg(&serialized_event)
+                    .ignore()
+                    .query_async::<redis::aio::MultiplexedConnection, ()>(&mut *redis_connection)
+                    .await;
+                continue;
+            }
+        };
+
+        let deliveries = webhooks
+            .into_iter()
+            .filter(|webhook| webhook.subscribes_to(&event.event_type))
+            .map(|webhook| {
+                let delivery = WebhookDeliveryMessage {
+                    webhook_id: webhook.id,
+                    event: event.clone(),
+                    attempt_number: 0,
+                };
+
+                deliver_webhook(
+                    delivery,
+                    &retry_policy,
+                    web_pool.clone(),
+                    redis_pool.clone(),
+                )
+            });
+
+        for result in futures::future::join_all(deliveries).await {
+            if let Err(err) = result {
+                log::error!("Failed to deliver webhook: {:?}", err);
+            }
+        }
+
+        // Failed deliveries were scheduled in webhook_retries, so the event is done.
+        let _ = redis::cmd("LREM")
+            .arg("webhook_processing")
+            .arg(1)
+            .arg(&serialized_event)
+            .query_async::<redis::aio::MultiplexedConnection, usize>(&mut *redis_connection)
+            .await;
+    }
+}
+
+/// Claims the deliveries in `webhook_retries` whose backoff has elapsed and attempts them again.
+async fn deliver_due_retries(
+    retry_policy: &RetryPolicy,
+    web_pool: actix_web::web::Data<models::Pool>,
+    redis_pool: actix_web::web::Data<models::RedisPool>,
+) -> Result<(), ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let due_deliveries: Vec<String> = redis::cmd("ZRANGEBYSCORE")
+        .arg("webhook_retries")
+        .arg("-inf")
+        .arg(chrono::Utc::now().timestamp())
+        .arg("LIMIT")
+        .arg(0)
+        .arg(100)
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    for serialized_delivery in due_deliveries {
+        // Only the worker which removes the entry delivers it.
+        let claimed: usize = redis::cmd("ZREM")
+            .arg("webhook_retries")
+            .arg(&serialized_delivery)
+            .query_async(&mut *redis_conn)
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+        if claimed == 0 {
+            continue;
+        }
+
+        let delivery: WebhookDeliveryMessage = match serde_json::from_str(&serialized_delivery) {
+            Ok(delivery) => delivery,
+            Err(err) => {
+                log::error!("Failed to parse webhook delivery: {:?}", err);
+                continue;
+            }
+        };
+
+        if let Err(err) =
+            deliver_webhook(delivery, retry_policy, web_pool.clone(), redis_pool.clone()).await
+        {
+            log::error!("Failed to deliver webhook: {:?}", err);
+        }
+    }
+
+    Ok(())
+}
+
+async fn deliver_webhook(
+    mut delivery: WebhookDeliveryMessage,
+    retry_policy: &RetryPolicy,
+    web_pool: actix_web::web::Data<models::Pool>,
+    redis_pool: actix_web::web::Data<models::RedisPool>,
+) -> Result<(), ServiceError> {
+    let webhook = match get_webhook_by_id_query(delivery.webhook_id, web_pool).await? {
+        Some(webhook) => webhook,
+        None => {
+            log::info!(
+                "Webhook {:?} was deleted, dropping delivery",
+                delivery.webhook_id
+            );
+            return Ok(());
+        }
+    };
+
+    let error = match send_webhook_query(&webhook, &delivery.event).await {
+        Ok(()) => {
+            log::info!(
+                "Delivered event {:?} to webhook {:?}",
+                delivery.event.id,
+                webhook.id
+            );
+            return Ok(());
+        }
+        Err(err) => err,
+    };
+
+    delivery.attempt_number += 1;
+
+    let serialized_delivery = serde_json::to_string(&delivery).map_err(|_| {
+        ServiceError::InternalServerError("Failed to reserialize delivery for retry".to_string())
+    })?;
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    if delivery.attempt_number >= retry_policy.max_attempts {
+        log::error!(
+            "Failed to deliver event {:?} to webhook {:?} {} times, dead lettering: {:?}",
+            delivery.event.id,
+            webhook.id,
+            delivery.attempt_number,
+            error
+        );
+
+        redis::cmd("lpush")
+            .arg("dead_letters_webhook")
+            .arg(serialized_delivery)
+            .query_async(&mut *redis_conn)
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+        return Ok(());
+    }
+
+    let retry_at =
+        chrono::Utc::now().timestamp() + retry_policy.delay_secs(delivery.attempt_number) as i64;
+
+    log::error!(
+        "Failed to deliver event {:?} to webhook {:?}, retry {:?} at {:?}: {:?}",
+        delivery.event.id,
+        webhook.id,
+        delivery.attempt_number,
+        retry_at,
+        error
+    );
+
+    redis::cmd("ZADD")
+        .arg("webhook_retries")
+        .arg(retry_at)
+        .arg(serialized_delivery)
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(())
+}
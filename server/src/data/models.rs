@@ -20,6 +20,7 @@ use crate::operators::analytics_operator::{
 use crate::operators::chunk_operator::{
     get_metadata_from_id_query, get_metadata_from_ids_query, HighlightStrategy,
 };
+use crate::operators::dedup_operator::{get_content_hash, get_simhash, get_simhash_bands};
 use crate::operators::parse_operator::convert_html_to_text;
 use crate::operators::search_operator::{
     get_group_metadata_filter_condition, get_group_tag_set_filter_condition,
@@ -729,6 +730,7 @@ impl From<ChunkMetadataWithScore> for ScoreChunk {
             chunk: NewChunkMetadataTypes::Metadata(val.clone().into()),
             highlights: None,
             score: val.score,
+            inner_hits: None,
         }
     }
 }
@@ -826,6 +828,8 @@ pub struct ScoreChunkDTO {
     pub metadata: Vec<ChunkMetadataTypes>,
     pub highlights: Option<Vec<String>>,
     pub score: f64,
+    /// Number of chunks collapsed into this one including itself. Only present when collapse was requested.
+    pub inner_hits: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, ToSchema, Clone)]
@@ -847,6 +851,8 @@ pub struct ScoreChunk {
     pub chunk: NewChunkMetadataTypes,
     pub highlights: Option<Vec<String>>,
     pub score: f32,
+    /// Number of chunks collapsed into this one including itself. Only present when collapse was requested.
+    pub inner_hits: Option<u64>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
@@ -943,6 +949,7 @@ impl From<ScoreChunkDTO> for ScoreChunk {
             chunk: score_chunk_dto.metadata[0].clone().into(),
             highlights: score_chunk_dto.highlights,
             score: score_chunk_dto.score as f32,
+            inner_hits: score_chunk_dto.inner_hits,
         }
     }
 }
@@ -1566,6 +1573,7 @@ pub struct UserDTOWithChunks {
     "link": "https://trieve.ai",
     "time_stamp": "2021-01-01 00:00:00.000",
     "dataset_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "pending": false,
 }))]
 #[diesel(table_name = files)]
 pub struct File {
@@ -1579,6 +1587,7 @@ pub struct File {
     pub time_stamp: Option<chrono::NaiveDateTime>,
     pub dataset_id: uuid::Uuid,
     pub tag_set: Option<Vec<Option<String>>>,
+    pub pending: bool,
 }
 
 impl File {
@@ -1610,6 +1619,7 @@ impl File {
                     .naive_local()
             }),
             dataset_id,
+            pending: false,
         }
     }
 }
@@ -1726,6 +1736,13 @@ pub enum EventType {
     ChunkActionFailed { chunk_id: uuid::Uuid, error: String },
     #[display(fmt = "chunk_updated")]
     ChunkUpdated { chunk_id: uuid::Uuid },
+    #[display(fmt = "chunk_duplicate_detected")]
+    ChunkDuplicateDetected {
+        chunk_id: uuid::Uuid,
+        duplicate_of_chunk_id: uuid::Uuid,
+        distance: u32,
+        action: DedupPolicy,
+    },
     #[display(fmt = "bulk_chunks_deleted")]
     BulkChunksDeleted { message: String },
     #[display(fmt = "dataset_delete_failed")]
@@ -1745,6 +1762,21 @@ pub enum EventType {
     GroupChunksUpdated { group_id: uuid::Uuid },
     #[display(fmt = "group_chunks_action_failed")]
     GroupChunksActionFailed { group_id: uuid::Uuid, error: String },
+    #[display(fmt = "dataset_clone_progress")]
+    DatasetCloneProgress {
+        source_dataset_id: uuid::Uuid,
+        chunk_count: usize,
+    },
+    #[display(fmt = "dataset_cloned")]
+    DatasetCloned {
+        source_dataset_id: uuid::Uuid,
+        chunk_count: usize,
+    },
+    #[display(fmt = "dataset_clone_failed")]
+    DatasetCloneFailed {
+        source_dataset_id: uuid::Uuid,
+        error: String,
+    },
 }
 
 impl EventType {
@@ -1755,12 +1787,16 @@ impl EventType {
             EventTypeRequest::ChunksUploaded,
             EventTypeRequest::ChunkActionFailed,
             EventTypeRequest::ChunkUpdated,
+            EventTypeRequest::ChunkDuplicateDetected,
             EventTypeRequest::BulkChunksDeleted,
             EventTypeRequest::DatasetDeleteFailed,
             EventTypeRequest::QdrantUploadFailed,
             EventTypeRequest::BulkChunkUploadFailed,
             EventTypeRequest::GroupChunksUpdated,
             EventTypeRequest::GroupChunksActionFailed,
+            EventTypeRequest::DatasetCloneProgress,
+            EventTypeRequest::DatasetCloned,
+            EventTypeRequest::DatasetCloneFailed,
         ]
     }
 }
@@ -1777,6 +1813,619 @@ impl WorkerEvent {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Selectable, Clone)]
+#[diesel(table_name = webhooks)]
+pub struct Webhook {
+    pub id: uuid::Uuid,
+    pub dataset_id: uuid::Uuid,
+    pub url: String,
+    pub event_types: Option<Vec<Option<String>>>,
+    pub secret: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl Webhook {
+    pub fn from_details(
+        dataset_id: uuid::Uuid,
+        url: String,
+        event_types: Option<Vec<EventTypeRequest>>,
+        secret: String,
+    ) -> Self {
+        Webhook {
+            id: uuid::Uuid::new_v4(),
+            dataset_id,
+            url,
+            event_types: event_types.map(|event_types| {
+                event_types
+                    .into_iter()
+                    .map(|event_type| Some(event_type.to_string()))
+                    .collect()
+            }),
+            secret,
+            created_at: chrono::Utc::now().naive_local(),
+            updated_at: chrono::Utc::now().naive_local(),
+        }
+    }
+
+    /// Webhooks without an event type filter receive every event.
+    pub fn subscribes_to(&self, event_type: &str) -> bool {
+        match &self.event_types {
+            Some(event_types) => event_types
+                .iter()
+                .flatten()
+                .any(|subscribed_type| subscribed_type == event_type),
+            None => true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example=json!({
+    "id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "dataset_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "url": "https://example.com/trieve-webhook",
+    "event_types": ["file_uploaded", "bulk_chunk_upload_failed"],
+    "created_at": "2021-01-01 00:00:00.000",
+    "updated_at": "2021-01-01 00:00:00.000",
+}))]
+pub struct WebhookDTO {
+    pub id: uuid::Uuid,
+    pub dataset_id: uuid::Uuid,
+    pub url: String,
+    pub event_types: Option<Vec<String>>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl From<Webhook> for WebhookDTO {
+    fn from(webhook: Webhook) -> Self {
+        WebhookDTO {
+            id: webhook.id,
+            dataset_id: webhook.dataset_id,
+            url: webhook.url,
+            event_types: webhook
+                .event_types
+                .map(|event_types| event_types.into_iter().flatten().collect()),
+            created_at: webhook.created_at,
+            updated_at: webhook.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WebhookDeliveryMessage {
+    pub webhook_id: uuid::Uuid,
+    pub event: WorkerEvent,
+    pub attempt_number: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example=json!({
+    "tracking_id": "dogs-are-loyal",
+    "grade": 3.0,
+}))]
+pub struct Judgment {
+    /// Id of the relevant chunk. Either chunk_id or tracking_id must be specified.
+    pub chunk_id: Option<uuid::Uuid>,
+    /// Tracking id of the relevant chunk. Either chunk_id or tracking_id must be specified.
+    pub tracking_id: Option<String>,
+    /// How relevant the chunk is to the query. Higher is more relevant, 0 is irrelevant.
+    pub grade: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example=json!({
+    "query": "loyal dog breeds",
+    "judgments": [
+        {"tracking_id": "dogs-are-loyal", "grade": 3.0},
+        {"chunk_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3", "grade": 1.0},
+    ],
+}))]
+pub struct JudgmentQuery {
+    /// The query which is searched for during an evaluation.
+    pub query: String,
+    /// The chunks which are relevant to the query. Chunks which are not listed are treated as irrelevant.
+    pub judgments: Vec<Judgment>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Selectable, Clone)]
+#[diesel(table_name = judgment_lists)]
+pub struct JudgmentList {
+    pub id: uuid::Uuid,
+    pub dataset_id: uuid::Uuid,
+    pub name: String,
+    pub queries: serde_json::Value,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl JudgmentList {
+    pub fn from_details(dataset_id: uuid::Uuid, name: String, queries: Vec<JudgmentQuery>) -> Self {
+        JudgmentList {
+            id: uuid::Uuid::new_v4(),
+            dataset_id,
+            name,
+            queries: serde_json::to_value(queries).unwrap_or_default(),
+            created_at: chrono::Utc::now().naive_local(),
+            updated_at: chrono::Utc::now().naive_local(),
+        }
+    }
+
+    pub fn judgment_queries(&self) -> Vec<JudgmentQuery> {
+        serde_json::from_value(self.queries.clone()).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example=json!({
+    "id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "dataset_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "name": "Dog breed queries",
+    "queries": [
+        {"query": "loyal dog breeds", "judgments": [{"tracking_id": "dogs-are-loyal", "grade": 3.0}]},
+    ],
+    "created_at": "2021-01-01 00:00:00.000",
+    "updated_at": "2021-01-01 00:00:00.000",
+}))]
+pub struct JudgmentListDTO {
+    pub id: uuid::Uuid,
+    pub dataset_id: uuid::Uuid,
+    pub name: String,
+    pub queries: Vec<JudgmentQuery>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl From<JudgmentList> for JudgmentListDTO {
+    fn from(judgment_list: JudgmentList) -> Self {
+        JudgmentListDTO {
+            queries: judgment_list.judgment_queries(),
+            id: judgment_list.id,
+            dataset_id: judgment_list.dataset_id,
+            name: judgment_list.name,
+            created_at: judgment_list.created_at,
+            updated_at: judgment_list.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Row, ToSchema)]
+pub struct SearchEvaluationClickhouse {
+    #[serde(with = "clickhouse::serde::uuid")]
+    pub id: uuid::Uuid,
+    #[serde(with = "clickhouse::serde::uuid")]
+    pub dataset_id: uuid::Uuid,
+    #[serde(with = "clickhouse::serde::uuid")]
+    pub judgment_list_id: uuid::Uuid,
+    pub request_params: String,
+    pub k: u32,
+    pub query_count: u32,
+    pub ndcg: f64,
+    pub mrr: f64,
+    pub recall: f64,
+    pub avg_latency: f32,
+    pub p95_latency: f32,
+    #[serde(with = "clickhouse::serde::time::datetime")]
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Row, ToSchema)]
+pub struct SearchEvaluationQueryClickhouse {
+    #[serde(with = "clickhouse::serde::uuid")]
+    pub id: uuid::Uuid,
+    #[serde(with = "clickhouse::serde::uuid")]
+    pub evaluation_id: uuid::Uuid,
+    #[serde(with = "clickhouse::serde::uuid")]
+    pub dataset_id: uuid::Uuid,
+    pub query: String,
+    pub ndcg: f64,
+    pub reciprocal_rank: f64,
+    pub recall: f64,
+    pub latency: f32,
+    pub results: Vec<String>,
+    #[serde(with = "clickhouse::serde::time::datetime")]
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example=json!({
+    "query": "loyal dog breeds",
+    "ndcg": 0.86,
+    "reciprocal_rank": 1.0,
+    "recall": 0.5,
+    "latency": 48.2,
+    "results": ["e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3"],
+}))]
+pub struct SearchEvaluationQueryResult {
+    pub query: String,
+    /// nDCG@k of the query.
+    pub ndcg: f64,
+    /// 1 divided by the rank of the first relevant result, or 0 if none of the first k results are relevant.
+    pub reciprocal_rank: f64,
+    /// Fraction of the relevant chunks found in the first k results.
+    pub recall: f64,
+    /// Search latency of the query in milliseconds.
+    pub latency: f32,
+    /// Ids of the first k results in order.
+    pub results: Vec<uuid::Uuid>,
+}
+
+impl From<SearchEvaluationQueryClickhouse> for SearchEvaluationQueryResult {
+    fn from(clickhouse_query: SearchEvaluationQueryClickhouse) -> Self {
+        SearchEvaluationQueryResult {
+            query: clickhouse_query.query,
+            ndcg: clickhouse_query.ndcg,
+            reciprocal_rank: clickhouse_query.reciprocal_rank,
+            recall: clickhouse_query.recall,
+            latency: clickhouse_query.latency,
+            results: clickhouse_query
+                .results
+                .iter()
+                .filter_map(|id| uuid::Uuid::parse_str(id).ok())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example=json!({
+    "id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "dataset_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "judgment_list_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "request_params": {"search_type": "hybrid", "query": ""},
+    "k": 10,
+    "query_count": 1,
+    "ndcg": 0.86,
+    "mrr": 1.0,
+    "recall": 0.5,
+    "avg_latency": 48.2,
+    "p95_latency": 48.2,
+    "created_at": "2021-01-01 00:00:00.000",
+}))]
+pub struct SearchEvaluation {
+    pub id: uuid::Uuid,
+    pub dataset_id: uuid::Uuid,
+    pub judgment_list_id: uuid::Uuid,
+    /// The search request template the queries were run with.
+    pub request_params: serde_json::Value,
+    pub k: u32,
+    pub query_count: u32,
+    /// Mean nDCG@k over the queries.
+    pub ndcg: f64,
+    /// Mean reciprocal rank over the queries.
+    pub mrr: f64,
+    /// Mean recall@k over the queries.
+    pub recall: f64,
+    /// Mean search latency in milliseconds.
+    pub avg_latency: f32,
+    /// 95th percentile search latency in milliseconds.
+    pub p95_latency: f32,
+    pub created_at: String,
+}
+
+impl From<SearchEvaluationClickhouse> for SearchEvaluation {
+    fn from(clickhouse_evaluation: SearchEvaluationClickhouse) -> Self {
+        SearchEvaluation {
+            id: uuid::Uuid::from_bytes(*clickhouse_evaluation.id.as_bytes()),
+            dataset_id: uuid::Uuid::from_bytes(*clickhouse_evaluation.dataset_id.as_bytes()),
+            judgment_list_id: uuid::Uuid::from_bytes(
+                *clickhouse_evaluation.judgment_list_id.as_bytes(),
+            ),
+            request_params: serde_json::from_str(&clickhouse_evaluation.request_params)
+                .unwrap_or_default(),
+            k: clickhouse_evaluation.k,
+            query_count: clickhouse_evaluation.query_count,
+            ndcg: clickhouse_evaluation.ndcg,
+            mrr: clickhouse_evaluation.mrr,
+            recall: clickhouse_evaluation.recall,
+            avg_latency: clickhouse_evaluation.avg_latency,
+            p95_latency: clickhouse_evaluation.p95_latency,
+            created_at: clickhouse_evaluation.created_at.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[serde(untagged)]
+/// A node of a regression tree. Split nodes send a chunk to `left` when its value for `feature` is below `threshold` and to `right` otherwise.
+pub enum RankTreeNode {
+    #[schema(title = "Leaf")]
+    Leaf { leaf: f64 },
+    #[schema(title = "Split")]
+    Split {
+        feature: String,
+        threshold: f64,
+        #[schema(value_type = Object)]
+        left: Box<RankTreeNode>,
+        #[schema(value_type = Object)]
+        right: Box<RankTreeNode>,
+    },
+}
+
+impl RankTreeNode {
+    pub fn predict(&self, features: &HashMap<String, f64>) -> f64 {
+        let mut node = self;
+        loop {
+            match node {
+                RankTreeNode::Leaf { leaf } => return *leaf,
+                RankTreeNode::Split {
+                    feature,
+                    threshold,
+                    left,
+                    right,
+                } => {
+                    node = if features.get(feature).copied().unwrap_or(0.0) < *threshold {
+                        left
+                    } else {
+                        right
+                    };
+                }
+            }
+        }
+    }
+
+    pub fn features(&self) -> Vec<&String> {
+        match self {
+            RankTreeNode::Leaf { .. } => vec![],
+            RankTreeNode::Split {
+                feature,
+                left,
+                right,
+                ..
+            } => std::iter::once(feature)
+                .chain(left.features())
+                .chain(right.features())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+/// The function which combines the features of a chunk into its final score.
+pub enum RankScorer {
+    /// The score is `bias` plus the sum of each feature multiplied by its weight.
+    #[schema(title = "Linear")]
+    Linear {
+        weights: HashMap<String, f64>,
+        bias: Option<f64>,
+    },
+    /// The score is `base_score` plus the sum of the leaf values of the trees, each multiplied by `learning_rate`.
+    #[schema(title = "GradientBoosted")]
+    GradientBoosted {
+        trees: Vec<RankTreeNode>,
+        base_score: Option<f64>,
+        learning_rate: Option<f64>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example=json!({
+    "scorer": {
+        "type": "linear",
+        "weights": {
+            "semantic_score": 0.6,
+            "splade_score": 0.2,
+            "bm25_score": 0.1,
+            "recency": 0.2,
+            "ctr": 0.5,
+            "tag:featured": 0.1,
+        },
+        "bias": 0.0,
+    },
+    "recency_half_life_days": 30.0,
+    "click_priors": {"e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3": 0.12},
+}))]
+/// A learning-to-rank model which rescores the chunks of a search. The features available to the scorer are `score` (the retrieval score), `semantic_score`, `splade_score`, `bm25_score`, `recency`, `num_value`, `weight`, `ctr` and `tag:<tag>` for each tag of the chunk. Features which are not known for a chunk are 0.
+pub struct RankModel {
+    pub scorer: RankScorer,
+    /// Age in days at which the `recency` feature of a chunk's time_stamp decays to 0.5. Chunks without a time_stamp have a recency of 0. Defaults to 30.
+    pub recency_half_life_days: Option<f64>,
+    /// Click-through priors keyed by chunk id, used as the `ctr` feature. These are typically computed offline from the CTR analytics of the dataset.
+    pub click_priors: Option<HashMap<uuid::Uuid, f64>>,
+}
+
+pub const RANK_MODEL_FEATURES: [&str; 9] = [
+    "score",
+    "semantic_score",
+    "splade_score",
+    "bm25_score",
+    "recency",
+    "num_value",
+    "weight",
+    "ctr",
+    "tag:",
+];
+
+impl RankModel {
+    pub fn score(&self, features: &HashMap<String, f64>) -> f64 {
+        match &self.scorer {
+            RankScorer::Linear { weights, bias } => {
+                bias.unwrap_or(0.0)
+                    + weights
+                        .iter()
+                        .map(|(feature, weight)| {
+                            features.get(feature).copied().unwrap_or(0.0) * weight
+                        })
+                        .sum::<f64>()
+            }
+            RankScorer::GradientBoosted {
+                trees,
+                base_score,
+                learning_rate,
+            } => {
+                base_score.unwrap_or(0.0)
+                    + learning_rate.unwrap_or(1.0)
+                        * trees.iter().map(|tree| tree.predict(features)).sum::<f64>()
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ServiceError> {
+        let features = match &self.scorer {
+            RankScorer::Linear { weights, .. } => weights.keys().collect::<Vec<&String>>(),
+            RankScorer::GradientBoosted { trees, .. } => {
+                trees.iter().flat_map(|tree| tree.features()).collect()
+            }
+        };
+
+        if let Some(feature) = features.into_iter().find(|feature| {
+            !RANK_MODEL_FEATURES.iter().any(|known| {
+                *known == feature.as_str() || (known.ends_with(':') && feature.starts_with(known))
+            })
+        }) {
+            return Err(ServiceError::BadRequest(format!(
+                "Unknown rank model feature: {}",
+                feature
+            )));
+        }
+
+        if self
+            .recency_half_life_days
+            .is_some_and(|half_life| half_life <= 0.0)
+        {
+            return Err(ServiceError::BadRequest(
+                "recency_half_life_days must be greater than 0".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Selectable, Clone)]
+#[diesel(table_name = rank_models)]
+pub struct DatasetRankModel {
+    pub id: uuid::Uuid,
+    pub dataset_id: uuid::Uuid,
+    pub model: serde_json::Value,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl DatasetRankModel {
+    pub fn from_details(dataset_id: uuid::Uuid, model: &RankModel) -> Self {
+        DatasetRankModel {
+            id: uuid::Uuid::new_v4(),
+            dataset_id,
+            model: serde_json::to_value(model).unwrap_or_default(),
+            created_at: chrono::Utc::now().naive_local(),
+            updated_at: chrono::Utc::now().naive_local(),
+        }
+    }
+
+    pub fn rank_model(&self) -> Result<RankModel, ServiceError> {
+        serde_json::from_value(self.model.clone())
+            .map_err(|_| ServiceError::InternalServerError("Invalid stored rank model".to_string()))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Selectable, Clone)]
+#[diesel(table_name = synonym_sets)]
+pub struct SynonymSet {
+    pub id: uuid::Uuid,
+    pub dataset_id: uuid::Uuid,
+    pub root: Option<String>,
+    pub synonyms: Vec<Option<String>>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl SynonymSet {
+    pub fn from_details(
+        dataset_id: uuid::Uuid,
+        root: Option<String>,
+        synonyms: Vec<String>,
+    ) -> Self {
+        SynonymSet {
+            id: uuid::Uuid::new_v4(),
+            dataset_id,
+            root,
+            synonyms: synonyms.into_iter().map(Some).collect(),
+            created_at: chrono::Utc::now().naive_local(),
+            updated_at: chrono::Utc::now().naive_local(),
+        }
+    }
+
+    /// Terms whose occurrence in a query expands it. One way sets are only triggered by their root, other sets by every synonym.
+    pub fn triggers(&self) -> Vec<String> {
+        match &self.root {
+            Some(root) => vec![root.clone()],
+            None => self.synonyms.iter().flatten().cloned().collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example=json!({
+    "id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "dataset_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "root": null,
+    "synonyms": ["laptop", "notebook"],
+    "created_at": "2021-01-01 00:00:00.000",
+    "updated_at": "2021-01-01 00:00:00.000",
+}))]
+pub struct SynonymSetDTO {
+    pub id: uuid::Uuid,
+    pub dataset_id: uuid::Uuid,
+    /// Root term of a one way set. Queries containing the root also match its synonyms, queries containing a synonym are not expanded. Not present for bidirectional sets, where every synonym matches all of the others.
+    pub root: Option<String>,
+    pub synonyms: Vec<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl From<SynonymSet> for SynonymSetDTO {
+    fn from(synonym_set: SynonymSet) -> Self {
+        SynonymSetDTO {
+            id: synonym_set.id,
+            dataset_id: synonym_set.dataset_id,
+            root: synonym_set.root,
+            synonyms: synonym_set.synonyms.into_iter().flatten().collect(),
+            created_at: synonym_set.created_at,
+            updated_at: synonym_set.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Selectable, Clone, PartialEq)]
+#[diesel(table_name = chunk_content_hashes)]
+pub struct ChunkContentHash {
+    pub chunk_id: uuid::Uuid,
+    pub dataset_id: uuid::Uuid,
+    pub content_hash: String,
+    pub simhash: i64,
+    pub simhash_bands: Vec<Option<i32>>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+impl ChunkContentHash {
+    pub fn from_details(chunk_id: uuid::Uuid, dataset_id: uuid::Uuid, content: &str) -> Self {
+        let simhash = get_simhash(content);
+
+        ChunkContentHash {
+            chunk_id,
+            dataset_id,
+            content_hash: get_content_hash(content),
+            simhash: simhash as i64,
+            simhash_bands: get_simhash_bands(simhash).into_iter().map(Some).collect(),
+            created_at: chrono::Utc::now().naive_local(),
+        }
+    }
+}
+
+/// Clicks on a chunk in the results of a query, summed up by ClickHouse.
+#[derive(Debug, Serialize, Deserialize, Clone, Row)]
+pub struct SearchChunkClicksClickhouse {
+    pub query: String,
+    #[serde(with = "clickhouse::serde::uuid")]
+    pub chunk_id: uuid::Uuid,
+    /// Sum of the position and rating weighted scores of the clicks.
+    pub score: f64,
+    pub clicks: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Queryable, Insertable, ValidGrouping)]
 #[diesel(table_name = dataset_group_counts)]
 pub struct DatasetGroupCount {
@@ -1960,6 +2609,119 @@ pub enum DistanceMetric {
     Dot,
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema, Display, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+/// Language used by the BM25 analyzer for stemming and built-in stop words. Chinese, Japanese and Korean are not stemmed and are segmented into character bigrams.
+pub enum Bm25Language {
+    #[display(fmt = "none")]
+    None,
+    #[display(fmt = "arabic")]
+    Arabic,
+    #[display(fmt = "danish")]
+    Danish,
+    #[display(fmt = "dutch")]
+    Dutch,
+    #[default]
+    #[display(fmt = "english")]
+    English,
+    #[display(fmt = "finnish")]
+    Finnish,
+    #[display(fmt = "french")]
+    French,
+    #[display(fmt = "german")]
+    German,
+    #[display(fmt = "greek")]
+    Greek,
+    #[display(fmt = "hungarian")]
+    Hungarian,
+    #[display(fmt = "italian")]
+    Italian,
+    #[display(fmt = "norwegian")]
+    Norwegian,
+    #[display(fmt = "portuguese")]
+    Portuguese,
+    #[display(fmt = "romanian")]
+    Romanian,
+    #[display(fmt = "russian")]
+    Russian,
+    #[display(fmt = "spanish")]
+    Spanish,
+    #[display(fmt = "swedish")]
+    Swedish,
+    #[display(fmt = "tamil")]
+    Tamil,
+    #[display(fmt = "turkish")]
+    Turkish,
+    #[display(fmt = "chinese")]
+    Chinese,
+    #[display(fmt = "japanese")]
+    Japanese,
+    #[display(fmt = "korean")]
+    Korean,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Display, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+/// How the BM25 analyzer splits words into terms. "word" keeps whole words, "ngram" indexes character n-grams of every word, and "cjk" indexes overlapping character bigrams of runs of Chinese, Japanese or Korean characters.
+pub enum Bm25Segmentation {
+    #[default]
+    #[display(fmt = "word")]
+    Word,
+    #[display(fmt = "ngram")]
+    Ngram,
+    #[display(fmt = "cjk")]
+    Cjk,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Display, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+/// What the ingestion worker does with a chunk whose content duplicates a chunk already in the dataset. "off" skips the check, "reject" drops the duplicate, "merge" drops the duplicate and adds the existing chunk to the duplicate's groups, and "flag" uploads the duplicate anyway. Every duplicate found emits a chunk_duplicate_detected event.
+pub enum DedupPolicy {
+    #[default]
+    #[display(fmt = "off")]
+    Off,
+    #[display(fmt = "reject")]
+    Reject,
+    #[display(fmt = "merge")]
+    Merge,
+    #[display(fmt = "flag")]
+    Flag,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+/// Analyzer settings used to tokenize text into BM25 terms. Derived from the BM25_* keys of the dataset configuration.
+pub struct Bm25AnalyzerOptions {
+    pub language: Bm25Language,
+    pub remove_stop_words: bool,
+    pub stop_words: Option<Vec<String>>,
+    pub segmentation: Bm25Segmentation,
+    pub ngram_min: usize,
+    pub ngram_max: usize,
+    pub ascii_folding: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+/// Model settings that produced a dataset's dense vectors. Derived from the EMBEDDING_* keys of the dataset configuration, changing any of them requires every chunk to be embedded again.
+pub struct EmbeddingModelOptions {
+    pub base_url: String,
+    pub model_name: String,
+    pub size: usize,
+}
+
+impl Default for Bm25AnalyzerOptions {
+    fn default() -> Self {
+        Bm25AnalyzerOptions {
+            language: Bm25Language::English,
+            remove_stop_words: false,
+            stop_words: None,
+            segmentation: Bm25Segmentation::Word,
+            ngram_min: 2,
+            ngram_max: 3,
+            ascii_folding: false,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 #[schema(example=json!({
     "LLM_BASE_URL": "https://api.openai.com/v1",
@@ -2006,6 +2768,13 @@ pub struct DatasetConfiguration {
     pub BM25_B: f32,
     pub BM25_K: f32,
     pub BM25_AVG_LEN: f32,
+    pub BM25_LANGUAGE: Bm25Language,
+    pub BM25_REMOVE_STOP_WORDS: bool,
+    pub BM25_STOP_WORDS: Option<Vec<String>>,
+    pub BM25_SEGMENTATION: Bm25Segmentation,
+    pub BM25_NGRAM_MIN: usize,
+    pub BM25_NGRAM_MAX: usize,
+    pub BM25_ASCII_FOLDING: bool,
     pub FULLTEXT_ENABLED: bool,
     pub SEMANTIC_ENABLED: bool,
     pub EMBEDDING_QUERY_PREFIX: String,
@@ -2019,6 +2788,8 @@ pub struct DatasetConfiguration {
     pub LOCKED: bool,
     pub SYSTEM_PROMPT: String,
     pub MAX_LIMIT: u64,
+    pub DEDUP_POLICY: DedupPolicy,
+    pub DEDUP_NEAR_DUPLICATE_DISTANCE: Option<u32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
@@ -2083,6 +2854,20 @@ pub struct DatasetConfigurationDTO {
     pub BM25_K: Option<f32>,
     /// The average length of the chunks in the index for BM25
     pub BM25_AVG_LEN: Option<f32>,
+    /// The language used to stem BM25 terms and pick built-in stop words. Changing the analyzer re-tokenizes the existing chunks of the dataset.
+    pub BM25_LANGUAGE: Option<Bm25Language>,
+    /// Whether to remove the built-in stop words of BM25_LANGUAGE from BM25 terms
+    pub BM25_REMOVE_STOP_WORDS: Option<bool>,
+    /// Additional stop words to remove from BM25 terms
+    pub BM25_STOP_WORDS: Option<Vec<String>>,
+    /// How words are split into BM25 terms. Can be "word", "ngram", or "cjk"
+    pub BM25_SEGMENTATION: Option<Bm25Segmentation>,
+    /// The minimum n-gram length when BM25_SEGMENTATION is "ngram"
+    pub BM25_NGRAM_MIN: Option<usize>,
+    /// The maximum n-gram length when BM25_SEGMENTATION is "ngram"
+    pub BM25_NGRAM_MAX: Option<usize>,
+    /// Whether to fold accented characters to their ASCII equivalents in BM25 terms
+    pub BM25_ASCII_FOLDING: Option<bool>,
     /// Whether to use fulltext search
     pub FULLTEXT_ENABLED: Option<bool>,
     /// Whether to use semantic search
@@ -2109,6 +2894,10 @@ pub struct DatasetConfigurationDTO {
     pub SYSTEM_PROMPT: Option<String>,
     /// The maximum limit for the number of chunks for counting
     pub MAX_LIMIT: Option<u64>,
+    /// What to do with chunks whose content duplicates a chunk already in the dataset. Can be "off", "reject", "merge", or "flag". Chunks are only compared against chunks uploaded while a policy was set
+    pub DEDUP_POLICY: Option<DedupPolicy>,
+    /// The maximum number of differing SimHash bits, at most 3, for two chunks to be considered near duplicates. Only exact duplicates are detected if not set
+    pub DEDUP_NEAR_DUPLICATE_DISTANCE: Option<u32>,
 }
 
 impl From<DatasetConfigurationDTO> for DatasetConfiguration {
@@ -2129,6 +2918,13 @@ impl From<DatasetConfigurationDTO> for DatasetConfiguration {
             BM25_B: dto.BM25_B.unwrap_or(0.75),
             BM25_K: dto.BM25_K.unwrap_or(0.75),
             BM25_AVG_LEN: dto.BM25_AVG_LEN.unwrap_or(256.0),
+            BM25_LANGUAGE: dto.BM25_LANGUAGE.unwrap_or_default(),
+            BM25_REMOVE_STOP_WORDS: dto.BM25_REMOVE_STOP_WORDS.unwrap_or(false),
+            BM25_STOP_WORDS: dto.BM25_STOP_WORDS,
+            BM25_SEGMENTATION: dto.BM25_SEGMENTATION.unwrap_or_default(),
+            BM25_NGRAM_MIN: dto.BM25_NGRAM_MIN.unwrap_or(2),
+            BM25_NGRAM_MAX: dto.BM25_NGRAM_MAX.unwrap_or(3),
+            BM25_ASCII_FOLDING: dto.BM25_ASCII_FOLDING.unwrap_or(false),
             FULLTEXT_ENABLED: dto.FULLTEXT_ENABLED.unwrap_or(true),
             SEMANTIC_ENABLED: dto.SEMANTIC_ENABLED.unwrap_or(true),
             EMBEDDING_QUERY_PREFIX: dto.EMBEDDING_QUERY_PREFIX.unwrap_or("".to_string()),
@@ -2142,6 +2938,8 @@ impl From<DatasetConfigurationDTO> for DatasetConfiguration {
             LOCKED: dto.LOCKED.unwrap_or(false),
             SYSTEM_PROMPT: dto.SYSTEM_PROMPT.unwrap_or("You are a helpful assistant".to_string()),
             MAX_LIMIT: dto.MAX_LIMIT.unwrap_or(10000),
+            DEDUP_POLICY: dto.DEDUP_POLICY.unwrap_or_default(),
+            DEDUP_NEAR_DUPLICATE_DISTANCE: dto.DEDUP_NEAR_DUPLICATE_DISTANCE,
         }
     }
 }
@@ -2164,6 +2962,13 @@ impl From<DatasetConfiguration> for DatasetConfigurationDTO {
             BM25_B: Some(config.BM25_B),
             BM25_K: Some(config.BM25_K),
             BM25_AVG_LEN: Some(config.BM25_AVG_LEN),
+            BM25_LANGUAGE: Some(config.BM25_LANGUAGE),
+            BM25_REMOVE_STOP_WORDS: Some(config.BM25_REMOVE_STOP_WORDS),
+            BM25_STOP_WORDS: config.BM25_STOP_WORDS,
+            BM25_SEGMENTATION: Some(config.BM25_SEGMENTATION),
+            BM25_NGRAM_MIN: Some(config.BM25_NGRAM_MIN),
+            BM25_NGRAM_MAX: Some(config.BM25_NGRAM_MAX),
+            BM25_ASCII_FOLDING: Some(config.BM25_ASCII_FOLDING),
             FULLTEXT_ENABLED: Some(config.FULLTEXT_ENABLED),
             SEMANTIC_ENABLED: Some(config.SEMANTIC_ENABLED),
             EMBEDDING_QUERY_PREFIX: Some(config.EMBEDDING_QUERY_PREFIX),
@@ -2177,6 +2982,8 @@ impl From<DatasetConfiguration> for DatasetConfigurationDTO {
             LOCKED: Some(config.LOCKED),
             SYSTEM_PROMPT: Some(config.SYSTEM_PROMPT),
             MAX_LIMIT: Some(config.MAX_LIMIT),
+            DEDUP_POLICY: Some(config.DEDUP_POLICY),
+            DEDUP_NEAR_DUPLICATE_DISTANCE: config.DEDUP_NEAR_DUPLICATE_DISTANCE,
         }
     }
 }
@@ -2199,6 +3006,13 @@ impl Default for DatasetConfiguration {
             BM25_B: 0.75,
             BM25_K: 0.75,
             BM25_AVG_LEN: 256.0,
+            BM25_LANGUAGE: Bm25Language::English,
+            BM25_REMOVE_STOP_WORDS: false,
+            BM25_STOP_WORDS: None,
+            BM25_SEGMENTATION: Bm25Segmentation::Word,
+            BM25_NGRAM_MIN: 2,
+            BM25_NGRAM_MAX: 3,
+            BM25_ASCII_FOLDING: false,
             FULLTEXT_ENABLED: true,
             SEMANTIC_ENABLED: true,
             EMBEDDING_QUERY_PREFIX: "".to_string(),
@@ -2212,6 +3026,8 @@ impl Default for DatasetConfiguration {
             MAX_TOKENS: None,
             SYSTEM_PROMPT: "You are a helpful assistant".to_string(),
             MAX_LIMIT: 10000,
+            DEDUP_POLICY: DedupPolicy::Off,
+            DEDUP_NEAR_DUPLICATE_DISTANCE: None,
         }
     }
 }
@@ -2382,6 +3198,37 @@ impl DatasetConfiguration {
                 .get("BM25_AVG_LEN")
                 .and_then(|v| v.as_f64().map(|f| f as f32))
                 .unwrap_or(256f32),
+            BM25_LANGUAGE: configuration
+                .get("BM25_LANGUAGE")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default(),
+            BM25_REMOVE_STOP_WORDS: configuration
+                .get("BM25_REMOVE_STOP_WORDS")
+                .unwrap_or(&json!(false))
+                .as_bool()
+                .unwrap_or(false),
+            BM25_STOP_WORDS: configuration
+                .get("BM25_STOP_WORDS")
+                .and_then(|v| serde_json::from_value(v.clone()).ok()),
+            BM25_SEGMENTATION: configuration
+                .get("BM25_SEGMENTATION")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default(),
+            BM25_NGRAM_MIN: configuration
+                .get("BM25_NGRAM_MIN")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize)
+                .unwrap_or(2),
+            BM25_NGRAM_MAX: configuration
+                .get("BM25_NGRAM_MAX")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize)
+                .unwrap_or(3),
+            BM25_ASCII_FOLDING: configuration
+                .get("BM25_ASCII_FOLDING")
+                .unwrap_or(&json!(false))
+                .as_bool()
+                .unwrap_or(false),
             EMBEDDING_QUERY_PREFIX: configuration
                 .get("EMBEDDING_QUERY_PREFIX")
                 .unwrap_or(&{
@@ -2453,6 +3300,14 @@ impl DatasetConfiguration {
             MAX_TOKENS: configuration
                 .get("MAX_TOKENS")
                 .and_then(|v| v.as_u64()),
+            DEDUP_POLICY: configuration
+                .get("DEDUP_POLICY")
+                .and_then(|v| serde_json::from_value(v.clone()).ok())
+                .unwrap_or_default(),
+            DEDUP_NEAR_DUPLICATE_DISTANCE: configuration
+                .get("DEDUP_NEAR_DUPLICATE_DISTANCE")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as u32),
         }
     }
 
@@ -2471,6 +3326,13 @@ impl DatasetConfiguration {
             "BM25_B": self.BM25_B,
             "BM25_K": self.BM25_K,
             "BM25_AVG_LEN": self.BM25_AVG_LEN,
+            "BM25_LANGUAGE": self.BM25_LANGUAGE,
+            "BM25_REMOVE_STOP_WORDS": self.BM25_REMOVE_STOP_WORDS,
+            "BM25_STOP_WORDS": self.BM25_STOP_WORDS,
+            "BM25_SEGMENTATION": self.BM25_SEGMENTATION,
+            "BM25_NGRAM_MIN": self.BM25_NGRAM_MIN,
+            "BM25_NGRAM_MAX": self.BM25_NGRAM_MAX,
+            "BM25_ASCII_FOLDING": self.BM25_ASCII_FOLDING,
             "FULLTEXT_ENABLED": self.FULLTEXT_ENABLED,
             "SEMANTIC_ENABLED": self.SEMANTIC_ENABLED,
             "EMBEDDING_QUERY_PREFIX": self.EMBEDDING_QUERY_PREFIX,
@@ -2484,8 +3346,46 @@ impl DatasetConfiguration {
             "SYSTEM_PROMPT": self.SYSTEM_PROMPT,
             "MAX_LIMIT": self.MAX_LIMIT,
             "MAX_TOKENS": self.MAX_TOKENS,
+            "DEDUP_POLICY": self.DEDUP_POLICY,
+            "DEDUP_NEAR_DUPLICATE_DISTANCE": self.DEDUP_NEAR_DUPLICATE_DISTANCE,
         })
     }
+
+    pub fn embedding_model_options(&self) -> EmbeddingModelOptions {
+        EmbeddingModelOptions {
+            base_url: self.EMBEDDING_BASE_URL.clone(),
+            model_name: self.EMBEDDING_MODEL_NAME.clone(),
+            size: self.EMBEDDING_SIZE,
+        }
+    }
+
+    pub fn with_embedding_model_options(self, options: EmbeddingModelOptions) -> Self {
+        DatasetConfiguration {
+            EMBEDDING_BASE_URL: options.base_url,
+            EMBEDDING_MODEL_NAME: options.model_name,
+            EMBEDDING_SIZE: options.size,
+            ..self
+        }
+    }
+
+    pub fn with_distance_metric(self, distance_metric: DistanceMetric) -> Self {
+        DatasetConfiguration {
+            DISTANCE_METRIC: distance_metric,
+            ..self
+        }
+    }
+
+    pub fn bm25_analyzer_options(&self) -> Bm25AnalyzerOptions {
+        Bm25AnalyzerOptions {
+            language: self.BM25_LANGUAGE,
+            remove_stop_words: self.BM25_REMOVE_STOP_WORDS,
+            stop_words: self.BM25_STOP_WORDS.clone(),
+            segmentation: self.BM25_SEGMENTATION,
+            ngram_min: self.BM25_NGRAM_MIN,
+            ngram_max: self.BM25_NGRAM_MAX,
+            ascii_folding: self.BM25_ASCII_FOLDING,
+        }
+    }
 }
 
 impl DatasetConfigurationDTO {
@@ -2544,6 +3444,28 @@ impl DatasetConfigurationDTO {
             BM25_AVG_LEN: self
                 .BM25_AVG_LEN
                 .unwrap_or(curr_dataset_config.BM25_AVG_LEN),
+            BM25_LANGUAGE: self
+                .BM25_LANGUAGE
+                .unwrap_or(curr_dataset_config.BM25_LANGUAGE),
+            BM25_REMOVE_STOP_WORDS: self
+                .BM25_REMOVE_STOP_WORDS
+                .unwrap_or(curr_dataset_config.BM25_REMOVE_STOP_WORDS),
+            BM25_STOP_WORDS: self
+                .BM25_STOP_WORDS
+                .clone()
+                .or(curr_dataset_config.BM25_STOP_WORDS),
+            BM25_SEGMENTATION: self
+                .BM25_SEGMENTATION
+                .unwrap_or(curr_dataset_config.BM25_SEGMENTATION),
+            BM25_NGRAM_MIN: self
+                .BM25_NGRAM_MIN
+                .unwrap_or(curr_dataset_config.BM25_NGRAM_MIN),
+            BM25_NGRAM_MAX: self
+                .BM25_NGRAM_MAX
+                .unwrap_or(curr_dataset_config.BM25_NGRAM_MAX),
+            BM25_ASCII_FOLDING: self
+                .BM25_ASCII_FOLDING
+                .unwrap_or(curr_dataset_config.BM25_ASCII_FOLDING),
             FULLTEXT_ENABLED: self
                 .FULLTEXT_ENABLED
                 .unwrap_or(curr_dataset_config.FULLTEXT_ENABLED),
@@ -2575,6 +3497,12 @@ impl DatasetConfigurationDTO {
                 .clone()
                 .unwrap_or(curr_dataset_config.SYSTEM_PROMPT),
             MAX_LIMIT: self.MAX_LIMIT.unwrap_or(curr_dataset_config.MAX_LIMIT),
+            DEDUP_POLICY: self
+                .DEDUP_POLICY
+                .unwrap_or(curr_dataset_config.DEDUP_POLICY),
+            DEDUP_NEAR_DUPLICATE_DISTANCE: self
+                .DEDUP_NEAR_DUPLICATE_DISTANCE
+                .or(curr_dataset_config.DEDUP_NEAR_DUPLICATE_DISTANCE),
         }
     }
 }
@@ -3284,11 +4212,138 @@ impl From<RetrievedPoint> for QdrantPayload {
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
-pub struct FileWorkerMessage {
-    pub file_id: uuid::Uuid,
+pub struct FileWorkerMessage {
+    pub file_id: uuid::Uuid,
+    pub dataset_id: uuid::Uuid,
+    pub upload_file_data: UploadFileReqPayload,
+    pub attempt_number: u8,
+    /// Chunks of a reprocessed file. They are only deleted once the file was parsed again and its new chunks were queued, so a failed reprocess keeps the old chunks.
+    #[serde(default)]
+    pub replaced_chunk_ids: Vec<uuid::Uuid>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum DatasetArchiveMessage {
+    Export {
+        archive_id: uuid::Uuid,
+        dataset_id: uuid::Uuid,
+        include_vectors: bool,
+        include_files: bool,
+    },
+    /// The job_id identifies the import's status, so importing an archive does not overwrite the status of its export.
+    Import {
+        job_id: uuid::Uuid,
+        archive_id: uuid::Uuid,
+        dataset_id: uuid::Uuid,
+    },
+    /// Copies a dataset into a new one without going through the bucket. The archive_id only identifies the job.
+    Clone {
+        archive_id: uuid::Uuid,
+        source_dataset_id: uuid::Uuid,
+        dataset_id: uuid::Uuid,
+    },
+}
+
+impl DatasetArchiveMessage {
+    pub fn archive_id(&self) -> uuid::Uuid {
+        match self {
+            DatasetArchiveMessage::Export { archive_id, .. } => *archive_id,
+            DatasetArchiveMessage::Import { archive_id, .. } => *archive_id,
+            DatasetArchiveMessage::Clone { archive_id, .. } => *archive_id,
+        }
+    }
+
+    /// Id the job's status is stored under. Exports and clones are identified by their archive_id.
+    pub fn job_id(&self) -> uuid::Uuid {
+        match self {
+            DatasetArchiveMessage::Import { job_id, .. } => *job_id,
+            _ => self.archive_id(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DatasetArchiveJobType {
+    Export,
+    Import,
+    Clone,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DatasetArchiveJobState {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[schema(example = json!({
+    "job_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "archive_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "job_type": "export",
+    "dataset_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "state": "completed",
+    "chunk_count": 1000,
+    "error": null,
+    "updated_at": "2021-01-01 00:00:00.000",
+}))]
+pub struct DatasetArchiveStatus {
+    /// Id of the job to poll with the get archive status route. Equal to the archive_id for exports and clones, imports get their own id.
+    pub job_id: uuid::Uuid,
+    /// Id of the archive. Exports write to it and imports read from it, clones only use it to identify the job.
+    pub archive_id: uuid::Uuid,
+    pub job_type: DatasetArchiveJobType,
+    /// The dataset being exported, or the new dataset being imported or cloned into.
+    pub dataset_id: uuid::Uuid,
+    pub state: DatasetArchiveJobState,
+    /// Number of chunks written to or read from the archive so far.
+    pub chunk_count: usize,
+    pub error: Option<String>,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+impl DatasetArchiveStatus {
+    pub fn from_details(
+        job_id: uuid::Uuid,
+        archive_id: uuid::Uuid,
+        job_type: DatasetArchiveJobType,
+        dataset_id: uuid::Uuid,
+        state: DatasetArchiveJobState,
+    ) -> Self {
+        DatasetArchiveStatus {
+            job_id,
+            archive_id,
+            job_type,
+            dataset_id,
+            state,
+            chunk_count: 0,
+            error: None,
+            updated_at: chrono::Utc::now().naive_local(),
+        }
+    }
+}
+
+/// Written as `manifest.json` at the root of every dataset archive once the export has finished.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct DatasetArchiveManifest {
+    pub version: u32,
+    pub archive_id: uuid::Uuid,
     pub dataset_id: uuid::Uuid,
-    pub upload_file_data: UploadFileReqPayload,
-    pub attempt_number: u8,
+    /// Organization of the exported dataset. Only its owners may import the archive. Not present in archives written before it was recorded.
+    #[serde(default)]
+    pub organization_id: Option<uuid::Uuid>,
+    pub dataset_name: String,
+    pub server_configuration: serde_json::Value,
+    pub embedding_size: usize,
+    pub include_vectors: bool,
+    pub include_files: bool,
+    pub group_count: usize,
+    pub file_count: usize,
+    pub chunk_count: usize,
+    pub created_at: chrono::NaiveDateTime,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
@@ -4749,6 +5804,8 @@ pub enum EventTypeRequest {
     ChunkActionFailed,
     #[display(fmt = "chunk_updated")]
     ChunkUpdated,
+    #[display(fmt = "chunk_duplicate_detected")]
+    ChunkDuplicateDetected,
     #[display(fmt = "bulk_chunks_deleted")]
     BulkChunksDeleted,
     #[display(fmt = "dataset_delete_failed")]
@@ -4761,11 +5818,56 @@ pub enum EventTypeRequest {
     GroupChunksUpdated,
     #[display(fmt = "group_chunks_action_failed")]
     GroupChunksActionFailed,
+    #[display(fmt = "dataset_clone_progress")]
+    DatasetCloneProgress,
+    #[display(fmt = "dataset_cloned")]
+    DatasetCloned,
+    #[display(fmt = "dataset_clone_failed")]
+    DatasetCloneFailed,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum MigrationMode {
-    BM25 { average_len: f32, k: f32, b: f32 },
+    BM25 {
+        average_len: f32,
+        k: f32,
+        b: f32,
+        #[serde(default)]
+        analyzer: Bm25AnalyzerOptions,
+        /// Set when the dataset's BM25 corpus statistics and vocabulary were cleared for this reindex, the reindexed points are counted back into them.
+        #[serde(default)]
+        corpus_stats_dataset_id: Option<uuid::Uuid>,
+    },
+    /// Part of an embedding model migration, embeds the points again with `model` under their migrated point ids.
+    EmbeddingModel {
+        dataset_id: uuid::Uuid,
+        migration_id: uuid::Uuid,
+        model: EmbeddingModelOptions,
+    },
+    /// Embeds the points again with `model`, replacing their dense vector in place.
+    Dense { model: EmbeddingModelOptions },
+    /// Recomputes the points' SPLADE sparse vectors.
+    Splade,
+    /// Part of a migration to a collection with another distance metric or quantization, copies the points unchanged under their migrated point ids.
+    MoveCollection {
+        dataset_id: uuid::Uuid,
+        migration_id: uuid::Uuid,
+    },
+    /// Rebuilds the points' payloads from their chunks in Postgres.
+    PayloadRefresh,
+}
+
+impl MigrationMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MigrationMode::BM25 { .. } => "bm25",
+            MigrationMode::EmbeddingModel { .. } => "embedding_model",
+            MigrationMode::Dense { .. } => "dense",
+            MigrationMode::Splade => "splade",
+            MigrationMode::MoveCollection { .. } => "move_collection",
+            MigrationMode::PayloadRefresh => "payload_refresh",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -4776,16 +5878,355 @@ pub struct MigratePointMessage {
     pub mode: MigrationMode,
 }
 
+/// Asks the reindex-worker to queue every point of a dataset as `MigratePointMessage`s, so requests don't have to scroll the whole collection themselves.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct MigrateDatasetMessage {
+    pub dataset_id: uuid::Uuid,
+    pub to_collection: String,
+    pub from_collection: String,
+    pub mode: MigrationMode,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingMigrationState {
+    Running,
+    Switching,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+#[schema(example = json!({
+    "migration_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "dataset_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "from_model": {"base_url": "https://api.openai.com/v1", "model_name": "text-embedding-3-small", "size": 1536},
+    "to_model": {"base_url": "https://api.openai.com/v1", "model_name": "text-embedding-3-large", "size": 3072},
+    "from_distance_metric": "cosine",
+    "to_distance_metric": "cosine",
+    "from_collection": "1536_vectors",
+    "to_collection": "3072_vectors",
+    "shadow_dataset_id": null,
+    "state": "running",
+    "total_points": 1000,
+    "migrated_points": 250,
+    "error": null,
+    "started_at": "2021-01-01 00:00:00.000",
+    "updated_at": "2021-01-01 00:00:00.000",
+}))]
+pub struct EmbeddingMigration {
+    pub migration_id: uuid::Uuid,
+    pub dataset_id: uuid::Uuid,
+    pub from_model: EmbeddingModelOptions,
+    pub to_model: EmbeddingModelOptions,
+    pub from_distance_metric: DistanceMetric,
+    /// Differs from `from_distance_metric` when the migration moves the dataset to a collection with another distance metric. The points are copied unchanged if the model stays the same.
+    pub to_distance_metric: DistanceMetric,
+    pub from_collection: String,
+    pub to_collection: String,
+    /// Set when both models share a collection. Migrated points carry it as their dataset_id until the switch so they stay out of the dataset's searches.
+    pub shadow_dataset_id: Option<uuid::Uuid>,
+    pub state: EmbeddingMigrationState,
+    /// Number of points queued to be embedded again, including points ingested while the migration runs.
+    pub total_points: u64,
+    pub migrated_points: u64,
+    pub error: Option<String>,
+    pub started_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+/// Shape of the curve a decay function follows as values move away from the origin.
+pub enum DecayFunctionType {
+    /// Normal decay, the score falls slowly near the origin, then quickly, then slowly again.
+    Gauss,
+    /// Exponential decay, the score falls quickly at first and then more and more slowly.
+    Exp,
+    /// Linear decay, the score falls at a constant rate and reaches 0 at `offset + scale / (1 - decay)` from the origin.
+    Linear,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+#[serde(untagged)]
+/// Origin of a decay function. Use a number for num_value and metadata keys, a location for location and a date string for time_stamp.
+pub enum DecayOrigin {
+    #[schema(title = "Number")]
+    Number(f64),
+    #[schema(title = "Location")]
+    Location(GeoInfo),
+    #[schema(title = "TimeStamp")]
+    TimeStamp(String),
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+#[schema(example = json!({
+    "field": "time_stamp",
+    "function": "gauss",
+    "scale": 7.0,
+    "offset": 1.0,
+    "decay": 0.5,
+    "weight": 0.5,
+}))]
+/// Decay functions lower the score of chunks the further the value of a field is from an origin. The decay of a chunk is 1.0 within `offset` of the origin and falls to `decay` at `offset + scale` from the origin. Chunks without a value for the field are not decayed.
+pub struct DecayFunction {
+    /// Field the distance from the origin is measured on. Can be "time_stamp", "num_value", "location" or "metadata.<key>" for any numeric key of the chunk metadata.
+    pub field: String,
+    /// Shape of the decay curve. Can be "gauss", "exp" or "linear".
+    pub function: DecayFunctionType,
+    /// Value at which chunks are not decayed. Defaults to now for time_stamp and is required for the other fields.
+    pub origin: Option<DecayOrigin>,
+    /// Distance from `origin + offset` at which the decay equals `decay`. Measured in days for time_stamp, kilometers for location and the field's own unit otherwise.
+    pub scale: f64,
+    /// Distance from the origin within which chunks are not decayed. Defaults to 0.
+    pub offset: Option<f64>,
+    /// Decay at `scale` from the origin, between 0 and 1 exclusive. Defaults to 0.5.
+    pub decay: Option<f64>,
+    /// How much of the score the decay is blended into, between 0 and 1. The score is multiplied by `1 - weight + weight * decay`. Defaults to 1.
+    pub weight: Option<f64>,
+}
+
+impl DecayFunction {
+    /// Decay of a value at `distance` from the origin.
+    pub fn decay_at(&self, distance: f64) -> f64 {
+        let distance = (distance.abs() - self.offset.unwrap_or(0.0)).max(0.0);
+        let decay = self.decay.unwrap_or(0.5);
+
+        match self.function {
+            DecayFunctionType::Gauss => decay.powf(distance.powi(2) / self.scale.powi(2)),
+            DecayFunctionType::Exp => decay.powf(distance / self.scale),
+            DecayFunctionType::Linear => (1.0 - distance * (1.0 - decay) / self.scale).max(0.0),
+        }
+    }
+
+    /// Distance of a chunk's value for the field from the origin, or None when the chunk has no value for the field.
+    pub fn distance(
+        &self,
+        metadata: &ChunkMetadata,
+        now: NaiveDateTime,
+    ) -> Result<Option<f64>, ServiceError> {
+        let number_origin = || match self.origin {
+            Some(DecayOrigin::Number(origin)) => Ok(origin),
+            _ => Err(ServiceError::BadRequest(format!(
+                "Decay function on {} requires a numeric origin",
+                self.field
+            ))),
+        };
+
+        match self.field.as_str() {
+            "time_stamp" => {
+                let origin = match &self.origin {
+                    Some(DecayOrigin::TimeStamp(origin)) => origin
+                        .parse::<DateTimeUtc>()
+                        .map_err(|_| {
+                            ServiceError::BadRequest("Invalid decay origin timestamp".to_string())
+                        })?
+                        .0
+                        .naive_utc(),
+                    None => now,
+                    _ => {
+                        return Err(ServiceError::BadRequest(
+                            "Decay function on time_stamp requires a timestamp origin".to_string(),
+                        ))
+                    }
+                };
+
+                Ok(metadata
+                    .time_stamp
+                    .map(|time_stamp| (time_stamp - origin).num_seconds() as f64 / 86400.0))
+            }
+            "num_value" => {
+                let origin = number_origin()?;
+                Ok(metadata.num_value.map(|num_value| num_value - origin))
+            }
+            "location" => match self.origin {
+                Some(DecayOrigin::Location(origin)) => Ok(metadata
+                    .location
+                    .map(|location| origin.haversine_distance_to(&location))),
+                _ => Err(ServiceError::BadRequest(
+                    "Decay function on location requires a location origin".to_string(),
+                )),
+            },
+            field => match field.strip_prefix("metadata.") {
+                Some(key) => {
+                    let origin = number_origin()?;
+                    Ok(metadata
+                        .metadata
+                        .as_ref()
+                        .and_then(|metadata| metadata.get(key))
+                        .and_then(|value| match value {
+                            serde_json::Value::String(value) => value.parse::<f64>().ok(),
+                            value => value.as_f64(),
+                        })
+                        .map(|value| value - origin))
+                }
+                None => Err(ServiceError::BadRequest(format!(
+                    "Unsupported decay function field: {}",
+                    field
+                ))),
+            },
+        }
+    }
+
+    /// Checks the origin, scale and decay of the function, a chunk without any values is never decayed so only the configuration can fail.
+    pub fn validate(&self) -> Result<(), ServiceError> {
+        self.score_multiplier(&ChunkMetadata::default(), chrono::Utc::now().naive_utc())
+            .map(|_| ())
+    }
+
+    /// Multiplier applied to the score of a chunk.
+    pub fn score_multiplier(
+        &self,
+        metadata: &ChunkMetadata,
+        now: NaiveDateTime,
+    ) -> Result<f64, ServiceError> {
+        if self.scale <= 0.0 {
+            return Err(ServiceError::BadRequest(
+                "Decay function scale must be greater than 0".to_string(),
+            ));
+        }
+        if self.decay.is_some_and(|decay| decay <= 0.0 || decay >= 1.0) {
+            return Err(ServiceError::BadRequest(
+                "Decay function decay must be between 0 and 1".to_string(),
+            ));
+        }
+
+        let weight = self.weight.unwrap_or(1.0).clamp(0.0, 1.0);
+
+        Ok(match self.distance(metadata, now)? {
+            Some(distance) => 1.0 - weight + weight * self.decay_at(distance),
+            None => 1.0,
+        })
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
 pub struct SortByField {
-    /// Field to sort by. This has to be a numeric field with a Qdrant `Range` index on it. i.e. num_value and timestamp
+    /// Field to sort by. This can be time_stamp, num_value, weight, score or a `metadata.<key>` path where nested keys are separated by dots. When this is the first key of a search, it also picks the candidates from Qdrant and has to be a numeric field with a Qdrant `Range` index on it, i.e. num_value and time_stamp. Score and weight are not stored in Qdrant, so the candidates are the nearest chunks when they come first.
     pub field: String,
-    /// Direction to sort by
+    /// Direction to sort by. If not specified, this defaults to desc.
     pub direction: Option<SortOrder>,
+    /// Whether chunks which are missing the field come first or last regardless of the direction. If not specified, this defaults to last.
+    pub nulls: Option<NullsOrder>,
     /// How many results to pull in before the sort
     pub prefetch_amount: Option<u64>,
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+/// Placement of chunks which do not have a value for a sort key.
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+/// Value of a sort key for a chunk. Numbers sort before strings.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SortValue {
+    Number(f64),
+    Text(String),
+}
+
+impl SortValue {
+    pub fn compare_to(&self, other: &SortValue) -> std::cmp::Ordering {
+        match (self, other) {
+            (SortValue::Number(a), SortValue::Number(b)) => {
+                a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal)
+            }
+            (SortValue::Text(a), SortValue::Text(b)) => a.cmp(b),
+            (SortValue::Number(_), SortValue::Text(_)) => std::cmp::Ordering::Less,
+            (SortValue::Text(_), SortValue::Number(_)) => std::cmp::Ordering::Greater,
+        }
+    }
+}
+
+impl SortByField {
+    pub fn validate(&self) -> Result<(), ServiceError> {
+        match self.field.as_str() {
+            "time_stamp" | "num_value" | "weight" | "score" => Ok(()),
+            field => match field.strip_prefix("metadata.") {
+                Some(key) if !key.is_empty() && !key.split('.').any(|part| part.is_empty()) => {
+                    Ok(())
+                }
+                _ => Err(ServiceError::BadRequest(format!(
+                    "Unsupported sort_by field: {}",
+                    self.field
+                ))),
+            },
+        }
+    }
+
+    /// Whether Qdrant can order points by this field, score and weight only exist after the chunks are retrieved.
+    pub fn is_payload_field(&self) -> bool {
+        !matches!(self.field.as_str(), "score" | "weight")
+    }
+
+    pub fn sort_value(&self, metadata: &ChunkMetadata, score: Option<f64>) -> Option<SortValue> {
+        match self.field.as_str() {
+            "time_stamp" => metadata
+                .time_stamp
+                .map(|time_stamp| SortValue::Number(time_stamp.and_utc().timestamp() as f64)),
+            "num_value" => metadata.num_value.map(SortValue::Number),
+            "weight" => Some(SortValue::Number(if metadata.weight == 0.0 {
+                1.0
+            } else {
+                metadata.weight
+            })),
+            "score" => score.map(SortValue::Number),
+            field => {
+                let key = field.strip_prefix("metadata.")?;
+                let value = metadata
+                    .metadata
+                    .as_ref()?
+                    .pointer(&format!("/{}", key.replace('.', "/")))?;
+                match value {
+                    serde_json::Value::Number(value) => value.as_f64().map(SortValue::Number),
+                    serde_json::Value::String(value) => Some(SortValue::Text(value.clone())),
+                    serde_json::Value::Bool(value) => {
+                        Some(SortValue::Number(if *value { 1.0 } else { 0.0 }))
+                    }
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    pub fn compare(&self, a: &Option<SortValue>, b: &Option<SortValue>) -> std::cmp::Ordering {
+        let nulls_first = self.nulls == Some(NullsOrder::First);
+        match (a, b) {
+            (Some(a), Some(b)) => {
+                if self.direction == Some(SortOrder::Asc) {
+                    a.compare_to(b)
+                } else {
+                    b.compare_to(a)
+                }
+            }
+            (None, None) => std::cmp::Ordering::Equal,
+            (None, Some(_)) if nulls_first => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (Some(_), None) if nulls_first => std::cmp::Ordering::Greater,
+            (Some(_), None) => std::cmp::Ordering::Less,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
+#[serde(untagged)]
+/// A single sort key or a list of sort keys. Later keys break the ties of earlier ones.
+pub enum SortByFields {
+    Field(SortByField),
+    Fields(Vec<SortByField>),
+}
+
+impl SortByFields {
+    pub fn into_fields(self) -> Vec<SortByField> {
+        match self {
+            SortByFields::Field(field) => vec![field],
+            SortByFields::Fields(fields) => fields,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
 pub struct SortBySearchType {
     /// Search Method to get candidates from
@@ -4798,12 +6239,24 @@ pub struct SortBySearchType {
 
 #[derive(Debug, Serialize, Deserialize, ToSchema, Clone)]
 #[serde(untagged)]
-/// Sort by lets you specify a method to sort the results by. If not specified, this defaults to the score of the chunks. If specified, this can be any key in the chunk metadata. This key must be a numeric value within the payload.
+/// Sort by lets you specify a method to sort the results by. If not specified, this defaults to the score of the chunks. If specified, this can be a field, a list of fields where later fields break the ties of earlier ones, or a search type to rerank the candidates with.
 pub enum QdrantSortBy {
     Field(SortByField),
+    Fields(Vec<SortByField>),
     SearchType(SortBySearchType),
 }
 
+impl QdrantSortBy {
+    /// Splits the sort by into the sort keys and the search type used to rerank the candidates.
+    pub fn into_parts(self) -> (Vec<SortByField>, Option<SortBySearchType>) {
+        match self {
+            QdrantSortBy::Field(field) => (vec![field], None),
+            QdrantSortBy::Fields(fields) => (fields, None),
+            QdrantSortBy::SearchType(search_type) => (vec![], Some(search_type)),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum ReRankOptions {
@@ -4824,7 +6277,7 @@ pub enum CTRType {
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Default)]
 /// Sort Options lets you specify different methods to rerank the chunks in the result set. If not specified, this defaults to the score of the chunks.
 pub struct SortOptions {
-    /// Sort by lets you specify a method to sort the results by. If not specified, this defaults to the score of the chunks. If specified, this can be any key in the chunk metadata. This key must be a numeric value within the payload.
+    /// Sort by lets you specify a method to sort the results by. If not specified, this defaults to the score of the chunks. If specified, this can be a field or a list of fields to sort by, each with its own direction and null ordering, or a search type to rerank the candidates with. Chunks which tie on every field keep their relevance order.
     pub sort_by: Option<QdrantSortBy>,
     /// Location lets you rank your results by distance from a location. If not specified, this has no effect. Bias allows you to determine how much of an effect the location of chunks will have on the search results. If not specified, this defaults to 0.0. We recommend setting this to 1.0 for a gentle reranking of the results, >3.0 for a strong reranking of the results.
     pub location_bias: Option<GeoInfoWithBias>,
@@ -4832,6 +6285,326 @@ pub struct SortOptions {
     pub use_weights: Option<bool>,
     /// Tag weights is a JSON object which can be used to boost the ranking of chunks with certain tags. This is useful for when you want to be able to bias towards chunks with a certain tag on the fly. The keys are the tag names and the values are the weights.
     pub tag_weights: Option<HashMap<String, f32>>,
+    /// Decay functions lower the score of chunks the further their time_stamp, num_value, location or a numeric metadata key is from an origin. This allows soft freshness or price boosting instead of a hard sort. The multipliers of all decay functions are applied to the score. If not specified, this has no effect.
+    pub decay_functions: Option<Vec<DecayFunction>>,
+    /// Set use_rank_model to true to rescore the chunks with the rank model uploaded for the dataset before the other sort options are applied. The model replaces use_weights, it sees the chunk weight as a feature. Only applies to chunk searches, including searches within a group and autocomplete. If not specified, this defaults to false.
+    pub use_rank_model: Option<bool>,
+}
+
+impl SortOptions {
+    pub fn validate(&self) -> Result<(), ServiceError> {
+        self.decay_functions
+            .iter()
+            .flatten()
+            .try_for_each(DecayFunction::validate)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Default)]
+#[schema(example = json!({
+    "terms": [{ "field": "tag_set", "limit": 10 }, { "field": "metadata.brand" }],
+    "ranges": [{ "field": "num_value", "ranges": [{ "to": 50.0 }, { "from": 50.0, "to": 100.0 }, { "from": 100.0 }] }],
+    "geo_distances": [{ "origin": { "lat": 40.7, "lon": -74.0 }, "ranges": [{ "to": 10.0 }, { "from": 10.0 }] }],
+    "sample_size": 1000
+}))]
+/// Facets lets you count how the chunks matching the filters of a search are distributed over tags, metadata values, numeric or time ranges and distances. Range and distance buckets are exact counts over every chunk matching the filters. Term counts fall back to a sample of sample_size chunks matching the filters, as the values of a field are not known up front, and are flagged as approximate when more chunks match. At most 100 range and distance buckets can be requested.
+pub struct FacetOptions {
+    /// Count the most common values of tag_set or of a `metadata.<key>` path.
+    pub terms: Option<Vec<TermFacet>>,
+    /// Count the matching chunks within ranges of num_value or time_stamp.
+    pub ranges: Option<Vec<RangeFacet>>,
+    /// Count the matching chunks within distance ranges of a location.
+    pub geo_distances: Option<Vec<GeoDistanceFacet>>,
+    /// Maximum number of chunks matching the filters the term facets are counted over. Does not apply to range and distance buckets. If not specified, this defaults to 1000. It is capped by the MAX_LIMIT of the dataset.
+    pub sample_size: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct TermFacet {
+    /// Field to count the values of. This can be tag_set or a `metadata.<key>` path where nested keys are separated by dots. Chunks with a list of values count once for each value.
+    pub field: String,
+    /// Number of the most common values to return. If not specified, this defaults to 10.
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct RangeFacet {
+    /// Field to bucket by, either num_value or time_stamp.
+    pub field: String,
+    /// Buckets to count the matching chunks in. Buckets may overlap.
+    pub ranges: Vec<FacetRange>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+/// Bucket of a range facet. From is inclusive and to is exclusive, a missing bound is unbounded.
+pub struct FacetRange {
+    pub from: Option<FacetBound>,
+    pub to: Option<FacetBound>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[serde(untagged)]
+/// Bound of a range facet bucket. Use a number for num_value and a date string for time_stamp.
+pub enum FacetBound {
+    #[schema(title = "Number")]
+    Number(f64),
+    #[schema(title = "TimeStamp")]
+    TimeStamp(String),
+}
+
+impl FacetBound {
+    /// Returns the bound in the unit the field is stored with in Qdrant, seconds since the epoch for time_stamp.
+    pub fn value(&self, field: &str) -> Result<f64, ServiceError> {
+        match (field, self) {
+            ("num_value", FacetBound::Number(value)) => Ok(*value),
+            ("time_stamp", FacetBound::TimeStamp(value)) => Ok(value
+                .parse::<DateTimeUtc>()
+                .map_err(|_| {
+                    ServiceError::BadRequest(format!("Invalid facet range timestamp: {}", value))
+                })?
+                .0
+                .timestamp() as f64),
+            ("num_value", _) => Err(ServiceError::BadRequest(
+                "Range facet on num_value requires numeric bounds".to_string(),
+            )),
+            ("time_stamp", _) => Err(ServiceError::BadRequest(
+                "Range facet on time_stamp requires timestamp bounds".to_string(),
+            )),
+            (field, _) => Err(ServiceError::BadRequest(format!(
+                "Unsupported range facet field: {}",
+                field
+            ))),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct GeoDistanceFacet {
+    /// Location the distances of the chunks are measured from.
+    pub origin: GeoInfo,
+    /// Buckets to count the matching chunks in.
+    pub ranges: Vec<DistanceRange>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+/// Bucket of a geo distance facet in kilometers. From is inclusive and to is exclusive, a missing bound is unbounded.
+pub struct DistanceRange {
+    pub from: Option<f64>,
+    pub to: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Default)]
+/// Counts over the chunks matching the filters of a search.
+pub struct FacetResults {
+    /// Number of chunks matching the filters the term facets were counted over, 0 when no term facets were requested.
+    pub sample_size: u64,
+    /// True when more chunks match the filters than the term facets were counted over, so their counts are estimates from the first sample_size chunks. Range and distance buckets are always exact.
+    pub approximate: bool,
+    pub terms: Vec<TermFacetResult>,
+    pub ranges: Vec<RangeFacetResult>,
+    pub geo_distances: Vec<GeoDistanceFacetResult>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct TermFacetResult {
+    pub field: String,
+    /// Most common values, ordered by count descending.
+    pub counts: Vec<TermCount>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, PartialEq)]
+pub struct TermCount {
+    pub value: String,
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct RangeFacetResult {
+    pub field: String,
+    pub buckets: Vec<RangeBucket>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct RangeBucket {
+    pub from: Option<FacetBound>,
+    pub to: Option<FacetBound>,
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct GeoDistanceFacetResult {
+    pub origin: GeoInfo,
+    pub buckets: Vec<DistanceBucket>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct DistanceBucket {
+    pub from: Option<f64>,
+    pub to: Option<f64>,
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "field": "tracking_id",
+    "tracking_id_delimiter": "_",
+    "candidate_limit": 200
+}))]
+/// Collapse lets you return only the best scoring chunk for each value of a field, e.g. one chunk per file or per product. Each returned chunk has an inner_hits count of the chunks collapsed into it.
+pub struct CollapseOptions {
+    /// Field to collapse on. This can be tracking_id, link, group_ids or a `metadata.<key>` path where nested keys are separated by dots. A chunk in several groups is collapsed into the best scoring chunk sharing any of its groups. Chunks without a value for the field are never collapsed.
+    pub field: String,
+    /// Only used when field is tracking_id. The part of the tracking_id before the first occurrence of the delimiter is the key, e.g. `product-42` for `product-42_variant-3` with `_`. If not specified, the whole tracking_id is the key.
+    pub tracking_id_delimiter: Option<String>,
+    /// Number of top chunks which are collapsed before the page is cut from the results. It has to cover page * page_size distinct keys for the page to be full. If not specified, this defaults to 10 times page * page_size. It is capped by the MAX_LIMIT of the dataset.
+    pub candidate_limit: Option<u64>,
+}
+
+impl CollapseOptions {
+    pub fn validate(&self) -> Result<(), ServiceError> {
+        match self.field.as_str() {
+            "tracking_id" | "link" | "group_ids" => Ok(()),
+            field => match field.strip_prefix("metadata.") {
+                Some(key) if !key.is_empty() && !key.split('.').any(|part| part.is_empty()) => {
+                    Ok(())
+                }
+                _ => Err(ServiceError::BadRequest(format!(
+                    "Unsupported collapse field: {}",
+                    self.field
+                ))),
+            },
+        }
+    }
+
+    pub fn get_candidate_limit(&self, page: u64, page_size: u64, max_limit: u64) -> u64 {
+        self.candidate_limit
+            .unwrap_or(page * page_size * 10)
+            .max(page * page_size)
+            .min(max_limit)
+    }
+
+    /// Returns the collapse keys of a chunk. Group ids are only used when collapsing on group_ids.
+    pub fn keys(&self, metadata: &ChunkMetadata, group_ids: &[uuid::Uuid]) -> Vec<String> {
+        match self.field.as_str() {
+            "tracking_id" => metadata
+                .tracking_id
+                .as_ref()
+                .map(|tracking_id| match &self.tracking_id_delimiter {
+                    Some(delimiter) if !delimiter.is_empty() => tracking_id
+                        .split(delimiter.as_str())
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                    _ => tracking_id.clone(),
+                })
+                .into_iter()
+                .collect(),
+            "link" => metadata.link.clone().into_iter().collect(),
+            "group_ids" => group_ids
+                .iter()
+                .map(|group_id| group_id.to_string())
+                .collect(),
+            field => field
+                .strip_prefix("metadata.")
+                .and_then(|key| {
+                    metadata
+                        .metadata
+                        .as_ref()?
+                        .pointer(&format!("/{}", key.replace('.', "/")))
+                })
+                .and_then(|value| match value {
+                    serde_json::Value::String(value) => Some(value.clone()),
+                    serde_json::Value::Number(value) => Some(value.to_string()),
+                    serde_json::Value::Bool(value) => Some(value.to_string()),
+                    _ => None,
+                })
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// Position of the last result of a page, handed out as the opaque search_after cursor. Results are ordered best first with ties broken by ascending id, so the results after the cursor stay the same while the ranks of earlier results change.
+pub struct SearchCursor {
+    pub score: f64,
+    /// Point id of the last chunk or id of the last group.
+    pub id: uuid::Uuid,
+    /// Number of results up to and including the last one, the next page is retrieved from this offset.
+    pub offset: u64,
+}
+
+impl SearchCursor {
+    /// Cursors are signed with the server's SECRET_KEY so clients can not forge an offset or position.
+    fn mac(payload: &str) -> hmac::Hmac<sha2::Sha256> {
+        use hmac::Mac;
+
+        let mut mac = hmac::Hmac::<sha2::Sha256>::new_from_slice(
+            crate::operators::user_operator::SECRET_KEY.as_bytes(),
+        )
+        .expect("HMAC can take a key of any size");
+        mac.update(payload.as_bytes());
+        mac
+    }
+
+    pub fn encode(&self) -> String {
+        use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+        use hmac::Mac;
+
+        let payload = format!("{}:{:x}:{}", self.offset, self.score.to_bits(), self.id);
+        let signature = hex::encode(Self::mac(&payload).finalize().into_bytes());
+
+        URL_SAFE_NO_PAD.encode(format!("{}:{}", payload, signature))
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self, ServiceError> {
+        use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+        use hmac::Mac;
+
+        let invalid_cursor = || ServiceError::BadRequest("Invalid search_after cursor".to_string());
+
+        let decoded = URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|decoded| String::from_utf8(decoded).ok())
+            .ok_or_else(invalid_cursor)?;
+
+        let (payload, signature) = decoded.rsplit_once(':').ok_or_else(invalid_cursor)?;
+        let signature = hex::decode(signature).map_err(|_| invalid_cursor())?;
+        Self::mac(payload)
+            .verify_slice(&signature)
+            .map_err(|_| invalid_cursor())?;
+
+        match payload.split(':').collect_vec().as_slice() {
+            [offset, score, id] => Ok(SearchCursor {
+                offset: offset.parse().map_err(|_| invalid_cursor())?,
+                score: f64::from_bits(
+                    u64::from_str_radix(score, 16).map_err(|_| invalid_cursor())?,
+                ),
+                id: id.parse().map_err(|_| invalid_cursor())?,
+            }),
+            _ => Err(invalid_cursor()),
+        }
+    }
+
+    /// Whether a result comes after the cursor in results ordered by descending or ascending score.
+    pub fn precedes(&self, score: f64, id: uuid::Uuid, descending: bool) -> bool {
+        if score == self.score {
+            id > self.id
+        } else if descending {
+            score < self.score
+        } else {
+            score > self.score
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+/// How misspelled query terms are handled. "suggest" returns the corrected query as a did-you-mean suggestion while searching with the original query, "apply" searches with the corrected query.
+pub enum SpellingCorrection {
+    Suggest,
+    Apply,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Default)]
@@ -4853,6 +6626,40 @@ pub struct HighlightOptions {
     pub highlight_window: Option<u32>,
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+/// Fusion method determines how the semantic and full-text result lists of a hybrid search are merged into a single ranking.
+pub enum FusionMethod {
+    /// Re-score the union of both result lists with the cross encoder at the dataset's RERANKER_BASE_URL.
+    #[default]
+    CrossEncoder,
+    /// Reciprocal rank fusion. Each result scores `weight / (rrf_k + rank)` for every list it appears in. Does not require a reranker.
+    #[serde(rename = "rrf", alias = "reciprocal_rank_fusion")]
+    ReciprocalRankFusion,
+    /// Min-max normalize the scores of each list to the range 0.0 to 1.0, then take the weighted sum. Does not require a reranker.
+    #[serde(rename = "weighted", alias = "weighted_sum")]
+    WeightedSum,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Default)]
+/// Hybrid Options lets you specify how the semantic and full-text results of a hybrid search are combined. If not specified, this defaults to re-ranking with the cross encoder.
+pub struct HybridSearchOptions {
+    /// Fusion method to merge the semantic and full-text results with. Can be "cross_encoder", "rrf", or "weighted". With "rrf" and "weighted", score_threshold cuts the semantic and full-text results before they are fused and the fused scores are not thresholded. If not specified, this defaults to "cross_encoder".
+    pub fusion_method: Option<FusionMethod>,
+    /// Weight applied to the semantic result list when fusing with "rrf" or "weighted". If not specified, this defaults to 1.0.
+    pub semantic_weight: Option<f32>,
+    /// Weight applied to the full-text result list when fusing with "rrf" or "weighted". If not specified, this defaults to 1.0.
+    pub fulltext_weight: Option<f32>,
+    /// Rank constant k used by reciprocal rank fusion. Larger values flatten the difference between high and low ranks. If not specified, this defaults to 60.
+    pub rrf_k: Option<f32>,
+}
+
+impl HybridSearchOptions {
+    pub fn get_fusion_method(&self) -> FusionMethod {
+        self.fusion_method.clone().unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema, Clone, Default)]
 /// LLM options to use for the completion. If not specified, this defaults to the dataset's LLM options.
 pub struct LLMOptions {
@@ -5003,10 +6810,15 @@ impl<'de> Deserialize<'de> for SearchChunksReqPayload {
             sort_options: Option<SortOptions>,
             highlight_options: Option<HighlightOptions>,
             score_threshold: Option<f32>,
+            hybrid_options: Option<HybridSearchOptions>,
+            facets: Option<FacetOptions>,
+            collapse: Option<CollapseOptions>,
+            search_after: Option<String>,
             slim_chunks: Option<bool>,
             content_only: Option<bool>,
             use_quote_negated_terms: Option<bool>,
             remove_stop_words: Option<bool>,
+            spelling_correction: Option<SpellingCorrection>,
             #[serde(flatten)]
             other: std::collections::HashMap<String, serde_json::Value>,
         }
@@ -5032,10 +6844,15 @@ impl<'de> Deserialize<'de> for SearchChunksReqPayload {
             sort_options,
             highlight_options,
             score_threshold: helper.score_threshold,
+            hybrid_options: helper.hybrid_options,
+            facets: helper.facets,
+            collapse: helper.collapse,
+            search_after: helper.search_after,
             slim_chunks: helper.slim_chunks,
             content_only: helper.content_only,
             use_quote_negated_terms: helper.use_quote_negated_terms,
             remove_stop_words: helper.remove_stop_words,
+            spelling_correction: helper.spelling_correction,
         })
     }
 }
@@ -5164,6 +6981,8 @@ impl<'de> Deserialize<'de> for SearchOverGroupsReqPayload {
             group_size: Option<u32>,
             highlight_options: Option<HighlightOptions>,
             score_threshold: Option<f32>,
+            hybrid_options: Option<HybridSearchOptions>,
+            search_after: Option<String>,
             slim_chunks: Option<bool>,
             use_quote_negated_terms: Option<bool>,
             remove_stop_words: Option<bool>,
@@ -5190,6 +7009,8 @@ impl<'de> Deserialize<'de> for SearchOverGroupsReqPayload {
             highlight_options,
             group_size: helper.group_size,
             score_threshold: helper.score_threshold,
+            hybrid_options: helper.hybrid_options,
+            search_after: helper.search_after,
             slim_chunks: helper.slim_chunks,
             use_quote_negated_terms: helper.use_quote_negated_terms,
             remove_stop_words: helper.remove_stop_words,
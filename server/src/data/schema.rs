@@ -1,5 +1,16 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    chunk_content_hashes (chunk_id) {
+        chunk_id -> Uuid,
+        dataset_id -> Uuid,
+        content_hash -> Text,
+        simhash -> Int8,
+        simhash_bands -> Array<Nullable<Int4>>,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     chunk_group (id) {
         id -> Uuid,
@@ -108,6 +119,7 @@ diesel::table! {
         time_stamp -> Nullable<Timestamp>,
         dataset_id -> Uuid,
         tag_set -> Nullable<Array<Nullable<Text>>>,
+        pending -> Bool,
     }
 }
 
@@ -134,6 +146,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    judgment_lists (id) {
+        id -> Uuid,
+        dataset_id -> Uuid,
+        name -> Text,
+        queries -> Jsonb,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     messages (id) {
         id -> Uuid,
@@ -174,6 +197,16 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    rank_models (id) {
+        id -> Uuid,
+        dataset_id -> Uuid,
+        model -> Jsonb,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     stripe_invoices (id) {
         id -> Uuid,
@@ -214,6 +247,17 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    synonym_sets (id) {
+        id -> Uuid,
+        dataset_id -> Uuid,
+        root -> Nullable<Text>,
+        synonyms -> Array<Nullable<Text>>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     topics (id) {
         id -> Uuid,
@@ -263,6 +307,20 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    webhooks (id) {
+        id -> Uuid,
+        dataset_id -> Uuid,
+        url -> Text,
+        event_types -> Nullable<Array<Nullable<Text>>>,
+        secret -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::joinable!(chunk_content_hashes -> chunk_metadata (chunk_id));
+diesel::joinable!(chunk_content_hashes -> datasets (dataset_id));
 diesel::joinable!(chunk_group -> datasets (dataset_id));
 diesel::joinable!(chunk_group_bookmarks -> chunk_group (group_id));
 diesel::joinable!(chunk_group_bookmarks -> chunk_metadata (chunk_metadata_id));
@@ -276,18 +334,23 @@ diesel::joinable!(datasets -> organizations (organization_id));
 diesel::joinable!(files -> datasets (dataset_id));
 diesel::joinable!(groups_from_files -> chunk_group (group_id));
 diesel::joinable!(groups_from_files -> files (file_id));
+diesel::joinable!(judgment_lists -> datasets (dataset_id));
 diesel::joinable!(messages -> datasets (dataset_id));
 diesel::joinable!(messages -> topics (topic_id));
 diesel::joinable!(organization_usage_counts -> organizations (org_id));
 diesel::joinable!(stripe_invoices -> organizations (org_id));
+diesel::joinable!(rank_models -> datasets (dataset_id));
 diesel::joinable!(stripe_subscriptions -> organizations (organization_id));
 diesel::joinable!(stripe_subscriptions -> stripe_plans (plan_id));
+diesel::joinable!(synonym_sets -> datasets (dataset_id));
 diesel::joinable!(topics -> datasets (dataset_id));
 diesel::joinable!(user_api_key -> users (user_id));
 diesel::joinable!(user_organizations -> organizations (organization_id));
 diesel::joinable!(user_organizations -> users (user_id));
+diesel::joinable!(webhooks -> datasets (dataset_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    chunk_content_hashes,
     chunk_group,
     chunk_group_bookmarks,
     chunk_metadata,
@@ -300,14 +363,18 @@ diesel::allow_tables_to_appear_in_same_query!(
     files,
     groups_from_files,
     invitations,
+    judgment_lists,
     messages,
     organization_usage_counts,
     organizations,
+    rank_models,
     stripe_invoices,
     stripe_plans,
     stripe_subscriptions,
+    synonym_sets,
     topics,
     user_api_key,
     user_organizations,
     users,
+    webhooks,
 );
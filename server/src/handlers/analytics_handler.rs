@@ -2,12 +2,17 @@ use super::auth_handler::AdminOnly;
 use crate::{
     data::models::{
         CTRAnalytics, CTRAnalyticsResponse, CTRType, ClusterAnalytics, ClusterAnalyticsResponse,
-        DatasetAndOrgWithSubAndPlan, Pool, RAGAnalytics, RAGAnalyticsResponse,
-        RecommendationAnalytics, RecommendationAnalyticsResponse, SearchAnalytics,
+        DatasetAndOrgWithSubAndPlan, JudgmentList, JudgmentListDTO, JudgmentQuery, Pool,
+        RAGAnalytics, RAGAnalyticsResponse, RecommendationAnalytics,
+        RecommendationAnalyticsResponse, SearchAnalytics, SearchAnalyticsFilter,
         SearchAnalyticsResponse,
     },
     errors::ServiceError,
     operators::analytics_operator::*,
+    operators::evaluation_operator::{
+        build_implicit_judgment_queries, create_judgment_list_query, ImplicitJudgmentOptions,
+        MAX_JUDGMENT_QUERIES,
+    },
 };
 use actix_web::{web, HttpResponse};
 use serde::{Deserialize, Serialize};
@@ -553,3 +558,111 @@ pub async fn get_ctr_analytics(
 
     Ok(HttpResponse::Ok().json(response))
 }
+
+#[derive(Deserialize, Serialize, Clone, Debug, ToSchema)]
+#[schema(example = json!({
+    "name": "Judgments from last month's clicks",
+    "filter": {
+        "date_range": {"gte": "2024-07-01 00:00:00.000"},
+    },
+    "min_dwell_time": 5.0,
+    "rating_weight": 2.0,
+    "max_grade": 3,
+    "min_clicks": 2,
+    "max_queries": 100,
+}))]
+pub struct GenerateJudgmentListReqPayload {
+    /// If specified, the generated judgments are saved as a judgment list with this name which can be used with the evaluation endpoint.
+    pub name: Option<String>,
+    /// Filter for the searches whose clicks are used.
+    pub filter: Option<SearchAnalyticsFilter>,
+    /// Clicks whose CTR metadata reports a `dwell_time` below this many seconds are treated as bounces and ignored. Clicks without a `dwell_time` are always used. Defaults to 5.
+    pub min_dwell_time: Option<f64>,
+    /// Clicks on searches which were rated positively with the rate query route count this many times more, clicks on searches rated negatively this many times less. Defaults to 2.
+    pub rating_weight: Option<f64>,
+    /// Grade given to the most clicked chunk of each query, the other chunks are graded relative to it. Defaults to 3.
+    pub max_grade: Option<u32>,
+    /// Queries with fewer clicks than this are left out. Defaults to 1.
+    pub min_clicks: Option<u32>,
+    /// Maximum number of queries to generate judgments for, the most clicked queries are kept. Defaults to 100, max 1000.
+    pub max_queries: Option<u32>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, ToSchema)]
+pub struct GenerateJudgmentListResponse {
+    /// The saved judgment list, only present when a name was specified.
+    pub judgment_list: Option<JudgmentListDTO>,
+    pub queries: Vec<JudgmentQuery>,
+}
+
+/// Generate Judgment List
+///
+/// This route builds implicit relevance judgments from the clicks sent to the CTR route and the ratings sent to the rate query route. Searches are grouped by their lowercased query and clicked chunks are graded by how often and how far down the results they were clicked, ignoring bounces and weighting rated searches. The judgments are returned and can optionally be saved as a judgment list.
+#[utoipa::path(
+    post,
+    path = "/analytics/judgment_list",
+    context_path = "/api",
+    tag = "Analytics",
+    request_body(content = GenerateJudgmentListReqPayload, description = "JSON request payload to generate a judgment list", content_type = "application/json"),
+    responses(
+        (status = 200, description = "The generated judgments", body = GenerateJudgmentListResponse),
+
+        (status = 400, description = "Service error relating to generating the judgment list", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+pub async fn generate_judgment_list(
+    data: web::Json<GenerateJudgmentListReqPayload>,
+    _user: AdminOnly,
+    clickhouse_client: web::Data<clickhouse::Client>,
+    pool: web::Data<Pool>,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+) -> Result<HttpResponse, ServiceError> {
+    let data = data.into_inner();
+
+    let options = ImplicitJudgmentOptions {
+        min_dwell_time: data.min_dwell_time.unwrap_or(5.0),
+        rating_weight: data.rating_weight.unwrap_or(2.0),
+        max_grade: data.max_grade.unwrap_or(3) as f64,
+        min_clicks: data.min_clicks.unwrap_or(1) as usize,
+        max_queries: (data.max_queries.unwrap_or(100) as usize).min(MAX_JUDGMENT_QUERIES),
+    };
+
+    if options.rating_weight <= 0.0 || options.max_grade <= 0.0 {
+        return Err(ServiceError::BadRequest(
+            "rating_weight and max_grade must be greater than 0".to_string(),
+        ));
+    }
+
+    let clicks = get_search_clicks_query(
+        dataset_org_plan_sub.dataset.id,
+        data.filter,
+        &options,
+        clickhouse_client.get_ref(),
+    )
+    .await?;
+
+    let queries = build_implicit_judgment_queries(clicks, &options);
+
+    let judgment_list = match data.name {
+        Some(name) => Some(
+            create_judgment_list_query(
+                JudgmentList::from_details(dataset_org_plan_sub.dataset.id, name, queries.clone()),
+                pool,
+            )
+            .await?
+            .into(),
+        ),
+        None => None,
+    };
+
+    Ok(HttpResponse::Ok().json(GenerateJudgmentListResponse {
+        judgment_list,
+        queries,
+    }))
+}
@@ -1,11 +1,12 @@
 use super::auth_handler::{AdminOnly, LoggedUser};
 use crate::data::models::{
     ChatMessageProxy, ChunkMetadata, ChunkMetadataStringTagSet, ChunkMetadataWithScore,
-    ConditionType, CountSearchMethod, DatasetAndOrgWithSubAndPlan, DatasetConfiguration, GeoInfo,
-    HighlightOptions, IngestSpecificChunkMetadata, Pool, QueryTypes, RagQueryEventClickhouse,
+    CollapseOptions, ConditionType, CountSearchMethod, DatasetAndOrgWithSubAndPlan,
+    DatasetConfiguration, FacetOptions, FacetResults, GeoInfo, HighlightOptions,
+    HybridSearchOptions, IngestSpecificChunkMetadata, Pool, QueryTypes, RagQueryEventClickhouse,
     RecommendType, RecommendationEventClickhouse, RecommendationStrategy, RedisPool, ScoreChunk,
     ScoreChunkDTO, SearchMethod, SearchQueryEventClickhouse, SlimChunkMetadataWithScore,
-    SortByField, SortOptions, UnifiedId, UpdateSpecificChunkMetadata,
+    SortByFields, SortOptions, SpellingCorrection, UnifiedId, UpdateSpecificChunkMetadata,
 };
 use crate::errors::ServiceError;
 use crate::get_env;
@@ -19,9 +20,10 @@ use crate::operators::qdrant_operator::{
     point_ids_exists_in_qdrant, recommend_qdrant_query, scroll_dataset_points,
 };
 use crate::operators::search_operator::{
-    assemble_qdrant_filter, autocomplete_chunks_query, count_chunks_query, search_chunks_query,
-    search_hybrid_chunks,
+    assemble_qdrant_filter, autocomplete_chunks_query, compare_sort_values, count_chunks_query,
+    search_chunks_query, search_hybrid_chunks,
 };
+use crate::operators::spelling_operator::get_corrected_query;
 use actix::Arbiter;
 use actix_web::web::Bytes;
 use actix_web::{web, HttpResponse};
@@ -146,7 +148,7 @@ pub enum ReturnQueuedChunk {
     "pos_in_queue": 1
 }))]
 pub struct SingleQueuedChunkResponse {
-    /// The chunk that got queue'd
+    /// The chunk that got queue'd. It is not created if the dataset's DEDUP_POLICY drops it as a duplicate
     pub chunk_metadata: ChunkMetadata,
     /// The current position the last access item is in the queue
     pub pos_in_queue: i32,
@@ -177,7 +179,7 @@ pub struct SingleQueuedChunkResponse {
     "pos_in_queue": 2
 }))]
 pub struct BatchQueuedChunkResponse {
-    // All the chunks that got queue'd
+    // All the chunks that got queue'd, including any the dataset's DEDUP_POLICY later drops as duplicates
     pub chunk_metadata: Vec<ChunkMetadata>,
     /// The current position the last access item is in the queue
     pub pos_in_queue: i32,
@@ -265,6 +267,7 @@ pub enum CreateChunkReqPayloadEnum {
 /// Create or Upsert Chunk or Chunks
 ///
 /// Create new chunk(s). If the chunk has the same tracking_id as an existing chunk, the request will fail. Once a chunk is created, it can be searched for using the search endpoint.
+/// Duplicates are only detected once the chunks are ingested, so under the "reject" and "merge" DEDUP_POLICY the response can contain ids of chunks which are never created. Each of them emits a chunk_duplicate_detected event naming the chunk it duplicates.
 /// If uploading in bulk, the maximum amount of chunks that can be uploaded at once is 120 chunks. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
 #[utoipa::path(
     post,
@@ -444,10 +447,11 @@ pub async fn create_chunk(
         ("ApiKey" = ["admin"]),
     )
 )]
-#[tracing::instrument(skip(pool))]
+#[tracing::instrument(skip(pool, redis_pool))]
 pub async fn delete_chunk(
     chunk_id: web::Path<uuid::Uuid>,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     _user: AdminOnly,
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
 ) -> Result<HttpResponse, actix_web::Error> {
@@ -463,6 +467,7 @@ pub async fn delete_chunk(
         deleted_at,
         dataset_org_plan_sub.dataset,
         pool,
+        redis_pool,
         dataset_config,
     )
     .await?;
@@ -490,10 +495,11 @@ pub async fn delete_chunk(
         ("ApiKey" = ["admin"]),
     )
 )]
-#[tracing::instrument(skip(pool))]
+#[tracing::instrument(skip(pool, redis_pool))]
 pub async fn delete_chunk_by_tracking_id(
     tracking_id: web::Path<String>,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     _user: AdminOnly,
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
 ) -> Result<HttpResponse, actix_web::Error> {
@@ -512,6 +518,7 @@ pub async fn delete_chunk_by_tracking_id(
         deleted_at,
         dataset_org_plan_sub.dataset,
         pool,
+        redis_pool,
         dataset_config,
     )
     .await?;
@@ -932,7 +939,7 @@ pub struct ChunkFilter {
     "score_threshold": 0.5
 }))]
 pub struct SearchChunksReqPayload {
-    /// Can be either "semantic", "fulltext", or "hybrid". If specified as "hybrid", it will pull in one page (10 chunks) of both semantic and full-text results then re-rank them using scores from a cross encoder model. "semantic" will pull in one page (10 chunks) of the nearest cosine distant vectors. "fulltext" will pull in one page (10 chunks) of full-text results based on SPLADE.
+    /// Can be either "semantic", "fulltext", or "hybrid". If specified as "hybrid", it will pull in one page (10 chunks) of both semantic and full-text results then re-rank them using scores from a cross encoder model, or fuse them as configured in hybrid_options. "semantic" will pull in one page (10 chunks) of the nearest cosine distant vectors. "fulltext" will pull in one page (10 chunks) of full-text results based on SPLADE.
     pub search_type: SearchMethod,
     /// Query is the search query. This can be any string. The query will be used to create an embedding vector and/or SPLADE vector which will be used to find the result set.  You can either provide one query, or multiple with weights. Multi-query only works with Semantic Search.
     pub query: QueryTypes,
@@ -952,7 +959,16 @@ pub struct SearchChunksReqPayload {
     /// For Manhattan Distance, Euclidean Distance, and Dot Product, it will filter out scores above the threshold distance
     /// This threshold applies before weight and bias modifications. If not specified, this defaults to no threshold
     /// A threshold of 0 will default to no threashold
+    /// Hybrid searches fused with "rrf" or "weighted" apply the threshold to the semantic and full-text scores of each list before fusion, hybrid searches reranked with the cross encoder apply it to the cross encoder scores.
     pub score_threshold: Option<f32>,
+    /// Hybrid Options lets you specify how the semantic and full-text results are merged when search_type is "hybrid". If not specified, the results are re-ranked with the cross encoder.
+    pub hybrid_options: Option<HybridSearchOptions>,
+    /// Facets lets you request counts of tags, metadata values, num_value or time_stamp ranges and distances over the chunks matching the search, e.g. to render filter sidebars. The counts cover every chunk matching the filters, regardless of the query, score_threshold and pagination. If not specified, no facets are computed.
+    pub facets: Option<FacetOptions>,
+    /// Collapse lets you return only the best scoring chunk for each tracking_id prefix, link, group or metadata value, along with an inner_hits count. Pagination and total pages apply to the collapsed results. If not specified, results are not collapsed.
+    pub collapse: Option<CollapseOptions>,
+    /// Search_after is the cursor returned with a previous page of results. Set it to fetch the page following that result instead of using page offsets, which keeps deep pagination stable while chunks are added or removed and skips the total page count. It can not be combined with sorting by fields or collapse. The cursor is returned for the first page and for every page fetched with a cursor.
+    pub search_after: Option<String>,
     /// Set slim_chunks to true to avoid returning the content and chunk_html of the chunks. This is useful for when you want to reduce amount of data over the wire for latency improvement (typically 10-50ms). Default is false.
     pub slim_chunks: Option<bool>,
     /// Set content_only to true to only returning the chunk_html of the chunks. This is useful for when you want to reduce amount of data over the wire for latency improvement (typically 10-50ms). Default is false.
@@ -961,6 +977,8 @@ pub struct SearchChunksReqPayload {
     pub use_quote_negated_terms: Option<bool>,
     /// If true, stop words (specified in server/src/stop-words.txt in the git repo) will be removed. Queries that are entirely stop words will be preserved.
     pub remove_stop_words: Option<bool>,
+    /// Spelling correction corrects query terms which do not occur in the dataset to the most frequent term of the dataset within one edit. The vocabulary is built from the BM25 terms of the chunks, so it is only kept for datasets with BM25 enabled. Set it to "suggest" to get the corrected query back as corrected_query, or to "apply" to also search with it. Only single queries are corrected. If not specified, the query is not corrected.
+    pub spelling_correction: Option<SpellingCorrection>,
 }
 
 impl Default for SearchChunksReqPayload {
@@ -975,10 +993,15 @@ impl Default for SearchChunksReqPayload {
             sort_options: None,
             highlight_options: None,
             score_threshold: None,
+            hybrid_options: None,
+            facets: None,
+            collapse: None,
+            search_after: None,
             slim_chunks: None,
             content_only: None,
             use_quote_negated_terms: None,
             remove_stop_words: None,
+            spelling_correction: None,
         }
     }
 }
@@ -988,6 +1011,12 @@ impl Default for SearchChunksReqPayload {
 pub struct SearchChunkQueryResponseBody {
     pub score_chunks: Vec<ScoreChunkDTO>,
     pub total_chunk_pages: i64,
+    /// Counts over the chunks matching the filters of the search, only present when facets were requested.
+    pub facets: Option<FacetResults>,
+    /// Cursor to pass as search_after to fetch the next page. Not present when no results remain or the results are not ordered by score.
+    pub search_after: Option<String>,
+    /// The query with its misspelled terms corrected, only present when spelling_correction was requested and a term was corrected. The results are for this query when spelling_correction is "apply".
+    pub corrected_query: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
@@ -996,6 +1025,12 @@ pub struct SearchResponseBody {
     pub id: uuid::Uuid,
     pub chunks: Vec<ScoreChunk>,
     pub total_pages: i64,
+    /// Counts over the chunks matching the filters of the search, only present when facets were requested.
+    pub facets: Option<FacetResults>,
+    /// Cursor to pass as search_after to fetch the next page. Not present when no results remain or the results are not ordered by score.
+    pub search_after: Option<String>,
+    /// The query with its misspelled terms corrected, only present when spelling_correction was requested and a term was corrected. The results are for this query when spelling_correction is "apply".
+    pub corrected_query: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
@@ -1017,6 +1052,9 @@ impl SearchChunkQueryResponseBody {
                 .map(|chunk| chunk.into())
                 .collect(),
             total_pages: self.total_chunk_pages,
+            facets: self.facets,
+            search_after: self.search_after,
+            corrected_query: self.corrected_query,
         }
     }
 }
@@ -1137,18 +1175,42 @@ pub fn parse_query(
         ("ApiKey" = ["readonly"]),
     )
 )]
-#[tracing::instrument(skip(pool, event_queue))]
+#[tracing::instrument(skip(pool, event_queue, redis_pool))]
 pub async fn search_chunks(
     mut data: web::Json<SearchChunksReqPayload>,
     _user: LoggedUser,
     pool: web::Data<Pool>,
     event_queue: web::Data<EventQueue>,
+    redis_pool: web::Data<RedisPool>,
     api_version: APIVersion,
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
 ) -> Result<HttpResponse, actix_web::Error> {
     let dataset_config =
         DatasetConfiguration::from_json(dataset_org_plan_sub.dataset.server_configuration.clone());
 
+    if let Some(sort_options) = &data.sort_options {
+        sort_options.validate()?;
+    }
+
+    let corrected_query = match (data.spelling_correction, &data.query) {
+        (Some(_), QueryTypes::Single(query)) => {
+            get_corrected_query(
+                query,
+                dataset_org_plan_sub.dataset.id,
+                &dataset_config,
+                redis_pool.clone(),
+            )
+            .await?
+        }
+        _ => None,
+    };
+
+    if let (Some(SpellingCorrection::Apply), Some(corrected_query)) =
+        (data.spelling_correction, corrected_query.as_ref())
+    {
+        data.query = QueryTypes::Single(corrected_query.clone());
+    }
+
     let parsed_query = match data.query.clone() {
         QueryTypes::Single(query) => ParsedQueryTypes::Single(parse_query(
             query.clone(),
@@ -1177,12 +1239,13 @@ pub async fn search_chunks(
     sentry::configure_scope(|scope| scope.set_span(Some(transaction.clone().into())));
     let mut timer = Timer::new();
 
-    let result_chunks = match data.search_type {
+    let mut result_chunks = match data.search_type {
         SearchMethod::Hybrid => {
             search_hybrid_chunks(
                 data.clone(),
                 parsed_query.to_parsed_query()?,
                 pool,
+                redis_pool,
                 dataset_org_plan_sub.dataset.clone(),
                 &dataset_config,
                 &mut timer,
@@ -1194,6 +1257,7 @@ pub async fn search_chunks(
                 data.clone(),
                 parsed_query,
                 pool,
+                redis_pool,
                 dataset_org_plan_sub.dataset.clone(),
                 &dataset_config,
                 &mut timer,
@@ -1201,6 +1265,7 @@ pub async fn search_chunks(
             .await?
         }
     };
+    result_chunks.corrected_query = corrected_query;
     timer.add("search_chunks");
 
     let search_id = uuid::Uuid::new_v4();
@@ -1340,10 +1405,15 @@ impl From<AutocompleteReqPayload> for SearchChunksReqPayload {
             sort_options: autocomplete_data.sort_options,
             highlight_options: autocomplete_data.highlight_options,
             score_threshold: autocomplete_data.score_threshold,
+            hybrid_options: None,
+            facets: None,
+            collapse: None,
+            search_after: None,
             slim_chunks: autocomplete_data.slim_chunks,
             content_only: autocomplete_data.content_only,
             use_quote_negated_terms: autocomplete_data.use_quote_negated_terms,
             remove_stop_words: autocomplete_data.remove_stop_words,
+            spelling_correction: None,
         }
     }
 }
@@ -1369,18 +1439,23 @@ impl From<AutocompleteReqPayload> for SearchChunksReqPayload {
         ("ApiKey" = ["readonly"]),
     )
 )]
-#[tracing::instrument(skip(pool, event_queue))]
+#[tracing::instrument(skip(pool, event_queue, redis_pool))]
 pub async fn autocomplete(
     data: web::Json<AutocompleteReqPayload>,
     _user: LoggedUser,
     pool: web::Data<Pool>,
     event_queue: web::Data<EventQueue>,
+    redis_pool: web::Data<RedisPool>,
     api_version: APIVersion,
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
 ) -> Result<HttpResponse, actix_web::Error> {
     let dataset_config =
         DatasetConfiguration::from_json(dataset_org_plan_sub.dataset.server_configuration.clone());
 
+    if let Some(sort_options) = &data.sort_options {
+        sort_options.validate()?;
+    }
+
     let parsed_query = parse_query(
         data.query.clone(),
         data.use_quote_negated_terms,
@@ -1397,6 +1472,7 @@ pub async fn autocomplete(
         data.clone(),
         parsed_query,
         pool,
+        redis_pool,
         dataset_org_plan_sub.dataset.clone(),
         &dataset_config,
         &mut timer,
@@ -1468,8 +1544,8 @@ pub struct ScrollChunksReqPayload {
     pub offset_chunk_id: Option<uuid::Uuid>,
     /// Get total page count for the query accounting for the applied filters. Defaults to false, but can be set to true when the latency penalty is acceptable (typically 50-200ms).
     pub filters: Option<ChunkFilter>,
-    /// Sort by lets you specify a key to sort the results by. If not specified, this defaults to the id's of the chunks. If specified, the field can be num_value, time_stamp, or any key in the chunk metadata. This key must be a numeric value within the payload.
-    pub sort_by: Option<SortByField>,
+    /// Sort by lets you specify a key or a list of keys to sort the results by. If not specified, this defaults to the id's of the chunks. If specified, the fields can be num_value, time_stamp, weight or a `metadata.<key>` path. Qdrant orders the chunks by the first key, which must be a numeric value within the payload, and the remaining keys break its ties within the page.
+    pub sort_by: Option<SortByFields>,
 }
 
 /// Scroll Chunks
@@ -1504,6 +1580,21 @@ pub async fn scroll_dataset_chunks(
     let dataset_id = dataset_org_plan_sub.dataset.id;
     let filters = data.filters.clone();
 
+    let sort_by = data
+        .sort_by
+        .clone()
+        .map(SortByFields::into_fields)
+        .unwrap_or_default();
+    for sort_by in sort_by.iter() {
+        sort_by.validate()?;
+        if sort_by.field == "score" {
+            return Err(ServiceError::BadRequest(
+                "Scrolled chunks have no score to sort by".to_string(),
+            )
+            .into());
+        }
+    }
+
     let filter = assemble_qdrant_filter(filters, None, None, dataset_id, pool.clone()).await?;
 
     let qdrant_point_id_of_offset_chunk = match data.offset_chunk_id {
@@ -1518,7 +1609,10 @@ pub async fn scroll_dataset_chunks(
     let qdrant_point_ids = scroll_dataset_points(
         data.page_size.unwrap_or(10),
         qdrant_point_id_of_offset_chunk,
-        data.sort_by.clone(),
+        sort_by
+            .first()
+            .filter(|sort_by| sort_by.is_payload_field())
+            .cloned(),
         dataset_config,
         filter,
     )
@@ -1539,6 +1633,15 @@ pub async fn scroll_dataset_chunks(
                 .find(|chunk| chunk.qdrant_point_id == point_id)
                 .cloned()
         })
+        .map(|chunk| {
+            let values = sort_by
+                .iter()
+                .map(|sort_by| sort_by.sort_value(&chunk, None))
+                .collect_vec();
+            (values, chunk)
+        })
+        .sorted_by(|(a, _), (b, _)| compare_sort_values(&sort_by, a, b))
+        .map(|(_, chunk)| chunk)
         .collect();
 
     let resp = ScrollChunksResponseBody {
@@ -1632,10 +1735,15 @@ impl From<CountChunksReqPayload> for SearchChunksReqPayload {
             sort_options: None,
             highlight_options: None,
             score_threshold: count_data.score_threshold,
+            hybrid_options: None,
+            facets: None,
+            collapse: None,
+            search_after: None,
             slim_chunks: None,
             content_only: None,
             use_quote_negated_terms: count_data.use_quote_negated_terms,
             remove_stop_words: None,
+            spelling_correction: None,
         }
     }
 }
@@ -1665,11 +1773,12 @@ pub struct CountChunkQueryResponseBody {
         ("ApiKey" = ["readonly"]),
     )
 )]
-#[tracing::instrument(skip(pool))]
+#[tracing::instrument(skip(pool, redis_pool))]
 pub async fn count_chunks(
     data: web::Json<CountChunksReqPayload>,
     _user: LoggedUser,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
 ) -> Result<HttpResponse, actix_web::Error> {
     let dataset_config =
@@ -1722,6 +1831,7 @@ pub async fn count_chunks(
         search_req_data.clone(),
         parsed_query,
         pool,
+        redis_pool,
         dataset_org_plan_sub.dataset.clone(),
         &dataset_config,
     )
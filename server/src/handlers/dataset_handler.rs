@@ -1,18 +1,27 @@
 use super::auth_handler::{AdminOnly, LoggedUser, OwnerOnly};
 use crate::{
     data::models::{
-        Dataset, DatasetAndOrgWithSubAndPlan, DatasetConfiguration, DatasetConfigurationDTO, Pool,
-        RedisPool, StripePlan, UnifiedId,
+        Dataset, DatasetAndOrgWithSubAndPlan, DatasetArchiveJobState, DatasetArchiveJobType,
+        DatasetArchiveMessage, DatasetArchiveStatus, DatasetConfiguration, DatasetConfigurationDTO,
+        Pool, RedisPool, StripePlan, UnifiedId,
     },
     errors::ServiceError,
     middleware::auth_middleware::{verify_admin, verify_owner},
     operators::{
+        chunk_operator::get_row_count_for_organization_id_query,
+        dataset_archive_operator::{
+            get_dataset_archive_manifest_query, get_dataset_archive_status_query,
+            queue_dataset_archive_query,
+        },
         dataset_operator::{
-            clear_dataset_by_dataset_id_query, create_dataset_query, get_dataset_by_id_query,
-            get_dataset_usage_query, get_datasets_by_organization_id, get_tags_in_dataset_query,
+            clear_bm25_corpus_stats_query, clear_dataset_by_dataset_id_query, create_dataset_query,
+            get_dataset_by_id_query, get_dataset_usage_query, get_datasets_by_organization_id,
+            get_tags_in_dataset_query, queue_dataset_bm25_reindex_query,
             soft_delete_dataset_by_id_query, update_dataset_query,
         },
+        migration_operator::{get_embedding_migration_query, start_embedding_migration_query},
         organization_operator::{get_org_dataset_count, get_org_from_id_query},
+        spelling_operator::clear_vocabulary_query,
     },
 };
 use actix_web::{web, FromRequest, HttpMessage, HttpResponse};
@@ -206,10 +215,11 @@ pub struct UpdateDatasetRequest {
         ("ApiKey" = ["owner"]),
     )
 )]
-#[tracing::instrument(skip(pool))]
+#[tracing::instrument(skip(pool, redis_pool))]
 pub async fn update_dataset(
     data: web::Json<UpdateDatasetRequest>,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     user: OwnerOnly,
 ) -> Result<HttpResponse, ServiceError> {
     let curr_dataset = if let Some(dataset_id) = data.dataset_id {
@@ -228,18 +238,59 @@ pub async fn update_dataset(
 
     let curr_dataset_config = DatasetConfiguration::from_json(curr_dataset.server_configuration);
 
+    let new_dataset_config = data
+        .server_configuration
+        .clone()
+        .map(|c| c.from_curr_dataset(curr_dataset_config.clone()))
+        .unwrap_or(curr_dataset_config.clone());
+
+    let bm25_analyzer_changed =
+        new_dataset_config.bm25_analyzer_options() != curr_dataset_config.bm25_analyzer_options();
+
+    let new_embedding_model_options = new_dataset_config.embedding_model_options();
+    let embedding_model_changed =
+        new_embedding_model_options != curr_dataset_config.embedding_model_options();
+
+    // The dataset keeps searching with its current model until the migration switches it over.
+    let new_dataset_config = if embedding_model_changed {
+        new_dataset_config
+            .with_embedding_model_options(curr_dataset_config.embedding_model_options())
+    } else {
+        new_dataset_config
+    };
+
     let d = update_dataset_query(
         curr_dataset.id,
         data.dataset_name.clone().unwrap_or(curr_dataset.name),
-        data.server_configuration
-            .clone()
-            .map(|c| c.from_curr_dataset(curr_dataset_config.clone()))
-            .unwrap_or(curr_dataset_config),
+        new_dataset_config.clone(),
         data.new_tracking_id.clone(),
         pool.clone(),
     )
     .await?;
 
+    if embedding_model_changed {
+        start_embedding_migration_query(
+            curr_dataset.id,
+            new_dataset_config.clone(),
+            new_embedding_model_options,
+            new_dataset_config.DISTANCE_METRIC.clone(),
+            redis_pool.clone(),
+        )
+        .await?;
+    }
+
+    if bm25_analyzer_changed
+        && new_dataset_config.BM25_ENABLED
+        && std::env::var("BM25_ACTIVE").unwrap_or("false".to_string()) == "true"
+    {
+        // Document lengths and terms counted with the old analyzer no longer match, the reindex counts every chunk again
+        clear_bm25_corpus_stats_query(curr_dataset.id, redis_pool.clone()).await?;
+        clear_vocabulary_query(curr_dataset.id, redis_pool.clone()).await?;
+
+        queue_dataset_bm25_reindex_query(curr_dataset.id, new_dataset_config, true, redis_pool)
+            .await?;
+    }
+
     Ok(HttpResponse::Ok().json(d))
 }
 
@@ -613,3 +664,404 @@ pub async fn get_all_tags(
         total: items.1,
     }))
 }
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ExportDatasetReqPayload {
+    /// Whether to include the dense and sparse vectors of every chunk so the import does not have to embed them again. Default is true.
+    pub include_vectors: Option<bool>,
+    /// Whether to copy the dataset's uploaded files into the archive. Default is true.
+    pub include_files: Option<bool>,
+}
+
+/// Export Dataset
+///
+/// Queue an export of the dataset's chunks, tags, groups, files and optionally vectors into a portable archive. Poll the returned archive_id with the get archive status route and import it once the export has completed. Auth'ed user must be an admin or owner of the dataset's organization to export it.
+#[utoipa::path(
+    post,
+    path = "/dataset/export",
+    context_path = "/api",
+    tag = "Dataset",
+    request_body(content = ExportDatasetReqPayload, description = "JSON request payload to export the dataset", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Export queued successfully", body = DatasetArchiveStatus),
+        (status = 400, description = "Service error relating to queueing the export", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(redis_pool))]
+pub async fn export_dataset(
+    data: web::Json<ExportDatasetReqPayload>,
+    redis_pool: web::Data<RedisPool>,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    _user: AdminOnly,
+) -> Result<HttpResponse, ServiceError> {
+    let archive_id = uuid::Uuid::new_v4();
+    let dataset_id = dataset_org_plan_sub.dataset.id;
+
+    let status = DatasetArchiveStatus::from_details(
+        archive_id,
+        archive_id,
+        DatasetArchiveJobType::Export,
+        dataset_id,
+        DatasetArchiveJobState::Queued,
+    );
+
+    queue_dataset_archive_query(
+        DatasetArchiveMessage::Export {
+            archive_id,
+            dataset_id,
+            include_vectors: data.include_vectors.unwrap_or(true),
+            include_files: data.include_files.unwrap_or(true),
+        },
+        status.clone(),
+        redis_pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(status))
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ImportDatasetReqPayload {
+    /// Id of a completed dataset export to import.
+    pub archive_id: uuid::Uuid,
+    /// Organization ID that the new dataset will belong to.
+    pub organization_id: uuid::Uuid,
+    /// Name of the new dataset. Defaults to the name of the exported dataset.
+    pub dataset_name: Option<String>,
+    /// Optional tracking ID for the new dataset. Must be unique within the organization.
+    pub tracking_id: Option<String>,
+    /// The configuration of the new dataset. Defaults to the configuration of the exported dataset. Archived vectors are only reused if the EMBEDDING_SIZE matches the exported dataset's, otherwise every chunk is embedded again.
+    pub server_configuration: Option<DatasetConfigurationDTO>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ImportDatasetResponse {
+    /// The new dataset the archive is being imported into.
+    pub dataset: Dataset,
+    /// Status of the import job. Poll its job_id with the get archive status route to follow the import.
+    pub status: DatasetArchiveStatus,
+}
+
+/// Import Dataset
+///
+/// Create a new dataset from a completed export. The dataset is returned right away and its groups, files and chunks are recreated in the background with new ids. Poll the returned job_id with the get archive status route to follow the import. Auth'ed user must be an owner of both the organization the archive was exported from and the organization to import it into.
+#[utoipa::path(
+    post,
+    path = "/dataset/import",
+    context_path = "/api",
+    tag = "Dataset",
+    request_body(content = ImportDatasetReqPayload, description = "JSON request payload to import a dataset archive", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Dataset created and import queued successfully", body = ImportDatasetResponse),
+        (status = 400, description = "Service error relating to importing the dataset", body = ErrorResponseBody),
+        (status = 403, description = "The archive was exported from an organization the user does not own", body = ErrorResponseBody),
+        (status = 404, description = "Dataset archive not found", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Organization" = String, Header, description = "The organization id to use for the request"),
+    ),
+    security(
+        ("ApiKey" = ["owner"]),
+    )
+)]
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn import_dataset(
+    data: web::Json<ImportDatasetReqPayload>,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+    user: OwnerOnly,
+) -> Result<HttpResponse, ServiceError> {
+    let org_id = data.organization_id;
+
+    if !verify_owner(&user, &org_id) {
+        return Err(ServiceError::Forbidden);
+    }
+
+    let manifest = get_dataset_archive_manifest_query(data.archive_id).await?;
+
+    let source_org_id = match manifest.organization_id {
+        Some(source_org_id) => source_org_id,
+        None => {
+            get_dataset_by_id_query(UnifiedId::TrieveUuid(manifest.dataset_id), pool.clone())
+                .await
+                .map_err(|_| ServiceError::Forbidden)?
+                .organization_id
+        }
+    };
+    if !verify_owner(&user, &source_org_id) {
+        return Err(ServiceError::Forbidden);
+    }
+
+    let organization_sub_plan = get_org_from_id_query(org_id, pool.clone()).await?;
+    let plan = organization_sub_plan.plan.unwrap_or_default();
+
+    let unlimited = std::env::var("UNLIMITED").unwrap_or("false".to_string());
+    if unlimited == "false" {
+        let dataset_count = get_org_dataset_count(org_id, pool.clone()).await?;
+        if dataset_count >= plan.dataset_count {
+            return Ok(HttpResponse::UpgradeRequired().json(
+                json!({"message": "Your plan must be upgraded to create additional datasets"}),
+            ));
+        }
+
+        let chunk_count = get_row_count_for_organization_id_query(org_id, pool.clone()).await?;
+        if chunk_count + manifest.chunk_count > plan.chunk_count as usize {
+            return Ok(HttpResponse::UpgradeRequired().json(
+                json!({"message": "Your plan must be upgraded to import this many chunks"}),
+            ));
+        }
+    }
+
+    let dataset_config = data
+        .server_configuration
+        .clone()
+        .map(|c| c.into())
+        .unwrap_or_else(|| DatasetConfiguration::from_json(manifest.server_configuration));
+
+    let dataset = Dataset::from_details(
+        data.dataset_name.clone().unwrap_or(manifest.dataset_name),
+        org_id,
+        data.tracking_id.clone(),
+        dataset_config,
+    );
+
+    let dataset = create_dataset_query(dataset, pool).await?;
+
+    let job_id = uuid::Uuid::new_v4();
+    let status = DatasetArchiveStatus::from_details(
+        job_id,
+        data.archive_id,
+        DatasetArchiveJobType::Import,
+        dataset.id,
+        DatasetArchiveJobState::Queued,
+    );
+
+    queue_dataset_archive_query(
+        DatasetArchiveMessage::Import {
+            job_id,
+            archive_id: data.archive_id,
+            dataset_id: dataset.id,
+        },
+        status.clone(),
+        redis_pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ImportDatasetResponse { dataset, status }))
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct CloneDatasetReqPayload {
+    /// Name of the new dataset. Defaults to the name of the cloned dataset followed by "(clone)".
+    pub dataset_name: Option<String>,
+    /// Optional tracking ID for the new dataset. Must be unique within the organization.
+    pub tracking_id: Option<String>,
+    /// Changes to the cloned dataset's configuration for the new dataset. The embedding model can not be changed as the vectors are copied as is.
+    pub server_configuration: Option<DatasetConfigurationDTO>,
+}
+
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct CloneDatasetResponse {
+    /// The new dataset the chunks are being copied into.
+    pub dataset: Dataset,
+    /// Status of the clone job. Poll its archive_id with the get archive status route to follow the clone.
+    pub status: DatasetArchiveStatus,
+}
+
+/// Clone Dataset
+///
+/// Create a new dataset in the same organization with a copy of the dataset's chunks, tags, groups and files. Chunks are copied in the background with their existing vectors, so nothing is embedded again. Progress is reported with dataset_clone_progress, dataset_cloned and dataset_clone_failed events on the new dataset. Auth'ed user must be an owner of the organization to clone a dataset.
+#[utoipa::path(
+    post,
+    path = "/dataset/{dataset_id}/clone",
+    context_path = "/api",
+    tag = "Dataset",
+    request_body(content = CloneDatasetReqPayload, description = "JSON request payload to clone a dataset", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Dataset created and clone queued successfully", body = CloneDatasetResponse),
+        (status = 400, description = "Service error relating to cloning the dataset", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        ("dataset_id" = uuid::Uuid, Path, description = "The id of the dataset to clone."),
+    ),
+    security(
+        ("ApiKey" = ["owner"]),
+    )
+)]
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn clone_dataset(
+    dataset_id: web::Path<uuid::Uuid>,
+    data: web::Json<CloneDatasetReqPayload>,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    user: OwnerOnly,
+) -> Result<HttpResponse, ServiceError> {
+    let source_dataset = dataset_org_plan_sub.dataset;
+    if source_dataset.id != *dataset_id {
+        return Err(ServiceError::BadRequest(
+            "Dataset header does not match provided dataset ID".to_string(),
+        ));
+    }
+
+    let org_id = source_dataset.organization_id;
+    if !verify_owner(&user, &org_id) {
+        return Err(ServiceError::Forbidden);
+    }
+
+    let plan = dataset_org_plan_sub.organization.plan.unwrap_or_default();
+
+    let unlimited = std::env::var("UNLIMITED").unwrap_or("false".to_string());
+    if unlimited == "false" {
+        let dataset_count = get_org_dataset_count(org_id, pool.clone()).await?;
+        if dataset_count >= plan.dataset_count {
+            return Ok(HttpResponse::UpgradeRequired().json(
+                json!({"message": "Your plan must be upgraded to create additional datasets"}),
+            ));
+        }
+
+        let chunk_count = get_row_count_for_organization_id_query(org_id, pool.clone()).await?;
+        let source_chunk_count = get_dataset_usage_query(source_dataset.id, pool.clone())
+            .await?
+            .chunk_count;
+        if chunk_count + source_chunk_count as usize > plan.chunk_count as usize {
+            return Ok(HttpResponse::UpgradeRequired()
+                .json(json!({"message": "Your plan must be upgraded to clone this many chunks"})));
+        }
+    }
+
+    let source_dataset_config =
+        DatasetConfiguration::from_json(source_dataset.server_configuration.clone());
+    let dataset_config = data
+        .server_configuration
+        .clone()
+        .map(|c| c.from_curr_dataset(source_dataset_config.clone()))
+        .unwrap_or(source_dataset_config.clone());
+
+    if dataset_config.embedding_model_options() != source_dataset_config.embedding_model_options() {
+        return Err(ServiceError::BadRequest(
+            "The embedding model can not be changed when cloning a dataset. Update the clone once it completes to migrate it to another model.".to_string(),
+        ));
+    }
+
+    let dataset = Dataset::from_details(
+        data.dataset_name
+            .clone()
+            .unwrap_or(format!("{} (clone)", source_dataset.name)),
+        org_id,
+        data.tracking_id.clone(),
+        dataset_config,
+    );
+
+    let dataset = create_dataset_query(dataset, pool).await?;
+
+    let archive_id = uuid::Uuid::new_v4();
+    let status = DatasetArchiveStatus::from_details(
+        archive_id,
+        archive_id,
+        DatasetArchiveJobType::Clone,
+        dataset.id,
+        DatasetArchiveJobState::Queued,
+    );
+
+    queue_dataset_archive_query(
+        DatasetArchiveMessage::Clone {
+            archive_id,
+            source_dataset_id: source_dataset.id,
+            dataset_id: dataset.id,
+        },
+        status.clone(),
+        redis_pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(CloneDatasetResponse { dataset, status }))
+}
+
+/// Get Dataset Archive Status
+///
+/// Get the status of an export, import or clone job by the job_id returned when it was queued. Job statuses expire a week after their last update. Auth'ed user must be an admin or owner of the organization of the dataset being exported or imported into.
+#[utoipa::path(
+    get,
+    path = "/dataset/archive/{job_id}",
+    context_path = "/api",
+    tag = "Dataset",
+    responses(
+        (status = 200, description = "Status of the archive's job", body = DatasetArchiveStatus),
+        (status = 400, description = "Service error relating to getting the archive status", body = ErrorResponseBody),
+        (status = 404, description = "Archive job not found", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Organization" = String, Header, description = "The organization id to use for the request"),
+        ("job_id" = uuid::Uuid, Path, description = "The job_id returned when the export, import or clone was queued."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn get_dataset_archive_status(
+    job_id: web::Path<uuid::Uuid>,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+    user: AdminOnly,
+) -> Result<HttpResponse, ServiceError> {
+    let status = get_dataset_archive_status_query(job_id.into_inner(), redis_pool).await?;
+
+    let dataset = get_dataset_by_id_query(UnifiedId::TrieveUuid(status.dataset_id), pool).await?;
+    if !verify_admin(&user, &dataset.organization_id) {
+        return Err(ServiceError::Forbidden);
+    }
+
+    Ok(HttpResponse::Ok().json(status))
+}
+
+/// Get Embedding Model Migration
+///
+/// Get the dataset's running or most recently finished embedding model migration. A migration is started by updating the dataset's EMBEDDING_BASE_URL, EMBEDDING_MODEL_NAME or EMBEDDING_SIZE, and the dataset keeps serving searches with its previous model until the migration completes. Finished migrations expire a week after they complete. Auth'ed user must be an admin or owner of the dataset's organization.
+#[utoipa::path(
+    get,
+    path = "/dataset/embedding_migration/{dataset_id}",
+    context_path = "/api",
+    tag = "Dataset",
+    responses(
+        (status = 200, description = "The dataset's embedding model migration", body = EmbeddingMigration),
+        (status = 400, description = "Service error relating to getting the embedding model migration", body = ErrorResponseBody),
+        (status = 404, description = "Dataset has no embedding model migration", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        ("dataset_id" = uuid::Uuid, Path, description = "The id of the dataset to get the embedding model migration of."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(redis_pool))]
+pub async fn get_embedding_migration(
+    dataset_id: web::Path<uuid::Uuid>,
+    redis_pool: web::Data<RedisPool>,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    _user: AdminOnly,
+) -> Result<HttpResponse, ServiceError> {
+    if dataset_org_plan_sub.dataset.id != *dataset_id {
+        return Err(ServiceError::BadRequest(
+            "Dataset header does not match provided dataset ID".to_string(),
+        ));
+    }
+
+    let migration = get_embedding_migration_query(dataset_org_plan_sub.dataset.id, redis_pool)
+        .await?
+        .ok_or(ServiceError::NotFound(
+            "Dataset has no embedding model migration".to_string(),
+        ))?;
+
+    Ok(HttpResponse::Ok().json(migration))
+}
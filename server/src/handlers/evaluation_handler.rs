@@ -0,0 +1,481 @@
+use super::auth_handler::AdminOnly;
+use super::chunk_handler::SearchChunksReqPayload;
+use crate::{
+    data::models::{
+        DatasetAndOrgWithSubAndPlan, DatasetConfiguration, JudgmentList, JudgmentListDTO,
+        JudgmentQuery, Pool, RedisPool, SearchEvaluation, SearchEvaluationQueryResult,
+    },
+    errors::ServiceError,
+    operators::evaluation_operator::{
+        aggregate_evaluation, create_judgment_list_query, delete_judgment_list_query,
+        evaluate_judgment_query, get_judgment_list_by_id_query,
+        get_judgment_lists_for_dataset_query, get_search_evaluation_query,
+        get_search_evaluations_query, insert_search_evaluation_query, update_judgment_list_query,
+        MAX_JUDGMENT_QUERIES,
+    },
+};
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+fn validate_judgment_queries(queries: &[JudgmentQuery]) -> Result<(), ServiceError> {
+    if queries.len() > MAX_JUDGMENT_QUERIES {
+        return Err(ServiceError::BadRequest(format!(
+            "Judgment lists can have at most {} queries",
+            MAX_JUDGMENT_QUERIES
+        )));
+    }
+
+    for query in queries {
+        if query.query.trim().is_empty() {
+            return Err(ServiceError::BadRequest(
+                "Judgment list queries cannot be empty".to_string(),
+            ));
+        }
+
+        if query
+            .judgments
+            .iter()
+            .any(|judgment| judgment.chunk_id.is_none() && judgment.tracking_id.is_none())
+        {
+            return Err(ServiceError::BadRequest(
+                "Either chunk_id or tracking_id must be specified for each judgment".to_string(),
+            ));
+        }
+
+        if query
+            .judgments
+            .iter()
+            .any(|judgment| !judgment.grade.is_finite() || judgment.grade < 0.0)
+        {
+            return Err(ServiceError::BadRequest(
+                "Judgment grades must be non-negative numbers".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "name": "Dog breed queries",
+    "queries": [
+        {
+            "query": "loyal dog breeds",
+            "judgments": [
+                {"tracking_id": "dogs-are-loyal", "grade": 3.0},
+                {"chunk_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3", "grade": 1.0},
+            ],
+        },
+    ],
+}))]
+pub struct CreateJudgmentListReqPayload {
+    /// Name of the judgment list.
+    pub name: String,
+    /// The labeled queries of the judgment list, at most 1000.
+    pub queries: Vec<JudgmentQuery>,
+}
+
+/// Create Judgment List
+///
+/// Create a judgment list for the dataset specified by the TR-Dataset header. A judgment list is a set of queries, each labeled with the chunks which are relevant to it and how relevant they are. Judgment lists are used to evaluate search quality with the evaluation endpoint. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    post,
+    path = "/judgment_list",
+    context_path = "/api",
+    tag = "Evaluation",
+    request_body(content = CreateJudgmentListReqPayload, description = "JSON request payload to create a judgment list", content_type = "application/json"),
+    responses(
+        (status = 200, description = "The created judgment list", body = JudgmentListDTO),
+        (status = 400, description = "Service error relating to creating the judgment list", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool))]
+pub async fn create_judgment_list(
+    data: web::Json<CreateJudgmentListReqPayload>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let data = data.into_inner();
+
+    validate_judgment_queries(&data.queries)?;
+
+    let judgment_list = create_judgment_list_query(
+        JudgmentList::from_details(dataset_org_plan_sub.dataset.id, data.name, data.queries),
+        pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(JudgmentListDTO::from(judgment_list)))
+}
+
+/// Get Judgment Lists
+///
+/// Get the judgment lists of the dataset specified by the TR-Dataset header. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    get,
+    path = "/judgment_list",
+    context_path = "/api",
+    tag = "Evaluation",
+    responses(
+        (status = 200, description = "Judgment lists of the dataset", body = Vec<JudgmentListDTO>),
+        (status = 400, description = "Service error relating to getting the judgment lists", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool))]
+pub async fn get_judgment_lists(
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let judgment_lists =
+        get_judgment_lists_for_dataset_query(dataset_org_plan_sub.dataset.id, pool).await?;
+
+    Ok(HttpResponse::Ok().json(
+        judgment_lists
+            .into_iter()
+            .map(JudgmentListDTO::from)
+            .collect::<Vec<JudgmentListDTO>>(),
+    ))
+}
+
+/// Get Judgment List
+///
+/// Get a judgment list of the dataset specified by the TR-Dataset header. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    get,
+    path = "/judgment_list/{judgment_list_id}",
+    context_path = "/api",
+    tag = "Evaluation",
+    responses(
+        (status = 200, description = "The judgment list", body = JudgmentListDTO),
+        (status = 400, description = "Service error relating to getting the judgment list", body = ErrorResponseBody),
+        (status = 404, description = "Judgment list not found", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        ("judgment_list_id" = uuid::Uuid, description = "The id of the judgment list to get"),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool))]
+pub async fn get_judgment_list(
+    judgment_list_id: web::Path<uuid::Uuid>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let judgment_list = get_judgment_list_by_id_query(
+        judgment_list_id.into_inner(),
+        dataset_org_plan_sub.dataset.id,
+        pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(JudgmentListDTO::from(judgment_list)))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "name": "Dog breed queries v2",
+}))]
+pub struct UpdateJudgmentListReqPayload {
+    /// New name of the judgment list. If not specified, the name is not changed.
+    pub name: Option<String>,
+    /// Labeled queries which replace the queries of the judgment list. If not specified, the queries are not changed.
+    pub queries: Option<Vec<JudgmentQuery>>,
+}
+
+/// Update Judgment List
+///
+/// Update the name or queries of a judgment list of the dataset specified by the TR-Dataset header. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    put,
+    path = "/judgment_list/{judgment_list_id}",
+    context_path = "/api",
+    tag = "Evaluation",
+    request_body(content = UpdateJudgmentListReqPayload, description = "JSON request payload to update a judgment list", content_type = "application/json"),
+    responses(
+        (status = 200, description = "The updated judgment list", body = JudgmentListDTO),
+        (status = 400, description = "Service error relating to updating the judgment list", body = ErrorResponseBody),
+        (status = 404, description = "Judgment list not found", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        ("judgment_list_id" = uuid::Uuid, description = "The id of the judgment list to update"),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool))]
+pub async fn update_judgment_list(
+    judgment_list_id: web::Path<uuid::Uuid>,
+    data: web::Json<UpdateJudgmentListReqPayload>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let data = data.into_inner();
+
+    if let Some(queries) = &data.queries {
+        validate_judgment_queries(queries)?;
+    }
+
+    let judgment_list = update_judgment_list_query(
+        judgment_list_id.into_inner(),
+        dataset_org_plan_sub.dataset.id,
+        data.name,
+        data.queries,
+        pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(JudgmentListDTO::from(judgment_list)))
+}
+
+/// Delete Judgment List
+///
+/// Delete a judgment list of the dataset specified by the TR-Dataset header. Evaluations which were run with the judgment list are kept. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    delete,
+    path = "/judgment_list/{judgment_list_id}",
+    context_path = "/api",
+    tag = "Evaluation",
+    responses(
+        (status = 204, description = "Confirmation that the judgment list was deleted"),
+        (status = 400, description = "Service error relating to deleting the judgment list", body = ErrorResponseBody),
+        (status = 404, description = "Judgment list not found", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        ("judgment_list_id" = uuid::Uuid, description = "The id of the judgment list to delete"),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool))]
+pub async fn delete_judgment_list(
+    judgment_list_id: web::Path<uuid::Uuid>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    delete_judgment_list_query(
+        judgment_list_id.into_inner(),
+        dataset_org_plan_sub.dataset.id,
+        pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "judgment_list_id": "e3e3e3e3-e3e3-e3e3-e3e3-e3e3e3e3e3e3",
+    "k": 10,
+    "request": {
+        "search_type": "hybrid",
+        "query": "",
+        "hybrid_options": {"fusion_method": "rrf"},
+    },
+}))]
+pub struct RunSearchEvaluationReqPayload {
+    /// Id of the judgment list whose queries are searched for.
+    pub judgment_list_id: uuid::Uuid,
+    /// Number of results per query the metrics are computed over. Defaults to 10.
+    pub k: Option<u32>,
+    /// Search request template each query is run with. Its query, page and page_size are replaced for every query of the judgment list, search_after, collapse and facets are ignored. If not specified, a default hybrid search is used.
+    pub request: Option<SearchChunksReqPayload>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct RunSearchEvaluationResponse {
+    pub evaluation: SearchEvaluation,
+    pub queries: Vec<SearchEvaluationQueryResult>,
+}
+
+/// Run Search Evaluation
+///
+/// Search for every query of a judgment list of the dataset specified by the TR-Dataset header and report nDCG@k, MRR, recall@k and latency. When analytics are enabled the evaluation is stored so that runs with different search configurations can be compared over time. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    post,
+    path = "/evaluation",
+    context_path = "/api",
+    tag = "Evaluation",
+    request_body(content = RunSearchEvaluationReqPayload, description = "JSON request payload to run a search evaluation", content_type = "application/json"),
+    responses(
+        (status = 200, description = "The aggregated and per query results of the evaluation", body = RunSearchEvaluationResponse),
+        (status = 400, description = "Service error relating to running the evaluation", body = ErrorResponseBody),
+        (status = 404, description = "Judgment list not found", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool, redis_pool, clickhouse_client))]
+pub async fn run_search_evaluation(
+    data: web::Json<RunSearchEvaluationReqPayload>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+    clickhouse_client: web::Data<clickhouse::Client>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let data = data.into_inner();
+    let k = data.k.unwrap_or(10);
+    if k == 0 || k > 100 {
+        return Err(ServiceError::BadRequest("k must be between 1 and 100".to_string()).into());
+    }
+
+    let judgment_list = get_judgment_list_by_id_query(
+        data.judgment_list_id,
+        dataset_org_plan_sub.dataset.id,
+        pool.clone(),
+    )
+    .await?;
+
+    let judgment_queries = judgment_list.judgment_queries();
+    validate_judgment_queries(&judgment_queries)?;
+
+    let request_template = data.request.unwrap_or_default();
+    if let Some(sort_options) = &request_template.sort_options {
+        sort_options.validate()?;
+    }
+    let dataset_config =
+        DatasetConfiguration::from_json(dataset_org_plan_sub.dataset.server_configuration.clone());
+
+    let mut query_results = vec![];
+    for judgment_query in judgment_queries {
+        query_results.push(
+            evaluate_judgment_query(
+                &judgment_query,
+                &request_template,
+                k,
+                pool.clone(),
+                redis_pool.clone(),
+                dataset_org_plan_sub.dataset.clone(),
+                &dataset_config,
+            )
+            .await?,
+        );
+    }
+
+    let evaluation = aggregate_evaluation(&judgment_list, &request_template, k, &query_results);
+
+    insert_search_evaluation_query(&evaluation, &query_results, clickhouse_client.get_ref())
+        .await?;
+
+    Ok(HttpResponse::Ok().json(RunSearchEvaluationResponse {
+        evaluation: evaluation.into(),
+        queries: query_results,
+    }))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetSearchEvaluationsQuery {
+    /// Only return evaluations which were run with this judgment list.
+    pub judgment_list_id: Option<uuid::Uuid>,
+    /// Page of evaluations to fetch, 10 per page, newest first. Page is 1-indexed.
+    pub page: Option<u32>,
+}
+
+/// Get Search Evaluations
+///
+/// Get the stored search evaluations of the dataset specified by the TR-Dataset header, newest first. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    get,
+    path = "/evaluation",
+    context_path = "/api",
+    tag = "Evaluation",
+    responses(
+        (status = 200, description = "Search evaluations of the dataset", body = Vec<SearchEvaluation>),
+        (status = 400, description = "Service error relating to getting the evaluations", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        GetSearchEvaluationsQuery,
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(clickhouse_client))]
+pub async fn get_search_evaluations(
+    query: web::Query<GetSearchEvaluationsQuery>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    clickhouse_client: web::Data<clickhouse::Client>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let query = query.into_inner();
+
+    let evaluations = get_search_evaluations_query(
+        dataset_org_plan_sub.dataset.id,
+        query.judgment_list_id,
+        query.page,
+        clickhouse_client.get_ref(),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(evaluations))
+}
+
+/// Get Search Evaluation
+///
+/// Get a stored search evaluation of the dataset specified by the TR-Dataset header along with the results of each of its queries. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    get,
+    path = "/evaluation/{evaluation_id}",
+    context_path = "/api",
+    tag = "Evaluation",
+    responses(
+        (status = 200, description = "The search evaluation and its per query results", body = SearchEvaluationWithQueries),
+        (status = 400, description = "Service error relating to getting the evaluation", body = ErrorResponseBody),
+        (status = 404, description = "Search evaluation not found", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        ("evaluation_id" = uuid::Uuid, description = "The id of the evaluation to get"),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(clickhouse_client))]
+pub async fn get_search_evaluation(
+    evaluation_id: web::Path<uuid::Uuid>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    clickhouse_client: web::Data<clickhouse::Client>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let evaluation = get_search_evaluation_query(
+        dataset_org_plan_sub.dataset.id,
+        evaluation_id.into_inner(),
+        clickhouse_client.get_ref(),
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(evaluation))
+}
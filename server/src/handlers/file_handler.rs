@@ -8,17 +8,22 @@ use crate::{
     middleware::auth_middleware::verify_member,
     operators::{
         file_operator::{
-            delete_file_query, get_aws_bucket, get_dataset_file_query, get_file_query,
+            claim_pending_file_query, create_pending_file_query, delete_file_query,
+            delete_pending_file_upload_query, get_aws_bucket, get_dataset_file_query,
+            get_file_chunk_ids_query, get_file_group_query, get_file_query, get_pending_file_query,
+            get_reprocessable_file_query, put_file_stream_to_bucket, release_pending_file_query,
         },
         organization_operator::get_file_size_sum_org,
     },
 };
+use actix_multipart::Multipart;
 use actix_web::{web, HttpResponse};
 use base64::{
     alphabet,
     engine::{self, general_purpose},
     Engine as _,
 };
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
@@ -53,6 +58,9 @@ pub fn validate_file_name(s: String) -> Result<String, actix_web::Error> {
     "create_chunks": true,
     "split_delimiters": [",",".","\n"],
     "target_splits_per_chunk": 20,
+    "chunk_strategy": "sentence",
+    "max_tokens_per_chunk": 512,
+    "overlap_tokens": 64,
 }))]
 pub struct UploadFileReqPayload {
     /// Base64 encoded file. This is the standard base64url encoding.
@@ -79,6 +87,46 @@ pub struct UploadFileReqPayload {
     pub target_splits_per_chunk: Option<usize>,
     /// Group tracking id is an optional field which allows you to specify the tracking id of the group that is created from the file. Chunks created will be created with the tracking id of `group_tracking_id|<index of chunk>`
     pub group_tracking_id: Option<String>,
+    /// Chunk strategy is an optional field which determines how the file is split into chunks. The default `sentence` strategy splits the plain text with `split_delimiters` and groups `target_splits_per_chunk` splits per chunk. The `heading` strategy splits on h1-h6 boundaries, keeps tables and list items whole, and records the heading path of each chunk under the `heading_path` key of its metadata.
+    pub chunk_strategy: Option<ChunkStrategy>,
+    /// Max tokens per chunk is an optional field which caps the size of every chunk created from the file, counted with the cl100k_base tokenizer. When set, sentence splits are packed into chunks up to this budget instead of using `target_splits_per_chunk`, and `heading` chunks over the budget are split further. cl100k_base is not the tokenizer of the embedding model, so chunks are packed to 90% of this budget to leave headroom. Set it at or below the token limit of your embedding model so chunks are never truncated when embedded.
+    pub max_tokens_per_chunk: Option<usize>,
+    /// Overlap tokens is an optional field which makes each chunk start with the last `overlap_tokens` tokens of the chunk before it so neighbouring chunks share context. Only used when `max_tokens_per_chunk` is set and must be smaller than it. Defaults to 0.
+    pub overlap_tokens: Option<usize>,
+    /// Column mapping is an optional field used for `.csv` and `.jsonl` files, which are parsed without Tika and turned into one chunk per row. It selects which columns (CSV header names or JSONL keys) populate each field of the row's chunk. If not specified, every column is written into `chunk_html`.
+    pub column_mapping: Option<FileColumnMapping>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
+#[schema(example = json!({
+    "chunk_html": ["name", "description"],
+    "tracking_id": "sku",
+    "tag_set": "categories",
+    "num_value": "price",
+    "time_stamp": "updated_at",
+    "metadata": ["brand", "color"],
+}))]
+pub struct FileColumnMapping {
+    /// Columns whose values are joined with newlines to form the chunk_html of the row. Defaults to every column formatted as `column: value` lines.
+    pub chunk_html: Option<Vec<String>>,
+    /// Column used as the tracking_id of the row's chunk. If not specified, `group_tracking_id|<row index>` is used when a group_tracking_id is set.
+    pub tracking_id: Option<String>,
+    /// Column holding the tags of the row. CSV values are split on commas and JSONL values can be an array of strings or a comma separated string. They are added to the file's tag_set.
+    pub tag_set: Option<String>,
+    /// Column parsed as the num_value of the row's chunk.
+    pub num_value: Option<String>,
+    /// Column used as the time_stamp of the row's chunk. Should be an ISO 8601 combined date and time without timezone.
+    pub time_stamp: Option<String>,
+    /// Columns copied into the metadata of the row's chunk, merged over the file's metadata.
+    pub metadata: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkStrategy {
+    #[default]
+    Sentence,
+    Heading,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
@@ -88,13 +136,13 @@ pub struct UploadFileResult {
 
 /// Upload File
 ///
-/// Upload a file to S3 attached to the server. The file will be converted to HTML with tika and chunked algorithmically, images will be OCR'ed with tesseract. The resulting chunks will be indexed and searchable. Optionally, you can only upload the file and manually create chunks associated to the file after. See docs.trieve.ai and/or contact us for more details and tips. Auth'ed user must be an admin or owner of the dataset's organization to upload a file.
+/// Upload a file to S3 attached to the server. The file will be converted to HTML with tika and chunked algorithmically, images will be OCR'ed with tesseract. Plain text and Markdown files are converted without tika, and CSV and JSONL files become one chunk per row according to `column_mapping`. The resulting chunks will be indexed and searchable. Optionally, you can only upload the file and manually create chunks associated to the file after. See docs.trieve.ai and/or contact us for more details and tips. Auth'ed user must be an admin or owner of the dataset's organization to upload a file.
 #[utoipa::path(
     post,
     path = "/file",
     context_path = "/api",
     tag = "File",
-    request_body(content = UploadFileReqPayload, description = "JSON request payload to upload a file", content_type = "application/json"),
+    request_body(content = UploadFileReqPayload, description = "JSON request payload to upload a file. A `multipart/form-data` body with the fields of UploadFileMultipartReqPayload is also accepted and streams the file to S3 instead of base64 decoding it.", content_type = "application/json"),
     responses(
         (status = 200, description = "Confirmation that the file is uploading", body = UploadFileResult),
         (status = 400, description = "Service error relating to uploading the file", body = ErrorResponseBody),
@@ -118,11 +166,6 @@ pub async fn upload_file_handler(
     let transaction = sentry::start_transaction(tx_ctx);
     sentry::configure_scope(|scope| scope.set_span(Some(transaction.clone().into())));
 
-    let mut redis_conn = redis_pool
-        .get()
-        .await
-        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
-
     let get_file_size_span = transaction.start_child("get_file_size_sum", "get_file_size_sum");
     let file_size_sum_pool = pool.clone();
     let file_size_sum = get_file_size_sum_org(
@@ -147,6 +190,8 @@ pub async fn upload_file_handler(
 
     let upload_file_data = data.into_inner();
 
+    validate_upload_file_data(&upload_file_data)?;
+
     let base64_decode_span = transaction.start_child("base64_decode", "base64_decode");
     let mut cleaned_base64 = upload_file_data
         .base64_file
@@ -178,47 +223,531 @@ pub async fn upload_file_handler(
 
     bucket_upload_span.finish();
 
-    let message = FileWorkerMessage {
+    let push_to_redis_span = transaction.start_child("push_to_redis", "push_to_redis");
+    let result = queue_file_worker_message(
         file_id,
-        dataset_id: dataset_org_plan_sub.dataset.id,
-        upload_file_data: upload_file_data.clone(),
-        attempt_number: 0,
+        decoded_file_data.len(),
+        upload_file_data,
+        dataset_org_plan_sub.dataset.id,
+        redis_pool,
+    )
+    .await?;
+    push_to_redis_span.finish();
+
+    transaction.finish();
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct UploadFileMultipartReqPayload {
+    /// The file to upload, sent as the form part named `file`. It is streamed to S3 as it is received rather than buffered in memory, so this is the preferred way to upload large files. The part's filename is used as the file_name unless a `file_name` part is sent.
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+    /// Name of the file being uploaded, including the extension. Defaults to the filename of the `file` part.
+    pub file_name: Option<String>,
+    /// Any other field of the JSON upload payload except `base64_file` can be sent as a form part with the same name. The non-string fields `tag_set`, `metadata`, `split_delimiters`, `column_mapping` and the number and boolean options must be JSON encoded, all other parts are used as plain strings. Send these parts before the `file` part so they are validated before the file is uploaded.
+    #[serde(flatten)]
+    #[schema(value_type = Object)]
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+fn validate_upload_file_data(upload_file_data: &UploadFileReqPayload) -> Result<(), ServiceError> {
+    if let Some(max_tokens_per_chunk) = upload_file_data.max_tokens_per_chunk {
+        if max_tokens_per_chunk == 0 {
+            return Err(ServiceError::BadRequest(
+                "max_tokens_per_chunk must be greater than 0".to_string(),
+            ));
+        }
+
+        if upload_file_data.overlap_tokens.unwrap_or(0) >= max_tokens_per_chunk {
+            return Err(ServiceError::BadRequest(
+                "overlap_tokens must be less than max_tokens_per_chunk".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+async fn queue_file_worker_message(
+    file_id: uuid::Uuid,
+    file_size: usize,
+    upload_file_data: UploadFileReqPayload,
+    dataset_id: uuid::Uuid,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<UploadFileResult, ServiceError> {
+    push_file_worker_message(
+        FileWorkerMessage {
+            file_id,
+            dataset_id,
+            upload_file_data: upload_file_data.clone(),
+            attempt_number: 0,
+            replaced_chunk_ids: vec![],
+        },
+        redis_pool,
+    )
+    .await?;
+
+    Ok(UploadFileResult {
+        file_metadata: File::from_details(
+            Some(file_id),
+            &upload_file_data.file_name,
+            file_size.try_into().unwrap(),
+            upload_file_data
+                .tag_set
+                .map(|t| t.into_iter().map(Some).collect()),
+            None,
+            None,
+            None,
+            dataset_id,
+        ),
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+#[schema(example = json!({
+    "file_name": "example.pdf",
+    "tag_set": ["tag1", "tag2"],
+    "link": "https://example.com",
+    "metadata": {
+        "key1": "value1",
+    },
+    "chunk_strategy": "heading",
+}))]
+pub struct CreatePresignedUrlForFileReqPayload {
+    /// Name of the file being uploaded, including the extension.
+    pub file_name: String,
+    /// Any other field of the JSON upload payload except `base64_file`. The options are kept until the upload is completed and are then used to process the file exactly as a regular upload would be.
+    #[serde(flatten)]
+    #[schema(value_type = Object)]
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct CreatePresignedUrlForFileResponse {
+    /// The pending file. Pass its id to the complete endpoint once the file has been uploaded.
+    pub file_metadata: File,
+    /// Presigned url to upload the file to with a PUT request. Expires after an hour.
+    pub presigned_put_url: String,
+}
+
+/// Create Presigned Url for File
+///
+/// Create a pending file and get a presigned url to PUT the file's bytes directly to S3, bypassing the server. Once the upload finishes, call the complete endpoint with the returned file_id to have the file processed. The upload options are kept for 24 hours. Auth'ed user must be an admin or owner of the dataset's organization to upload a file.
+#[utoipa::path(
+    post,
+    path = "/file/presigned_url",
+    context_path = "/api",
+    tag = "File",
+    request_body(content = CreatePresignedUrlForFileReqPayload, description = "JSON request payload with the options to process the file with once uploaded", content_type = "application/json"),
+    responses(
+        (status = 200, description = "The pending file and the url to upload it to", body = CreatePresignedUrlForFileResponse),
+        (status = 400, description = "Service error relating to creating the file", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn create_presigned_url_for_file_handler(
+    data: web::Json<CreatePresignedUrlForFileReqPayload>,
+    pool: web::Data<Pool>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let file_size_sum = get_file_size_sum_org(
+        dataset_org_plan_sub.organization.organization.id,
+        pool.clone(),
+    )
+    .await
+    .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    if file_size_sum
+        >= dataset_org_plan_sub
+            .clone()
+            .organization
+            .plan
+            .unwrap_or_default()
+            .file_storage
+    {
+        return Err(ServiceError::BadRequest("File size limit reached".to_string()).into());
+    }
+
+    let data = data.into_inner();
+    let mut fields = data.fields;
+    fields.insert(
+        "file_name".to_string(),
+        serde_json::Value::String(data.file_name),
+    );
+    fields.insert(
+        "base64_file".to_string(),
+        serde_json::Value::String("".to_string()),
+    );
+
+    let upload_file_data: UploadFileReqPayload =
+        serde_json::from_value(serde_json::Value::Object(fields))
+            .map_err(|err| ServiceError::BadRequest(format!("Invalid upload options: {}", err)))?;
+
+    validate_upload_file_data(&upload_file_data)?;
+
+    let file_id = uuid::Uuid::new_v4();
+
+    let bucket = get_aws_bucket()?;
+    let presigned_put_url = bucket
+        .presign_put(file_id.to_string(), 3600, None)
+        .await
+        .map_err(|e| {
+            log::error!("Could not get presigned put url {:?}", e);
+            ServiceError::BadRequest("Could not get presigned put url".to_string())
+        })?;
+
+    let file_metadata = create_pending_file_query(
+        file_id,
+        upload_file_data,
+        dataset_org_plan_sub.dataset.id,
+        pool,
+        redis_pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(CreatePresignedUrlForFileResponse {
+        file_metadata,
+        presigned_put_url,
+    }))
+}
+
+/// Complete File Upload
+///
+/// Complete a presigned upload. The uploaded object is checked against the organization's file storage limit and the file is queued to be processed with the options given when the presigned url was created. An upload can only be completed once. Pending files whose upload was not completed within 24 hours are deleted. Auth'ed user must be an admin or owner of the dataset's organization to upload a file.
+#[utoipa::path(
+    post,
+    path = "/file/{file_id}/complete",
+    context_path = "/api",
+    tag = "File",
+    responses(
+        (status = 200, description = "Confirmation that the file is uploading", body = UploadFileResult),
+        (status = 400, description = "Service error relating to completing the upload", body = ErrorResponseBody),
+        (status = 404, description = "Pending file not found or its upload options expired", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        ("file_id" = uuid::Uuid, description = "The id of the pending file returned when the presigned url was created"),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn complete_file_upload_handler(
+    file_id: web::Path<uuid::Uuid>,
+    pool: web::Data<Pool>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let file_id = file_id.into_inner();
+
+    let (_, upload_file_data) = get_pending_file_query(
+        file_id,
+        dataset_org_plan_sub.dataset.id,
+        pool.clone(),
+        redis_pool.clone(),
+    )
+    .await?;
+
+    let bucket = get_aws_bucket()?;
+    let (head_object, _) = bucket.head_object(file_id.to_string()).await.map_err(|_| {
+        ServiceError::BadRequest("File has not been uploaded to the presigned url".to_string())
+    })?;
+
+    let file_size: usize = head_object
+        .content_length
+        .unwrap_or(0)
+        .try_into()
+        .unwrap_or(0);
+
+    let file_size_sum = get_file_size_sum_org(
+        dataset_org_plan_sub.organization.organization.id,
+        pool.clone(),
+    )
+    .await
+    .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let file_storage_limit = dataset_org_plan_sub
+        .clone()
+        .organization
+        .plan
+        .unwrap_or_default()
+        .file_storage;
+
+    let file_size_mb = (file_size as f64 / 1024.0 / 1024.0).round() as i64;
+    if file_size_sum + file_size_mb > file_storage_limit {
+        bucket
+            .delete_object(file_id.to_string())
+            .await
+            .map_err(|e| {
+                log::error!("Could not delete file from S3 {:?}", e);
+                ServiceError::BadRequest("Could not delete file from S3".to_string())
+            })?;
+
+        return Err(ServiceError::BadRequest("File size limit reached".to_string()).into());
+    }
+
+    claim_pending_file_query(
+        file_id,
+        dataset_org_plan_sub.dataset.id,
+        file_size.try_into().unwrap_or(0),
+        pool.clone(),
+    )
+    .await?;
+
+    let result = match queue_file_worker_message(
+        file_id,
+        file_size,
+        upload_file_data,
+        dataset_org_plan_sub.dataset.id,
+        redis_pool.clone(),
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(err) => {
+            release_pending_file_query(file_id, pool).await?;
+            return Err(err.into());
+        }
     };
 
+    delete_pending_file_upload_query(file_id, redis_pool).await?;
+
+    Ok(HttpResponse::Ok().json(result))
+}
+
+async fn push_file_worker_message(
+    message: FileWorkerMessage,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
     let serialized_message = serde_json::to_string(&message).map_err(|e| {
         log::error!("Could not serialize message: {:?}", e);
         ServiceError::BadRequest("Could not serialize message".to_string())
     })?;
 
-    let push_to_redis_span = transaction.start_child("push_to_redis", "push_to_redis");
     redis::cmd("lpush")
         .arg("file_ingestion")
         .arg(&serialized_message)
         .query_async(&mut *redis_conn)
         .await
         .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
-    push_to_redis_span.finish();
 
-    let result = UploadFileResult {
-        file_metadata: File::from_details(
-            Some(file_id),
-            &upload_file_data.file_name,
-            decoded_file_data.len().try_into().unwrap(),
-            upload_file_data
-                .tag_set
-                .map(|t| t.into_iter().map(Some).collect()),
-            None,
-            None,
-            None,
-            dataset_org_plan_sub.dataset.id,
-        ),
+    Ok(())
+}
+
+/// Upload File (multipart)
+///
+/// Same as the JSON upload, but takes a `multipart/form-data` body whose `file` part is streamed straight to S3. The organization's file storage limit is enforced while the file is streamed, and the uploaded object is removed again if the request fails afterwards. Routed to when the request's content type is `multipart/form-data`.
+#[tracing::instrument(skip(payload, pool, redis_pool))]
+pub async fn upload_file_multipart_handler(
+    mut payload: Multipart,
+    pool: web::Data<Pool>,
+    user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let file_size_sum = get_file_size_sum_org(
+        dataset_org_plan_sub.organization.organization.id,
+        pool.clone(),
+    )
+    .await
+    .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let file_storage_limit = dataset_org_plan_sub
+        .clone()
+        .organization
+        .plan
+        .unwrap_or_default()
+        .file_storage;
+
+    if file_size_sum >= file_storage_limit {
+        return Err(ServiceError::BadRequest("File size limit reached".to_string()).into());
+    }
+
+    let file_id = uuid::Uuid::new_v4();
+    let mut file_size: Option<usize> = None;
+    let mut part_file_name: Option<String> = None;
+    let mut form_fields: Vec<(String, String)> = vec![];
+
+    while let Some(field) = payload.next().await {
+        let mut field = match field {
+            Ok(field) => field,
+            Err(err) => {
+                delete_uploaded_multipart_file(file_id, file_size.is_some()).await;
+                return Err(
+                    ServiceError::BadRequest(format!("Invalid multipart body: {}", err)).into(),
+                );
+            }
+        };
+        let field_name = field.name().unwrap_or_default().to_string();
+
+        if field_name == "file" {
+            if file_size.is_some() {
+                delete_uploaded_multipart_file(file_id, true).await;
+                return Err(ServiceError::BadRequest(
+                    "Only one file part can be uploaded per request".to_string(),
+                )
+                .into());
+            }
+
+            part_file_name = field
+                .content_disposition()
+                .and_then(|content_disposition| content_disposition.get_filename())
+                .map(|file_name| file_name.to_string());
+
+            // Parts sent before the file are checked here so a bad request never reaches S3.
+            upload_file_data_from_form_fields(&form_fields, part_file_name.clone())?;
+
+            file_size = Some(
+                put_file_stream_to_bucket(
+                    file_id.to_string(),
+                    &mut field,
+                    (file_storage_limit - file_size_sum) as u64 * 1024 * 1024,
+                )
+                .await?,
+            );
+            continue;
+        }
+
+        let mut value = vec![];
+        while let Some(chunk) = field.next().await {
+            match chunk {
+                Ok(chunk) => value.extend_from_slice(&chunk),
+                Err(err) => {
+                    delete_uploaded_multipart_file(file_id, file_size.is_some()).await;
+                    return Err(ServiceError::BadRequest(format!(
+                        "Invalid multipart body: {}",
+                        err
+                    ))
+                    .into());
+                }
+            }
+        }
+        form_fields.push((field_name, String::from_utf8_lossy(&value).to_string()));
+    }
+
+    let file_size = file_size.ok_or(ServiceError::BadRequest(
+        "Multipart body must contain a file part".to_string(),
+    ))?;
+
+    // Parts sent after the file can only be checked once it is uploaded, so the object is removed again if they are invalid.
+    let upload_file_data = match upload_file_data_from_form_fields(&form_fields, part_file_name) {
+        Ok(upload_file_data) => upload_file_data,
+        Err(err) => {
+            delete_uploaded_multipart_file(file_id, true).await;
+            return Err(err.into());
+        }
     };
 
-    transaction.finish();
+    let result = match queue_file_worker_message(
+        file_id,
+        file_size,
+        upload_file_data,
+        dataset_org_plan_sub.dataset.id,
+        redis_pool,
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(err) => {
+            delete_uploaded_multipart_file(file_id, true).await;
+            return Err(err.into());
+        }
+    };
 
     Ok(HttpResponse::Ok().json(result))
 }
 
+/// Fields of `UploadFileReqPayload` which are not strings and are sent JSON encoded in multipart form parts.
+const MULTIPART_JSON_FIELDS: [&str; 9] = [
+    "tag_set",
+    "metadata",
+    "create_chunks",
+    "rebalance_chunks",
+    "split_delimiters",
+    "target_splits_per_chunk",
+    "max_tokens_per_chunk",
+    "overlap_tokens",
+    "column_mapping",
+];
+
+/// Builds and validates the upload payload from the text parts of a multipart upload. Only the fields in `MULTIPART_JSON_FIELDS` are JSON decoded, every other part is kept verbatim as a string so values like a description of `123` or `"quoted"` are not reinterpreted.
+fn upload_file_data_from_form_fields(
+    form_fields: &[(String, String)],
+    part_file_name: Option<String>,
+) -> Result<UploadFileReqPayload, ServiceError> {
+    let mut fields = serde_json::Map::new();
+
+    for (field_name, value) in form_fields {
+        let value = if MULTIPART_JSON_FIELDS.contains(&field_name.as_str()) {
+            serde_json::from_str::<serde_json::Value>(value).map_err(|err| {
+                ServiceError::BadRequest(format!(
+                    "Form part {} must be JSON encoded: {}",
+                    field_name, err
+                ))
+            })?
+        } else {
+            serde_json::Value::String(value.clone())
+        };
+        fields.insert(field_name.clone(), value);
+    }
+
+    if !fields.contains_key("file_name") {
+        let file_name = part_file_name.ok_or(ServiceError::BadRequest(
+            "file_name must be set as a part or as the filename of the file part".to_string(),
+        ))?;
+        fields.insert(
+            "file_name".to_string(),
+            serde_json::Value::String(file_name),
+        );
+    }
+    fields.insert(
+        "base64_file".to_string(),
+        serde_json::Value::String("".to_string()),
+    );
+
+    let upload_file_data: UploadFileReqPayload =
+        serde_json::from_value(serde_json::Value::Object(fields)).map_err(|err| {
+            ServiceError::BadRequest(format!("Invalid upload file form fields: {}", err))
+        })?;
+
+    validate_upload_file_data(&upload_file_data)?;
+
+    Ok(upload_file_data)
+}
+
+/// Removes the object of a multipart upload which failed after its file part was streamed to S3.
+async fn delete_uploaded_multipart_file(file_id: uuid::Uuid, uploaded: bool) {
+    if !uploaded {
+        return;
+    }
+
+    match get_aws_bucket() {
+        Ok(bucket) => {
+            if let Err(err) = bucket.delete_object(file_id.to_string()).await {
+                log::error!("Could not delete file {} from S3 {:?}", file_id, err);
+            }
+        }
+        Err(err) => log::error!("Could not delete file {} from S3 {:?}", file_id, err),
+    }
+}
+
 /// Get File
 ///
 /// Download a file based on its id.
@@ -361,6 +890,121 @@ pub async fn delete_file_handler(
     Ok(HttpResponse::NoContent().finish())
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Default, ToSchema)]
+#[schema(example = json!({
+    "split_delimiters": [",",".","\n"],
+    "target_splits_per_chunk": 20,
+    "chunk_strategy": "sentence",
+    "max_tokens_per_chunk": 512,
+    "overlap_tokens": 64,
+}))]
+pub struct ReprocessFileReqPayload {
+    /// Rebalance chunks works the same as on upload. Defaults to true.
+    pub rebalance_chunks: Option<bool>,
+    /// Split delimiters to split the file with before chunking. Defaults to [.!?\n].
+    pub split_delimiters: Option<Vec<String>>,
+    /// Number of splits per chunk. Defaults to 20.
+    pub target_splits_per_chunk: Option<usize>,
+    /// Chunk strategy to re-chunk the file with. Defaults to `sentence`.
+    pub chunk_strategy: Option<ChunkStrategy>,
+    /// Max tokens per chunk counted with the cl100k_base tokenizer. Replaces `target_splits_per_chunk` when set.
+    pub max_tokens_per_chunk: Option<usize>,
+    /// Tokens shared between neighbouring chunks. Only used with `max_tokens_per_chunk`.
+    pub overlap_tokens: Option<usize>,
+    /// Column mapping for `.csv` and `.jsonl` files.
+    pub column_mapping: Option<FileColumnMapping>,
+}
+
+/// Reprocess File
+///
+/// Re-chunk a file which was already uploaded with new chunking options. The original file is fetched from S3 and chunked again, and the chunks in the file's group are only deleted once the new chunks were queued, so the file keeps its chunks if reprocessing fails. The file and its group keep their ids and the group keeps its tracking_id, so chunk tracking_ids of the form `group_tracking_id|<index of chunk>` are stable as well. Auth'ed user must be an admin or owner of the dataset's organization to reprocess a file.
+#[utoipa::path(
+    post,
+    path = "/file/{file_id}/reprocess",
+    context_path = "/api",
+    tag = "File",
+    request_body(content = ReprocessFileReqPayload, description = "JSON request payload with the chunking options to reprocess the file with", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Confirmation that the file is being reprocessed", body = UploadFileResult),
+        (status = 400, description = "Service error relating to reprocessing the file", body = ErrorResponseBody),
+        (status = 404, description = "File not found", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        ("file_id" = uuid::Uuid, description = "The id of the file to reprocess"),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn reprocess_file_handler(
+    file_id: web::Path<uuid::Uuid>,
+    data: web::Json<ReprocessFileReqPayload>,
+    pool: web::Data<Pool>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let file_id = file_id.into_inner();
+    let data = data.into_inner();
+
+    let file = get_reprocessable_file_query(file_id, dataset_org_plan_sub.dataset.id, pool.clone())
+        .await?;
+
+    let file_group = get_file_group_query(file_id, pool.clone()).await?;
+
+    let upload_file_data = UploadFileReqPayload {
+        base64_file: "".to_string(),
+        file_name: file.file_name.clone(),
+        tag_set: file
+            .tag_set
+            .clone()
+            .map(|tag_set| tag_set.into_iter().flatten().collect()),
+        description: file_group.as_ref().map(|group| group.description.clone()),
+        link: file.link.clone(),
+        time_stamp: file
+            .time_stamp
+            .map(|time_stamp| time_stamp.format("%Y-%m-%dT%H:%M:%S%.f").to_string()),
+        metadata: file.metadata.clone(),
+        create_chunks: Some(true),
+        rebalance_chunks: data.rebalance_chunks,
+        split_delimiters: data.split_delimiters,
+        target_splits_per_chunk: data.target_splits_per_chunk,
+        group_tracking_id: file_group
+            .as_ref()
+            .and_then(|group| group.tracking_id.clone()),
+        chunk_strategy: data.chunk_strategy,
+        max_tokens_per_chunk: data.max_tokens_per_chunk,
+        overlap_tokens: data.overlap_tokens,
+        column_mapping: data.column_mapping,
+    };
+
+    validate_upload_file_data(&upload_file_data)?;
+
+    // The old chunks are replaced by the file worker once the file was parsed again
+    let replaced_chunk_ids = match file_group {
+        Some(file_group) => get_file_chunk_ids_query(file_group.id, pool.clone()).await?,
+        None => vec![],
+    };
+
+    push_file_worker_message(
+        FileWorkerMessage {
+            file_id,
+            dataset_id: dataset_org_plan_sub.dataset.id,
+            upload_file_data,
+            attempt_number: 0,
+            replaced_chunk_ids,
+        },
+        redis_pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(UploadFileResult {
+        file_metadata: file,
+    }))
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 pub struct GetImageResponse {
     pub signed_url: String,
@@ -401,3 +1045,82 @@ pub async fn get_signed_url(
 
     Ok(HttpResponse::Ok().json(GetImageResponse { signed_url }))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn form_fields(fields: &[(&str, &str)]) -> Vec<(String, String)> {
+        fields
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_upload_file_data_from_form_fields() {
+        let upload_file_data = upload_file_data_from_form_fields(
+            &form_fields(&[
+                ("description", "123"),
+                ("link", "\"quoted\""),
+                ("group_tracking_id", "true"),
+                ("tag_set", "[\"tag1\",\"tag2\"]"),
+                ("metadata", "{\"key\":1}"),
+                ("create_chunks", "false"),
+                ("max_tokens_per_chunk", "512"),
+                ("overlap_tokens", "64"),
+            ]),
+            Some("report.pdf".to_string()),
+        )
+        .unwrap();
+
+        assert_eq!(upload_file_data.file_name, "report.pdf");
+        assert_eq!(upload_file_data.description.as_deref(), Some("123"));
+        assert_eq!(upload_file_data.link.as_deref(), Some("\"quoted\""));
+        assert_eq!(upload_file_data.group_tracking_id.as_deref(), Some("true"));
+        assert_eq!(
+            upload_file_data.tag_set,
+            Some(vec!["tag1".to_string(), "tag2".to_string()])
+        );
+        assert_eq!(
+            upload_file_data.metadata,
+            Some(serde_json::json!({"key": 1}))
+        );
+        assert_eq!(upload_file_data.create_chunks, Some(false));
+        assert_eq!(upload_file_data.max_tokens_per_chunk, Some(512));
+        assert_eq!(upload_file_data.overlap_tokens, Some(64));
+    }
+
+    #[test]
+    fn test_upload_file_data_from_form_fields_file_name() {
+        let upload_file_data = upload_file_data_from_form_fields(
+            &form_fields(&[("file_name", "notes.md")]),
+            Some("upload.bin".to_string()),
+        )
+        .unwrap();
+        assert_eq!(upload_file_data.file_name, "notes.md");
+
+        assert!(upload_file_data_from_form_fields(&[], None).is_err());
+    }
+
+    #[test]
+    fn test_upload_file_data_from_form_fields_rejects_invalid_fields() {
+        assert!(upload_file_data_from_form_fields(
+            &form_fields(&[("tag_set", "tag1,tag2")]),
+            Some("report.pdf".to_string()),
+        )
+        .is_err());
+
+        assert!(upload_file_data_from_form_fields(
+            &form_fields(&[("max_tokens_per_chunk", "64"), ("overlap_tokens", "64")]),
+            Some("report.pdf".to_string()),
+        )
+        .is_err());
+
+        assert!(upload_file_data_from_form_fields(
+            &form_fields(&[("chunk_strategy", "paragraph")]),
+            Some("report.pdf".to_string()),
+        )
+        .is_err());
+    }
+}
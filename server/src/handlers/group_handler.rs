@@ -8,9 +8,9 @@ use crate::{
     data::models::{
         ChunkGroup, ChunkGroupAndFileId, ChunkGroupBookmark, ChunkMetadata,
         ChunkMetadataStringTagSet, DatasetAndOrgWithSubAndPlan, DatasetConfiguration,
-        HighlightOptions, Pool, QueryTypes, RecommendType, RecommendationEventClickhouse,
-        RecommendationStrategy, RedisPool, ScoreChunk, ScoreChunkDTO, SearchMethod,
-        SearchQueryEventClickhouse, SortOptions, UnifiedId,
+        HighlightOptions, HybridSearchOptions, Pool, QueryTypes, RecommendType,
+        RecommendationEventClickhouse, RecommendationStrategy, RedisPool, ScoreChunk,
+        ScoreChunkDTO, SearchMethod, SearchQueryEventClickhouse, SortOptions, UnifiedId,
     },
     errors::ServiceError,
     middleware::api_version::APIVersion,
@@ -521,11 +521,12 @@ pub struct DeleteGroupByTrackingIDData {
         ("ApiKey" = ["admin"]),
     )
 )]
-#[tracing::instrument(skip(pool))]
+#[tracing::instrument(skip(pool, redis_pool))]
 pub async fn delete_group_by_tracking_id(
     tracking_id: web::Path<String>,
     data: web::Query<DeleteGroupByTrackingIDData>,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
     _user: AdminOnly,
 ) -> Result<HttpResponse, actix_web::Error> {
@@ -549,6 +550,7 @@ pub async fn delete_group_by_tracking_id(
         deleted_at,
         data.delete_chunks,
         delete_group_pool,
+        redis_pool,
         dataset_config,
     )
     .await?;
@@ -582,11 +584,12 @@ pub struct DeleteGroupData {
         ("ApiKey" = ["admin"]),
     )
 )]
-#[tracing::instrument(skip(pool))]
+#[tracing::instrument(skip(pool, redis_pool))]
 pub async fn delete_chunk_group(
     group_id: web::Path<uuid::Uuid>,
     data: web::Query<DeleteGroupData>,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
     _user: AdminOnly,
 ) -> Result<HttpResponse, actix_web::Error> {
@@ -611,6 +614,7 @@ pub async fn delete_chunk_group(
         deleted_at,
         data.delete_chunks,
         delete_group_pool,
+        redis_pool,
         dataset_config,
     )
     .await?;
@@ -1418,10 +1422,15 @@ impl From<SearchWithinGroupReqPayload> for SearchChunksReqPayload {
             sort_options: search_within_group_data.sort_options,
             highlight_options: search_within_group_data.highlight_options,
             score_threshold: search_within_group_data.score_threshold,
+            hybrid_options: None,
+            facets: None,
+            collapse: None,
+            search_after: None,
             slim_chunks: search_within_group_data.slim_chunks,
             content_only: search_within_group_data.content_only,
             use_quote_negated_terms: search_within_group_data.use_quote_negated_terms,
             remove_stop_words: search_within_group_data.remove_stop_words,
+            spelling_correction: None,
         }
     }
 }
@@ -1486,11 +1495,12 @@ impl SearchWithinGroupResults {
         ("ApiKey" = ["readonly"]),
     )
 )]
-#[tracing::instrument(skip(pool, event_queue))]
+#[tracing::instrument(skip(pool, event_queue, redis_pool))]
 pub async fn search_within_group(
     data: web::Json<SearchWithinGroupReqPayload>,
     pool: web::Data<Pool>,
     event_queue: web::Data<EventQueue>,
+    redis_pool: web::Data<RedisPool>,
     api_version: APIVersion,
     _required_user: LoggedUser,
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
@@ -1498,6 +1508,10 @@ pub async fn search_within_group(
     let dataset_config =
         DatasetConfiguration::from_json(dataset_org_plan_sub.dataset.server_configuration.clone());
 
+    if let Some(sort_options) = &data.sort_options {
+        sort_options.validate()?;
+    }
+
     //search over the links as well
     let group_id = data.group_id;
     let dataset_id = dataset_org_plan_sub.dataset.id;
@@ -1545,6 +1559,7 @@ pub async fn search_within_group(
                 parsed_query.to_parsed_query()?,
                 group,
                 search_pool,
+                redis_pool,
                 dataset_org_plan_sub.dataset.clone(),
                 &dataset_config,
             )
@@ -1556,6 +1571,7 @@ pub async fn search_within_group(
                 parsed_query,
                 group,
                 search_pool,
+                redis_pool,
                 dataset_org_plan_sub.dataset.clone(),
                 &dataset_config,
             )
@@ -1622,8 +1638,12 @@ pub struct SearchOverGroupsReqPayload {
     pub filters: Option<ChunkFilter>,
     /// Highlight Options lets you specify different methods to highlight the chunks in the result set. If not specified, this defaults to the score of the chunks.
     pub highlight_options: Option<HighlightOptions>,
-    /// Set score_threshold to a float to filter out chunks with a score below the threshold. This threshold applies before weight and bias modifications. If not specified, this defaults to 0.0.
+    /// Set score_threshold to a float to filter out chunks with a score below the threshold. This threshold applies before weight and bias modifications. Hybrid searches fused with "rrf" or "weighted" apply the threshold to the semantic and full-text scores of each list before fusion. If not specified, this defaults to 0.0.
     pub score_threshold: Option<f32>,
+    /// Hybrid Options lets you specify how the semantic and full-text group results are merged when search_type is "hybrid". If not specified, the results are re-ranked with the cross encoder.
+    pub hybrid_options: Option<HybridSearchOptions>,
+    /// Search_after is the cursor returned with a previous page of group results. Set it to fetch the page following that group instead of using page offsets, which keeps deep pagination stable and skips the total page count. The cursor is returned for the first page and for every page fetched with a cursor.
+    pub search_after: Option<String>,
     /// Group_size is the number of chunks to fetch for each group. The default is 3. If a group has less than group_size chunks, all chunks will be returned. If this is set to a large number, we recommend setting slim_chunks to true to avoid returning the content and chunk_html of the chunks so as to lower the amount of time required for content download and serialization.
     pub group_size: Option<u32>,
     /// Set slim_chunks to true to avoid returning the content and chunk_html of the chunks. This is useful for when you want to reduce amount of data over the wire for latency improvement (typicall 10-50ms). Default is false.
@@ -1656,11 +1676,12 @@ pub struct SearchOverGroupsReqPayload {
         ("ApiKey" = ["readonly"]),
     )
 )]
-#[tracing::instrument(skip(pool, event_queue))]
+#[tracing::instrument(skip(pool, event_queue, redis_pool))]
 pub async fn search_over_groups(
     data: web::Json<SearchOverGroupsReqPayload>,
     pool: web::Data<Pool>,
     event_queue: web::Data<EventQueue>,
+    redis_pool: web::Data<RedisPool>,
     api_version: APIVersion,
     _required_user: LoggedUser,
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
@@ -1704,6 +1725,7 @@ pub async fn search_over_groups(
                 data.clone(),
                 parsed_query,
                 pool,
+                redis_pool,
                 dataset_org_plan_sub.dataset.clone(),
                 &dataset_config,
                 &mut timer,
@@ -1715,6 +1737,7 @@ pub async fn search_over_groups(
                 data.clone(),
                 parsed_query.to_parsed_query()?,
                 pool,
+                redis_pool,
                 dataset_org_plan_sub.dataset.clone(),
                 &dataset_config,
                 &mut timer,
@@ -1732,6 +1755,7 @@ pub async fn search_over_groups(
                 data.clone(),
                 parsed_query,
                 pool,
+                redis_pool,
                 dataset_org_plan_sub.dataset.clone(),
                 &dataset_config,
                 &mut timer,
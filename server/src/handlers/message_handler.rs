@@ -5,7 +5,7 @@ use super::{
 use crate::{
     data::models::{
         self, ChunkMetadataTypes, DatasetAndOrgWithSubAndPlan, DatasetConfiguration,
-        HighlightOptions, LLMOptions, Pool, SearchMethod,
+        HighlightOptions, LLMOptions, Pool, RedisPool, SearchMethod,
     },
     errors::ServiceError,
     get_env,
@@ -120,13 +120,14 @@ pub struct CreateMessageReqPayload {
         ("ApiKey" = ["readonly"]),
     )
 )]
-#[tracing::instrument(skip(pool, event_queue))]
+#[tracing::instrument(skip(pool, event_queue, redis_pool))]
 pub async fn create_message(
     data: web::Json<CreateMessageReqPayload>,
     user: AdminOnly,
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
     event_queue: web::Data<EventQueue>,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let message_count_pool = pool.clone();
     let message_count_org_id = dataset_org_plan_sub.organization.organization.id;
@@ -210,6 +211,7 @@ pub async fn create_message(
         dataset_org_plan_sub.dataset,
         stream_response_pool,
         event_queue,
+        redis_pool,
         dataset_config,
         create_message_data,
     )
@@ -353,13 +355,14 @@ impl From<RegenerateMessageReqPayload> for CreateMessageReqPayload {
         ("ApiKey" = ["readonly"]),
     )
 )]
-#[tracing::instrument(skip(pool, event_queue))]
+#[tracing::instrument(skip(pool, event_queue, redis_pool))]
 pub async fn edit_message(
     data: web::Json<EditMessageReqPayload>,
     user: AdminOnly,
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
     pool: web::Data<Pool>,
     event_queue: web::Data<EventQueue>,
+    redis_pool: web::Data<RedisPool>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let topic_id: uuid::Uuid = data.topic_id;
     let message_sort_order = data.message_sort_order;
@@ -392,6 +395,7 @@ pub async fn edit_message(
         dataset_org_plan_sub,
         event_queue,
         third_pool,
+        redis_pool,
     )
     .await
 }
@@ -417,13 +421,14 @@ pub async fn edit_message(
         ("ApiKey" = ["readonly"]),
     )
 )]
-#[tracing::instrument(skip(pool, event_queue))]
+#[tracing::instrument(skip(pool, event_queue, redis_pool))]
 pub async fn regenerate_message_patch(
     data: web::Json<RegenerateMessageReqPayload>,
     user: AdminOnly,
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
     pool: web::Data<Pool>,
     event_queue: web::Data<EventQueue>,
+    redis_pool: web::Data<RedisPool>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let topic_id = data.topic_id;
     let dataset_config =
@@ -451,6 +456,7 @@ pub async fn regenerate_message_patch(
             dataset_org_plan_sub.dataset,
             create_message_pool,
             event_queue,
+            redis_pool,
             dataset_config,
             data.into_inner().into(),
         )
@@ -505,6 +511,7 @@ pub async fn regenerate_message_patch(
         dataset_org_plan_sub.dataset,
         create_message_pool,
         event_queue,
+        redis_pool,
         dataset_config,
         data.into_inner().into(),
     )
@@ -533,13 +540,14 @@ pub async fn regenerate_message_patch(
     )
 )]
 #[deprecated]
-#[tracing::instrument(skip(pool, event_queue))]
+#[tracing::instrument(skip(pool, event_queue, redis_pool))]
 pub async fn regenerate_message(
     data: web::Json<RegenerateMessageReqPayload>,
     user: AdminOnly,
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
     pool: web::Data<Pool>,
     event_queue: web::Data<EventQueue>,
+    redis_pool: web::Data<RedisPool>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let topic_id = data.topic_id;
     let dataset_config =
@@ -567,6 +575,7 @@ pub async fn regenerate_message(
             dataset_org_plan_sub.dataset,
             create_message_pool,
             event_queue,
+            redis_pool,
             dataset_config,
             data.into_inner().into(),
         )
@@ -621,6 +630,7 @@ pub async fn regenerate_message(
         dataset_org_plan_sub.dataset,
         create_message_pool,
         event_queue,
+        redis_pool,
         dataset_config,
         data.into_inner().into(),
     )
@@ -659,11 +669,12 @@ pub struct SuggestedQueriesResponse {
     )
 
 )]
-#[tracing::instrument(skip(pool))]
+#[tracing::instrument(skip(pool, redis_pool))]
 pub async fn get_suggested_queries(
     data: web::Json<SuggestedQueriesReqPayload>,
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     _required_user: LoggedUser,
 ) -> Result<HttpResponse, ServiceError> {
     let dataset_config =
@@ -703,6 +714,7 @@ pub async fn get_suggested_queries(
             negated_words: None,
         },
         pool,
+        redis_pool,
         dataset_org_plan_sub.dataset.clone(),
         &dataset_config,
         &mut Timer::new(),
@@ -2,6 +2,7 @@ pub mod analytics_handler;
 pub mod auth_handler;
 pub mod chunk_handler;
 pub mod dataset_handler;
+pub mod evaluation_handler;
 pub mod event_handler;
 pub mod file_handler;
 pub mod group_handler;
@@ -9,6 +10,9 @@ pub mod invitation_handler;
 pub mod message_handler;
 pub mod metrics_handler;
 pub mod organization_handler;
+pub mod rank_model_handler;
 pub mod stripe_handler;
+pub mod synonym_handler;
 pub mod topic_handler;
 pub mod user_handler;
+pub mod webhook_handler;
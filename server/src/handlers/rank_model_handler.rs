@@ -0,0 +1,114 @@
+use super::auth_handler::AdminOnly;
+use crate::{
+    data::models::{DatasetAndOrgWithSubAndPlan, DatasetRankModel, Pool, RankModel},
+    errors::ServiceError,
+    operators::rank_model_operator::{
+        delete_rank_model_query, get_rank_model_query, upsert_rank_model_query,
+    },
+};
+use actix_web::{web, HttpResponse};
+
+/// Upload Rank Model
+///
+/// Upload the learning-to-rank model of the dataset specified by the TR-Dataset header, replacing the previous one. The model is either linear or a set of gradient boosted regression trees over features of each chunk, see RankModel for the available features. Searches opt into rescoring with the model by setting `sort_options.use_rank_model` to true. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    put,
+    path = "/rank_model",
+    context_path = "/api",
+    tag = "Rank Model",
+    request_body(content = RankModel, description = "JSON request payload to upload a rank model", content_type = "application/json"),
+    responses(
+        (status = 200, description = "The uploaded rank model", body = RankModel),
+        (status = 400, description = "Service error relating to uploading the rank model", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool, data))]
+pub async fn upload_rank_model(
+    data: web::Json<RankModel>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let rank_model = data.into_inner();
+    rank_model.validate()?;
+
+    let dataset_rank_model = upsert_rank_model_query(
+        DatasetRankModel::from_details(dataset_org_plan_sub.dataset.id, &rank_model),
+        pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(dataset_rank_model.rank_model()?))
+}
+
+/// Get Rank Model
+///
+/// Get the learning-to-rank model of the dataset specified by the TR-Dataset header. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    get,
+    path = "/rank_model",
+    context_path = "/api",
+    tag = "Rank Model",
+    responses(
+        (status = 200, description = "The rank model of the dataset", body = RankModel),
+        (status = 400, description = "Service error relating to getting the rank model", body = ErrorResponseBody),
+        (status = 404, description = "No rank model was uploaded for the dataset", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool))]
+pub async fn get_rank_model(
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let dataset_rank_model = get_rank_model_query(dataset_org_plan_sub.dataset.id, pool)
+        .await?
+        .ok_or(ServiceError::NotFound(
+            "No rank model found for dataset".to_string(),
+        ))?;
+
+    Ok(HttpResponse::Ok().json(dataset_rank_model.rank_model()?))
+}
+
+/// Delete Rank Model
+///
+/// Delete the learning-to-rank model of the dataset specified by the TR-Dataset header. Searches which set `sort_options.use_rank_model` fail until a new model is uploaded. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    delete,
+    path = "/rank_model",
+    context_path = "/api",
+    tag = "Rank Model",
+    responses(
+        (status = 204, description = "Confirmation that the rank model was deleted"),
+        (status = 400, description = "Service error relating to deleting the rank model", body = ErrorResponseBody),
+        (status = 404, description = "No rank model was uploaded for the dataset", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool))]
+pub async fn delete_rank_model(
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    delete_rank_model_query(dataset_org_plan_sub.dataset.id, pool).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,256 @@
+use super::auth_handler::AdminOnly;
+use crate::{
+    data::models::{DatasetAndOrgWithSubAndPlan, Pool, RedisPool, SynonymSet, SynonymSetDTO},
+    errors::ServiceError,
+    operators::synonym_operator::{
+        clear_synonym_sets_cache_query, create_synonym_set_query, delete_synonym_set_query,
+        get_synonym_set_by_id_query, get_synonym_sets_for_dataset_query, update_synonym_set_query,
+    },
+};
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "synonyms": ["laptop", "notebook"],
+}))]
+pub struct SynonymSetReqPayload {
+    /// Root term of a one way set. Queries containing the root also match its synonyms, queries containing a synonym are not expanded. If not specified, the set is bidirectional and every synonym matches all of the others.
+    pub root: Option<String>,
+    /// Terms or phrases which are treated as synonyms. Bidirectional sets need at least two synonyms, one way sets at least one.
+    pub synonyms: Vec<String>,
+}
+
+impl SynonymSetReqPayload {
+    /// Trims the terms of the set and checks that it can expand a query.
+    fn into_parts(self) -> Result<(Option<String>, Vec<String>), ServiceError> {
+        let root = self.root.map(|root| root.trim().to_string());
+        let synonyms = self
+            .synonyms
+            .into_iter()
+            .map(|synonym| synonym.trim().to_string())
+            .collect::<Vec<String>>();
+
+        if root.as_ref().is_some_and(|root| root.is_empty())
+            || synonyms.iter().any(|synonym| synonym.is_empty())
+        {
+            return Err(ServiceError::BadRequest(
+                "Synonyms and the root cannot be empty".to_string(),
+            ));
+        }
+
+        let min_synonyms = if root.is_some() { 1 } else { 2 };
+        if synonyms.len() < min_synonyms {
+            return Err(ServiceError::BadRequest(
+                "Bidirectional synonym sets need at least two synonyms and one way sets at least one"
+                    .to_string(),
+            ));
+        }
+
+        Ok((root, synonyms))
+    }
+}
+
+/// Create Synonym Set
+///
+/// Create a synonym set for the dataset specified by the TR-Dataset header. Fulltext and BM25 queries and highlights containing a term of the set also match its synonyms. Synonyms are applied at query time, so chunks do not have to be re-ingested. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    post,
+    path = "/synonym_set",
+    context_path = "/api",
+    tag = "Synonym",
+    request_body(content = SynonymSetReqPayload, description = "JSON request payload to create a synonym set", content_type = "application/json"),
+    responses(
+        (status = 200, description = "The created synonym set", body = SynonymSetDTO),
+        (status = 400, description = "Service error relating to creating the synonym set", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn create_synonym_set(
+    data: web::Json<SynonymSetReqPayload>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (root, synonyms) = data.into_inner().into_parts()?;
+
+    let synonym_set = create_synonym_set_query(
+        SynonymSet::from_details(dataset_org_plan_sub.dataset.id, root, synonyms),
+        pool,
+    )
+    .await?;
+
+    clear_synonym_sets_cache_query(dataset_org_plan_sub.dataset.id, redis_pool).await?;
+
+    Ok(HttpResponse::Ok().json(SynonymSetDTO::from(synonym_set)))
+}
+
+/// Get Synonym Sets
+///
+/// Get the synonym sets of the dataset specified by the TR-Dataset header. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    get,
+    path = "/synonym_set",
+    context_path = "/api",
+    tag = "Synonym",
+    responses(
+        (status = 200, description = "Synonym sets of the dataset", body = Vec<SynonymSetDTO>),
+        (status = 400, description = "Service error relating to getting the synonym sets", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool))]
+pub async fn get_synonym_sets(
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let synonym_sets =
+        get_synonym_sets_for_dataset_query(dataset_org_plan_sub.dataset.id, pool).await?;
+
+    Ok(HttpResponse::Ok().json(
+        synonym_sets
+            .into_iter()
+            .map(SynonymSetDTO::from)
+            .collect::<Vec<SynonymSetDTO>>(),
+    ))
+}
+
+/// Get Synonym Set
+///
+/// Get a synonym set of the dataset specified by the TR-Dataset header. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    get,
+    path = "/synonym_set/{synonym_set_id}",
+    context_path = "/api",
+    tag = "Synonym",
+    responses(
+        (status = 200, description = "The synonym set", body = SynonymSetDTO),
+        (status = 400, description = "Service error relating to getting the synonym set", body = ErrorResponseBody),
+        (status = 404, description = "Synonym set not found", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        ("synonym_set_id" = uuid::Uuid, description = "The id of the synonym set to get"),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool))]
+pub async fn get_synonym_set(
+    synonym_set_id: web::Path<uuid::Uuid>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let synonym_set = get_synonym_set_by_id_query(
+        synonym_set_id.into_inner(),
+        dataset_org_plan_sub.dataset.id,
+        pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(SynonymSetDTO::from(synonym_set)))
+}
+
+/// Update Synonym Set
+///
+/// Replace the root and synonyms of a synonym set of the dataset specified by the TR-Dataset header. Searches use the new synonyms right away. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    put,
+    path = "/synonym_set/{synonym_set_id}",
+    context_path = "/api",
+    tag = "Synonym",
+    request_body(content = SynonymSetReqPayload, description = "JSON request payload to replace a synonym set", content_type = "application/json"),
+    responses(
+        (status = 200, description = "The updated synonym set", body = SynonymSetDTO),
+        (status = 400, description = "Service error relating to updating the synonym set", body = ErrorResponseBody),
+        (status = 404, description = "Synonym set not found", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        ("synonym_set_id" = uuid::Uuid, description = "The id of the synonym set to update"),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn update_synonym_set(
+    synonym_set_id: web::Path<uuid::Uuid>,
+    data: web::Json<SynonymSetReqPayload>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (root, synonyms) = data.into_inner().into_parts()?;
+
+    let synonym_set = update_synonym_set_query(
+        synonym_set_id.into_inner(),
+        dataset_org_plan_sub.dataset.id,
+        root,
+        synonyms,
+        pool,
+    )
+    .await?;
+
+    clear_synonym_sets_cache_query(dataset_org_plan_sub.dataset.id, redis_pool).await?;
+
+    Ok(HttpResponse::Ok().json(SynonymSetDTO::from(synonym_set)))
+}
+
+/// Delete Synonym Set
+///
+/// Delete a synonym set of the dataset specified by the TR-Dataset header. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    delete,
+    path = "/synonym_set/{synonym_set_id}",
+    context_path = "/api",
+    tag = "Synonym",
+    responses(
+        (status = 204, description = "Confirmation that the synonym set was deleted"),
+        (status = 400, description = "Service error relating to deleting the synonym set", body = ErrorResponseBody),
+        (status = 404, description = "Synonym set not found", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        ("synonym_set_id" = uuid::Uuid, description = "The id of the synonym set to delete"),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn delete_synonym_set(
+    synonym_set_id: web::Path<uuid::Uuid>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    delete_synonym_set_query(
+        synonym_set_id.into_inner(),
+        dataset_org_plan_sub.dataset.id,
+        pool,
+    )
+    .await?;
+
+    clear_synonym_sets_cache_query(dataset_org_plan_sub.dataset.id, redis_pool).await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
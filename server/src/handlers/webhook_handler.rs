@@ -0,0 +1,169 @@
+use super::auth_handler::AdminOnly;
+use crate::{
+    data::models::{
+        DatasetAndOrgWithSubAndPlan, EventTypeRequest, Pool, RedisPool, Webhook, WebhookDTO,
+    },
+    operators::webhook_operator::{
+        create_webhook_query, delete_webhook_query, generate_webhook_secret,
+        get_webhooks_for_dataset_query, parse_webhook_url, resolve_webhook_url,
+    },
+};
+use actix_web::{web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[schema(example = json!({
+    "url": "https://example.com/trieve-webhook",
+    "event_types": ["file_uploaded", "bulk_chunk_upload_failed"],
+}))]
+pub struct CreateWebhookReqPayload {
+    /// The http or https url events are POSTed to. It must resolve to a public address, redirects are not followed.
+    pub url: String,
+    /// The types of events to deliver. Leave undefined or empty to receive all events.
+    pub event_types: Option<Vec<EventTypeRequest>>,
+    /// Secret used to sign deliveries. If not specified, one is generated and returned.
+    pub secret: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct CreateWebhookResponse {
+    pub webhook: WebhookDTO,
+    /// Secret used to sign deliveries. It is only returned when the webhook is created. Each delivery carries an `X-Trieve-Signature` header of the form `sha256=<hex>`, the HMAC-SHA256 of `<X-Trieve-Timestamp>.<request body>` keyed with this secret.
+    pub secret: String,
+}
+
+/// Create Webhook
+///
+/// Subscribe a url to the worker events of the dataset specified by the TR-Dataset header, such as file_uploaded or bulk_chunk_upload_failed. Events are POSTed as JSON by the webhook-worker and signed with the webhook's secret. Failed deliveries are retried with exponential backoff. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    post,
+    path = "/webhook",
+    context_path = "/api",
+    tag = "Webhook",
+    request_body(content = CreateWebhookReqPayload, description = "JSON request payload to create a webhook", content_type = "application/json"),
+    responses(
+        (status = 200, description = "The created webhook and its signing secret", body = CreateWebhookResponse),
+        (status = 400, description = "Service error relating to creating the webhook", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn create_webhook(
+    data: web::Json<CreateWebhookReqPayload>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let data = data.into_inner();
+
+    let url = parse_webhook_url(&data.url)?;
+    resolve_webhook_url(&url).await?;
+
+    let secret = match data.secret {
+        Some(secret) if !secret.is_empty() => secret,
+        _ => generate_webhook_secret(),
+    };
+
+    let event_types = data
+        .event_types
+        .filter(|event_types| !event_types.is_empty());
+
+    let webhook = create_webhook_query(
+        Webhook::from_details(
+            dataset_org_plan_sub.dataset.id,
+            url.to_string(),
+            event_types,
+            secret.clone(),
+        ),
+        pool,
+        redis_pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(CreateWebhookResponse {
+        webhook: webhook.into(),
+        secret,
+    }))
+}
+
+/// Get Webhooks
+///
+/// Get the webhooks of the dataset specified by the TR-Dataset header. Secrets are not returned. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    get,
+    path = "/webhook",
+    context_path = "/api",
+    tag = "Webhook",
+    responses(
+        (status = 200, description = "Webhooks of the dataset", body = Vec<WebhookDTO>),
+        (status = 400, description = "Service error relating to getting the webhooks", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool))]
+pub async fn get_webhooks(
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let webhooks = get_webhooks_for_dataset_query(dataset_org_plan_sub.dataset.id, pool).await?;
+
+    Ok(HttpResponse::Ok().json(
+        webhooks
+            .into_iter()
+            .map(WebhookDTO::from)
+            .collect::<Vec<WebhookDTO>>(),
+    ))
+}
+
+/// Delete Webhook
+///
+/// Delete a webhook of the dataset specified by the TR-Dataset header. Deliveries which are waiting to be retried are dropped. Auth'ed user or api key must have an admin or owner role for the specified dataset's organization.
+#[utoipa::path(
+    delete,
+    path = "/webhook/{webhook_id}",
+    context_path = "/api",
+    tag = "Webhook",
+    responses(
+        (status = 204, description = "Confirmation that the webhook was deleted"),
+        (status = 400, description = "Service error relating to deleting the webhook", body = ErrorResponseBody),
+        (status = 404, description = "Webhook not found", body = ErrorResponseBody),
+    ),
+    params(
+        ("TR-Dataset" = String, Header, description = "The dataset id or tracking_id to use for the request. We assume you intend to use an id if the value is a valid uuid."),
+        ("webhook_id" = uuid::Uuid, description = "The id of the webhook to delete"),
+    ),
+    security(
+        ("ApiKey" = ["admin"]),
+    )
+)]
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn delete_webhook(
+    webhook_id: web::Path<uuid::Uuid>,
+    _user: AdminOnly,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<HttpResponse, actix_web::Error> {
+    delete_webhook_query(
+        webhook_id.into_inner(),
+        dataset_org_plan_sub.dataset.id,
+        pool,
+        redis_pool,
+    )
+    .await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -17,6 +17,8 @@ use actix_identity::IdentityMiddleware;
 use actix_session::{config::PersistentSession, storage::RedisSessionStore, SessionMiddleware};
 use actix_web::{
     cookie::{Key, SameSite},
+    guard,
+    http::header,
     middleware::{Compress, Logger},
     web::{self, PayloadConfig},
     App, HttpServer,
@@ -206,7 +208,29 @@ impl Modify for SecurityAddon {
         handlers::file_handler::upload_file_handler,
         handlers::file_handler::get_file_handler,
         handlers::file_handler::delete_file_handler,
+        handlers::file_handler::create_presigned_url_for_file_handler,
+        handlers::file_handler::complete_file_upload_handler,
+        handlers::file_handler::reprocess_file_handler,
         handlers::event_handler::get_events,
+        handlers::webhook_handler::create_webhook,
+        handlers::webhook_handler::get_webhooks,
+        handlers::webhook_handler::delete_webhook,
+        handlers::evaluation_handler::create_judgment_list,
+        handlers::evaluation_handler::get_judgment_lists,
+        handlers::evaluation_handler::get_judgment_list,
+        handlers::evaluation_handler::update_judgment_list,
+        handlers::evaluation_handler::delete_judgment_list,
+        handlers::evaluation_handler::run_search_evaluation,
+        handlers::evaluation_handler::get_search_evaluations,
+        handlers::evaluation_handler::get_search_evaluation,
+        handlers::synonym_handler::create_synonym_set,
+        handlers::synonym_handler::get_synonym_sets,
+        handlers::synonym_handler::get_synonym_set,
+        handlers::synonym_handler::update_synonym_set,
+        handlers::synonym_handler::delete_synonym_set,
+        handlers::rank_model_handler::upload_rank_model,
+        handlers::rank_model_handler::get_rank_model,
+        handlers::rank_model_handler::delete_rank_model,
         handlers::organization_handler::create_organization,
         handlers::organization_handler::get_organization,
         handlers::organization_handler::update_organization,
@@ -222,6 +246,11 @@ impl Modify for SecurityAddon {
         handlers::dataset_handler::get_usage_by_dataset_id,
         handlers::dataset_handler::get_datasets_from_organization,
         handlers::dataset_handler::clear_dataset,
+        handlers::dataset_handler::export_dataset,
+        handlers::dataset_handler::import_dataset,
+        handlers::dataset_handler::get_dataset_archive_status,
+        handlers::dataset_handler::get_embedding_migration,
+        handlers::dataset_handler::clone_dataset,
         handlers::stripe_handler::direct_to_payment_link,
         handlers::stripe_handler::cancel_subscription,
         handlers::stripe_handler::update_subscription_plan,
@@ -235,6 +264,7 @@ impl Modify for SecurityAddon {
         handlers::analytics_handler::send_ctr_data,
         handlers::analytics_handler::get_ctr_analytics,
         handlers::analytics_handler::set_query_rating,
+        handlers::analytics_handler::generate_judgment_list,
         handlers::metrics_handler::get_metrics,
     ),
     components(
@@ -311,9 +341,23 @@ impl Modify for SecurityAddon {
             handlers::user_handler::DeleteUserApiKeyRequest,
             operators::group_operator::GroupsForChunk,
             handlers::file_handler::UploadFileReqPayload,
+            handlers::file_handler::ChunkStrategy,
+            handlers::file_handler::FileColumnMapping,
             handlers::file_handler::UploadFileResult,
+            handlers::file_handler::UploadFileMultipartReqPayload,
+            handlers::file_handler::CreatePresignedUrlForFileReqPayload,
+            handlers::file_handler::CreatePresignedUrlForFileResponse,
+            handlers::file_handler::ReprocessFileReqPayload,
             handlers::invitation_handler::InvitationData,
             handlers::event_handler::GetEventsData,
+            handlers::webhook_handler::CreateWebhookReqPayload,
+            handlers::webhook_handler::CreateWebhookResponse,
+            handlers::evaluation_handler::CreateJudgmentListReqPayload,
+            handlers::evaluation_handler::UpdateJudgmentListReqPayload,
+            handlers::evaluation_handler::RunSearchEvaluationReqPayload,
+            handlers::synonym_handler::SynonymSetReqPayload,
+            handlers::evaluation_handler::RunSearchEvaluationResponse,
+            operators::evaluation_operator::SearchEvaluationWithQueries,
             handlers::organization_handler::CreateOrganizationReqPayload,
             handlers::organization_handler::UpdateOrganizationReqPayload,
             handlers::organization_handler::UpdateAllOrgDatasetConfigsReqPayload,
@@ -326,6 +370,11 @@ impl Modify for SecurityAddon {
             handlers::dataset_handler::CreateDatasetRequest,
             handlers::dataset_handler::UpdateDatasetRequest,
             handlers::dataset_handler::GetDatasetsPagination,
+            handlers::dataset_handler::ExportDatasetReqPayload,
+            handlers::dataset_handler::ImportDatasetReqPayload,
+            handlers::dataset_handler::CloneDatasetReqPayload,
+            handlers::dataset_handler::ImportDatasetResponse,
+            handlers::dataset_handler::CloneDatasetResponse,
             data::models::DatasetConfigurationDTO,
             operators::analytics_operator::HeadQueryResponse,
             operators::analytics_operator::LatencyGraphResponse,
@@ -340,6 +389,8 @@ impl Modify for SecurityAddon {
             operators::analytics_operator::CTRRecommendationsWithClicksResponse,
             operators::analytics_operator::CTRRecommendationsWithoutClicksResponse,
             handlers::analytics_handler::CTRDataRequestBody,
+            handlers::analytics_handler::GenerateJudgmentListReqPayload,
+            handlers::analytics_handler::GenerateJudgmentListResponse,
             operators::chunk_operator::HighlightStrategy,
             handlers::stripe_handler::CreateSetupCheckoutSessionResPayload,
             data::models::DateRange,
@@ -404,14 +455,53 @@ impl Modify for SecurityAddon {
             data::models::SortOptions,
             data::models::LLMOptions,
             data::models::HighlightOptions,
+            data::models::CollapseOptions,
+            data::models::SpellingCorrection,
+            data::models::FacetOptions,
+            data::models::TermFacet,
+            data::models::RangeFacet,
+            data::models::FacetRange,
+            data::models::FacetBound,
+            data::models::GeoDistanceFacet,
+            data::models::DistanceRange,
+            data::models::FacetResults,
+            data::models::TermFacetResult,
+            data::models::TermCount,
+            data::models::RangeFacetResult,
+            data::models::RangeBucket,
+            data::models::GeoDistanceFacetResult,
+            data::models::DistanceBucket,
+            data::models::HybridSearchOptions,
+            data::models::FusionMethod,
             data::models::SortByField,
+            data::models::SortByFields,
+            data::models::NullsOrder,
             data::models::SortBySearchType,
+            data::models::DecayFunction,
+            data::models::DecayFunctionType,
+            data::models::DecayOrigin,
             data::models::ReRankOptions,
             data::models::Topic,
             data::models::Message,
             data::models::ChunkMetadata,
             data::models::ChatMessageProxy,
             data::models::WorkerEvent,
+            data::models::WebhookDTO,
+            data::models::Judgment,
+            data::models::JudgmentQuery,
+            data::models::JudgmentListDTO,
+            data::models::SearchEvaluation,
+            data::models::SynonymSetDTO,
+            data::models::SearchEvaluationQueryResult,
+            data::models::RankModel,
+            data::models::RankScorer,
+            data::models::RankTreeNode,
+            data::models::DatasetArchiveStatus,
+            data::models::DatasetArchiveJobType,
+            data::models::DatasetArchiveJobState,
+            data::models::EmbeddingModelOptions,
+            data::models::EmbeddingMigration,
+            data::models::EmbeddingMigrationState,
             data::models::File,
             data::models::ChunkGroup,
             data::models::ChunkGroupAndFileId,
@@ -442,6 +532,9 @@ impl Modify for SecurityAddon {
             data::models::ConditionType,
             data::models::HasIDCondition,
             data::models::DistanceMetric,
+            data::models::Bm25Language,
+            data::models::Bm25Segmentation,
+            data::models::DedupPolicy,
             errors::ErrorResponseBody,
             middleware::api_version::APIVersion,
         )
@@ -456,6 +549,10 @@ impl Modify for SecurityAddon {
         (name = "Chunk Group", description = "Chunk groups endpoint. Think of a chunk_group as a bookmark folder within the dataset."),
         (name = "File", description = "File endpoint. When files are uploaded, they are stored in S3 and broken up into chunks with text extraction from Apache Tika. You can upload files of pretty much any type up to 1GB in size. See chunking algorithm details at `docs.trieve.ai` for more information on how chunking works. Improved default chunking is on our roadmap."),
         (name = "Events", description = "Notifications endpoint. Files are uploaded asynchronously and events are sent to the user when the upload is complete."),
+        (name = "Evaluation", description = "Evaluation endpoint. Store judgment lists of labeled queries and measure search relevance against them with nDCG, MRR and recall."),
+        (name = "Rank Model", description = "Rank model endpoint. Upload a per-dataset learning-to-rank model which rescores chunk search results from features such as the retrieval scores, recency and click-through priors."),
+        (name = "Synonym", description = "Synonym endpoint. Manage per-dataset synonym sets which expand fulltext and BM25 queries and highlights at search time."),
+        (name = "Webhook", description = "Webhook endpoint. Subscribe urls to a dataset's events to have them delivered as signed POST requests instead of polling the events endpoint."),
         (name = "Topic", description = "Topic chat endpoint. Think of topics as the storage system for gen-ai chat memory. Gen AI messages belong to topics."),
         (name = "Message", description = "Message chat endpoint. Messages are units belonging to a topic in the context of a chat with a LLM. There are system, user, and assistant messages."),
         (name = "Stripe", description = "Stripe endpoint. Used for the managed SaaS version of this app. Eventually this will become a micro-service. Reach out to the team using contact info found at `docs.trieve.ai` for more information."),
@@ -591,7 +688,7 @@ pub fn main() -> std::io::Result<()> {
         }
 
 
-        let (clickhouse_client, event_queue) = if std::env::var("USE_ANALYTICS").unwrap_or("false".to_string()).parse().unwrap_or(false) {
+        let (clickhouse_client, mut event_queue) = if std::env::var("USE_ANALYTICS").unwrap_or("false".to_string()).parse().unwrap_or(false) {
             log::info!("Analytics enabled");
 
             let args  = SetupArgs {
@@ -622,6 +719,8 @@ pub fn main() -> std::io::Result<()> {
             (clickhouse::Client::default(), EventQueue::default())
         };
 
+        event_queue.enable_webhooks(redis_pool.clone());
+
 
         let metrics = Metrics::new().map_err(|e| {
             std::io::Error::new(std::io::ErrorKind::Other, format!("Failed to create metrics {:?}", e))
@@ -742,6 +841,26 @@ pub fn main() -> std::io::Result<()> {
                                     web::resource("/get_all_tags")
                                         .route(web::post().to(handlers::dataset_handler::get_all_tags)),
                                 )
+                                .service(
+                                    web::resource("/export")
+                                        .route(web::post().to(handlers::dataset_handler::export_dataset)),
+                                )
+                                .service(
+                                    web::resource("/import")
+                                        .route(web::post().to(handlers::dataset_handler::import_dataset)),
+                                )
+                                .service(
+                                    web::resource("/archive/{job_id}")
+                                        .route(web::get().to(handlers::dataset_handler::get_dataset_archive_status)),
+                                )
+                                .service(
+                                    web::resource("/embedding_migration/{dataset_id}")
+                                        .route(web::get().to(handlers::dataset_handler::get_embedding_migration)),
+                                )
+                                .service(
+                                    web::resource("/{dataset_id}/clone")
+                                        .route(web::post().to(handlers::dataset_handler::clone_dataset)),
+                                )
                                 .service(
                                     web::resource("/{dataset_id}")
                                         .route(web::get().to(handlers::dataset_handler::get_dataset))
@@ -981,8 +1100,40 @@ pub fn main() -> std::io::Result<()> {
                         .service(
                             web::scope("/file")
                                 .service(
-                                    web::resource("").route(
-                                        web::post().to(handlers::file_handler::upload_file_handler),
+                                    web::resource("")
+                                        .route(
+                                            web::post()
+                                                .guard(guard::fn_guard(|ctx| {
+                                                    ctx.head()
+                                                        .headers()
+                                                        .get(header::CONTENT_TYPE)
+                                                        .and_then(|content_type| content_type.to_str().ok())
+                                                        .is_some_and(|content_type| {
+                                                            content_type.starts_with("multipart/form-data")
+                                                        })
+                                                }))
+                                                .to(handlers::file_handler::upload_file_multipart_handler),
+                                        )
+                                        .route(
+                                            web::post().to(handlers::file_handler::upload_file_handler),
+                                        ),
+                                )
+                                .service(
+                                    web::resource("/presigned_url").route(
+                                        web::post().to(
+                                            handlers::file_handler::create_presigned_url_for_file_handler,
+                                        ),
+                                    ),
+                                )
+                                .service(
+                                    web::resource("/{file_id}/complete").route(
+                                        web::post()
+                                            .to(handlers::file_handler::complete_file_upload_handler),
+                                    ),
+                                )
+                                .service(
+                                    web::resource("/{file_id}/reprocess").route(
+                                        web::post().to(handlers::file_handler::reprocess_file_handler),
                                     ),
                                 )
                                 .service(
@@ -1004,6 +1155,65 @@ pub fn main() -> std::io::Result<()> {
                                     .route(web::post().to(handlers::event_handler::get_events)),
                             ),
                         )
+                        .service(
+                            web::scope("/webhook")
+                                .service(
+                                    web::resource("")
+                                        .route(web::post().to(handlers::webhook_handler::create_webhook))
+                                        .route(web::get().to(handlers::webhook_handler::get_webhooks)),
+                                )
+                                .service(
+                                    web::resource("/{webhook_id}").route(
+                                        web::delete().to(handlers::webhook_handler::delete_webhook),
+                                    ),
+                                ),
+                        )
+                        .service(
+                            web::scope("/judgment_list")
+                                .service(
+                                    web::resource("")
+                                        .route(web::post().to(handlers::evaluation_handler::create_judgment_list))
+                                        .route(web::get().to(handlers::evaluation_handler::get_judgment_lists)),
+                                )
+                                .service(
+                                    web::resource("/{judgment_list_id}")
+                                        .route(web::get().to(handlers::evaluation_handler::get_judgment_list))
+                                        .route(web::put().to(handlers::evaluation_handler::update_judgment_list))
+                                        .route(web::delete().to(handlers::evaluation_handler::delete_judgment_list)),
+                                ),
+                        )
+                        .service(
+                            web::scope("/synonym_set")
+                                .service(
+                                    web::resource("")
+                                        .route(web::post().to(handlers::synonym_handler::create_synonym_set))
+                                        .route(web::get().to(handlers::synonym_handler::get_synonym_sets)),
+                                )
+                                .service(
+                                    web::resource("/{synonym_set_id}")
+                                        .route(web::get().to(handlers::synonym_handler::get_synonym_set))
+                                        .route(web::put().to(handlers::synonym_handler::update_synonym_set))
+                                        .route(web::delete().to(handlers::synonym_handler::delete_synonym_set)),
+                                ),
+                        )
+                        .service(
+                            web::resource("/rank_model")
+                                .route(web::put().to(handlers::rank_model_handler::upload_rank_model))
+                                .route(web::get().to(handlers::rank_model_handler::get_rank_model))
+                                .route(web::delete().to(handlers::rank_model_handler::delete_rank_model)),
+                        )
+                        .service(
+                            web::scope("/evaluation")
+                                .service(
+                                    web::resource("")
+                                        .route(web::post().to(handlers::evaluation_handler::run_search_evaluation))
+                                        .route(web::get().to(handlers::evaluation_handler::get_search_evaluations)),
+                                )
+                                .service(
+                                    web::resource("/{evaluation_id}")
+                                        .route(web::get().to(handlers::evaluation_handler::get_search_evaluation)),
+                                ),
+                        )
                         .service(
                             web::resource("/health")
                                 .route(web::get().to(handlers::auth_handler::health_check)),
@@ -1125,6 +1335,10 @@ pub fn main() -> std::io::Result<()> {
                                 .route(web::put().to(handlers::analytics_handler::send_ctr_data))
                                 .route(web::post().to(handlers::analytics_handler::get_ctr_analytics)),
                             )
+                            .service(
+                                web::resource("/judgment_list")
+                                .route(web::post().to(handlers::analytics_handler::generate_judgment_list)),
+                            )
                         ),
                 )
         })
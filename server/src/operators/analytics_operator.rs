@@ -13,8 +13,9 @@ use crate::{
         RecommendationCTRMetrics, RecommendationEvent, RecommendationEventClickhouse,
         RecommendationsWithClicksCTRResponse, RecommendationsWithClicksCTRResponseClickhouse,
         RecommendationsWithoutClicksCTRResponse, RecommendationsWithoutClicksCTRResponseClickhouse,
-        SearchAnalyticsFilter, SearchCTRMetrics, SearchCTRMetricsClickhouse, SearchClusterTopics,
-        SearchLatencyGraph, SearchLatencyGraphClickhouse, SearchQueriesWithClicksCTRResponse,
+        SearchAnalyticsFilter, SearchCTRMetrics, SearchCTRMetricsClickhouse,
+        SearchChunkClicksClickhouse, SearchClusterTopics, SearchLatencyGraph,
+        SearchLatencyGraphClickhouse, SearchQueriesWithClicksCTRResponse,
         SearchQueriesWithClicksCTRResponseClickhouse, SearchQueriesWithoutClicksCTRResponse,
         SearchQueriesWithoutClicksCTRResponseClickhouse, SearchQueryEvent,
         SearchQueryEventClickhouse, SearchSortBy, SearchTypeCount, SortOrder, UsageGraphPoint,
@@ -25,6 +26,7 @@ use crate::{
 };
 
 use super::chunk_operator::get_metadata_from_tracking_id_query;
+use super::evaluation_operator::ImplicitJudgmentOptions;
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct SearchClusterResponse {
@@ -853,6 +855,75 @@ pub async fn send_ctr_data_query(
     Ok(())
 }
 
+/// Sums up the clicks on each chunk in the results of the most clicked queries. Each click scores its chunk `1 + log2(position)`, so that clicks further down the result list count for more as the user skipped the results above them, and is weighted by the rating of its search. Clicks with a `dwell_time` below `min_dwell_time` are bounces and are left out.
+pub async fn get_search_clicks_query(
+    dataset_id: uuid::Uuid,
+    filter: Option<SearchAnalyticsFilter>,
+    options: &ImplicitJudgmentOptions,
+    clickhouse_client: &clickhouse::Client,
+) -> Result<Vec<SearchChunkClicksClickhouse>, ServiceError> {
+    let mut search_queries_string = String::from(
+        "SELECT
+            id,
+            lower(trim(BOTH ' ' FROM query)) AS query,
+            JSONExtractInt(query_rating, 'rating') AS rating
+        FROM default.search_queries
+        WHERE dataset_id = ? AND search_queries.is_duplicate = 0",
+    );
+
+    if let Some(filter) = filter {
+        search_queries_string = filter.add_to_query(search_queries_string);
+    }
+
+    let query_string = format!(
+        "WITH clicks AS (
+            SELECT
+                searches.query AS query,
+                ctr_data.chunk_id AS chunk_id,
+                (1 + log2(greatest(ctr_data.`position`, 1))) * multiIf(searches.rating > 0, ?, searches.rating < 0, 1 / ?, 1) AS score
+            FROM default.ctr_data
+            JOIN ({}) AS searches ON ctr_data.request_id = searches.id
+            WHERE ctr_data.dataset_id = ? AND ctr_data.type = 'search' AND searches.query != ''
+                AND (NOT JSONHas(ctr_data.metadata, 'dwell_time') OR JSONExtractFloat(ctr_data.metadata, 'dwell_time') >= ?)
+        ),
+        top_queries AS (
+            SELECT query
+            FROM clicks
+            GROUP BY query
+            HAVING count() >= ?
+            ORDER BY count() DESC, query ASC
+            LIMIT ?
+        )
+        SELECT
+            query,
+            chunk_id,
+            sum(score) AS score,
+            count() AS clicks
+        FROM clicks
+        WHERE query IN (SELECT query FROM top_queries)
+        GROUP BY query, chunk_id",
+        search_queries_string
+    );
+
+    let clicks = clickhouse_client
+        .query(query_string.as_str())
+        .bind(options.rating_weight)
+        .bind(options.rating_weight)
+        .bind(dataset_id)
+        .bind(dataset_id)
+        .bind(options.min_dwell_time)
+        .bind(options.min_clicks as u64)
+        .bind(options.max_queries as u64)
+        .fetch_all::<SearchChunkClicksClickhouse>()
+        .await
+        .map_err(|e| {
+            log::error!("Error fetching search clicks: {:?}", e);
+            ServiceError::InternalServerError("Error fetching search clicks".to_string())
+        })?;
+
+    Ok(clicks)
+}
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct CTRSearchQueryWithClicksResponse {
     pub queries: Vec<SearchQueriesWithClicksCTRResponse>,
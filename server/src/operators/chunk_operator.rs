@@ -5,6 +5,7 @@ use crate::data::models::{
 };
 use crate::handlers::chunk_handler::UploadIngestionMessage;
 use crate::handlers::chunk_handler::{BulkUploadIngestionMessage, ChunkReqPayload};
+use crate::operators::dataset_operator::remove_chunks_from_bm25_corpus_stats;
 use crate::operators::group_operator::{
     check_group_ids_exist_query, get_group_ids_from_tracking_ids_query,
 };
@@ -13,7 +14,7 @@ use crate::operators::qdrant_operator::{
     delete_points_from_qdrant, get_qdrant_collection_from_dataset_config,
 };
 use crate::{
-    data::models::{ChunkMetadata, Pool},
+    data::models::{ChunkMetadata, Pool, RedisPool},
     errors::ServiceError,
 };
 use actix_web::web;
@@ -1190,6 +1191,7 @@ pub async fn delete_chunk_metadata_query(
     deleted_at: chrono::NaiveDateTime,
     dataset: Dataset,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     dataset_config: DatasetConfiguration,
 ) -> Result<(), ServiceError> {
     use crate::data::schema::chunk_group_bookmarks::dsl as chunk_group_bookmarks_columns;
@@ -1218,8 +1220,11 @@ pub async fn delete_chunk_metadata_query(
                             .filter(chunk_metadata_columns::dataset_id.eq(dataset.id))
                             .filter(chunk_metadata_columns::created_at.le(deleted_at)),
                     )
-                    .returning(chunk_metadata_columns::qdrant_point_id)
-                    .get_results::<uuid::Uuid>(conn)
+                    .returning((
+                        chunk_metadata_columns::qdrant_point_id,
+                        chunk_metadata_columns::chunk_html,
+                    ))
+                    .get_results::<(uuid::Uuid, Option<String>)>(conn)
                     .await?;
 
                     Ok(deleted_points)
@@ -1231,19 +1236,29 @@ pub async fn delete_chunk_metadata_query(
 
     let qdrant_collection = get_qdrant_collection_from_dataset_config(&dataset_config);
 
-    match transaction_result {
-        Ok(deleted_points) => delete_points_from_qdrant(deleted_points, qdrant_collection)
-            .await
-            .map_err(|_e| {
-                ServiceError::BadRequest("Failed to delete chunk from qdrant".to_string())
-            })
-            .map(|_| ()),
-        Err(_) => {
-            return Err(ServiceError::BadRequest(
-                "Failed to delete chunk data".to_string(),
-            ))
-        }
-    }
+    let (deleted_points, deleted_chunk_htmls): (Vec<uuid::Uuid>, Vec<Option<String>>) =
+        match transaction_result {
+            Ok(deleted_points) => deleted_points.into_iter().unzip(),
+            Err(_) => {
+                return Err(ServiceError::BadRequest(
+                    "Failed to delete chunk data".to_string(),
+                ))
+            }
+        };
+
+    delete_points_from_qdrant(deleted_points, qdrant_collection)
+        .await
+        .map_err(|_e| ServiceError::BadRequest("Failed to delete chunk from qdrant".to_string()))?;
+
+    remove_chunks_from_bm25_corpus_stats(
+        dataset.id,
+        deleted_chunk_htmls.into_iter().flatten().collect(),
+        &dataset_config,
+        redis_pool,
+    )
+    .await;
+
+    Ok(())
 }
 
 #[tracing::instrument(skip(pool))]
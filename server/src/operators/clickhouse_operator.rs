@@ -4,12 +4,14 @@ use tokio::sync::mpsc;
 
 use crate::{
     data::models::{
-        RagQueryEventClickhouse, RecommendationEventClickhouse, SearchQueryEventClickhouse,
-        WorkerEventClickhouse,
+        RagQueryEventClickhouse, RecommendationEventClickhouse, RedisPool,
+        SearchQueryEventClickhouse, WorkerEvent, WorkerEventClickhouse,
     },
     errors::ServiceError,
 };
 
+use super::webhook_operator::queue_webhook_event_query;
+
 #[derive(Debug, Clone)]
 pub enum ClickHouseEvent {
     SearchQueryEvent(SearchQueryEventClickhouse),
@@ -193,6 +195,7 @@ pub async fn send_to_clickhouse(
 pub struct EventQueue {
     sender: Option<mpsc::Sender<ClickHouseEvent>>,
     clickhouse_client: clickhouse::Client,
+    webhook_redis_pool: Option<RedisPool>,
 }
 
 impl EventQueue {
@@ -200,9 +203,15 @@ impl EventQueue {
         Self {
             sender: None,
             clickhouse_client,
+            webhook_redis_pool: None,
         }
     }
 
+    /// Worker events sent after this is called are also queued for the webhook-worker. Works with analytics disabled.
+    pub fn enable_webhooks(&mut self, redis_pool: RedisPool) {
+        self.webhook_redis_pool = Some(redis_pool);
+    }
+
     pub fn start_service(&mut self) {
         let clickhouse_client = self.clickhouse_client.clone();
         let (sender, mut reciever) = mpsc::channel(1000);
@@ -246,6 +255,16 @@ impl EventQueue {
     }
 
     pub async fn send(&self, event: ClickHouseEvent) {
+        if let (Some(redis_pool), ClickHouseEvent::WorkerEvent(worker_event)) =
+            (&self.webhook_redis_pool, &event)
+        {
+            if let Err(e) =
+                queue_webhook_event_query(WorkerEvent::from(worker_event.clone()), redis_pool).await
+            {
+                log::error!("Error queueing webhook event: {:?}", e);
+            }
+        }
+
         match &self.sender {
             Some(sender) => {
                 sender.send(event).await.unwrap();
@@ -0,0 +1,1426 @@
+use super::chunk_operator::{
+    bulk_insert_chunk_metadata_query, create_chunk_metadata, get_chunk_metadatas_from_point_ids,
+};
+use super::clickhouse_operator::{ClickHouseEvent, EventQueue};
+use super::dataset_operator::{
+    get_dataset_by_id_query, increment_bm25_corpus_stats_query, queue_dataset_bm25_reindex_query,
+};
+use super::file_operator::{get_aws_bucket, BucketObjectWriter};
+use super::group_operator::{create_group_from_file_query, create_groups_query};
+use super::model_operator::{get_bm25_doc_lengths, get_vocabulary_terms};
+use super::parse_operator::convert_html_to_text;
+use super::qdrant_operator::{
+    bulk_upsert_qdrant_points_query, get_point_vectors_query, scroll_dataset_points,
+};
+use super::search_operator::assemble_qdrant_filter;
+use super::spelling_operator::increment_vocabulary_query;
+use crate::{
+    data::models::{
+        ChunkData, ChunkGroup, ChunkMetadata, ChunkMetadataTable, DatasetArchiveJobState,
+        DatasetArchiveManifest, DatasetArchiveMessage, DatasetArchiveStatus, DatasetConfiguration,
+        EventType, File, FileAndGroupId, Pool, QdrantPayload, RedisPool, UnifiedId, WorkerEvent,
+    },
+    errors::ServiceError,
+    handlers::chunk_handler::ChunkReqPayload,
+};
+use actix_web::web;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use futures_util::{Stream, StreamExt};
+use itertools::Itertools;
+use qdrant_client::qdrant::{Filter, PointStruct, SparseIndices, Vector};
+use s3::Bucket;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Bumped whenever the layout of the archive changes in a way older importers can not read.
+pub const DATASET_ARCHIVE_VERSION: u32 = 1;
+
+const DATASET_ARCHIVE_BATCH_SIZE: usize = 120;
+
+/// Seconds the status of an export or import job is kept around after its last update.
+const DATASET_ARCHIVE_STATUS_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// A chunk as stored in `chunks.jsonl`, including the ids needed to relink it to its groups and vectors on import.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArchivedChunk {
+    #[serde(flatten)]
+    pub chunk: ChunkMetadataTable,
+    pub tag_set: Vec<String>,
+    pub group_ids: Vec<uuid::Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ArchivedVector {
+    Dense(Vec<f32>),
+    Sparse { indices: Vec<u32>, values: Vec<f32> },
+}
+
+impl From<Vector> for ArchivedVector {
+    fn from(vector: Vector) -> Self {
+        match vector.indices {
+            Some(indices) => ArchivedVector::Sparse {
+                indices: indices.data,
+                values: vector.data,
+            },
+            None => ArchivedVector::Dense(vector.data),
+        }
+    }
+}
+
+impl From<ArchivedVector> for Vector {
+    fn from(vector: ArchivedVector) -> Self {
+        match vector {
+            ArchivedVector::Dense(data) => Vector {
+                data,
+                indices: None,
+                vectors_count: None,
+            },
+            ArchivedVector::Sparse { indices, values } => Vector {
+                data: values,
+                indices: Some(SparseIndices { data: indices }),
+                vectors_count: None,
+            },
+        }
+    }
+}
+
+/// A line of `vectors.jsonl`. There is exactly one per line of `chunks.jsonl`, in the same order, with no vectors if the point was missing from qdrant.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArchivedChunkVectors {
+    pub qdrant_point_id: uuid::Uuid,
+    pub vectors: HashMap<String, ArchivedVector>,
+}
+
+pub fn get_dataset_archive_path(archive_id: uuid::Uuid, name: &str) -> String {
+    format!("dataset_archives/{}/{}", archive_id, name)
+}
+
+pub fn get_dataset_archive_status_key(job_id: uuid::Uuid) -> String {
+    format!("dataset_archive:{}", job_id)
+}
+
+#[tracing::instrument(skip(redis_pool))]
+pub async fn set_dataset_archive_status_query(
+    status: &DatasetArchiveStatus,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    let serialized_status = serde_json::to_string(status).map_err(|_| {
+        ServiceError::BadRequest("Failed to serialize dataset archive status".to_string())
+    })?;
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    redis::cmd("SET")
+        .arg(get_dataset_archive_status_key(status.job_id))
+        .arg(serialized_status)
+        .arg("EX")
+        .arg(DATASET_ARCHIVE_STATUS_TTL_SECS)
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(())
+}
+
+#[tracing::instrument(skip(redis_pool))]
+pub async fn get_dataset_archive_status_query(
+    job_id: uuid::Uuid,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<DatasetArchiveStatus, ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let serialized_status: Option<String> = redis::cmd("GET")
+        .arg(get_dataset_archive_status_key(job_id))
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let serialized_status = serialized_status.ok_or(ServiceError::NotFound(
+        "Dataset archive job not found".to_string(),
+    ))?;
+
+    serde_json::from_str(&serialized_status).map_err(|_| {
+        ServiceError::BadRequest("Failed to deserialize dataset archive status".to_string())
+    })
+}
+
+#[tracing::instrument(skip(redis_pool))]
+pub async fn queue_dataset_archive_query(
+    message: DatasetArchiveMessage,
+    status: DatasetArchiveStatus,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    set_dataset_archive_status_query(&status, redis_pool.clone()).await?;
+
+    let serialized_message = serde_json::to_string(&message).map_err(|_| {
+        ServiceError::BadRequest("Failed to serialize dataset archive message".to_string())
+    })?;
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    redis::cmd("lpush")
+        .arg("dataset_archive")
+        .arg(&serialized_message)
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(())
+}
+
+/// Reads the manifest of an archive, failing if the export has not finished or was written by a newer version.
+#[tracing::instrument]
+pub async fn get_dataset_archive_manifest_query(
+    archive_id: uuid::Uuid,
+) -> Result<DatasetArchiveManifest, ServiceError> {
+    let bucket = get_aws_bucket()?;
+
+    let manifest_path = get_dataset_archive_path(archive_id, "manifest.json");
+    let (_, status_code) = bucket
+        .head_object(&manifest_path)
+        .await
+        .map_err(|_| ServiceError::NotFound("Dataset archive not found".to_string()))?;
+    if status_code != 200 {
+        return Err(ServiceError::NotFound(
+            "Dataset archive not found".to_string(),
+        ));
+    }
+
+    let manifest_data = bucket.get_object(&manifest_path).await.map_err(|e| {
+        log::error!("Could not get dataset archive manifest {:?}", e);
+        ServiceError::BadRequest("Could not get dataset archive manifest".to_string())
+    })?;
+
+    let manifest: DatasetArchiveManifest = serde_json::from_slice(manifest_data.as_slice())
+        .map_err(|_| ServiceError::BadRequest("Invalid dataset archive manifest".to_string()))?;
+
+    if manifest.version > DATASET_ARCHIVE_VERSION {
+        return Err(ServiceError::BadRequest(format!(
+            "Dataset archive version {} is newer than the supported version {}",
+            manifest.version, DATASET_ARCHIVE_VERSION
+        )));
+    }
+
+    Ok(manifest)
+}
+
+async fn write_archive_line<T: Serialize>(
+    writer: &mut BucketObjectWriter,
+    value: &T,
+) -> Result<(), ServiceError> {
+    let mut line = serde_json::to_vec(value)
+        .map_err(|_| ServiceError::BadRequest("Failed to serialize archive line".to_string()))?;
+    line.push(b'\n');
+
+    writer.write(&line).await
+}
+
+/// Finishes every writer if `result` is ok and aborts all of them otherwise.
+async fn close_archive_writers<T>(
+    result: Result<T, ServiceError>,
+    writers: Vec<BucketObjectWriter>,
+) -> Result<T, ServiceError> {
+    match result {
+        Ok(value) => {
+            for writer in writers {
+                writer.finish().await?;
+            }
+            Ok(value)
+        }
+        Err(err) => {
+            for writer in writers {
+                writer.abort().await;
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Reads newline delimited JSON from a byte stream, holding at most one partial line in memory.
+pub struct JsonLinesReader<S> {
+    stream: S,
+    buffer: Vec<u8>,
+    exhausted: bool,
+}
+
+impl<S, B, E> JsonLinesReader<S>
+where
+    S: Stream<Item = Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: std::fmt::Display,
+{
+    pub fn new(stream: S) -> Self {
+        JsonLinesReader {
+            stream,
+            buffer: vec![],
+            exhausted: false,
+        }
+    }
+
+    pub async fn next_line<T: DeserializeOwned>(&mut self) -> Result<Option<T>, ServiceError> {
+        loop {
+            let line = match self.buffer.iter().position(|byte| *byte == b'\n') {
+                Some(newline_index) => {
+                    let mut line = self.buffer.drain(..=newline_index).collect_vec();
+                    line.pop();
+                    line
+                }
+                None if self.exhausted => std::mem::take(&mut self.buffer),
+                None => {
+                    match self.stream.next().await {
+                        Some(bytes) => {
+                            let bytes = bytes.map_err(|err| {
+                                ServiceError::BadRequest(format!(
+                                    "Could not read dataset archive: {}",
+                                    err
+                                ))
+                            })?;
+                            self.buffer.extend_from_slice(bytes.as_ref());
+                        }
+                        None => self.exhausted = true,
+                    }
+                    continue;
+                }
+            };
+
+            if line.iter().all(u8::is_ascii_whitespace) {
+                if self.exhausted && self.buffer.is_empty() {
+                    return Ok(None);
+                }
+                continue;
+            }
+
+            return serde_json::from_slice(&line).map(Some).map_err(|err| {
+                ServiceError::BadRequest(format!("Invalid line in dataset archive: {}", err))
+            });
+        }
+    }
+}
+
+async fn open_archive_reader(
+    bucket: &Bucket,
+    archive_id: uuid::Uuid,
+    name: &str,
+) -> Result<JsonLinesReader<s3::request::DataStream>, ServiceError> {
+    let response = bucket
+        .get_object_stream(get_dataset_archive_path(archive_id, name))
+        .await
+        .map_err(|e| {
+            log::error!("Could not read {} from dataset archive {:?}", name, e);
+            ServiceError::BadRequest(format!("Could not read {} from dataset archive", name))
+        })?;
+
+    Ok(JsonLinesReader::new(response.bytes))
+}
+
+#[tracing::instrument(skip(pool))]
+async fn export_groups(
+    archive_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<usize, ServiceError> {
+    use crate::data::schema::chunk_group::dsl as chunk_group_columns;
+
+    let mut writer = BucketObjectWriter::new(
+        get_aws_bucket()?,
+        get_dataset_archive_path(archive_id, "groups.jsonl"),
+    );
+
+    let result = async {
+        let mut conn = pool.get().await.map_err(|_| {
+            ServiceError::BadRequest("Could not get database connection".to_string())
+        })?;
+
+        let mut group_count = 0;
+        let mut last_group_id: Option<uuid::Uuid> = None;
+
+        loop {
+            let mut query = chunk_group_columns::chunk_group
+                .filter(chunk_group_columns::dataset_id.eq(dataset_id))
+                .order_by(chunk_group_columns::id)
+                .limit(DATASET_ARCHIVE_BATCH_SIZE as i64)
+                .into_boxed();
+            if let Some(last_group_id) = last_group_id {
+                query = query.filter(chunk_group_columns::id.gt(last_group_id));
+            }
+
+            let groups = query
+                .select(ChunkGroup::as_select())
+                .load::<ChunkGroup>(&mut conn)
+                .await
+                .map_err(|_| ServiceError::BadRequest("Failed to load groups".to_string()))?;
+
+            for group in groups.iter() {
+                write_archive_line(&mut writer, group).await?;
+            }
+
+            group_count += groups.len();
+            last_group_id = groups.last().map(|group| group.id);
+
+            if groups.len() < DATASET_ARCHIVE_BATCH_SIZE {
+                break;
+            }
+        }
+
+        Ok(group_count)
+    }
+    .await;
+
+    close_archive_writers(result, vec![writer]).await
+}
+
+#[tracing::instrument(skip(pool))]
+async fn export_files(
+    archive_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    include_files: bool,
+    pool: web::Data<Pool>,
+) -> Result<usize, ServiceError> {
+    use crate::data::schema::files::dsl as files_columns;
+    use crate::data::schema::groups_from_files::dsl as groups_from_files_columns;
+
+    let bucket = get_aws_bucket()?;
+    let mut writer = BucketObjectWriter::new(
+        bucket.clone(),
+        get_dataset_archive_path(archive_id, "files.jsonl"),
+    );
+
+    let result = async {
+        let mut conn = pool.get().await.map_err(|_| {
+            ServiceError::BadRequest("Could not get database connection".to_string())
+        })?;
+
+        let mut file_count = 0;
+        let mut last_file_id: Option<uuid::Uuid> = None;
+
+        loop {
+            let mut query = files_columns::files
+                .left_join(
+                    groups_from_files_columns::groups_from_files
+                        .on(groups_from_files_columns::file_id.eq(files_columns::id)),
+                )
+                .filter(files_columns::dataset_id.eq(dataset_id))
+                .filter(files_columns::pending.eq(false))
+                .order_by(files_columns::id)
+                .limit(DATASET_ARCHIVE_BATCH_SIZE as i64)
+                .into_boxed();
+            if let Some(last_file_id) = last_file_id {
+                query = query.filter(files_columns::id.gt(last_file_id));
+            }
+
+            let files = query
+                .select((
+                    File::as_select(),
+                    groups_from_files_columns::group_id.nullable(),
+                ))
+                .load::<(File, Option<uuid::Uuid>)>(&mut conn)
+                .await
+                .map_err(|_| ServiceError::BadRequest("Failed to load files".to_string()))?;
+
+            for (file, group_id) in files.iter() {
+                if include_files {
+                    bucket
+                        .copy_object_internal(
+                            file.id.to_string(),
+                            get_dataset_archive_path(archive_id, &format!("files/{}", file.id)),
+                        )
+                        .await
+                        .map_err(|e| {
+                            log::error!("Could not copy file to dataset archive {:?}", e);
+                            ServiceError::BadRequest(
+                                "Could not copy file to dataset archive".to_string(),
+                            )
+                        })?;
+                }
+
+                write_archive_line(
+                    &mut writer,
+                    &FileAndGroupId {
+                        file: file.clone(),
+                        group_id: *group_id,
+                    },
+                )
+                .await?;
+            }
+
+            file_count += files.len();
+            last_file_id = files.last().map(|(file, _)| file.id);
+
+            if files.len() < DATASET_ARCHIVE_BATCH_SIZE {
+                break;
+            }
+        }
+
+        Ok(file_count)
+    }
+    .await;
+
+    close_archive_writers(result, vec![writer]).await
+}
+
+#[tracing::instrument(skip(pool))]
+async fn get_chunk_group_ids_query(
+    chunk_ids: Vec<uuid::Uuid>,
+    pool: web::Data<Pool>,
+) -> Result<HashMap<uuid::Uuid, Vec<uuid::Uuid>>, ServiceError> {
+    use crate::data::schema::chunk_group_bookmarks::dsl as chunk_group_bookmarks_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let bookmarks = chunk_group_bookmarks_columns::chunk_group_bookmarks
+        .filter(chunk_group_bookmarks_columns::chunk_metadata_id.eq_any(chunk_ids))
+        .select((
+            chunk_group_bookmarks_columns::chunk_metadata_id,
+            chunk_group_bookmarks_columns::group_id,
+        ))
+        .load::<(uuid::Uuid, uuid::Uuid)>(&mut conn)
+        .await
+        .map_err(|_| ServiceError::BadRequest("Failed to load group bookmarks".to_string()))?;
+
+    Ok(bookmarks.into_iter().into_group_map())
+}
+
+/// A page of the dataset's chunks in archive form along with their vectors if requested, and the offset of the next page.
+type ArchivedChunkPage = (
+    Vec<(ArchivedChunk, HashMap<String, ArchivedVector>)>,
+    Option<uuid::Uuid>,
+);
+
+/// Loads the page of the dataset's chunks starting at the point `offset`, which the first page leaves unset.
+#[tracing::instrument(skip(pool, filter))]
+async fn get_archived_chunk_page(
+    offset: Option<uuid::Uuid>,
+    dataset_config: DatasetConfiguration,
+    filter: Filter,
+    include_vectors: bool,
+    pool: web::Data<Pool>,
+) -> Result<ArchivedChunkPage, ServiceError> {
+    // The scroll offset is inclusive, so one extra point is fetched to start the next page from.
+    let mut point_ids = scroll_dataset_points(
+        DATASET_ARCHIVE_BATCH_SIZE as u64 + 1,
+        offset,
+        None,
+        dataset_config.clone(),
+        filter,
+    )
+    .await?;
+    let next_offset = if point_ids.len() > DATASET_ARCHIVE_BATCH_SIZE {
+        point_ids.pop()
+    } else {
+        None
+    };
+
+    let chunks: Vec<ChunkMetadata> =
+        get_chunk_metadatas_from_point_ids(point_ids.clone(), pool.clone())
+            .await?
+            .into_iter()
+            .map(ChunkMetadata::from)
+            .collect();
+
+    let mut chunk_group_ids =
+        get_chunk_group_ids_query(chunks.iter().map(|chunk| chunk.id).collect(), pool).await?;
+
+    let mut point_vectors = if include_vectors {
+        get_point_vectors_query(point_ids, dataset_config).await?
+    } else {
+        HashMap::new()
+    };
+
+    let archived_chunks = chunks
+        .into_iter()
+        .map(|chunk| {
+            let vectors = point_vectors
+                .remove(&chunk.qdrant_point_id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(name, vector)| (name, vector.into()))
+                .collect();
+
+            let archived_chunk = ArchivedChunk {
+                tag_set: chunk
+                    .tag_set
+                    .clone()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .flatten()
+                    .collect(),
+                group_ids: chunk_group_ids.remove(&chunk.id).unwrap_or_default(),
+                chunk: chunk.into(),
+            };
+
+            (archived_chunk, vectors)
+        })
+        .collect();
+
+    Ok((archived_chunks, next_offset))
+}
+
+/// Scrolls every point of the dataset, writing its chunk and, if requested, its vectors in lockstep.
+#[tracing::instrument(skip(pool, redis_pool, status))]
+async fn export_chunks(
+    archive_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    dataset_config: DatasetConfiguration,
+    include_vectors: bool,
+    status: &mut DatasetArchiveStatus,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<usize, ServiceError> {
+    let bucket = get_aws_bucket()?;
+    let mut chunk_writer = BucketObjectWriter::new(
+        bucket.clone(),
+        get_dataset_archive_path(archive_id, "chunks.jsonl"),
+    );
+    let mut vector_writer = BucketObjectWriter::new(
+        bucket,
+        get_dataset_archive_path(archive_id, "vectors.jsonl"),
+    );
+
+    let result = async {
+        let filter = assemble_qdrant_filter(None, None, None, dataset_id, pool.clone()).await?;
+
+        let mut chunk_count = 0;
+        let mut offset: Option<uuid::Uuid> = None;
+
+        loop {
+            let (chunks, next_offset) = get_archived_chunk_page(
+                offset,
+                dataset_config.clone(),
+                filter.clone(),
+                include_vectors,
+                pool.clone(),
+            )
+            .await?;
+            offset = next_offset;
+
+            chunk_count += chunks.len();
+
+            for (archived_chunk, vectors) in chunks {
+                write_archive_line(&mut chunk_writer, &archived_chunk).await?;
+
+                if include_vectors {
+                    write_archive_line(
+                        &mut vector_writer,
+                        &ArchivedChunkVectors {
+                            qdrant_point_id: archived_chunk.chunk.qdrant_point_id,
+                            vectors,
+                        },
+                    )
+                    .await?;
+                }
+            }
+
+            status.chunk_count = chunk_count;
+            status.updated_at = chrono::Utc::now().naive_local();
+            set_dataset_archive_status_query(status, redis_pool.clone()).await?;
+
+            if offset.is_none() {
+                break;
+            }
+        }
+
+        Ok(chunk_count)
+    }
+    .await;
+
+    close_archive_writers(result, vec![chunk_writer, vector_writer]).await
+}
+
+/// Writes the dataset's groups, files, chunks and optionally its vectors to `dataset_archives/{archive_id}/` in the bucket. The manifest is written last so an archive without one is incomplete.
+#[tracing::instrument(skip(pool, redis_pool, status))]
+pub async fn export_dataset_query(
+    archive_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    include_vectors: bool,
+    include_files: bool,
+    status: &mut DatasetArchiveStatus,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<DatasetArchiveManifest, ServiceError> {
+    let dataset = get_dataset_by_id_query(UnifiedId::TrieveUuid(dataset_id), pool.clone()).await?;
+    let dataset_config = DatasetConfiguration::from_json(dataset.server_configuration.clone());
+
+    let group_count = export_groups(archive_id, dataset_id, pool.clone()).await?;
+    let file_count = export_files(archive_id, dataset_id, include_files, pool.clone()).await?;
+    let chunk_count = export_chunks(
+        archive_id,
+        dataset_id,
+        dataset_config.clone(),
+        include_vectors,
+        status,
+        pool,
+        redis_pool,
+    )
+    .await?;
+
+    let manifest = DatasetArchiveManifest {
+        version: DATASET_ARCHIVE_VERSION,
+        archive_id,
+        dataset_id,
+        organization_id: Some(dataset.organization_id),
+        dataset_name: dataset.name,
+        server_configuration: dataset.server_configuration,
+        embedding_size: dataset_config.EMBEDDING_SIZE,
+        include_vectors,
+        include_files,
+        group_count,
+        file_count,
+        chunk_count,
+        created_at: chrono::Utc::now().naive_local(),
+    };
+
+    let serialized_manifest = serde_json::to_vec_pretty(&manifest)
+        .map_err(|_| ServiceError::BadRequest("Failed to serialize manifest".to_string()))?;
+
+    get_aws_bucket()?
+        .put_object(
+            get_dataset_archive_path(archive_id, "manifest.json"),
+            serialized_manifest.as_slice(),
+        )
+        .await
+        .map_err(|e| {
+            log::error!("Could not write dataset archive manifest {:?}", e);
+            ServiceError::BadRequest("Could not write dataset archive manifest".to_string())
+        })?;
+
+    Ok(manifest)
+}
+
+/// Recreates the archived groups under new ids and returns the new id and tag set of each keyed by its archived id.
+#[tracing::instrument(skip(pool))]
+async fn import_groups(
+    archive_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<HashMap<uuid::Uuid, ChunkGroup>, ServiceError> {
+    let bucket = get_aws_bucket()?;
+    let mut reader = open_archive_reader(&bucket, archive_id, "groups.jsonl").await?;
+
+    let mut groups_by_archived_id = HashMap::new();
+
+    loop {
+        let mut new_groups = vec![];
+        while new_groups.len() < DATASET_ARCHIVE_BATCH_SIZE {
+            match reader.next_line::<ChunkGroup>().await? {
+                Some(group) => {
+                    let new_group = ChunkGroup::from_details(
+                        Some(group.name),
+                        Some(group.description),
+                        dataset_id,
+                        group.tracking_id,
+                        group.metadata,
+                        group.tag_set,
+                    );
+                    groups_by_archived_id.insert(group.id, new_group.clone());
+                    new_groups.push(new_group);
+                }
+                None => break,
+            }
+        }
+
+        let batch_len = new_groups.len();
+        create_groups_query(new_groups, false, pool.clone()).await?;
+
+        if batch_len < DATASET_ARCHIVE_BATCH_SIZE {
+            break;
+        }
+    }
+
+    Ok(groups_by_archived_id)
+}
+
+#[tracing::instrument(skip(pool, groups_by_archived_id))]
+async fn import_files(
+    manifest: &DatasetArchiveManifest,
+    dataset_id: uuid::Uuid,
+    groups_by_archived_id: &HashMap<uuid::Uuid, ChunkGroup>,
+    pool: web::Data<Pool>,
+) -> Result<usize, ServiceError> {
+    use crate::data::schema::files::dsl as files_columns;
+
+    let bucket = get_aws_bucket()?;
+    let mut reader = open_archive_reader(&bucket, manifest.archive_id, "files.jsonl").await?;
+
+    let mut file_count = 0;
+
+    while let Some(archived_file) = reader.next_line::<FileAndGroupId>().await? {
+        let new_file = File {
+            id: uuid::Uuid::new_v4(),
+            dataset_id,
+            created_at: chrono::Utc::now().naive_local(),
+            updated_at: chrono::Utc::now().naive_local(),
+            pending: false,
+            ..archived_file.file
+        };
+
+        if manifest.include_files {
+            bucket
+                .copy_object_internal(
+                    get_dataset_archive_path(
+                        manifest.archive_id,
+                        &format!("files/{}", archived_file.file.id),
+                    ),
+                    new_file.id.to_string(),
+                )
+                .await
+                .map_err(|e| {
+                    log::error!("Could not copy file from dataset archive {:?}", e);
+                    ServiceError::BadRequest("Could not copy file from dataset archive".to_string())
+                })?;
+        }
+
+        let mut conn = pool.get().await.map_err(|_| {
+            ServiceError::BadRequest("Could not get database connection".to_string())
+        })?;
+
+        diesel::insert_into(files_columns::files)
+            .values(&new_file)
+            .execute(&mut conn)
+            .await
+            .map_err(|_| ServiceError::BadRequest("Could not create file".to_string()))?;
+
+        if let Some(group) = archived_file
+            .group_id
+            .and_then(|group_id| groups_by_archived_id.get(&group_id))
+        {
+            create_group_from_file_query(group.id, new_file.id, pool.clone()).await?;
+        }
+
+        file_count += 1;
+    }
+
+    Ok(file_count)
+}
+
+/// Inserts chunks whose archived vectors can be reused directly into postgres and qdrant.
+#[tracing::instrument(skip_all)]
+async fn insert_chunks_with_vectors(
+    chunks: Vec<(ArchivedChunk, HashMap<String, ArchivedVector>)>,
+    dataset_id: uuid::Uuid,
+    dataset_config: &DatasetConfiguration,
+    groups_by_archived_id: &HashMap<uuid::Uuid, ChunkGroup>,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    if chunks.is_empty() {
+        return Ok(());
+    }
+
+    let mut vectors_by_point_id = HashMap::new();
+    let mut group_tag_sets_by_chunk_id = HashMap::new();
+
+    let chunk_data = chunks
+        .into_iter()
+        .map(|(archived_chunk, vectors)| {
+            let groups = archived_chunk
+                .group_ids
+                .iter()
+                .filter_map(|group_id| groups_by_archived_id.get(group_id))
+                .collect_vec();
+
+            let chunk_table = ChunkMetadataTable {
+                id: uuid::Uuid::new_v4(),
+                qdrant_point_id: uuid::Uuid::new_v4(),
+                dataset_id,
+                created_at: chrono::Utc::now().naive_local(),
+                updated_at: chrono::Utc::now().naive_local(),
+                ..archived_chunk.chunk
+            };
+            let chunk_metadata =
+                ChunkMetadata::from_table_and_tag_set(chunk_table, archived_chunk.tag_set);
+
+            vectors_by_point_id.insert(chunk_metadata.qdrant_point_id, vectors);
+            if !groups.is_empty() {
+                group_tag_sets_by_chunk_id.insert(
+                    chunk_metadata.id,
+                    groups
+                        .iter()
+                        .filter_map(|group| group.tag_set.clone())
+                        .flatten()
+                        .dedup()
+                        .collect::<Vec<Option<String>>>(),
+                );
+            }
+
+            ChunkData {
+                content: convert_html_to_text(
+                    &chunk_metadata.chunk_html.clone().unwrap_or_default(),
+                ),
+                chunk_metadata,
+                group_ids: if groups.is_empty() {
+                    None
+                } else {
+                    Some(groups.iter().map(|group| group.id).collect())
+                },
+                upsert_by_tracking_id: false,
+                fulltext_boost: None,
+                semantic_boost: None,
+            }
+        })
+        .collect_vec();
+
+    let inserted_chunks =
+        bulk_insert_chunk_metadata_query(chunk_data, dataset_id, false, pool).await?;
+
+    let contents = inserted_chunks
+        .iter()
+        .map(|chunk_data| chunk_data.content.clone())
+        .collect_vec();
+
+    let points = inserted_chunks
+        .into_iter()
+        .map(|chunk_data| {
+            let qdrant_point_id = chunk_data.chunk_metadata.qdrant_point_id;
+            let vectors: HashMap<String, Vector> = vectors_by_point_id
+                .remove(&qdrant_point_id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(name, vector)| (name, vector.into()))
+                .collect();
+            let group_tag_set = group_tag_sets_by_chunk_id.remove(&chunk_data.chunk_metadata.id);
+
+            let payload = QdrantPayload::new(
+                chunk_data.chunk_metadata,
+                chunk_data.group_ids,
+                None,
+                group_tag_set,
+            );
+
+            PointStruct::new(qdrant_point_id.to_string(), vectors, payload)
+        })
+        .collect_vec();
+
+    if !points.is_empty() {
+        bulk_upsert_qdrant_points_query(points, dataset_config.clone()).await?;
+    }
+
+    if dataset_config.BM25_ENABLED
+        && std::env::var("BM25_ACTIVE").unwrap_or("false".to_string()) == "true"
+    {
+        let analyzer = dataset_config.bm25_analyzer_options();
+        let vocabulary_terms = get_vocabulary_terms(contents.clone(), &analyzer);
+        let doc_lengths = get_bm25_doc_lengths(contents, &analyzer);
+        increment_bm25_corpus_stats_query(dataset_id, doc_lengths, redis_pool.clone()).await?;
+        increment_vocabulary_query(
+            dataset_id,
+            vocabulary_terms,
+            analyzer.ascii_folding,
+            redis_pool,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Queues a BM25 reindex of `dataset_id` when its points were copied with BM25 vectors computed under other BM25 settings than its own, or without any.
+async fn reindex_copied_bm25_vectors(
+    source_dataset_config: &DatasetConfiguration,
+    dataset_id: uuid::Uuid,
+    dataset_config: &DatasetConfiguration,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    if !dataset_config.BM25_ENABLED
+        || std::env::var("BM25_ACTIVE").unwrap_or("false".to_string()) != "true"
+    {
+        return Ok(());
+    }
+
+    let bm25_settings_match = source_dataset_config.BM25_ENABLED
+        && source_dataset_config.bm25_analyzer_options() == dataset_config.bm25_analyzer_options()
+        && source_dataset_config.BM25_K == dataset_config.BM25_K
+        && source_dataset_config.BM25_B == dataset_config.BM25_B
+        && source_dataset_config.BM25_AVG_LEN == dataset_config.BM25_AVG_LEN;
+
+    if bm25_settings_match {
+        return Ok(());
+    }
+
+    queue_dataset_bm25_reindex_query(dataset_id, dataset_config.clone(), false, redis_pool).await
+}
+
+/// Queues chunks which have to be embedded again onto the ingestion queue like a bulk upload.
+#[tracing::instrument(skip_all)]
+async fn queue_chunks_for_embedding(
+    chunks: Vec<ArchivedChunk>,
+    dataset_id: uuid::Uuid,
+    dataset_config: &DatasetConfiguration,
+    groups_by_archived_id: &HashMap<uuid::Uuid, ChunkGroup>,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    if chunks.is_empty() {
+        return Ok(());
+    }
+
+    let chunk_payloads = chunks
+        .into_iter()
+        .map(|archived_chunk| {
+            let group_ids = archived_chunk
+                .group_ids
+                .iter()
+                .filter_map(|group_id| groups_by_archived_id.get(group_id))
+                .map(|group| group.id)
+                .collect_vec();
+
+            ChunkReqPayload {
+                chunk_html: archived_chunk.chunk.chunk_html,
+                semantic_content: None,
+                link: archived_chunk.chunk.link,
+                tag_set: if archived_chunk.tag_set.is_empty() {
+                    None
+                } else {
+                    Some(archived_chunk.tag_set)
+                },
+                num_value: archived_chunk.chunk.num_value,
+                metadata: archived_chunk.chunk.metadata,
+                tracking_id: archived_chunk.chunk.tracking_id,
+                upsert_by_tracking_id: None,
+                group_ids: if group_ids.is_empty() {
+                    None
+                } else {
+                    Some(group_ids)
+                },
+                group_tracking_ids: None,
+                time_stamp: archived_chunk
+                    .chunk
+                    .time_stamp
+                    .map(|time_stamp| time_stamp.format("%Y-%m-%dT%H:%M:%S%.f").to_string()),
+                location: archived_chunk.chunk.location,
+                image_urls: archived_chunk
+                    .chunk
+                    .image_urls
+                    .map(|image_urls| image_urls.into_iter().flatten().collect()),
+                weight: Some(archived_chunk.chunk.weight),
+                split_avg: None,
+                convert_html_to_text: None,
+                fulltext_boost: None,
+                semantic_boost: None,
+            }
+        })
+        .collect_vec();
+
+    let (ingestion_message, _) =
+        create_chunk_metadata(chunk_payloads, dataset_id, dataset_config.clone(), pool).await?;
+
+    let serialized_message = serde_json::to_string(&ingestion_message).map_err(|_| {
+        ServiceError::BadRequest("Failed to Serialize BulkUploadMessage".to_string())
+    })?;
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    redis::cmd("lpush")
+        .arg("ingestion")
+        .arg(&serialized_message)
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(())
+}
+
+/// Recreates an archived dataset's groups, files and chunks in `dataset_id`, which should be a freshly created dataset. Archived vectors are reused when their dense vector size matches the dataset's `EMBEDDING_SIZE`, otherwise the chunks are queued to be embedded again.
+#[tracing::instrument(skip(pool, redis_pool, status))]
+pub async fn import_dataset_query(
+    archive_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    status: &mut DatasetArchiveStatus,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<usize, ServiceError> {
+    let manifest = get_dataset_archive_manifest_query(archive_id).await?;
+
+    let dataset = get_dataset_by_id_query(UnifiedId::TrieveUuid(dataset_id), pool.clone()).await?;
+    let dataset_config = DatasetConfiguration::from_json(dataset.server_configuration);
+
+    let reuse_vectors =
+        manifest.include_vectors && manifest.embedding_size == dataset_config.EMBEDDING_SIZE;
+
+    let groups_by_archived_id = import_groups(archive_id, dataset_id, pool.clone()).await?;
+    import_files(&manifest, dataset_id, &groups_by_archived_id, pool.clone()).await?;
+
+    let bucket = get_aws_bucket()?;
+    let mut chunk_reader = open_archive_reader(&bucket, archive_id, "chunks.jsonl").await?;
+    let mut vector_reader = if reuse_vectors {
+        Some(open_archive_reader(&bucket, archive_id, "vectors.jsonl").await?)
+    } else {
+        None
+    };
+
+    let mut imported_count = 0;
+
+    loop {
+        let mut chunks_with_vectors = vec![];
+        let mut chunks_to_embed = vec![];
+
+        while chunks_with_vectors.len() + chunks_to_embed.len() < DATASET_ARCHIVE_BATCH_SIZE {
+            let archived_chunk = match chunk_reader.next_line::<ArchivedChunk>().await? {
+                Some(archived_chunk) => archived_chunk,
+                None => break,
+            };
+
+            let archived_vectors = match vector_reader.as_mut() {
+                Some(vector_reader) => vector_reader.next_line::<ArchivedChunkVectors>().await?,
+                None => None,
+            };
+
+            match archived_vectors {
+                Some(archived_vectors) if !archived_vectors.vectors.is_empty() => {
+                    if archived_vectors.qdrant_point_id != archived_chunk.chunk.qdrant_point_id {
+                        return Err(ServiceError::BadRequest(
+                            "Dataset archive vectors are out of order with its chunks".to_string(),
+                        ));
+                    }
+                    chunks_with_vectors.push((archived_chunk, archived_vectors.vectors));
+                }
+                _ => chunks_to_embed.push(archived_chunk),
+            }
+        }
+
+        let batch_len = chunks_with_vectors.len() + chunks_to_embed.len();
+
+        insert_chunks_with_vectors(
+            chunks_with_vectors,
+            dataset_id,
+            &dataset_config,
+            &groups_by_archived_id,
+            pool.clone(),
+            redis_pool.clone(),
+        )
+        .await?;
+        queue_chunks_for_embedding(
+            chunks_to_embed,
+            dataset_id,
+            &dataset_config,
+            &groups_by_archived_id,
+            pool.clone(),
+            redis_pool.clone(),
+        )
+        .await?;
+
+        imported_count += batch_len;
+
+        status.chunk_count = imported_count;
+        status.updated_at = chrono::Utc::now().naive_local();
+        set_dataset_archive_status_query(status, redis_pool.clone()).await?;
+
+        if batch_len < DATASET_ARCHIVE_BATCH_SIZE {
+            break;
+        }
+    }
+
+    if reuse_vectors {
+        reindex_copied_bm25_vectors(
+            &DatasetConfiguration::from_json(manifest.server_configuration),
+            dataset_id,
+            &dataset_config,
+            redis_pool,
+        )
+        .await?;
+    }
+
+    Ok(imported_count)
+}
+
+/// Copies the source dataset's groups under new ids and returns the new group keyed by the id of the group it was copied from.
+#[tracing::instrument(skip(pool))]
+async fn clone_groups(
+    source_dataset_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<HashMap<uuid::Uuid, ChunkGroup>, ServiceError> {
+    use crate::data::schema::chunk_group::dsl as chunk_group_columns;
+
+    let mut groups_by_source_id = HashMap::new();
+    let mut last_group_id: Option<uuid::Uuid> = None;
+
+    loop {
+        let mut conn = pool.get().await.map_err(|_| {
+            ServiceError::BadRequest("Could not get database connection".to_string())
+        })?;
+
+        let mut query = chunk_group_columns::chunk_group
+            .filter(chunk_group_columns::dataset_id.eq(source_dataset_id))
+            .order_by(chunk_group_columns::id)
+            .limit(DATASET_ARCHIVE_BATCH_SIZE as i64)
+            .into_boxed();
+        if let Some(last_group_id) = last_group_id {
+            query = query.filter(chunk_group_columns::id.gt(last_group_id));
+        }
+
+        let groups = query
+            .select(ChunkGroup::as_select())
+            .load::<ChunkGroup>(&mut conn)
+            .await
+            .map_err(|_| ServiceError::BadRequest("Failed to load groups".to_string()))?;
+        drop(conn);
+
+        let batch_len = groups.len();
+        last_group_id = groups.last().map(|group| group.id);
+
+        let new_groups = groups
+            .into_iter()
+            .map(|group| {
+                let new_group = ChunkGroup::from_details(
+                    Some(group.name),
+                    Some(group.description),
+                    dataset_id,
+                    group.tracking_id,
+                    group.metadata,
+                    group.tag_set,
+                );
+                groups_by_source_id.insert(group.id, new_group.clone());
+                new_group
+            })
+            .collect_vec();
+
+        create_groups_query(new_groups, false, pool.clone()).await?;
+
+        if batch_len < DATASET_ARCHIVE_BATCH_SIZE {
+            break;
+        }
+    }
+
+    Ok(groups_by_source_id)
+}
+
+/// Copies the source dataset's files and their objects in the bucket, relinking them to the copied groups.
+#[tracing::instrument(skip(pool, groups_by_source_id))]
+async fn clone_files(
+    source_dataset_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    groups_by_source_id: &HashMap<uuid::Uuid, ChunkGroup>,
+    pool: web::Data<Pool>,
+) -> Result<usize, ServiceError> {
+    use crate::data::schema::files::dsl as files_columns;
+    use crate::data::schema::groups_from_files::dsl as groups_from_files_columns;
+
+    let bucket = get_aws_bucket()?;
+
+    let mut file_count = 0;
+    let mut last_file_id: Option<uuid::Uuid> = None;
+
+    loop {
+        let mut conn = pool.get().await.map_err(|_| {
+            ServiceError::BadRequest("Could not get database connection".to_string())
+        })?;
+
+        let mut query = files_columns::files
+            .left_join(
+                groups_from_files_columns::groups_from_files
+                    .on(groups_from_files_columns::file_id.eq(files_columns::id)),
+            )
+            .filter(files_columns::dataset_id.eq(source_dataset_id))
+            .filter(files_columns::pending.eq(false))
+            .order_by(files_columns::id)
+            .limit(DATASET_ARCHIVE_BATCH_SIZE as i64)
+            .into_boxed();
+        if let Some(last_file_id) = last_file_id {
+            query = query.filter(files_columns::id.gt(last_file_id));
+        }
+
+        let files = query
+            .select((
+                File::as_select(),
+                groups_from_files_columns::group_id.nullable(),
+            ))
+            .load::<(File, Option<uuid::Uuid>)>(&mut conn)
+            .await
+            .map_err(|_| ServiceError::BadRequest("Failed to load files".to_string()))?;
+
+        let batch_len = files.len();
+        last_file_id = files.last().map(|(file, _)| file.id);
+
+        for (file, group_id) in files {
+            let new_file = File {
+                id: uuid::Uuid::new_v4(),
+                dataset_id,
+                created_at: chrono::Utc::now().naive_local(),
+                updated_at: chrono::Utc::now().naive_local(),
+                ..file.clone()
+            };
+
+            bucket
+                .copy_object_internal(file.id.to_string(), new_file.id.to_string())
+                .await
+                .map_err(|e| {
+                    log::error!("Could not copy file for dataset clone {:?}", e);
+                    ServiceError::BadRequest("Could not copy file for dataset clone".to_string())
+                })?;
+
+            diesel::insert_into(files_columns::files)
+                .values(&new_file)
+                .execute(&mut conn)
+                .await
+                .map_err(|_| ServiceError::BadRequest("Could not create file".to_string()))?;
+
+            if let Some(group) = group_id.and_then(|group_id| groups_by_source_id.get(&group_id)) {
+                create_group_from_file_query(group.id, new_file.id, pool.clone()).await?;
+            }
+        }
+
+        file_count += batch_len;
+
+        if batch_len < DATASET_ARCHIVE_BATCH_SIZE {
+            break;
+        }
+    }
+
+    Ok(file_count)
+}
+
+/// Copies the source dataset's groups, files and chunks into `dataset_id`, which should be a freshly created dataset with the same embedding model. Points are duplicated with their existing vectors so nothing is embedded again, BM25 vectors are recomputed afterwards if the BM25 settings differ. Progress is sent as worker events of the new dataset.
+#[tracing::instrument(skip(pool, redis_pool, event_queue, status))]
+pub async fn clone_dataset_query(
+    source_dataset_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    status: &mut DatasetArchiveStatus,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+    event_queue: web::Data<EventQueue>,
+) -> Result<usize, ServiceError> {
+    let source_dataset =
+        get_dataset_by_id_query(UnifiedId::TrieveUuid(source_dataset_id), pool.clone()).await?;
+    let source_dataset_config =
+        DatasetConfiguration::from_json(source_dataset.server_configuration);
+
+    let dataset = get_dataset_by_id_query(UnifiedId::TrieveUuid(dataset_id), pool.clone()).await?;
+    let dataset_config = DatasetConfiguration::from_json(dataset.server_configuration);
+
+    if source_dataset_config.embedding_model_options() != dataset_config.embedding_model_options() {
+        return Err(ServiceError::BadRequest(
+            "A dataset can only be cloned into a dataset with the same embedding model".to_string(),
+        ));
+    }
+
+    let groups_by_source_id = clone_groups(source_dataset_id, dataset_id, pool.clone()).await?;
+    clone_files(
+        source_dataset_id,
+        dataset_id,
+        &groups_by_source_id,
+        pool.clone(),
+    )
+    .await?;
+
+    let filter = assemble_qdrant_filter(None, None, None, source_dataset_id, pool.clone()).await?;
+
+    let mut cloned_count = 0;
+    let mut offset: Option<uuid::Uuid> = None;
+
+    loop {
+        let (chunks, next_offset) = get_archived_chunk_page(
+            offset,
+            source_dataset_config.clone(),
+            filter.clone(),
+            true,
+            pool.clone(),
+        )
+        .await?;
+        offset = next_offset;
+
+        cloned_count += chunks.len();
+
+        insert_chunks_with_vectors(
+            chunks,
+            dataset_id,
+            &dataset_config,
+            &groups_by_source_id,
+            pool.clone(),
+            redis_pool.clone(),
+        )
+        .await?;
+
+        status.chunk_count = cloned_count;
+        status.updated_at = chrono::Utc::now().naive_local();
+        set_dataset_archive_status_query(status, redis_pool.clone()).await?;
+
+        event_queue
+            .send(ClickHouseEvent::WorkerEvent(
+                WorkerEvent::from_details(
+                    dataset_id,
+                    EventType::DatasetCloneProgress {
+                        source_dataset_id,
+                        chunk_count: cloned_count,
+                    },
+                )
+                .into(),
+            ))
+            .await;
+
+        if offset.is_none() {
+            break;
+        }
+    }
+
+    reindex_copied_bm25_vectors(
+        &source_dataset_config,
+        dataset_id,
+        &dataset_config,
+        redis_pool,
+    )
+    .await?;
+
+    Ok(cloned_count)
+}
+
+/// Marks the job as failed so the status endpoint reports why it stopped.
+pub async fn fail_dataset_archive_job_query(
+    mut status: DatasetArchiveStatus,
+    error: &ServiceError,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    status.state = DatasetArchiveJobState::Failed;
+    status.error = Some(error.to_string());
+    status.updated_at = chrono::Utc::now().naive_local();
+
+    set_dataset_archive_status_query(&status, redis_pool).await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_archived_vector_round_trip() {
+        let dense = Vector::from(vec![0.25, -1.0, 3.5]);
+        let sparse = Vector::from(vec![(7, 0.5), (42, 1.25)]);
+
+        let archived_dense = ArchivedVector::from(dense.clone());
+        let archived_sparse = ArchivedVector::from(sparse.clone());
+        assert_eq!(archived_dense, ArchivedVector::Dense(vec![0.25, -1.0, 3.5]));
+        assert_eq!(
+            archived_sparse,
+            ArchivedVector::Sparse {
+                indices: vec![7, 42],
+                values: vec![0.5, 1.25],
+            }
+        );
+
+        let serialized = serde_json::to_string(&vec![&archived_dense, &archived_sparse]).unwrap();
+        assert_eq!(
+            serialized,
+            r#"[[0.25,-1.0,3.5],{"indices":[7,42],"values":[0.5,1.25]}]"#
+        );
+
+        let deserialized: Vec<ArchivedVector> = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(Vector::from(deserialized[0].clone()), dense);
+        assert_eq!(Vector::from(deserialized[1].clone()), sparse);
+    }
+}
@@ -1,11 +1,15 @@
 use crate::data::models::{
     DatasetAndOrgWithSubAndPlan, DatasetAndUsage, DatasetConfiguration, DatasetUsageCount,
-    Organization, OrganizationWithSubAndPlan, RedisPool, StripePlan, StripeSubscription, UnifiedId,
+    MigrateDatasetMessage, MigratePointMessage, MigrationMode, Organization,
+    OrganizationWithSubAndPlan, RedisPool, StripePlan, StripeSubscription, UnifiedId,
 };
 use crate::handlers::dataset_handler::{GetDatasetsPagination, TagsWithCount};
 use crate::operators::clickhouse_operator::ClickHouseEvent;
+use crate::operators::model_operator::{get_bm25_doc_lengths, get_vocabulary_terms};
+use crate::operators::parse_operator::convert_html_to_text;
 use crate::operators::qdrant_operator::{
     delete_points_from_qdrant, get_qdrant_collection_from_dataset_config,
+    scroll_qdrant_collection_ids,
 };
 use crate::{
     data::models::{Dataset, EventType, Pool, WorkerEvent},
@@ -16,9 +20,11 @@ use diesel::dsl::count;
 use diesel::prelude::*;
 use diesel::result::{DatabaseErrorKind, Error as DBError};
 use diesel_async::RunQueryDsl;
+use qdrant_client::qdrant::{Condition, Filter};
 use serde::{Deserialize, Serialize};
 
 use super::clickhouse_operator::EventQueue;
+use super::spelling_operator::decrement_vocabulary_query;
 
 #[tracing::instrument(skip(pool))]
 pub async fn create_dataset_query(
@@ -80,6 +86,36 @@ pub async fn get_dataset_by_id_query(
     Ok(dataset)
 }
 
+/// Returns every dataset which is not deleted, optionally only those of one organization.
+#[tracing::instrument(skip(pool))]
+pub async fn get_all_datasets_query(
+    organization_id: Option<uuid::Uuid>,
+    pool: web::Data<Pool>,
+) -> Result<Vec<Dataset>, ServiceError> {
+    use crate::data::schema::datasets::dsl as datasets_columns;
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let mut query = datasets_columns::datasets
+        .filter(datasets_columns::deleted.eq(0))
+        .order(datasets_columns::created_at.asc())
+        .select(Dataset::as_select())
+        .into_boxed();
+
+    if let Some(organization_id) = organization_id {
+        query = query.filter(datasets_columns::organization_id.eq(organization_id));
+    }
+
+    let datasets = query
+        .load::<Dataset>(&mut conn)
+        .await
+        .map_err(|_| ServiceError::BadRequest("Failed to load datasets".to_string()))?;
+
+    Ok(datasets)
+}
+
 #[tracing::instrument(skip(pool))]
 pub async fn get_deleted_dataset_by_unifiedid_query(
     id: UnifiedId,
@@ -668,3 +704,278 @@ pub async fn get_tags_in_dataset_query(
 
     Ok((items, total_count))
 }
+
+/// Queues the dataset for the reindex-worker so its BM25 vectors are recomputed in place with the dataset's current analyzer and parameters. The worker scrolls the points itself.
+/// With `rebuild_corpus_stats` the reindex also counts every point into the BM25 corpus statistics, which the caller has to have cleared.
+#[tracing::instrument(skip(redis_pool))]
+pub async fn queue_dataset_bm25_reindex_query(
+    dataset_id: uuid::Uuid,
+    dataset_config: DatasetConfiguration,
+    rebuild_corpus_stats: bool,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    let qdrant_collection = get_qdrant_collection_from_dataset_config(&dataset_config);
+
+    let message = MigrateDatasetMessage {
+        dataset_id,
+        from_collection: qdrant_collection.clone(),
+        to_collection: qdrant_collection,
+        mode: MigrationMode::BM25 {
+            average_len: dataset_config.BM25_AVG_LEN,
+            k: dataset_config.BM25_K,
+            b: dataset_config.BM25_B,
+            analyzer: dataset_config.bm25_analyzer_options(),
+            corpus_stats_dataset_id: rebuild_corpus_stats.then_some(dataset_id),
+        },
+    };
+
+    let serialized_message =
+        serde_json::to_string(&message).map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    redis::cmd("lpush")
+        .arg("dataset_migration")
+        .arg(&serialized_message)
+        .query_async::<_, ()>(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(())
+}
+
+/// Queues every point of the dataset in `from_collection` for the reindex-worker to migrate into `to_collection` with `mode`. Returns the number of points queued.
+#[tracing::instrument(skip(redis_pool))]
+pub async fn queue_dataset_migration_query(
+    dataset_id: uuid::Uuid,
+    from_collection: String,
+    to_collection: String,
+    mode: MigrationMode,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<usize, ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let mut queued_points = 0;
+    let mut offset = Some(uuid::Uuid::nil().to_string());
+
+    while let Some(cur_offset) = offset {
+        let (qdrant_point_ids, new_offset) = scroll_qdrant_collection_ids(
+            from_collection.clone(),
+            Some(cur_offset),
+            Some(1000),
+            Some(Filter::must([Condition::matches(
+                "dataset_id",
+                dataset_id.to_string(),
+            )])),
+        )
+        .await?;
+
+        if !qdrant_point_ids.is_empty() {
+            queued_points += qdrant_point_ids.len();
+
+            let message = MigratePointMessage {
+                qdrant_point_ids,
+                from_collection: from_collection.clone(),
+                to_collection: to_collection.clone(),
+                mode: mode.clone(),
+            };
+
+            let serialized_message = serde_json::to_string(&message)
+                .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+            redis::cmd("lpush")
+                .arg("collection_migration")
+                .arg(&serialized_message)
+                .query_async(&mut *redis_conn)
+                .await
+                .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+        }
+
+        offset = new_offset;
+    }
+
+    Ok(queued_points)
+}
+
+/// Running document length totals for a dataset, kept by the ingestion worker so BM25_AVG_LEN can be derived from the corpus instead of guessed.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Bm25CorpusStats {
+    pub doc_count: u64,
+    pub token_count: u64,
+}
+
+impl Bm25CorpusStats {
+    pub fn avg_len(&self) -> Option<f32> {
+        if self.doc_count == 0 {
+            return None;
+        }
+
+        Some(self.token_count as f32 / self.doc_count as f32)
+    }
+}
+
+pub fn get_bm25_corpus_stats_key(dataset_id: uuid::Uuid) -> String {
+    format!("bm25_stats:{}", dataset_id)
+}
+
+/// Adds signed deltas to the running totals. Totals may go below zero when chunks ingested before the stats were kept are removed, so they are clamped when read.
+async fn add_to_bm25_corpus_stats(
+    dataset_id: uuid::Uuid,
+    doc_count: i64,
+    token_count: i64,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let stats_key = get_bm25_corpus_stats_key(dataset_id);
+
+    redis::pipe()
+        .atomic()
+        .cmd("HINCRBY")
+        .arg(&stats_key)
+        .arg("doc_count")
+        .arg(doc_count)
+        .ignore()
+        .cmd("HINCRBY")
+        .arg(&stats_key)
+        .arg("token_count")
+        .arg(token_count)
+        .ignore()
+        .query_async::<_, ()>(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(())
+}
+
+#[tracing::instrument(skip(redis_pool))]
+pub async fn increment_bm25_corpus_stats_query(
+    dataset_id: uuid::Uuid,
+    doc_lengths: Vec<usize>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    if doc_lengths.is_empty() {
+        return Ok(());
+    }
+
+    add_to_bm25_corpus_stats(
+        dataset_id,
+        doc_lengths.len() as i64,
+        doc_lengths.iter().sum::<usize>() as i64,
+        redis_pool,
+    )
+    .await
+}
+
+#[tracing::instrument(skip(redis_pool))]
+pub async fn decrement_bm25_corpus_stats_query(
+    dataset_id: uuid::Uuid,
+    doc_lengths: Vec<usize>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    if doc_lengths.is_empty() {
+        return Ok(());
+    }
+
+    add_to_bm25_corpus_stats(
+        dataset_id,
+        -(doc_lengths.len() as i64),
+        -(doc_lengths.iter().sum::<usize>() as i64),
+        redis_pool,
+    )
+    .await
+}
+
+/// Takes chunks which were deleted or whose content was replaced back out of the dataset's BM25 corpus statistics and vocabulary, tokenized with the dataset's current analyzer.
+/// Failing to do so only makes the derived BM25_AVG_LEN and spelling corrections less accurate, so errors are logged rather than returned.
+pub async fn remove_chunks_from_bm25_corpus_stats(
+    dataset_id: uuid::Uuid,
+    chunk_htmls: Vec<String>,
+    dataset_config: &DatasetConfiguration,
+    redis_pool: web::Data<RedisPool>,
+) {
+    if chunk_htmls.is_empty()
+        || !dataset_config.BM25_ENABLED
+        || std::env::var("BM25_ACTIVE").unwrap_or("false".to_string()) != "true"
+    {
+        return;
+    }
+
+    let contents: Vec<String> = chunk_htmls
+        .iter()
+        .map(|chunk_html| convert_html_to_text(chunk_html))
+        .filter(|content| !content.is_empty())
+        .collect();
+    let analyzer = dataset_config.bm25_analyzer_options();
+    let vocabulary_terms = get_vocabulary_terms(contents.clone(), &analyzer);
+    let doc_lengths = get_bm25_doc_lengths(contents, &analyzer);
+
+    if let Err(err) =
+        decrement_bm25_corpus_stats_query(dataset_id, doc_lengths, redis_pool.clone()).await
+    {
+        log::error!("Failed to remove chunks from BM25 corpus stats: {:?}", err);
+    }
+
+    if let Err(err) = decrement_vocabulary_query(
+        dataset_id,
+        vocabulary_terms,
+        analyzer.ascii_folding,
+        redis_pool,
+    )
+    .await
+    {
+        log::error!("Failed to remove vocabulary terms: {:?}", err);
+    }
+}
+
+#[tracing::instrument(skip(redis_pool))]
+pub async fn get_bm25_corpus_stats_query(
+    dataset_id: uuid::Uuid,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<Bm25CorpusStats, ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let (doc_count, token_count): (Option<i64>, Option<i64>) = redis::cmd("HMGET")
+        .arg(get_bm25_corpus_stats_key(dataset_id))
+        .arg("doc_count")
+        .arg("token_count")
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(Bm25CorpusStats {
+        doc_count: doc_count.unwrap_or(0).max(0) as u64,
+        token_count: token_count.unwrap_or(0).max(0) as u64,
+    })
+}
+
+#[tracing::instrument(skip(redis_pool))]
+pub async fn clear_bm25_corpus_stats_query(
+    dataset_id: uuid::Uuid,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    redis::cmd("DEL")
+        .arg(get_bm25_corpus_stats_key(dataset_id))
+        .query_async::<_, ()>(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(())
+}
@@ -0,0 +1,310 @@
+use crate::{
+    data::models::{ChunkContentHash, DatasetConfiguration, Pool},
+    errors::ServiceError,
+};
+use actix_web::web;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use diesel_async::RunQueryDsl;
+use itertools::Itertools;
+
+/// Number of 16 bit bands a SimHash is split into. Two SimHashes which differ in fewer bits than there are bands share at least one band, which is what near duplicate candidates are looked up by.
+pub const SIMHASH_BANDS: u32 = 4;
+pub const MAX_NEAR_DUPLICATE_DISTANCE: u32 = SIMHASH_BANDS - 1;
+
+fn get_content_words(content: &str) -> Vec<String> {
+    content
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+/// blake3 hash of the content with case, punctuation and whitespace normalized away.
+pub fn get_content_hash(content: &str) -> String {
+    blake3::hash(get_content_words(content).join(" ").as_bytes()).to_string()
+}
+
+/// 64 bit SimHash of the word trigrams of the content. Contents which share most of their trigrams have SimHashes which differ in few bits.
+pub fn get_simhash(content: &str) -> u64 {
+    let words = get_content_words(content);
+    let features = if words.len() < 3 {
+        words
+    } else {
+        words.windows(3).map(|window| window.join(" ")).collect()
+    };
+
+    let mut weights = [0i64; 64];
+    for feature in features {
+        let hash = blake3::hash(feature.as_bytes());
+        let hash = u64::from_le_bytes(
+            hash.as_bytes()[..8]
+                .try_into()
+                .expect("blake3 hashes are 32 bytes"),
+        );
+
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if (hash >> bit) & 1 == 1 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0, |simhash, (bit, _)| simhash | (1 << bit))
+}
+
+/// Splits a SimHash into its bands. Each band is tagged with its position so that equal bits at different positions do not match.
+pub fn get_simhash_bands(simhash: u64) -> Vec<i32> {
+    (0..SIMHASH_BANDS)
+        .map(|band| ((band << 16) | ((simhash >> (16 * band)) & 0xffff) as u32) as i32)
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateChunk {
+    pub chunk_id: uuid::Uuid,
+    pub duplicate_of_chunk_id: uuid::Uuid,
+    /// Number of differing SimHash bits, 0 for exact duplicates.
+    pub distance: u32,
+}
+
+fn get_distance(
+    candidate: &ChunkContentHash,
+    original: &ChunkContentHash,
+    near_duplicate_distance: Option<u32>,
+) -> Option<u32> {
+    if candidate.content_hash == original.content_hash {
+        return Some(0);
+    }
+
+    let distance = (candidate.simhash ^ original.simhash).count_ones();
+    near_duplicate_distance
+        .filter(|max_distance| distance <= *max_distance)
+        .map(|_| distance)
+}
+
+/// Finds the candidates which duplicate an existing chunk or an earlier candidate of the same batch. Hashes are paired with the tracking_id of their chunk, a chunk is never a duplicate of a chunk with the same tracking_id as that is an update of the chunk.
+pub fn find_duplicate_chunks(
+    candidates: &[(ChunkContentHash, Option<String>)],
+    existing: &[(ChunkContentHash, Option<String>)],
+    near_duplicate_distance: Option<u32>,
+) -> Vec<DuplicateChunk> {
+    let mut originals = existing.iter().collect_vec();
+    let mut duplicate_chunks = vec![];
+
+    for candidate in candidates {
+        let (candidate_hash, candidate_tracking_id) = candidate;
+
+        let duplicate_of = originals
+            .iter()
+            .filter(|(original_hash, original_tracking_id)| {
+                original_hash.chunk_id != candidate_hash.chunk_id
+                    && (candidate_tracking_id.is_none()
+                        || original_tracking_id != candidate_tracking_id)
+            })
+            .filter_map(|(original_hash, _)| {
+                get_distance(candidate_hash, original_hash, near_duplicate_distance)
+                    .map(|distance| (original_hash.chunk_id, distance))
+            })
+            .min_by_key(|(_, distance)| *distance);
+
+        match duplicate_of {
+            Some((duplicate_of_chunk_id, distance)) => duplicate_chunks.push(DuplicateChunk {
+                chunk_id: candidate_hash.chunk_id,
+                duplicate_of_chunk_id,
+                distance,
+            }),
+            None => originals.push(candidate),
+        }
+    }
+
+    duplicate_chunks
+}
+
+/// Gets the stored hashes of the dataset which could be duplicates of the candidates, paired with the tracking_id of their chunk.
+#[tracing::instrument(skip(pool))]
+pub async fn get_matching_chunk_content_hashes_query(
+    candidates: &[ChunkContentHash],
+    dataset_id: uuid::Uuid,
+    near_duplicates: bool,
+    pool: web::Data<Pool>,
+) -> Result<Vec<(ChunkContentHash, Option<String>)>, ServiceError> {
+    use crate::data::schema::chunk_content_hashes::dsl as chunk_content_hashes_columns;
+    use crate::data::schema::chunk_metadata::dsl as chunk_metadata_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let content_hashes = candidates
+        .iter()
+        .map(|candidate| candidate.content_hash.clone())
+        .unique()
+        .collect_vec();
+    let simhash_bands = if near_duplicates {
+        candidates
+            .iter()
+            .flat_map(|candidate| candidate.simhash_bands.clone())
+            .unique()
+            .collect_vec()
+    } else {
+        vec![]
+    };
+
+    let matching_hashes = chunk_content_hashes_columns::chunk_content_hashes
+        .inner_join(chunk_metadata_columns::chunk_metadata)
+        .filter(chunk_content_hashes_columns::dataset_id.eq(dataset_id))
+        .filter(
+            chunk_content_hashes_columns::content_hash
+                .eq_any(content_hashes)
+                .or(chunk_content_hashes_columns::simhash_bands.overlaps_with(simhash_bands)),
+        )
+        .select((
+            ChunkContentHash::as_select(),
+            chunk_metadata_columns::tracking_id,
+        ))
+        .load::<(ChunkContentHash, Option<String>)>(&mut conn)
+        .await
+        .map_err(|err| {
+            log::error!("Failed to get chunk content hashes: {:?}", err);
+            ServiceError::BadRequest("Failed to get chunk content hashes".to_string())
+        })?;
+
+    Ok(matching_hashes)
+}
+
+/// Finds the candidates which are duplicates according to the dedup settings of the dataset configuration.
+#[tracing::instrument(skip(candidates, pool))]
+pub async fn get_duplicate_chunks_query(
+    candidates: Vec<(ChunkContentHash, Option<String>)>,
+    dataset_id: uuid::Uuid,
+    dataset_config: &DatasetConfiguration,
+    pool: web::Data<Pool>,
+) -> Result<Vec<DuplicateChunk>, ServiceError> {
+    if candidates.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let near_duplicate_distance = dataset_config
+        .DEDUP_NEAR_DUPLICATE_DISTANCE
+        .map(|distance| distance.min(MAX_NEAR_DUPLICATE_DISTANCE));
+
+    let existing = get_matching_chunk_content_hashes_query(
+        &candidates
+            .iter()
+            .map(|(candidate, _)| candidate.clone())
+            .collect_vec(),
+        dataset_id,
+        near_duplicate_distance.is_some(),
+        pool,
+    )
+    .await?;
+
+    Ok(find_duplicate_chunks(
+        &candidates,
+        &existing,
+        near_duplicate_distance,
+    ))
+}
+
+#[tracing::instrument(skip(chunk_content_hashes, pool))]
+pub async fn upsert_chunk_content_hashes_query(
+    chunk_content_hashes: Vec<ChunkContentHash>,
+    pool: web::Data<Pool>,
+) -> Result<(), ServiceError> {
+    use crate::data::schema::chunk_content_hashes::dsl as chunk_content_hashes_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    diesel::insert_into(chunk_content_hashes_columns::chunk_content_hashes)
+        .values(&chunk_content_hashes)
+        .on_conflict(chunk_content_hashes_columns::chunk_id)
+        .do_update()
+        .set((
+            chunk_content_hashes_columns::content_hash
+                .eq(excluded(chunk_content_hashes_columns::content_hash)),
+            chunk_content_hashes_columns::simhash
+                .eq(excluded(chunk_content_hashes_columns::simhash)),
+            chunk_content_hashes_columns::simhash_bands
+                .eq(excluded(chunk_content_hashes_columns::simhash_bands)),
+        ))
+        .execute(&mut conn)
+        .await
+        .map_err(|err| {
+            log::error!("Failed to save chunk content hashes: {:?}", err);
+            ServiceError::BadRequest("Failed to save chunk content hashes".to_string())
+        })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hash(content: &str, tracking_id: Option<&str>) -> (ChunkContentHash, Option<String>) {
+        (
+            ChunkContentHash::from_details(uuid::Uuid::new_v4(), uuid::Uuid::nil(), content),
+            tracking_id.map(|tracking_id| tracking_id.to_string()),
+        )
+    }
+
+    #[test]
+    fn test_simhash_distance() {
+        let content = "The quick brown fox jumps over the lazy dog while the farmer sleeps under the old oak tree next to the barn and the river flows quietly past the fields of golden wheat";
+        let near_duplicate = "The quick brown fox jumps over the lazy dog while the farmer sleeps under the old oak tree next to the barn and the river flows quietly past the fields of golden barley";
+
+        assert_eq!(
+            get_content_hash(content),
+            get_content_hash(&format!("  {}!", content.to_uppercase()))
+        );
+        assert!(
+            (get_simhash(content) ^ get_simhash(near_duplicate)).count_ones()
+                < (get_simhash(content)
+                    ^ get_simhash("An entirely unrelated sentence about tax law"))
+                .count_ones()
+        );
+
+        let bands = get_simhash_bands(0xffff_0000_0000_0001);
+        assert_eq!(bands, vec![1, 1 << 16, 2 << 16, (3 << 16) | 0xffff]);
+    }
+
+    #[test]
+    fn test_find_duplicate_chunks() {
+        let existing = vec![hash("Return policy: 30 days", Some("policy"))];
+        let candidates = vec![
+            hash("return POLICY - 30 days", None),
+            hash("Return policy: 30 days", Some("policy")),
+            hash("Shipping takes 5 days", None),
+            hash("shipping takes 5 days.", None),
+        ];
+
+        let duplicates = find_duplicate_chunks(&candidates, &existing, None);
+
+        assert_eq!(
+            duplicates,
+            vec![
+                DuplicateChunk {
+                    chunk_id: candidates[0].0.chunk_id,
+                    duplicate_of_chunk_id: existing[0].0.chunk_id,
+                    distance: 0,
+                },
+                DuplicateChunk {
+                    chunk_id: candidates[3].0.chunk_id,
+                    duplicate_of_chunk_id: candidates[2].0.chunk_id,
+                    distance: 0,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,666 @@
+use super::search_operator::{search_chunks_query, search_hybrid_chunks};
+use crate::{
+    data::models::{
+        Dataset, DatasetConfiguration, Judgment, JudgmentList, JudgmentQuery, Pool, QueryTypes,
+        RedisPool, SearchChunkClicksClickhouse, SearchEvaluation, SearchEvaluationClickhouse,
+        SearchEvaluationQueryClickhouse, SearchEvaluationQueryResult, SearchMethod,
+    },
+    errors::ServiceError,
+    handlers::chunk_handler::{parse_query, ParsedQueryTypes, SearchChunksReqPayload},
+};
+use actix_web::web;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use simple_server_timing_header::Timer;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// Maximum number of queries in a judgment list, as an evaluation runs a search for each of them.
+pub const MAX_JUDGMENT_QUERIES: usize = 1000;
+
+#[tracing::instrument(skip(pool))]
+pub async fn create_judgment_list_query(
+    judgment_list: JudgmentList,
+    pool: web::Data<Pool>,
+) -> Result<JudgmentList, ServiceError> {
+    use crate::data::schema::judgment_lists::dsl as judgment_lists_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let created_judgment_list: JudgmentList =
+        diesel::insert_into(judgment_lists_columns::judgment_lists)
+            .values(&judgment_list)
+            .get_result(&mut conn)
+            .await
+            .map_err(|_| ServiceError::BadRequest("Could not create judgment list".to_string()))?;
+
+    Ok(created_judgment_list)
+}
+
+#[tracing::instrument(skip(pool))]
+pub async fn get_judgment_lists_for_dataset_query(
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<Vec<JudgmentList>, ServiceError> {
+    use crate::data::schema::judgment_lists::dsl as judgment_lists_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let judgment_lists: Vec<JudgmentList> = judgment_lists_columns::judgment_lists
+        .filter(judgment_lists_columns::dataset_id.eq(dataset_id))
+        .order(judgment_lists_columns::created_at.asc())
+        .load(&mut conn)
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get judgment lists".to_string()))?;
+
+    Ok(judgment_lists)
+}
+
+#[tracing::instrument(skip(pool))]
+pub async fn get_judgment_list_by_id_query(
+    judgment_list_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<JudgmentList, ServiceError> {
+    use crate::data::schema::judgment_lists::dsl as judgment_lists_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let judgment_list: Option<JudgmentList> = judgment_lists_columns::judgment_lists
+        .filter(judgment_lists_columns::id.eq(judgment_list_id))
+        .filter(judgment_lists_columns::dataset_id.eq(dataset_id))
+        .first(&mut conn)
+        .await
+        .optional()
+        .map_err(|_| ServiceError::BadRequest("Could not get judgment list".to_string()))?;
+
+    judgment_list.ok_or(ServiceError::NotFound(
+        "Judgment list with specified id not found".to_string(),
+    ))
+}
+
+#[tracing::instrument(skip(pool))]
+pub async fn update_judgment_list_query(
+    judgment_list_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    name: Option<String>,
+    queries: Option<Vec<JudgmentQuery>>,
+    pool: web::Data<Pool>,
+) -> Result<JudgmentList, ServiceError> {
+    use crate::data::schema::judgment_lists::dsl as judgment_lists_columns;
+
+    let prev_judgment_list =
+        get_judgment_list_by_id_query(judgment_list_id, dataset_id, pool.clone()).await?;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let queries = match queries {
+        Some(queries) => serde_json::to_value(queries).unwrap_or_default(),
+        None => prev_judgment_list.queries,
+    };
+
+    let updated_judgment_list: JudgmentList = diesel::update(
+        judgment_lists_columns::judgment_lists
+            .filter(judgment_lists_columns::id.eq(judgment_list_id))
+            .filter(judgment_lists_columns::dataset_id.eq(dataset_id)),
+    )
+    .set((
+        judgment_lists_columns::name.eq(name.unwrap_or(prev_judgment_list.name)),
+        judgment_lists_columns::queries.eq(queries),
+        judgment_lists_columns::updated_at.eq(chrono::Utc::now().naive_local()),
+    ))
+    .get_result(&mut conn)
+    .await
+    .map_err(|_| ServiceError::BadRequest("Could not update judgment list".to_string()))?;
+
+    Ok(updated_judgment_list)
+}
+
+#[tracing::instrument(skip(pool))]
+pub async fn delete_judgment_list_query(
+    judgment_list_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<(), ServiceError> {
+    use crate::data::schema::judgment_lists::dsl as judgment_lists_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let deleted_count = diesel::delete(
+        judgment_lists_columns::judgment_lists
+            .filter(judgment_lists_columns::id.eq(judgment_list_id))
+            .filter(judgment_lists_columns::dataset_id.eq(dataset_id)),
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(|_| ServiceError::BadRequest("Could not delete judgment list".to_string()))?;
+
+    if deleted_count == 0 {
+        return Err(ServiceError::NotFound(
+            "Judgment list with specified id not found".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Grade of a result according to the judgments of its query. A result matches a judgment by either its id or its tracking_id, results without a judgment are graded 0.
+fn grade_result(judgments: &[Judgment], id: uuid::Uuid, tracking_id: Option<&str>) -> f64 {
+    judgments
+        .iter()
+        .find(|judgment| {
+            judgment.chunk_id == Some(id)
+                || (judgment.tracking_id.is_some()
+                    && judgment.tracking_id.as_deref() == tracking_id)
+        })
+        .map(|judgment| judgment.grade)
+        .unwrap_or(0.0)
+}
+
+fn discounted_cumulative_gain(grades: &[f64]) -> f64 {
+    grades
+        .iter()
+        .enumerate()
+        .map(|(rank, grade)| (2f64.powf(*grade) - 1.0) / (rank as f64 + 2.0).log2())
+        .sum()
+}
+
+/// Computes nDCG@k, reciprocal rank and recall@k from the grades of the first k results of a query.
+pub fn score_query_results(
+    result_grades: &[f64],
+    judgments: &[Judgment],
+    k: usize,
+) -> (f64, f64, f64) {
+    let result_grades = &result_grades[..result_grades.len().min(k)];
+
+    let mut ideal_grades = judgments
+        .iter()
+        .map(|judgment| judgment.grade)
+        .filter(|grade| *grade > 0.0)
+        .collect::<Vec<f64>>();
+    ideal_grades.sort_by(|a, b| b.partial_cmp(a).unwrap_or(std::cmp::Ordering::Equal));
+    let relevant_count = ideal_grades.len();
+    ideal_grades.truncate(k);
+
+    let ideal_dcg = discounted_cumulative_gain(&ideal_grades);
+    let ndcg = if ideal_dcg > 0.0 {
+        discounted_cumulative_gain(result_grades) / ideal_dcg
+    } else {
+        0.0
+    };
+
+    let reciprocal_rank = result_grades
+        .iter()
+        .position(|grade| *grade > 0.0)
+        .map(|rank| 1.0 / (rank as f64 + 1.0))
+        .unwrap_or(0.0);
+
+    let recall = if relevant_count > 0 {
+        result_grades.iter().filter(|grade| **grade > 0.0).count() as f64 / relevant_count as f64
+    } else {
+        0.0
+    };
+
+    (ndcg, reciprocal_rank, recall)
+}
+
+#[tracing::instrument(skip(pool, redis_pool, dataset, config))]
+pub async fn evaluate_judgment_query(
+    judgment_query: &JudgmentQuery,
+    request_template: &SearchChunksReqPayload,
+    k: u32,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+    dataset: Dataset,
+    config: &DatasetConfiguration,
+) -> Result<SearchEvaluationQueryResult, actix_web::Error> {
+    let mut data = request_template.clone();
+    data.query = QueryTypes::Single(judgment_query.query.clone());
+    data.page = Some(1);
+    data.page_size = Some(k.into());
+    data.get_total_pages = Some(false);
+    data.slim_chunks = Some(true);
+    data.search_after = None;
+    data.collapse = None;
+    data.facets = None;
+
+    let parsed_query = parse_query(
+        judgment_query.query.clone(),
+        data.use_quote_negated_terms,
+        data.remove_stop_words,
+    );
+
+    let mut timer = Timer::new();
+    let start = std::time::Instant::now();
+
+    let result_chunks = match data.search_type {
+        SearchMethod::Hybrid => {
+            search_hybrid_chunks(
+                data,
+                parsed_query,
+                pool,
+                redis_pool,
+                dataset,
+                config,
+                &mut timer,
+            )
+            .await?
+        }
+        _ => {
+            search_chunks_query(
+                data,
+                ParsedQueryTypes::Single(parsed_query),
+                pool,
+                redis_pool,
+                dataset,
+                config,
+                &mut timer,
+            )
+            .await?
+        }
+    };
+
+    let latency = start.elapsed().as_secs_f32() * 1000.0;
+
+    let (results, result_grades): (Vec<uuid::Uuid>, Vec<f64>) = result_chunks
+        .score_chunks
+        .iter()
+        .filter_map(|score_chunk| score_chunk.metadata.get(0))
+        .take(k as usize)
+        .map(|metadata| {
+            let metadata = metadata.metadata();
+            let grade = grade_result(
+                &judgment_query.judgments,
+                metadata.id,
+                metadata.tracking_id.as_deref(),
+            );
+            (metadata.id, grade)
+        })
+        .unzip();
+
+    let (ndcg, reciprocal_rank, recall) =
+        score_query_results(&result_grades, &judgment_query.judgments, k as usize);
+
+    Ok(SearchEvaluationQueryResult {
+        query: judgment_query.query.clone(),
+        ndcg,
+        reciprocal_rank,
+        recall,
+        latency,
+        results,
+    })
+}
+
+/// Aggregates the per query results of an evaluation into the evaluation row stored in ClickHouse.
+pub fn aggregate_evaluation(
+    judgment_list: &JudgmentList,
+    request_template: &SearchChunksReqPayload,
+    k: u32,
+    query_results: &[SearchEvaluationQueryResult],
+) -> SearchEvaluationClickhouse {
+    let query_count = query_results.len();
+    let mean = |values: Vec<f64>| {
+        if query_count == 0 {
+            0.0
+        } else {
+            values.iter().sum::<f64>() / query_count as f64
+        }
+    };
+
+    let mut latencies = query_results
+        .iter()
+        .map(|result| result.latency)
+        .collect::<Vec<f32>>();
+    latencies.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let p95_latency = latencies
+        .get(((latencies.len() as f32 * 0.95).ceil() as usize).saturating_sub(1))
+        .copied()
+        .unwrap_or(0.0);
+
+    SearchEvaluationClickhouse {
+        id: uuid::Uuid::new_v4(),
+        dataset_id: judgment_list.dataset_id,
+        judgment_list_id: judgment_list.id,
+        request_params: serde_json::to_string(request_template).unwrap_or_default(),
+        k,
+        query_count: query_count as u32,
+        ndcg: mean(query_results.iter().map(|result| result.ndcg).collect()),
+        mrr: mean(
+            query_results
+                .iter()
+                .map(|result| result.reciprocal_rank)
+                .collect(),
+        ),
+        recall: mean(query_results.iter().map(|result| result.recall).collect()),
+        avg_latency: mean(
+            query_results
+                .iter()
+                .map(|result| result.latency as f64)
+                .collect(),
+        ) as f32,
+        p95_latency,
+        created_at: time::OffsetDateTime::now_utc(),
+    }
+}
+
+pub async fn insert_search_evaluation_query(
+    evaluation: &SearchEvaluationClickhouse,
+    query_results: &[SearchEvaluationQueryResult],
+    clickhouse_client: &clickhouse::Client,
+) -> Result<(), ServiceError> {
+    if std::env::var("USE_ANALYTICS").unwrap_or("false".to_string()) != "true" {
+        return Ok(());
+    }
+
+    let mut evaluations_inserter = clickhouse_client
+        .insert("default.search_evaluations")
+        .map_err(|e| {
+            log::error!("Error inserting search evaluation: {:?}", e);
+            ServiceError::InternalServerError("Error inserting search evaluation".to_string())
+        })?;
+
+    evaluations_inserter.write(evaluation).await.map_err(|e| {
+        log::error!("Error writing search evaluation: {:?}", e);
+        ServiceError::InternalServerError("Error writing search evaluation".to_string())
+    })?;
+
+    evaluations_inserter.end().await.map_err(|e| {
+        log::error!("Error inserting search evaluation: {:?}", e);
+        ServiceError::InternalServerError("Error inserting search evaluation".to_string())
+    })?;
+
+    let mut evaluation_queries_inserter = clickhouse_client
+        .insert("default.search_evaluation_queries")
+        .map_err(|e| {
+            log::error!("Error inserting search evaluation queries: {:?}", e);
+            ServiceError::InternalServerError(
+                "Error inserting search evaluation queries".to_string(),
+            )
+        })?;
+
+    for query_result in query_results {
+        evaluation_queries_inserter
+            .write(&SearchEvaluationQueryClickhouse {
+                id: uuid::Uuid::new_v4(),
+                evaluation_id: evaluation.id,
+                dataset_id: evaluation.dataset_id,
+                query: query_result.query.clone(),
+                ndcg: query_result.ndcg,
+                reciprocal_rank: query_result.reciprocal_rank,
+                recall: query_result.recall,
+                latency: query_result.latency,
+                results: query_result
+                    .results
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect(),
+                created_at: evaluation.created_at,
+            })
+            .await
+            .map_err(|e| {
+                log::error!("Error writing search evaluation query: {:?}", e);
+                ServiceError::InternalServerError(
+                    "Error writing search evaluation query".to_string(),
+                )
+            })?;
+    }
+
+    evaluation_queries_inserter.end().await.map_err(|e| {
+        log::error!("Error inserting search evaluation queries: {:?}", e);
+        ServiceError::InternalServerError("Error inserting search evaluation queries".to_string())
+    })?;
+
+    Ok(())
+}
+
+pub async fn get_search_evaluations_query(
+    dataset_id: uuid::Uuid,
+    judgment_list_id: Option<uuid::Uuid>,
+    page: Option<u32>,
+    clickhouse_client: &clickhouse::Client,
+) -> Result<Vec<SearchEvaluation>, ServiceError> {
+    let mut query_string =
+        String::from("SELECT ?fields FROM search_evaluations WHERE dataset_id = ?");
+
+    if judgment_list_id.is_some() {
+        query_string.push_str(" AND judgment_list_id = ?");
+    }
+
+    query_string.push_str(&format!(
+        " ORDER BY created_at DESC LIMIT 10 OFFSET {}",
+        (page.unwrap_or(1).max(1) - 1) * 10
+    ));
+
+    let mut query = clickhouse_client
+        .query(query_string.as_str())
+        .bind(dataset_id);
+
+    if let Some(judgment_list_id) = judgment_list_id {
+        query = query.bind(judgment_list_id);
+    }
+
+    let clickhouse_evaluations = query
+        .fetch_all::<SearchEvaluationClickhouse>()
+        .await
+        .map_err(|e| {
+            log::error!("Error fetching search evaluations: {:?}", e);
+            ServiceError::InternalServerError("Error fetching search evaluations".to_string())
+        })?;
+
+    Ok(clickhouse_evaluations
+        .into_iter()
+        .map(SearchEvaluation::from)
+        .collect())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct SearchEvaluationWithQueries {
+    pub evaluation: SearchEvaluation,
+    pub queries: Vec<SearchEvaluationQueryResult>,
+}
+
+pub async fn get_search_evaluation_query(
+    dataset_id: uuid::Uuid,
+    evaluation_id: uuid::Uuid,
+    clickhouse_client: &clickhouse::Client,
+) -> Result<SearchEvaluationWithQueries, ServiceError> {
+    let clickhouse_evaluation = clickhouse_client
+        .query("SELECT ?fields FROM search_evaluations WHERE id = ? AND dataset_id = ?")
+        .bind(evaluation_id)
+        .bind(dataset_id)
+        .fetch_optional::<SearchEvaluationClickhouse>()
+        .await
+        .map_err(|e| {
+            log::error!("Error fetching search evaluation: {:?}", e);
+            ServiceError::InternalServerError("Error fetching search evaluation".to_string())
+        })?
+        .ok_or(ServiceError::NotFound(
+            "Search evaluation with specified id not found".to_string(),
+        ))?;
+
+    let clickhouse_queries = clickhouse_client
+        .query("SELECT ?fields FROM search_evaluation_queries WHERE evaluation_id = ? AND dataset_id = ?")
+        .bind(evaluation_id)
+        .bind(dataset_id)
+        .fetch_all::<SearchEvaluationQueryClickhouse>()
+        .await
+        .map_err(|e| {
+            log::error!("Error fetching search evaluation queries: {:?}", e);
+            ServiceError::InternalServerError(
+                "Error fetching search evaluation queries".to_string(),
+            )
+        })?;
+
+    Ok(SearchEvaluationWithQueries {
+        evaluation: clickhouse_evaluation.into(),
+        queries: clickhouse_queries
+            .into_iter()
+            .map(SearchEvaluationQueryResult::from)
+            .collect(),
+    })
+}
+
+/// Weights used to turn clicks on search results into implicit relevance judgments.
+#[derive(Debug, Clone)]
+pub struct ImplicitJudgmentOptions {
+    /// Clicks which report a `dwell_time` below this many seconds are treated as bounces and ignored.
+    pub min_dwell_time: f64,
+    /// Clicks on searches rated positively are multiplied by this weight, clicks on searches rated negatively are divided by it.
+    pub rating_weight: f64,
+    /// Grade given to the most clicked chunk of each query.
+    pub max_grade: f64,
+    /// Queries with fewer clicks than this are left out.
+    pub min_clicks: usize,
+    /// Maximum number of queries, the most clicked queries are kept.
+    pub max_queries: usize,
+}
+
+/// Builds judgment queries from the clicks summed up per query and chunk by [get_search_clicks_query](super::analytics_operator::get_search_clicks_query). Click scores are scaled so the highest scoring chunk of each query gets `max_grade`.
+pub fn build_implicit_judgment_queries(
+    chunk_clicks: Vec<SearchChunkClicksClickhouse>,
+    options: &ImplicitJudgmentOptions,
+) -> Vec<JudgmentQuery> {
+    let mut query_scores: HashMap<String, (u64, HashMap<uuid::Uuid, f64>)> = HashMap::new();
+
+    for chunk_click in chunk_clicks {
+        let (click_count, chunk_scores) = query_scores.entry(chunk_click.query).or_default();
+        *click_count += chunk_click.clicks;
+        *chunk_scores.entry(chunk_click.chunk_id).or_default() += chunk_click.score;
+    }
+
+    query_scores
+        .into_iter()
+        .filter(|(_, (click_count, _))| *click_count >= options.min_clicks as u64)
+        .sorted_by(|(a_query, (a_count, _)), (b_query, (b_count, _))| {
+            b_count.cmp(a_count).then_with(|| a_query.cmp(b_query))
+        })
+        .take(options.max_queries)
+        .map(|(query, (_, chunk_scores))| {
+            let max_score = chunk_scores.values().copied().fold(0.0, f64::max);
+
+            let judgments = chunk_scores
+                .into_iter()
+                .map(|(chunk_id, score)| Judgment {
+                    chunk_id: Some(chunk_id),
+                    tracking_id: None,
+                    grade: (options.max_grade * score / max_score).ceil().max(1.0),
+                })
+                .sorted_by(|a, b| {
+                    b.grade
+                        .partial_cmp(&a.grade)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                        .then_with(|| a.chunk_id.cmp(&b.chunk_id))
+                })
+                .collect();
+
+            JudgmentQuery { query, judgments }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn judgment(chunk_id: uuid::Uuid, grade: f64) -> Judgment {
+        Judgment {
+            chunk_id: Some(chunk_id),
+            tracking_id: None,
+            grade,
+        }
+    }
+
+    #[test]
+    fn test_score_query_results() {
+        let judgments = vec![
+            judgment(uuid::Uuid::new_v4(), 3.0),
+            judgment(uuid::Uuid::new_v4(), 1.0),
+        ];
+
+        let (ndcg, reciprocal_rank, recall) = score_query_results(&[3.0, 1.0, 0.0], &judgments, 3);
+        assert!((ndcg - 1.0).abs() < 1e-9);
+        assert_eq!(reciprocal_rank, 1.0);
+        assert_eq!(recall, 1.0);
+
+        let (ndcg, reciprocal_rank, recall) = score_query_results(&[0.0, 1.0, 0.0], &judgments, 3);
+        assert!(ndcg > 0.0 && ndcg < 1.0);
+        assert_eq!(reciprocal_rank, 0.5);
+        assert_eq!(recall, 0.5);
+
+        let (ndcg, reciprocal_rank, recall) = score_query_results(&[0.0, 0.0, 3.0], &judgments, 2);
+        assert_eq!(ndcg, 0.0);
+        assert_eq!(reciprocal_rank, 0.0);
+        assert_eq!(recall, 0.0);
+    }
+
+    #[test]
+    fn test_grade_result_by_tracking_id() {
+        let id = uuid::Uuid::new_v4();
+        let judgments = vec![Judgment {
+            chunk_id: None,
+            tracking_id: Some("dogs-are-loyal".to_string()),
+            grade: 2.0,
+        }];
+
+        assert_eq!(grade_result(&judgments, id, Some("dogs-are-loyal")), 2.0);
+        assert_eq!(grade_result(&judgments, id, Some("cats")), 0.0);
+        assert_eq!(grade_result(&judgments, id, None), 0.0);
+    }
+
+    #[test]
+    fn test_build_implicit_judgment_queries() {
+        let first_chunk = uuid::Uuid::new_v4();
+        let second_chunk = uuid::Uuid::new_v4();
+        let chunk_clicks = |query: &str, chunk_id, score, clicks| SearchChunkClicksClickhouse {
+            query: query.to_string(),
+            chunk_id,
+            score,
+            clicks,
+        };
+
+        let options = ImplicitJudgmentOptions {
+            min_dwell_time: 5.0,
+            rating_weight: 2.0,
+            max_grade: 3.0,
+            min_clicks: 2,
+            max_queries: 10,
+        };
+
+        let judgment_queries = build_implicit_judgment_queries(
+            vec![
+                chunk_clicks("dogs", first_chunk, 3.0, 2),
+                chunk_clicks("dogs", second_chunk, 1.0, 1),
+                chunk_clicks("cats", first_chunk, 1.0, 1),
+            ],
+            &options,
+        );
+
+        assert_eq!(judgment_queries.len(), 1);
+        assert_eq!(judgment_queries[0].query, "dogs");
+        assert_eq!(judgment_queries[0].judgments.len(), 2);
+        assert_eq!(judgment_queries[0].judgments[0].chunk_id, Some(first_chunk));
+        assert_eq!(judgment_queries[0].judgments[0].grade, 3.0);
+        assert_eq!(
+            judgment_queries[0].judgments[1].chunk_id,
+            Some(second_chunk)
+        );
+        assert_eq!(judgment_queries[0].judgments[1].grade, 1.0);
+    }
+}
@@ -0,0 +1,496 @@
+use super::qdrant_operator::{count_filtered_points_query, scroll_filtered_payloads_query};
+use crate::{
+    data::models::{
+        DatasetConfiguration, DistanceBucket, FacetOptions, FacetResults, GeoDistanceFacet,
+        GeoDistanceFacetResult, GeoInfo, RangeBucket, RangeFacet, RangeFacetResult, TermCount,
+        TermFacetResult,
+    },
+    errors::ServiceError,
+};
+use itertools::Itertools;
+use qdrant_client::qdrant::{Condition, Filter, GeoPoint, GeoRadius, Range};
+use std::collections::HashMap;
+
+fn get_metadata_path(field: &str) -> Option<String> {
+    let key = field.strip_prefix("metadata.")?;
+    if key.is_empty() || key.split('.').any(|part| part.is_empty()) {
+        return None;
+    }
+
+    Some(format!("/{}", key.replace('.', "/")))
+}
+
+fn get_term_value(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(value) => Some(value.clone()),
+        serde_json::Value::Number(value) => Some(value.to_string()),
+        serde_json::Value::Bool(value) => Some(value.to_string()),
+        _ => None,
+    }
+}
+
+fn get_term_values(payload: &HashMap<String, serde_json::Value>, field: &str) -> Vec<String> {
+    let value = match get_metadata_path(field) {
+        Some(path) => payload
+            .get("metadata")
+            .and_then(|metadata| metadata.pointer(&path)),
+        None => payload.get(field),
+    };
+
+    match value {
+        Some(serde_json::Value::Array(values)) => {
+            values.iter().filter_map(get_term_value).unique().collect()
+        }
+        Some(value) => get_term_value(value).into_iter().collect(),
+        None => vec![],
+    }
+}
+
+/// Payload fields Qdrant has to return for the term facets to be counted.
+fn get_term_payload_fields(facets: &FacetOptions) -> Result<Vec<String>, ServiceError> {
+    facets
+        .terms
+        .iter()
+        .flatten()
+        .map(|term_facet| {
+            if term_facet.field != "tag_set" && get_metadata_path(&term_facet.field).is_none() {
+                return Err(ServiceError::BadRequest(format!(
+                    "Unsupported term facet field: {}",
+                    term_facet.field
+                )));
+            }
+            Ok(term_facet.field.clone())
+        })
+        .collect::<Result<Vec<String>, ServiceError>>()
+        .map(|payload_fields| payload_fields.into_iter().unique().collect())
+}
+
+/// Counts the most common values of the term facets over the payloads of the sampled chunks.
+pub fn compute_term_facets(
+    facets: &FacetOptions,
+    payloads: &[HashMap<String, serde_json::Value>],
+) -> Vec<TermFacetResult> {
+    facets
+        .terms
+        .iter()
+        .flatten()
+        .map(|term_facet| {
+            let counts = payloads
+                .iter()
+                .flat_map(|payload| get_term_values(payload, &term_facet.field))
+                .counts()
+                .into_iter()
+                .map(|(value, count)| TermCount {
+                    value,
+                    count: count as u64,
+                })
+                .sorted_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)))
+                .take(term_facet.limit.unwrap_or(10))
+                .collect();
+
+            TermFacetResult {
+                field: term_facet.field.clone(),
+                counts,
+            }
+        })
+        .collect()
+}
+
+/// Maximum number of range and distance buckets counted for a search, each bucket is a count request to Qdrant.
+pub const MAX_FACET_BUCKETS: usize = 100;
+
+fn validate_bucket_count(facets: &FacetOptions) -> Result<(), ServiceError> {
+    let bucket_count = facets
+        .ranges
+        .iter()
+        .flatten()
+        .map(|range_facet| range_facet.ranges.len())
+        .chain(
+            facets
+                .geo_distances
+                .iter()
+                .flatten()
+                .map(|geo_distance_facet| geo_distance_facet.ranges.len()),
+        )
+        .sum::<usize>();
+
+    if bucket_count > MAX_FACET_BUCKETS {
+        return Err(ServiceError::BadRequest(format!(
+            "Facets can count at most {} range and distance buckets, {} were requested",
+            MAX_FACET_BUCKETS, bucket_count
+        )));
+    }
+
+    Ok(())
+}
+
+fn with_conditions(filter: &Filter, must: Vec<Condition>, must_not: Vec<Condition>) -> Filter {
+    let mut filter = filter.clone();
+    filter.must.extend(must);
+    filter.must_not.extend(must_not);
+    filter
+}
+
+/// The search filter narrowed to each bucket of the range facet.
+fn get_range_bucket_filters(
+    range_facet: &RangeFacet,
+    filter: &Filter,
+) -> Result<Vec<Filter>, ServiceError> {
+    if range_facet.field != "num_value" && range_facet.field != "time_stamp" {
+        return Err(ServiceError::BadRequest(format!(
+            "Unsupported range facet field: {}",
+            range_facet.field
+        )));
+    }
+
+    range_facet
+        .ranges
+        .iter()
+        .map(|range| {
+            let gte = range
+                .from
+                .as_ref()
+                .map(|from| from.value(&range_facet.field))
+                .transpose()?;
+            let lt = range
+                .to
+                .as_ref()
+                .map(|to| to.value(&range_facet.field))
+                .transpose()?;
+
+            Ok(with_conditions(
+                filter,
+                vec![Condition::range(
+                    range_facet.field.as_str(),
+                    Range {
+                        gte,
+                        lt,
+                        ..Default::default()
+                    },
+                )],
+                vec![],
+            ))
+        })
+        .collect()
+}
+
+fn get_within_distance_condition(origin: &GeoInfo, distance: f64) -> Condition {
+    Condition::geo_radius(
+        "location",
+        GeoRadius {
+            center: Some(GeoPoint {
+                lat: origin.lat.into(),
+                lon: origin.lon.into(),
+            }),
+            radius: (distance * 1000.0) as f32,
+        },
+    )
+}
+
+/// The search filter narrowed to each bucket of the geo distance facet. Chunks without a location are in no bucket.
+fn get_distance_bucket_filters(
+    geo_distance_facet: &GeoDistanceFacet,
+    filter: &Filter,
+) -> Vec<Filter> {
+    geo_distance_facet
+        .ranges
+        .iter()
+        .map(|range| {
+            let must = range
+                .to
+                .map(|to| get_within_distance_condition(&geo_distance_facet.origin, to))
+                .into_iter()
+                .collect();
+            let mut must_not = range
+                .from
+                .map(|from| get_within_distance_condition(&geo_distance_facet.origin, from))
+                .into_iter()
+                .collect_vec();
+            if range.to.is_none() {
+                must_not.push(Condition::is_empty("location"));
+            }
+
+            with_conditions(filter, must, must_not)
+        })
+        .collect()
+}
+
+/// Computes the facets over the chunks matching the filter of a search, ignoring the query and pagination. Returns None when no facets were requested.
+///
+/// Range and distance buckets are exact counts. Qdrant cannot list the values of a field, so term facets fall back to counting over the first sample_size chunks matching the filter, capped at the dataset's MAX_LIMIT. They are flagged as approximate when more chunks match the filter.
+#[tracing::instrument]
+pub async fn get_facets_query(
+    facets: Option<FacetOptions>,
+    filter: Filter,
+    dataset_config: DatasetConfiguration,
+) -> Result<Option<FacetResults>, ServiceError> {
+    let facets = match facets {
+        Some(facets) => facets,
+        None => return Ok(None),
+    };
+    let payload_fields = get_term_payload_fields(&facets)?;
+    validate_bucket_count(&facets)?;
+
+    let range_bucket_filters = facets
+        .ranges
+        .iter()
+        .flatten()
+        .map(|range_facet| get_range_bucket_filters(range_facet, &filter))
+        .collect::<Result<Vec<Vec<Filter>>, ServiceError>>()?;
+    let distance_bucket_filters = facets
+        .geo_distances
+        .iter()
+        .flatten()
+        .map(|geo_distance_facet| get_distance_bucket_filters(geo_distance_facet, &filter))
+        .collect_vec();
+
+    // The chunks matching the filter are only counted to tell whether the term facets are approximate
+    let term_filter = (!payload_fields.is_empty()).then(|| filter.clone());
+
+    let mut bucket_counts = count_filtered_points_query(
+        term_filter
+            .into_iter()
+            .chain(
+                range_bucket_filters
+                    .iter()
+                    .chain(distance_bucket_filters.iter())
+                    .flatten()
+                    .cloned(),
+            )
+            .collect(),
+        dataset_config.clone(),
+    )
+    .await?
+    .into_iter();
+    let matching_count = if payload_fields.is_empty() {
+        0
+    } else {
+        bucket_counts.next().unwrap_or(0)
+    };
+
+    let ranges = facets
+        .ranges
+        .iter()
+        .flatten()
+        .map(|range_facet| RangeFacetResult {
+            field: range_facet.field.clone(),
+            buckets: range_facet
+                .ranges
+                .iter()
+                .map(|range| RangeBucket {
+                    from: range.from.clone(),
+                    to: range.to.clone(),
+                    count: bucket_counts.next().unwrap_or(0),
+                })
+                .collect(),
+        })
+        .collect();
+
+    let geo_distances = facets
+        .geo_distances
+        .iter()
+        .flatten()
+        .map(|geo_distance_facet| GeoDistanceFacetResult {
+            origin: geo_distance_facet.origin,
+            buckets: geo_distance_facet
+                .ranges
+                .iter()
+                .map(|range| DistanceBucket {
+                    from: range.from,
+                    to: range.to,
+                    count: bucket_counts.next().unwrap_or(0),
+                })
+                .collect(),
+        })
+        .collect();
+
+    let payloads = if payload_fields.is_empty() {
+        vec![]
+    } else {
+        scroll_filtered_payloads_query(
+            facets
+                .sample_size
+                .unwrap_or(1000)
+                .min(dataset_config.MAX_LIMIT),
+            filter,
+            payload_fields,
+            dataset_config,
+        )
+        .await?
+    };
+
+    Ok(Some(FacetResults {
+        sample_size: payloads.len() as u64,
+        approximate: matching_count > payloads.len() as u64,
+        terms: compute_term_facets(&facets, &payloads),
+        ranges,
+        geo_distances,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::models::{DistanceRange, FacetBound, FacetRange, GeoTypes, TermFacet};
+    use qdrant_client::qdrant::condition::ConditionOneOf;
+    use serde_json::json;
+
+    #[test]
+    fn test_compute_term_facets() {
+        let payloads = vec![
+            json!({
+                "tag_set": ["shoes", "sale", "sale"],
+                "metadata": { "brand": { "name": "acme" } }
+            }),
+            json!({
+                "tag_set": ["shoes"],
+                "metadata": { "brand": { "name": "globex" } }
+            }),
+            json!({
+                "tag_set": ["sale"],
+                "metadata": { "brand": { "name": "acme" } }
+            }),
+        ]
+        .into_iter()
+        .map(|payload| serde_json::from_value(payload).unwrap())
+        .collect_vec();
+
+        let facets = FacetOptions {
+            terms: Some(vec![
+                TermFacet {
+                    field: "tag_set".to_string(),
+                    limit: None,
+                },
+                TermFacet {
+                    field: "metadata.brand.name".to_string(),
+                    limit: Some(1),
+                },
+            ]),
+            ..Default::default()
+        };
+
+        let terms = compute_term_facets(&facets, &payloads);
+
+        assert_eq!(
+            terms[0].counts,
+            vec![
+                TermCount {
+                    value: "sale".to_string(),
+                    count: 2
+                },
+                TermCount {
+                    value: "shoes".to_string(),
+                    count: 2
+                },
+            ]
+        );
+        assert_eq!(
+            terms[1].counts,
+            vec![TermCount {
+                value: "acme".to_string(),
+                count: 2
+            }]
+        );
+    }
+
+    #[test]
+    fn test_bucket_filters_narrow_the_search_filter() {
+        let filter = Filter::must([Condition::matches("tag_set", "shoes".to_string())]);
+
+        let range_filters = get_range_bucket_filters(
+            &RangeFacet {
+                field: "time_stamp".to_string(),
+                ranges: vec![FacetRange {
+                    from: Some(FacetBound::TimeStamp("2024-03-01T00:00:00Z".to_string())),
+                    to: None,
+                }],
+            },
+            &filter,
+        )
+        .unwrap();
+
+        assert_eq!(range_filters.len(), 1);
+        assert_eq!(range_filters[0].must.len(), 2);
+        assert!(matches!(
+            &range_filters[0].must[1].condition_one_of,
+            Some(ConditionOneOf::Field(field)) if field.range == Some(Range {
+                gte: Some(1709251200.0),
+                ..Default::default()
+            })
+        ));
+
+        let distance_filters = get_distance_bucket_filters(
+            &GeoDistanceFacet {
+                origin: GeoInfo {
+                    lat: GeoTypes::Float(40.7306),
+                    lon: GeoTypes::Float(-73.9352),
+                },
+                ranges: vec![
+                    DistanceRange {
+                        from: None,
+                        to: Some(100.0),
+                    },
+                    DistanceRange {
+                        from: Some(100.0),
+                        to: None,
+                    },
+                ],
+            },
+            &filter,
+        );
+
+        assert_eq!(
+            distance_filters
+                .iter()
+                .map(|filter| (filter.must.len(), filter.must_not.len()))
+                .collect_vec(),
+            vec![(2, 0), (1, 2)]
+        );
+    }
+
+    #[test]
+    fn test_facets_reject_unsupported_fields() {
+        let facets = FacetOptions {
+            terms: Some(vec![TermFacet {
+                field: "content".to_string(),
+                limit: None,
+            }]),
+            ..Default::default()
+        };
+
+        assert!(get_term_payload_fields(&facets).is_err());
+        assert!(get_range_bucket_filters(
+            &RangeFacet {
+                field: "weight".to_string(),
+                ranges: vec![],
+            },
+            &Filter::default(),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_facets_reject_too_many_buckets() {
+        let range_facet = |buckets| RangeFacet {
+            field: "num_value".to_string(),
+            ranges: vec![
+                FacetRange {
+                    from: None,
+                    to: None,
+                };
+                buckets
+            ],
+        };
+
+        assert!(validate_bucket_count(&FacetOptions {
+            ranges: Some(vec![range_facet(MAX_FACET_BUCKETS)]),
+            ..Default::default()
+        })
+        .is_ok());
+        assert!(validate_bucket_count(&FacetOptions {
+            ranges: Some(vec![range_facet(MAX_FACET_BUCKETS), range_facet(1)]),
+            ..Default::default()
+        })
+        .is_err());
+    }
+}
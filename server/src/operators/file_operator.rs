@@ -1,27 +1,35 @@
+use std::cmp;
 use std::collections::HashMap;
 
 use super::chunk_operator::{create_chunk_metadata, get_row_count_for_organization_id_query};
 use super::clickhouse_operator::{ClickHouseEvent, EventQueue};
 use super::group_operator::{create_group_from_file_query, create_groups_query};
-use super::parse_operator::{build_chunking_regex, coarse_doc_chunker, convert_html_to_text};
+use super::parse_operator::{
+    build_chunking_regex, coarse_doc_chunker, convert_html_to_text, count_tokens,
+    heading_doc_chunker, token_budget_doc_chunker, token_budget_with_margin,
+};
 use crate::data::models::ChunkGroup;
 use crate::data::models::FileDTO;
 use crate::data::models::{Dataset, DatasetAndOrgWithSubAndPlan, DatasetConfiguration, EventType};
 use crate::handlers::chunk_handler::ChunkReqPayload;
-use crate::handlers::file_handler::UploadFileReqPayload;
+use crate::handlers::file_handler::{ChunkStrategy, UploadFileReqPayload};
 use crate::{data::models::WorkerEvent, get_env};
 use crate::{
-    data::models::{File, Pool},
+    data::models::{File, Pool, RedisPool},
     errors::ServiceError,
 };
 use actix_web::web;
 use diesel::dsl::sql;
 use diesel::prelude::*;
 use diesel::sql_types::BigInt;
+use diesel::upsert::excluded;
 use diesel_async::scoped_futures::ScopedFutureExt;
 use diesel_async::{AsyncConnection, RunQueryDsl};
+use futures_util::{Stream, StreamExt};
+use itertools::Itertools;
 use redis::aio::MultiplexedConnection;
 use regex::Regex;
+use s3::serde_types::Part;
 use s3::{creds::Credentials, Bucket, Region};
 
 #[tracing::instrument]
@@ -63,6 +71,162 @@ pub fn get_aws_bucket() -> Result<Bucket, ServiceError> {
     Ok(aws_bucket)
 }
 
+/// S3 requires every part of a multipart upload except the last to be at least 5MiB.
+const STREAM_UPLOAD_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// Writes an object to the bucket incrementally with an S3 multipart upload so at most one part is held in memory. Objects smaller than a single part are written with a plain put. Call `abort` if the object can not be finished.
+pub struct BucketObjectWriter {
+    bucket: Bucket,
+    s3_path: String,
+    buffer: Vec<u8>,
+    upload_id: Option<String>,
+    parts: Vec<Part>,
+}
+
+impl BucketObjectWriter {
+    const CONTENT_TYPE: &'static str = "application/octet-stream";
+
+    pub fn new(bucket: Bucket, s3_path: String) -> Self {
+        BucketObjectWriter {
+            bucket,
+            s3_path,
+            buffer: Vec::with_capacity(STREAM_UPLOAD_PART_SIZE),
+            upload_id: None,
+            parts: vec![],
+        }
+    }
+
+    pub async fn write(&mut self, data: &[u8]) -> Result<(), ServiceError> {
+        self.buffer.extend_from_slice(data);
+
+        if self.buffer.len() >= STREAM_UPLOAD_PART_SIZE {
+            self.put_part().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn put_part(&mut self) -> Result<(), ServiceError> {
+        let upload_id = match self.upload_id {
+            Some(ref upload_id) => upload_id.clone(),
+            None => {
+                let initiated_upload = self
+                    .bucket
+                    .initiate_multipart_upload(&self.s3_path, Self::CONTENT_TYPE)
+                    .await
+                    .map_err(|e| {
+                        log::error!("Could not start multipart upload to S3 {:?}", e);
+                        ServiceError::BadRequest("Could not upload file to S3".to_string())
+                    })?;
+                self.upload_id = Some(initiated_upload.upload_id.clone());
+                initiated_upload.upload_id
+            }
+        };
+
+        let part = self
+            .bucket
+            .put_multipart_chunk(
+                std::mem::take(&mut self.buffer),
+                &self.s3_path,
+                self.parts.len() as u32 + 1,
+                &upload_id,
+                Self::CONTENT_TYPE,
+            )
+            .await
+            .map_err(|e| {
+                log::error!("Could not upload file part to S3 {:?}", e);
+                ServiceError::BadRequest("Could not upload file to S3".to_string())
+            })?;
+        self.parts.push(part);
+
+        Ok(())
+    }
+
+    pub async fn finish(mut self) -> Result<(), ServiceError> {
+        match self.upload_id.clone() {
+            // Objects smaller than a single part skip the multipart upload entirely.
+            None => {
+                self.bucket
+                    .put_object(&self.s3_path, self.buffer.as_slice())
+                    .await
+                    .map_err(|e| {
+                        log::error!("Could not upload file to S3 {:?}", e);
+                        ServiceError::BadRequest("Could not upload file to S3".to_string())
+                    })?;
+            }
+            Some(upload_id) => {
+                if !self.buffer.is_empty() {
+                    self.put_part().await?;
+                }
+
+                self.bucket
+                    .complete_multipart_upload(&self.s3_path, &upload_id, self.parts.clone())
+                    .await
+                    .map_err(|e| {
+                        log::error!("Could not complete multipart upload to S3 {:?}", e);
+                        ServiceError::BadRequest("Could not upload file to S3".to_string())
+                    })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn abort(self) {
+        if let Some(upload_id) = self.upload_id {
+            if let Err(e) = self.bucket.abort_upload(&self.s3_path, &upload_id).await {
+                log::error!("Could not abort multipart upload to S3 {:?}", e);
+            }
+        }
+    }
+}
+
+/// Streams `stream` into the bucket under `s3_path` using an S3 multipart upload, so at most one part is held in memory. Fails and aborts the upload as soon as more than `max_size_bytes` have been received. Returns the number of bytes uploaded.
+#[tracing::instrument(skip(stream))]
+pub async fn put_file_stream_to_bucket<S, B, E>(
+    s3_path: String,
+    stream: &mut S,
+    max_size_bytes: u64,
+) -> Result<usize, ServiceError>
+where
+    S: Stream<Item = Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: std::fmt::Display,
+{
+    let mut writer = BucketObjectWriter::new(get_aws_bucket()?, s3_path);
+    let mut total_size: usize = 0;
+
+    let upload_result: Result<(), ServiceError> = async {
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|err| {
+                ServiceError::BadRequest(format!("Could not read file upload: {}", err))
+            })?;
+            total_size += chunk.as_ref().len();
+
+            if total_size as u64 > max_size_bytes {
+                return Err(ServiceError::BadRequest(
+                    "File size limit reached".to_string(),
+                ));
+            }
+
+            writer.write(chunk.as_ref()).await?;
+        }
+
+        Ok(())
+    }
+    .await;
+
+    match upload_result {
+        Ok(()) => writer.finish().await?,
+        Err(err) => {
+            writer.abort().await;
+            return Err(err);
+        }
+    }
+
+    Ok(total_size)
+}
+
 #[tracing::instrument(skip(pool))]
 pub async fn create_file_query(
     file_id: uuid::Uuid,
@@ -91,6 +255,88 @@ pub async fn create_file_query(
         dataset_id,
     );
 
+    // Files uploaded through a presigned url already have a pending row which is finalized here.
+    let created_file: File = diesel::insert_into(files_columns::files)
+        .values(&new_file)
+        .on_conflict(files_columns::id)
+        .do_update()
+        .set((
+            files_columns::file_name.eq(excluded(files_columns::file_name)),
+            files_columns::size.eq(excluded(files_columns::size)),
+            files_columns::metadata.eq(excluded(files_columns::metadata)),
+            files_columns::link.eq(excluded(files_columns::link)),
+            files_columns::time_stamp.eq(excluded(files_columns::time_stamp)),
+            files_columns::tag_set.eq(excluded(files_columns::tag_set)),
+            files_columns::pending.eq(false),
+            files_columns::updated_at.eq(diesel::dsl::now),
+        ))
+        .get_result(&mut conn)
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not create file, try again".to_string()))?;
+
+    Ok(created_file)
+}
+
+pub fn get_pending_file_upload_key(file_id: uuid::Uuid) -> String {
+    format!("pending_file_upload:{}", file_id)
+}
+
+/// Seconds the options of a presigned upload are kept around waiting for the complete call.
+pub const PENDING_FILE_UPLOAD_TTL_SECS: u64 = 60 * 60 * 24;
+
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn create_pending_file_query(
+    file_id: uuid::Uuid,
+    upload_file_data: UploadFileReqPayload,
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<File, ServiceError> {
+    use crate::data::schema::files::dsl as files_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    delete_expired_pending_files_query(dataset_id, pool.clone()).await?;
+
+    let new_file = File {
+        pending: true,
+        ..File::from_details(
+            Some(file_id),
+            &upload_file_data.file_name,
+            0,
+            upload_file_data
+                .tag_set
+                .clone()
+                .map(|tag_set| tag_set.into_iter().map(Some).collect()),
+            upload_file_data.metadata.clone(),
+            upload_file_data.link.clone(),
+            upload_file_data.time_stamp.clone(),
+            dataset_id,
+        )
+    };
+
+    let serialized_upload_file_data = serde_json::to_string(&upload_file_data).map_err(|e| {
+        log::error!("Could not serialize upload file data: {:?}", e);
+        ServiceError::BadRequest("Could not serialize upload file data".to_string())
+    })?;
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    redis::cmd("SET")
+        .arg(get_pending_file_upload_key(file_id))
+        .arg(serialized_upload_file_data)
+        .arg("EX")
+        .arg(PENDING_FILE_UPLOAD_TTL_SECS)
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
     let created_file: File = diesel::insert_into(files_columns::files)
         .values(&new_file)
         .get_result(&mut conn)
@@ -100,6 +346,178 @@ pub async fn create_file_query(
     Ok(created_file)
 }
 
+/// Returns a pending file along with the upload options it was created with. Errors with NotFound once the options have expired.
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn get_pending_file_query(
+    file_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(File, UploadFileReqPayload), ServiceError> {
+    use crate::data::schema::files::dsl as files_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let file: File = files_columns::files
+        .filter(files_columns::id.eq(file_id))
+        .filter(files_columns::dataset_id.eq(dataset_id))
+        .filter(files_columns::pending.eq(true))
+        .get_result(&mut conn)
+        .await
+        .map_err(|_| {
+            ServiceError::NotFound("Pending file with specified id not found".to_string())
+        })?;
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let serialized_upload_file_data: Option<String> = redis::cmd("GET")
+        .arg(get_pending_file_upload_key(file_id))
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let upload_file_data = serialized_upload_file_data
+        .ok_or(ServiceError::NotFound(
+            "Presigned upload has expired, create a new one".to_string(),
+        ))
+        .and_then(|data| {
+            serde_json::from_str::<UploadFileReqPayload>(&data).map_err(|_| {
+                ServiceError::BadRequest("Could not deserialize upload file data".to_string())
+            })
+        })?;
+
+    Ok((file, upload_file_data))
+}
+
+#[tracing::instrument(skip(redis_pool))]
+pub async fn delete_pending_file_upload_query(
+    file_id: uuid::Uuid,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    redis::cmd("DEL")
+        .arg(get_pending_file_upload_key(file_id))
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(())
+}
+
+/// Marks a pending file as uploaded with a conditional update, so only one of several concurrent complete calls claims the file and queues it.
+#[tracing::instrument(skip(pool))]
+pub async fn claim_pending_file_query(
+    file_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    file_size: i64,
+    pool: web::Data<Pool>,
+) -> Result<File, ServiceError> {
+    use crate::data::schema::files::dsl as files_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let claimed_file: Option<File> = diesel::update(
+        files_columns::files
+            .filter(files_columns::id.eq(file_id))
+            .filter(files_columns::dataset_id.eq(dataset_id))
+            .filter(files_columns::pending.eq(true)),
+    )
+    .set((
+        files_columns::pending.eq(false),
+        files_columns::size.eq(file_size),
+        files_columns::updated_at.eq(diesel::dsl::now),
+    ))
+    .get_result(&mut conn)
+    .await
+    .optional()
+    .map_err(|_| ServiceError::BadRequest("Could not complete file upload".to_string()))?;
+
+    claimed_file.ok_or(ServiceError::BadRequest(
+        "File upload has already been completed".to_string(),
+    ))
+}
+
+/// Puts a claimed file back to pending when it could not be queued, so the upload can be completed again.
+#[tracing::instrument(skip(pool))]
+pub async fn release_pending_file_query(
+    file_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<(), ServiceError> {
+    use crate::data::schema::files::dsl as files_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    diesel::update(files_columns::files.filter(files_columns::id.eq(file_id)))
+        .set(files_columns::pending.eq(true))
+        .execute(&mut conn)
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not release pending file".to_string()))?;
+
+    Ok(())
+}
+
+/// Deletes the pending files of a dataset whose upload options have expired along with anything uploaded to their presigned urls. Their uploads can no longer be completed.
+#[tracing::instrument(skip(pool))]
+pub async fn delete_expired_pending_files_query(
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<(), ServiceError> {
+    use crate::data::schema::files::dsl as files_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let expired_before = chrono::Utc::now().naive_utc()
+        - chrono::Duration::seconds(PENDING_FILE_UPLOAD_TTL_SECS as i64);
+
+    let expired_file_ids: Vec<uuid::Uuid> = diesel::delete(
+        files_columns::files
+            .filter(files_columns::dataset_id.eq(dataset_id))
+            .filter(files_columns::pending.eq(true))
+            .filter(files_columns::created_at.lt(expired_before)),
+    )
+    .returning(files_columns::id)
+    .get_results(&mut conn)
+    .await
+    .map_err(|_| ServiceError::BadRequest("Could not delete expired pending files".to_string()))?;
+
+    if expired_file_ids.is_empty() {
+        return Ok(());
+    }
+
+    let bucket = get_aws_bucket()?;
+    for file_id in expired_file_ids {
+        // Most expired uploads were never sent, so a missing object is expected
+        if let Err(err) = bucket.delete_object(file_id.to_string()).await {
+            log::info!(
+                "Could not delete expired pending file {} from S3 {:?}",
+                file_id,
+                err
+            );
+        }
+    }
+
+    Ok(())
+}
+
 #[allow(clippy::too_many_arguments)]
 #[tracing::instrument(skip(pool, redis_conn, event_queue))]
 pub async fn create_file_chunks(
@@ -109,12 +527,11 @@ pub async fn create_file_chunks(
     dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
     pool: web::Data<Pool>,
     event_queue: web::Data<EventQueue>,
-    mut redis_conn: MultiplexedConnection,
+    redis_conn: MultiplexedConnection,
 ) -> Result<(), ServiceError> {
-    let file_text = convert_html_to_text(&html_content);
-
     let split_regex: Option<Regex> = upload_file_data
         .split_delimiters
+        .clone()
         .map(|delimiters| {
             build_chunking_regex(delimiters).map_err(|e| {
                 log::error!("Could not parse chunking delimiters {:?}", e);
@@ -123,66 +540,87 @@ pub async fn create_file_chunks(
         })
         .transpose()?;
 
-    let rebalance_chunks = upload_file_data.rebalance_chunks.unwrap_or(true);
-    let target_splits_per_chunk = upload_file_data.target_splits_per_chunk.unwrap_or(20);
+    let max_tokens_per_chunk = upload_file_data
+        .max_tokens_per_chunk
+        .map(token_budget_with_margin);
+    let overlap_tokens = upload_file_data.overlap_tokens.unwrap_or(0);
 
-    let chunk_htmls = coarse_doc_chunker(
-        file_text,
-        split_regex,
-        rebalance_chunks,
-        target_splits_per_chunk,
-    );
+    let chunk_htmls: Vec<(String, Option<Vec<String>>)> =
+        match upload_file_data.chunk_strategy.unwrap_or_default() {
+            ChunkStrategy::Heading => heading_doc_chunker(html_content, 2000)
+                .into_iter()
+                .flat_map(|chunk| match max_tokens_per_chunk {
+                    Some(max_tokens_per_chunk) => {
+                        // The heading path is embedded with the chunk, so it counts against the budget.
+                        let heading_tokens = count_tokens(&chunk.heading_path.join(" > ")) + 2;
+                        let budget =
+                            cmp::max(max_tokens_per_chunk.saturating_sub(heading_tokens), 1);
 
-    let mut chunks: Vec<ChunkReqPayload> = [].to_vec();
+                        if count_tokens(&convert_html_to_text(&chunk.chunk_html)) <= budget {
+                            return vec![(chunk.chunk_html, Some(chunk.heading_path))];
+                        }
 
-    let name = format!("Group for file {}", upload_file_data.file_name);
+                        token_budget_doc_chunker(
+                            chunk.chunk_html,
+                            split_regex.clone(),
+                            budget,
+                            cmp::min(overlap_tokens, budget.saturating_sub(1)),
+                        )
+                        .into_iter()
+                        .map(|chunk_html| (chunk_html, Some(chunk.heading_path.clone())))
+                        .collect()
+                    }
+                    None => vec![(chunk.chunk_html, Some(chunk.heading_path))],
+                })
+                .collect(),
+            ChunkStrategy::Sentence => {
+                let file_text = convert_html_to_text(&html_content);
 
-    let chunk_group = ChunkGroup::from_details(
-        Some(name.clone()),
-        upload_file_data.description.clone(),
-        dataset_org_plan_sub.dataset.id,
-        upload_file_data.group_tracking_id.clone(),
-        None,
-        upload_file_data
-            .tag_set
-            .clone()
-            .map(|tag_set| tag_set.into_iter().map(Some).collect()),
-    );
+                match max_tokens_per_chunk {
+                    Some(max_tokens_per_chunk) => token_budget_doc_chunker(
+                        file_text,
+                        split_regex,
+                        max_tokens_per_chunk,
+                        cmp::min(overlap_tokens, max_tokens_per_chunk.saturating_sub(1)),
+                    ),
+                    None => coarse_doc_chunker(
+                        file_text,
+                        split_regex,
+                        upload_file_data.rebalance_chunks.unwrap_or(true),
+                        upload_file_data.target_splits_per_chunk.unwrap_or(20),
+                    ),
+                }
+                .into_iter()
+                .map(|chunk_html| (chunk_html, None))
+                .collect()
+            }
+        };
 
-    let chunk_group_option = create_groups_query(vec![chunk_group], true, pool.clone())
-        .await
-        .map_err(|e| {
-            log::error!("Could not create group {:?}", e);
-            ServiceError::BadRequest("Could not create group".to_string())
-        })?
-        .pop();
+    let mut chunks: Vec<ChunkReqPayload> = [].to_vec();
 
-    let chunk_group = match chunk_group_option {
-        Some(group) => group,
-        None => {
-            return Err(ServiceError::BadRequest(
-                "Could not create group from file".to_string(),
-            ));
-        }
-    };
+    for (i, (chunk_html, heading_path)) in chunk_htmls.into_iter().enumerate() {
+        let mut metadata = upload_file_data.metadata.clone();
+        let mut semantic_content = None;
 
-    let group_id = chunk_group.id;
+        if let Some(heading_path) = heading_path.filter(|path| !path.is_empty()) {
+            // Embedding the heading path alongside the chunk gives sections that only make sense under their title the missing context.
+            semantic_content = Some(format!("{}\n\n{}", heading_path.join(" > "), chunk_html));
 
-    create_group_from_file_query(group_id, created_file_id, pool.clone())
-        .await
-        .map_err(|e| {
-            log::error!("Could not create group from file {:?}", e);
-            e
-        })?;
+            match metadata.get_or_insert_with(|| serde_json::json!({})) {
+                serde_json::Value::Object(metadata) => {
+                    metadata.insert("heading_path".to_string(), serde_json::json!(heading_path));
+                }
+                _ => log::info!("File metadata is not an object, skipping heading_path"),
+            }
+        }
 
-    for (i, chunk_html) in chunk_htmls.iter().enumerate() {
         let create_chunk_data = ChunkReqPayload {
-            chunk_html: Some(chunk_html.clone()),
-            semantic_content: None,
+            chunk_html: Some(chunk_html),
+            semantic_content,
             link: upload_file_data.link.clone(),
             tag_set: upload_file_data.tag_set.clone(),
-            metadata: upload_file_data.metadata.clone(),
-            group_ids: Some(vec![group_id]),
+            metadata,
+            group_ids: None,
             group_tracking_ids: None,
             location: None,
             tracking_id: upload_file_data
@@ -202,6 +640,239 @@ pub async fn create_file_chunks(
         chunks.push(create_chunk_data);
     }
 
+    queue_file_chunks(
+        created_file_id,
+        upload_file_data,
+        chunks,
+        dataset_org_plan_sub,
+        pool,
+        event_queue,
+        redis_conn,
+    )
+    .await
+}
+
+fn get_row_value_as_string(
+    row: &serde_json::Map<String, serde_json::Value>,
+    column: &str,
+) -> Option<String> {
+    match row.get(column)? {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(value) if value.is_empty() => None,
+        serde_json::Value::String(value) => Some(value.clone()),
+        value => Some(value.to_string()),
+    }
+}
+
+/// Turns each parsed CSV or JSONL row into a chunk using the upload's column mapping and queues them.
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(skip(rows, pool, redis_conn, event_queue))]
+pub async fn create_file_row_chunks(
+    created_file_id: uuid::Uuid,
+    upload_file_data: UploadFileReqPayload,
+    rows: Vec<serde_json::Map<String, serde_json::Value>>,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+    event_queue: web::Data<EventQueue>,
+    redis_conn: MultiplexedConnection,
+) -> Result<(), ServiceError> {
+    let column_mapping = upload_file_data.column_mapping.clone().unwrap_or_default();
+
+    let chunks: Vec<ChunkReqPayload> = rows
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, row)| {
+            let chunk_html = match column_mapping.chunk_html {
+                Some(ref columns) => columns
+                    .iter()
+                    .filter_map(|column| get_row_value_as_string(&row, column))
+                    .join("\n"),
+                None => row
+                    .keys()
+                    .filter_map(|column| {
+                        get_row_value_as_string(&row, column)
+                            .map(|value| format!("{}: {}", column, value))
+                    })
+                    .join("\n"),
+            };
+
+            if chunk_html.trim().is_empty() {
+                return None;
+            }
+
+            let row_tags: Vec<String> = match column_mapping
+                .tag_set
+                .as_ref()
+                .and_then(|column| row.get(column))
+            {
+                Some(serde_json::Value::Array(tags)) => tags
+                    .iter()
+                    .filter_map(|tag| match tag {
+                        serde_json::Value::String(tag) => Some(tag.clone()),
+                        serde_json::Value::Null => None,
+                        tag => Some(tag.to_string()),
+                    })
+                    .collect(),
+                Some(serde_json::Value::String(tags)) => tags
+                    .split(',')
+                    .map(|tag| tag.trim().to_string())
+                    .filter(|tag| !tag.is_empty())
+                    .collect(),
+                Some(serde_json::Value::Null) | None => vec![],
+                Some(tag) => vec![tag.to_string()],
+            };
+
+            let tag_set = match (upload_file_data.tag_set.clone(), row_tags.is_empty()) {
+                (Some(file_tags), _) => Some(
+                    file_tags
+                        .into_iter()
+                        .chain(row_tags)
+                        .unique()
+                        .collect::<Vec<String>>(),
+                ),
+                (None, false) => Some(row_tags),
+                (None, true) => None,
+            };
+
+            let num_value = column_mapping
+                .num_value
+                .as_ref()
+                .and_then(|column| row.get(column))
+                .and_then(|value| match value {
+                    serde_json::Value::Number(num_value) => num_value.as_f64(),
+                    serde_json::Value::String(num_value) => num_value.trim().parse().ok(),
+                    _ => None,
+                });
+
+            let time_stamp = column_mapping
+                .time_stamp
+                .as_ref()
+                .and_then(|column| get_row_value_as_string(&row, column))
+                .or(upload_file_data.time_stamp.clone());
+
+            let tracking_id = match column_mapping.tracking_id {
+                Some(ref column) => get_row_value_as_string(&row, column),
+                None => upload_file_data
+                    .group_tracking_id
+                    .clone()
+                    .map(|tracking_id| format!("{}|{}", tracking_id, i)),
+            };
+
+            let metadata = match column_mapping.metadata {
+                Some(ref columns) => {
+                    let mut metadata = match upload_file_data.metadata.clone() {
+                        Some(serde_json::Value::Object(metadata)) => metadata,
+                        _ => serde_json::Map::new(),
+                    };
+
+                    for column in columns {
+                        if let Some(value) = row.get(column) {
+                            metadata.insert(column.clone(), value.clone());
+                        }
+                    }
+
+                    Some(serde_json::Value::Object(metadata))
+                }
+                None => upload_file_data.metadata.clone(),
+            };
+
+            Some(ChunkReqPayload {
+                chunk_html: Some(chunk_html),
+                semantic_content: None,
+                link: upload_file_data.link.clone(),
+                tag_set,
+                metadata,
+                group_ids: None,
+                group_tracking_ids: None,
+                location: None,
+                tracking_id,
+                upsert_by_tracking_id: None,
+                time_stamp,
+                weight: None,
+                split_avg: None,
+                convert_html_to_text: None,
+                image_urls: None,
+                num_value,
+                fulltext_boost: None,
+                semantic_boost: None,
+            })
+        })
+        .collect();
+
+    queue_file_chunks(
+        created_file_id,
+        upload_file_data,
+        chunks,
+        dataset_org_plan_sub,
+        pool,
+        event_queue,
+        redis_conn,
+    )
+    .await
+}
+
+/// Creates the group holding the file's chunks and queues the chunks for ingestion.
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(skip(chunks, pool, redis_conn, event_queue))]
+pub async fn queue_file_chunks(
+    created_file_id: uuid::Uuid,
+    upload_file_data: UploadFileReqPayload,
+    mut chunks: Vec<ChunkReqPayload>,
+    dataset_org_plan_sub: DatasetAndOrgWithSubAndPlan,
+    pool: web::Data<Pool>,
+    event_queue: web::Data<EventQueue>,
+    mut redis_conn: MultiplexedConnection,
+) -> Result<(), ServiceError> {
+    let name = format!("Group for file {}", upload_file_data.file_name);
+
+    // Reprocessed files keep the group they were first chunked into.
+    let group_id = match get_file_group_query(created_file_id, pool.clone()).await? {
+        Some(file_group) => file_group.id,
+        None => {
+            let chunk_group = ChunkGroup::from_details(
+                Some(name.clone()),
+                upload_file_data.description.clone(),
+                dataset_org_plan_sub.dataset.id,
+                upload_file_data.group_tracking_id.clone(),
+                None,
+                upload_file_data
+                    .tag_set
+                    .clone()
+                    .map(|tag_set| tag_set.into_iter().map(Some).collect()),
+            );
+
+            let chunk_group_option = create_groups_query(vec![chunk_group], true, pool.clone())
+                .await
+                .map_err(|e| {
+                    log::error!("Could not create group {:?}", e);
+                    ServiceError::BadRequest("Could not create group".to_string())
+                })?
+                .pop();
+
+            let chunk_group = match chunk_group_option {
+                Some(group) => group,
+                None => {
+                    return Err(ServiceError::BadRequest(
+                        "Could not create group from file".to_string(),
+                    ));
+                }
+            };
+
+            create_group_from_file_query(chunk_group.id, created_file_id, pool.clone())
+                .await
+                .map_err(|e| {
+                    log::error!("Could not create group from file {:?}", e);
+                    e
+                })?;
+
+            chunk_group.id
+        }
+    };
+
+    for chunk in chunks.iter_mut() {
+        chunk.group_ids = Some(vec![group_id]);
+    }
+
     let chunk_count = get_row_count_for_organization_id_query(
         dataset_org_plan_sub.organization.organization.id,
         pool.clone(),
@@ -292,6 +963,7 @@ pub async fn get_file_query(
     let file: File = files_columns::files
         .filter(files_columns::id.eq(file_uuid))
         .filter(files_columns::dataset_id.eq(dataset_id))
+        .filter(files_columns::pending.eq(false))
         .get_result(&mut conn)
         .await
         .map_err(|e| {
@@ -342,6 +1014,7 @@ pub async fn get_dataset_file_query(
                 .on(groups_from_files_columns::file_id.eq(files_columns::id)),
         )
         .filter(files_columns::dataset_id.eq(dataset_id))
+        .filter(files_columns::pending.eq(false))
         .select((
             File::as_select(),
             sql::<BigInt>("count(*) OVER()"),
@@ -356,6 +1029,104 @@ pub async fn get_dataset_file_query(
     Ok(file_metadata)
 }
 
+#[tracing::instrument(skip(pool))]
+pub async fn get_reprocessable_file_query(
+    file_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<File, ServiceError> {
+    use crate::data::schema::files::dsl as files_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let file: File = files_columns::files
+        .filter(files_columns::id.eq(file_id))
+        .filter(files_columns::dataset_id.eq(dataset_id))
+        .filter(files_columns::pending.eq(false))
+        .get_result(&mut conn)
+        .await
+        .map_err(|_| ServiceError::NotFound("File with specified id not found".to_string()))?;
+
+    Ok(file)
+}
+
+#[tracing::instrument(skip(pool))]
+pub async fn get_file_group_query(
+    file_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<Option<ChunkGroup>, ServiceError> {
+    use crate::data::schema::chunk_group::dsl as chunk_group_columns;
+    use crate::data::schema::groups_from_files::dsl as groups_from_files_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let file_group: Option<ChunkGroup> = groups_from_files_columns::groups_from_files
+        .inner_join(
+            chunk_group_columns::chunk_group
+                .on(chunk_group_columns::id.eq(groups_from_files_columns::group_id)),
+        )
+        .filter(groups_from_files_columns::file_id.eq(file_id))
+        .select(ChunkGroup::as_select())
+        .first(&mut conn)
+        .await
+        .optional()
+        .map_err(|_| ServiceError::BadRequest("Could not get group for file".to_string()))?;
+
+    Ok(file_group)
+}
+
+#[tracing::instrument(skip(pool))]
+pub async fn get_file_chunk_ids_query(
+    group_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<Vec<uuid::Uuid>, ServiceError> {
+    use crate::data::schema::chunk_group_bookmarks::dsl as chunk_group_bookmarks_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let chunk_ids: Vec<uuid::Uuid> = chunk_group_bookmarks_columns::chunk_group_bookmarks
+        .filter(chunk_group_bookmarks_columns::group_id.eq(group_id))
+        .select(chunk_group_bookmarks_columns::chunk_metadata_id)
+        .load(&mut conn)
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get chunks for file".to_string()))?;
+
+    Ok(chunk_ids)
+}
+
+/// Frees the tracking ids of chunks which are about to be replaced, so the chunks replacing them can be created with the same tracking ids while the old ones are still searchable.
+#[tracing::instrument(skip(pool))]
+pub async fn clear_chunk_tracking_ids_query(
+    chunk_ids: Vec<uuid::Uuid>,
+    pool: web::Data<Pool>,
+) -> Result<(), ServiceError> {
+    use crate::data::schema::chunk_metadata::dsl as chunk_metadata_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    diesel::update(
+        chunk_metadata_columns::chunk_metadata.filter(chunk_metadata_columns::id.eq_any(chunk_ids)),
+    )
+    .set(chunk_metadata_columns::tracking_id.eq(None::<String>))
+    .execute(&mut conn)
+    .await
+    .map_err(|_| ServiceError::BadRequest("Could not clear tracking ids of chunks".to_string()))?;
+
+    Ok(())
+}
+
 #[tracing::instrument(skip(pool))]
 pub async fn delete_file_query(
     file_uuid: uuid::Uuid,
@@ -17,6 +17,7 @@ use diesel::upsert::excluded;
 use diesel_async::scoped_futures::ScopedFutureExt;
 use diesel_async::{AsyncConnection, RunQueryDsl};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use utoipa::ToSchema;
 
 #[tracing::instrument(skip(pool))]
@@ -251,13 +252,14 @@ pub async fn get_group_by_id_query(
     Ok(ChunkGroupAndFileId::from_group(group, file_id))
 }
 
-#[tracing::instrument(skip(pool))]
+#[tracing::instrument(skip(pool, redis_pool))]
 pub async fn delete_group_by_id_query(
     group_id: uuid::Uuid,
     dataset: Dataset,
     deleted_at: chrono::NaiveDateTime,
     delete_chunks: Option<bool>,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     dataset_config: DatasetConfiguration,
 ) -> Result<(), ServiceError> {
     use crate::data::schema::chunk_group::dsl as chunk_group_columns;
@@ -317,6 +319,7 @@ pub async fn delete_group_by_id_query(
             deleted_at,
             dataset.clone(),
             pool.clone(),
+            redis_pool,
             dataset_config.clone(),
         )
         .await?;
@@ -643,6 +646,41 @@ pub async fn get_point_ids_from_unified_group_ids(
     Ok(qdrant_point_ids)
 }
 
+/// Returns the ids of the groups each of the chunks is bookmarked in. Chunks without groups are left out.
+#[tracing::instrument(skip(pool))]
+pub async fn get_group_ids_for_chunks_query(
+    chunk_ids: Vec<uuid::Uuid>,
+    pool: web::Data<Pool>,
+) -> Result<HashMap<uuid::Uuid, Vec<uuid::Uuid>>, ServiceError> {
+    use crate::data::schema::chunk_group_bookmarks::dsl as chunk_group_bookmarks_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let bookmarks: Vec<(uuid::Uuid, uuid::Uuid)> =
+        chunk_group_bookmarks_columns::chunk_group_bookmarks
+            .filter(chunk_group_bookmarks_columns::chunk_metadata_id.eq_any(&chunk_ids))
+            .select((
+                chunk_group_bookmarks_columns::chunk_metadata_id,
+                chunk_group_bookmarks_columns::group_id,
+            ))
+            .load(&mut conn)
+            .await
+            .map_err(|_| ServiceError::BadRequest("Error getting groups for chunks".to_string()))?;
+
+    let mut group_ids_for_chunks: HashMap<uuid::Uuid, Vec<uuid::Uuid>> = HashMap::new();
+    for (chunk_id, group_id) in bookmarks {
+        group_ids_for_chunks
+            .entry(chunk_id)
+            .or_default()
+            .push(group_id);
+    }
+
+    Ok(group_ids_for_chunks)
+}
+
 #[tracing::instrument(skip(pool))]
 pub async fn get_groups_from_group_ids_query(
     group_ids: Vec<uuid::Uuid>,
@@ -9,7 +9,7 @@ use crate::handlers::message_handler::CreateMessageReqPayload;
 use crate::operators::clickhouse_operator::ClickHouseEvent;
 use crate::operators::parse_operator::convert_html_to_text;
 use crate::{
-    data::models::{Message, Pool, SearchQueryEventClickhouse},
+    data::models::{Message, Pool, RedisPool, SearchQueryEventClickhouse},
     errors::ServiceError,
 };
 use actix::Arbiter;
@@ -219,13 +219,15 @@ pub async fn delete_message_query(
     Ok(())
 }
 
-#[tracing::instrument(skip(pool, event_queue))]
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(skip(pool, event_queue, redis_pool))]
 pub async fn stream_response(
     messages: Vec<models::Message>,
     topic_id: uuid::Uuid,
     dataset: Dataset,
     pool: web::Data<Pool>,
     event_queue: web::Data<EventQueue>,
+    redis_pool: web::Data<RedisPool>,
     dataset_config: DatasetConfiguration,
     create_message_req_payload: CreateMessageReqPayload,
 ) -> Result<HttpResponse, actix_web::Error> {
@@ -371,6 +373,7 @@ pub async fn stream_response(
         search_chunk_data.clone(),
         parsed_query,
         pool.clone(),
+        redis_pool,
         dataset.clone(),
         &dataset_config,
         &mut search_timer,
@@ -0,0 +1,1102 @@
+use super::chunk_operator::get_chunk_metadatas_from_point_ids;
+use super::group_operator::get_groups_from_group_ids_query;
+use super::model_operator::get_dense_vectors;
+use super::qdrant_operator::{
+    delete_points_from_qdrant, get_qdrant_collection_from_dataset_config, get_qdrant_connection,
+    scroll_qdrant_collection_ids,
+};
+use crate::{
+    data::models::{
+        ChunkMetadata, DatasetConfiguration, DistanceMetric, EmbeddingMigration,
+        EmbeddingMigrationState, EmbeddingModelOptions, MigrateDatasetMessage, MigratePointMessage,
+        MigrationMode, Pool, QdrantPayload, RedisPool,
+    },
+    errors::ServiceError,
+    get_env,
+};
+use actix_web::web;
+use diesel::prelude::*;
+use diesel::sql_types;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_async::{AsyncConnection, RunQueryDsl};
+use itertools::Itertools;
+use qdrant_client::qdrant::{
+    point_id::PointIdOptions, vectors::VectorsOptions, Condition, Filter, GetPointsBuilder,
+    PointId, PointStruct, RetrievedPoint, SetPayloadPointsBuilder, Value, Vector,
+};
+use qdrant_client::Payload;
+use std::collections::{HashMap, HashSet};
+
+/// Seconds a finished migration is kept around so its outcome can still be read.
+const FINISHED_EMBEDDING_MIGRATION_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+const EMBEDDING_MIGRATION_BATCH_SIZE: u32 = 100;
+
+const EMBEDDING_MIGRATION_SWITCH_PAGE_SIZE: i64 = 5000;
+
+/// Expiry of the lock taken by the call switching a migration, in case it dies halfway.
+const EMBEDDING_MIGRATION_SWITCH_LOCK_TTL_SECS: u64 = 60 * 60;
+
+pub fn get_embedding_migration_key(dataset_id: uuid::Uuid) -> String {
+    format!("embedding_migration:{}", dataset_id)
+}
+
+pub fn get_embedding_migration_progress_key(migration_id: uuid::Uuid) -> String {
+    format!("embedding_migration_progress:{}", migration_id)
+}
+
+pub fn get_embedding_migration_switch_lock_key(migration_id: uuid::Uuid) -> String {
+    format!("embedding_migration_switch:{}", migration_id)
+}
+
+/// Migrated points get a new id derived from their old one, so writing the same point twice is idempotent and the chunk can be repointed at the switch without storing a mapping.
+pub fn get_migrated_point_id(migration_id: uuid::Uuid, qdrant_point_id: uuid::Uuid) -> uuid::Uuid {
+    uuid::Uuid::new_v5(&migration_id, qdrant_point_id.as_bytes())
+}
+
+fn get_dense_vector_name(model: &EmbeddingModelOptions) -> String {
+    format!("{}_vectors", model.size)
+}
+
+fn get_point_migration_mode(migration: &EmbeddingMigration) -> MigrationMode {
+    if migration.from_model == migration.to_model {
+        MigrationMode::MoveCollection {
+            dataset_id: migration.dataset_id,
+            migration_id: migration.migration_id,
+        }
+    } else {
+        MigrationMode::EmbeddingModel {
+            dataset_id: migration.dataset_id,
+            migration_id: migration.migration_id,
+            model: migration.to_model.clone(),
+        }
+    }
+}
+
+#[tracing::instrument(skip(redis_pool))]
+async fn set_embedding_migration_query(
+    migration: &EmbeddingMigration,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    let serialized_migration = serde_json::to_string(migration).map_err(|_| {
+        ServiceError::BadRequest("Failed to serialize embedding migration".to_string())
+    })?;
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let mut cmd = redis::cmd("SET");
+    cmd.arg(get_embedding_migration_key(migration.dataset_id))
+        .arg(serialized_migration);
+    if matches!(
+        migration.state,
+        EmbeddingMigrationState::Completed | EmbeddingMigrationState::Failed
+    ) {
+        cmd.arg("EX").arg(FINISHED_EMBEDDING_MIGRATION_TTL_SECS);
+    }
+
+    cmd.query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(())
+}
+
+/// Returns the dataset's current or most recently finished embedding migration, with up to date progress counts.
+#[tracing::instrument(skip(redis_pool))]
+pub async fn get_embedding_migration_query(
+    dataset_id: uuid::Uuid,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<Option<EmbeddingMigration>, ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let serialized_migration: Option<String> = redis::cmd("GET")
+        .arg(get_embedding_migration_key(dataset_id))
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let mut migration: EmbeddingMigration = match serialized_migration {
+        Some(serialized_migration) => {
+            serde_json::from_str(&serialized_migration).map_err(|_| {
+                ServiceError::BadRequest("Failed to deserialize embedding migration".to_string())
+            })?
+        }
+        None => return Ok(None),
+    };
+
+    let (total_points, migrated_points): (Option<u64>, Option<u64>) = redis::cmd("HMGET")
+        .arg(get_embedding_migration_progress_key(migration.migration_id))
+        .arg("total_points")
+        .arg("migrated_points")
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    migration.total_points = total_points.unwrap_or(migration.total_points);
+    migration.migrated_points = migrated_points.unwrap_or(migration.migrated_points);
+
+    Ok(Some(migration))
+}
+
+/// Adds `qdrant_point_ids` to the migration's work and queues them for the reindex-worker.
+#[tracing::instrument(skip(redis_pool))]
+async fn queue_embedding_migration_points_query(
+    migration: &EmbeddingMigration,
+    qdrant_point_ids: Vec<uuid::Uuid>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    let message = MigratePointMessage {
+        qdrant_point_ids: qdrant_point_ids.clone(),
+        from_collection: migration.from_collection.clone(),
+        to_collection: migration.to_collection.clone(),
+        mode: get_point_migration_mode(migration),
+    };
+
+    let serialized_message =
+        serde_json::to_string(&message).map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    // The batch is counted before it is queued so the migration can not be switched while it is in flight.
+    redis::pipe()
+        .atomic()
+        .cmd("HINCRBY")
+        .arg(get_embedding_migration_progress_key(migration.migration_id))
+        .arg("pending_batches")
+        .arg(1)
+        .ignore()
+        .cmd("HINCRBY")
+        .arg(get_embedding_migration_progress_key(migration.migration_id))
+        .arg("total_points")
+        .arg(qdrant_point_ids.len())
+        .ignore()
+        .query_async::<_, ()>(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    redis::cmd("lpush")
+        .arg("collection_migration")
+        .arg(&serialized_message)
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(())
+}
+
+/// Starts moving the dataset's dense vectors to `to_model` and the collection for `to_distance_metric`. The dataset keeps serving searches with its current configuration until every point has been embedded again, or copied if only the distance metric changes, at which point its configuration is switched. Its existing points are queued by the reindex-worker so the request doesn't have to scroll the collection.
+#[tracing::instrument(skip(redis_pool))]
+pub async fn start_embedding_migration_query(
+    dataset_id: uuid::Uuid,
+    dataset_config: DatasetConfiguration,
+    to_model: EmbeddingModelOptions,
+    to_distance_metric: DistanceMetric,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<EmbeddingMigration, ServiceError> {
+    if let Some(migration) = get_embedding_migration_query(dataset_id, redis_pool.clone()).await? {
+        if matches!(
+            migration.state,
+            EmbeddingMigrationState::Running | EmbeddingMigrationState::Switching
+        ) {
+            return Err(ServiceError::BadRequest(
+                "An embedding model migration is already running for this dataset".to_string(),
+            ));
+        }
+    }
+
+    let migration_id = uuid::Uuid::new_v4();
+    let from_collection = get_qdrant_collection_from_dataset_config(&dataset_config);
+    let to_collection = get_qdrant_collection_from_dataset_config(
+        &dataset_config
+            .clone()
+            .with_embedding_model_options(to_model.clone())
+            .with_distance_metric(to_distance_metric.clone()),
+    );
+
+    if to_model == dataset_config.embedding_model_options() && from_collection == to_collection {
+        return Err(ServiceError::BadRequest(format!(
+            "Dataset already uses {} with the {} distance metric",
+            to_collection, to_distance_metric
+        )));
+    }
+
+    let migration = EmbeddingMigration {
+        migration_id,
+        dataset_id,
+        from_model: dataset_config.embedding_model_options(),
+        to_model,
+        from_distance_metric: dataset_config.DISTANCE_METRIC.clone(),
+        to_distance_metric,
+        shadow_dataset_id: if from_collection == to_collection {
+            Some(migration_id)
+        } else {
+            None
+        },
+        from_collection,
+        to_collection,
+        state: EmbeddingMigrationState::Running,
+        total_points: 0,
+        migrated_points: 0,
+        error: None,
+        started_at: chrono::Utc::now().naive_local(),
+        updated_at: chrono::Utc::now().naive_local(),
+    };
+
+    let message = MigrateDatasetMessage {
+        dataset_id,
+        from_collection: migration.from_collection.clone(),
+        to_collection: migration.to_collection.clone(),
+        mode: get_point_migration_mode(&migration),
+    };
+
+    let serialized_message =
+        serde_json::to_string(&message).map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    {
+        let mut redis_conn = redis_pool
+            .get()
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+        // Holds the migration open until the reindex-worker has queued the existing points.
+        redis::cmd("HSET")
+            .arg(get_embedding_migration_progress_key(migration_id))
+            .arg("pending_batches")
+            .arg(1)
+            .query_async::<_, ()>(&mut *redis_conn)
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+    }
+
+    // Stored before the existing points are queued so points ingested from now on are migrated as well.
+    set_embedding_migration_query(&migration, redis_pool.clone()).await?;
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    redis::cmd("lpush")
+        .arg("dataset_migration")
+        .arg(&serialized_message)
+        .query_async::<_, ()>(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(migration)
+}
+
+/// Queues the points the dataset had when its migration started, run by the reindex-worker. Releases the hold taken when the migration was started, so the migration can switch once the last batch is done.
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn queue_embedding_migration_query(
+    dataset_id: uuid::Uuid,
+    migration_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<u64, ServiceError> {
+    let mut migration = match get_embedding_migration_query(dataset_id, redis_pool.clone()).await? {
+        Some(migration)
+            if migration.migration_id == migration_id
+                && migration.state == EmbeddingMigrationState::Running =>
+        {
+            migration
+        }
+        _ => return Ok(0),
+    };
+
+    let queue_result: Result<(), ServiceError> = async {
+        let mut offset = Some(uuid::Uuid::nil().to_string());
+
+        while let Some(cur_offset) = offset {
+            let (qdrant_point_ids, new_offset) = scroll_qdrant_collection_ids(
+                migration.from_collection.clone(),
+                Some(cur_offset),
+                Some(EMBEDDING_MIGRATION_BATCH_SIZE),
+                Some(Filter::must([Condition::matches(
+                    "dataset_id",
+                    dataset_id.to_string(),
+                )])),
+            )
+            .await?;
+
+            if !qdrant_point_ids.is_empty() {
+                migration.total_points += qdrant_point_ids.len() as u64;
+                queue_embedding_migration_points_query(
+                    &migration,
+                    qdrant_point_ids,
+                    redis_pool.clone(),
+                )
+                .await?;
+            }
+
+            offset = new_offset;
+        }
+
+        Ok(())
+    }
+    .await;
+
+    if let Err(err) = queue_result {
+        fail_embedding_migration_query(dataset_id, migration_id, err.to_string(), redis_pool)
+            .await?;
+        return Err(err);
+    }
+
+    finish_embedding_migration_batch_query(dataset_id, migration_id, 0, pool, redis_pool).await?;
+
+    Ok(migration.total_points)
+}
+
+/// Queues points which were just written to the dataset's current collection if a migration is running, so new ingests reach the target collection too.
+#[tracing::instrument(skip(redis_pool))]
+pub async fn queue_new_points_for_embedding_migration_query(
+    dataset_id: uuid::Uuid,
+    qdrant_point_ids: Vec<uuid::Uuid>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    if qdrant_point_ids.is_empty() {
+        return Ok(());
+    }
+
+    match get_embedding_migration_query(dataset_id, redis_pool.clone()).await? {
+        Some(migration)
+            if matches!(
+                migration.state,
+                EmbeddingMigrationState::Running | EmbeddingMigrationState::Switching
+            ) =>
+        {
+            queue_embedding_migration_points_query(&migration, qdrant_point_ids, redis_pool).await
+        }
+        _ => Ok(()),
+    }
+}
+
+#[tracing::instrument(skip(redis_pool))]
+pub async fn fail_embedding_migration_query(
+    dataset_id: uuid::Uuid,
+    migration_id: uuid::Uuid,
+    error: String,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    let mut migration = match get_embedding_migration_query(dataset_id, redis_pool.clone()).await? {
+        Some(migration) if migration.migration_id == migration_id => migration,
+        _ => return Ok(()),
+    };
+
+    migration.state = EmbeddingMigrationState::Failed;
+    migration.error = Some(error);
+    migration.updated_at = chrono::Utc::now().naive_local();
+
+    set_embedding_migration_query(&migration, redis_pool).await
+}
+
+/// Keeps the dataset's running embedding migration from switching while points are written to its current collection. Returns the migration in progress, if any. A running migration stays held until the caller releases it with `finish_embedding_migration_batch_query` once the written points are queued, a switching one can not be held and the write has to wait for the switch to finish. Call this before reading the dataset's configuration so the points go to the collection the migration expects.
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn hold_embedding_migration_query(
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<Option<EmbeddingMigration>, ServiceError> {
+    let migration = match get_embedding_migration_query(dataset_id, redis_pool.clone()).await? {
+        Some(migration) if migration.state == EmbeddingMigrationState::Running => migration,
+        Some(migration) if migration.state == EmbeddingMigrationState::Switching => {
+            return Ok(Some(migration))
+        }
+        _ => return Ok(None),
+    };
+
+    {
+        let mut redis_conn = redis_pool
+            .get()
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+        redis::cmd("HINCRBY")
+            .arg(get_embedding_migration_progress_key(migration.migration_id))
+            .arg("pending_batches")
+            .arg(1)
+            .query_async::<_, ()>(&mut *redis_conn)
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+    }
+
+    // The migration may have started switching before the hold was counted.
+    match get_embedding_migration_query(dataset_id, redis_pool.clone()).await? {
+        Some(current)
+            if current.migration_id == migration.migration_id
+                && current.state == EmbeddingMigrationState::Running =>
+        {
+            Ok(Some(current))
+        }
+        current => {
+            finish_embedding_migration_batch_query(
+                dataset_id,
+                migration.migration_id,
+                0,
+                pool,
+                redis_pool,
+            )
+            .await?;
+
+            Ok(current.filter(|current| current.state == EmbeddingMigrationState::Switching))
+        }
+    }
+}
+
+/// Records a finished batch or released hold. The last one to finish switches the dataset over to the new model.
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn finish_embedding_migration_batch_query(
+    dataset_id: uuid::Uuid,
+    migration_id: uuid::Uuid,
+    migrated_points: usize,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    let progress_key = get_embedding_migration_progress_key(migration_id);
+    let switch_lock_key = get_embedding_migration_switch_lock_key(migration_id);
+
+    let pending_batches: i64 = {
+        let mut redis_conn = redis_pool
+            .get()
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+        let (pending_batches,): (i64,) = redis::pipe()
+            .atomic()
+            .cmd("HINCRBY")
+            .arg(&progress_key)
+            .arg("migrated_points")
+            .arg(migrated_points)
+            .ignore()
+            .cmd("HINCRBY")
+            .arg(&progress_key)
+            .arg("pending_batches")
+            .arg(-1)
+            .query_async(&mut *redis_conn)
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+        pending_batches
+    };
+
+    if pending_batches > 0 {
+        return Ok(());
+    }
+
+    let migration = loop {
+        // Several callers can see no pending batches, only the one holding the lock switches.
+        let locked: Option<String> = {
+            let mut redis_conn = redis_pool
+                .get()
+                .await
+                .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+            redis::cmd("SET")
+                .arg(&switch_lock_key)
+                .arg(1)
+                .arg("NX")
+                .arg("EX")
+                .arg(EMBEDDING_MIGRATION_SWITCH_LOCK_TTL_SECS)
+                .query_async(&mut *redis_conn)
+                .await
+                .map_err(|err| ServiceError::BadRequest(err.to_string()))?
+        };
+        if locked.is_none() {
+            return Ok(());
+        }
+
+        let mut migration =
+            match get_embedding_migration_query(dataset_id, redis_pool.clone()).await? {
+                Some(migration)
+                    if migration.migration_id == migration_id
+                        && migration.state == EmbeddingMigrationState::Running =>
+                {
+                    migration
+                }
+                _ => return Ok(()),
+            };
+
+        // Writes to the dataset are held off from here on, see `hold_embedding_migration_query`.
+        migration.state = EmbeddingMigrationState::Switching;
+        migration.updated_at = chrono::Utc::now().naive_local();
+        set_embedding_migration_query(&migration, redis_pool.clone()).await?;
+
+        if get_pending_embedding_migration_batches(migration_id, redis_pool.clone()).await? <= 0 {
+            break migration;
+        }
+
+        // A write or batch was counted while the state was being changed, the last one to finish switches instead.
+        migration.state = EmbeddingMigrationState::Running;
+        set_embedding_migration_query(&migration, redis_pool.clone()).await?;
+
+        {
+            let mut redis_conn = redis_pool
+                .get()
+                .await
+                .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+            redis::cmd("DEL")
+                .arg(&switch_lock_key)
+                .query_async::<_, ()>(&mut *redis_conn)
+                .await
+                .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+        }
+
+        // Unless it already finished while the migration was switching and left the switch to this call.
+        if get_pending_embedding_migration_batches(migration_id, redis_pool.clone()).await? > 0 {
+            return Ok(());
+        }
+    };
+
+    if let Err(err) = switch_embedding_migration_query(&migration, pool).await {
+        fail_embedding_migration_query(dataset_id, migration_id, err.to_string(), redis_pool)
+            .await?;
+        return Err(err);
+    }
+
+    let mut migration = get_embedding_migration_query(dataset_id, redis_pool.clone())
+        .await?
+        .unwrap_or(migration);
+    migration.state = EmbeddingMigrationState::Completed;
+    migration.updated_at = chrono::Utc::now().naive_local();
+    set_embedding_migration_query(&migration, redis_pool.clone()).await?;
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    redis::cmd("EXPIRE")
+        .arg(&progress_key)
+        .arg(FINISHED_EMBEDDING_MIGRATION_TTL_SECS)
+        .query_async::<_, ()>(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(())
+}
+
+async fn get_pending_embedding_migration_batches(
+    migration_id: uuid::Uuid,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<i64, ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let pending_batches: Option<i64> = redis::cmd("HGET")
+        .arg(get_embedding_migration_progress_key(migration_id))
+        .arg("pending_batches")
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(pending_batches.unwrap_or(0))
+}
+
+/// Checks which of the migrated points were written to the migration's target collection.
+async fn get_written_migrated_point_ids(
+    qdrant_client: &qdrant_client::Qdrant,
+    migration: &EmbeddingMigration,
+    migrated_point_ids: &[uuid::Uuid],
+) -> Result<HashSet<uuid::Uuid>, ServiceError> {
+    if migrated_point_ids.is_empty() {
+        return Ok(HashSet::new());
+    }
+
+    let points: Vec<PointId> = migrated_point_ids
+        .iter()
+        .map(|point_id| point_id.to_string().into())
+        .collect();
+
+    let written_points = qdrant_client
+        .get_points(
+            GetPointsBuilder::new(migration.to_collection.clone(), points)
+                .with_payload(false)
+                .with_vectors(false)
+                .build(),
+        )
+        .await
+        .map_err(|err| {
+            log::error!("Failed to fetch migrated points from qdrant {:?}", err);
+            ServiceError::BadRequest("Failed to fetch migrated points from qdrant".to_string())
+        })?;
+
+    Ok(written_points
+        .result
+        .into_iter()
+        .filter_map(|point| match point.id?.point_id_options? {
+            PointIdOptions::Uuid(id) => uuid::Uuid::parse_str(&id).ok(),
+            PointIdOptions::Num(_) => None,
+        })
+        .collect())
+}
+
+/// Makes the migrated points the dataset's own. Chunks are repointed at their migrated points and the dataset's configuration is switched to the new model in one transaction. Once it is committed, migrated points sharing the old collection get the dataset's id and the old points are deleted. The switch is aborted if any chunk's migrated point is missing from the target collection, as deleting its old point would lose it.
+#[tracing::instrument(skip(pool))]
+async fn switch_embedding_migration_query(
+    migration: &EmbeddingMigration,
+    pool: web::Data<Pool>,
+) -> Result<(), ServiceError> {
+    use crate::data::schema::chunk_metadata::dsl as chunk_metadata_columns;
+    use crate::data::schema::datasets::dsl as datasets_columns;
+
+    let qdrant_client = get_qdrant_connection(
+        Some(get_env!("QDRANT_URL", "QDRANT_URL should be set")),
+        Some(get_env!("QDRANT_API_KEY", "QDRANT_API_KEY should be set")),
+    )
+    .await?;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let dataset_id = migration.dataset_id;
+    let migration_id = migration.migration_id;
+    let to_model = migration.to_model.clone();
+    let to_distance_metric = migration.to_distance_metric.clone();
+    let qdrant_client = &qdrant_client;
+
+    let old_point_ids = conn
+        .transaction::<_, ServiceError, _>(|conn| {
+            async move {
+                let server_configuration = datasets_columns::datasets
+                    .filter(datasets_columns::id.eq(dataset_id))
+                    .select(datasets_columns::server_configuration)
+                    .for_update()
+                    .first::<serde_json::Value>(conn)
+                    .await?;
+
+                let mut old_point_ids = vec![];
+                let mut last_chunk_id = uuid::Uuid::nil();
+
+                loop {
+                    let chunks = chunk_metadata_columns::chunk_metadata
+                        .filter(chunk_metadata_columns::dataset_id.eq(dataset_id))
+                        .filter(chunk_metadata_columns::id.gt(last_chunk_id))
+                        .order_by(chunk_metadata_columns::id)
+                        .limit(EMBEDDING_MIGRATION_SWITCH_PAGE_SIZE)
+                        .select((
+                            chunk_metadata_columns::id,
+                            chunk_metadata_columns::qdrant_point_id,
+                        ))
+                        .load::<(uuid::Uuid, uuid::Uuid)>(conn)
+                        .await?;
+
+                    let (chunk_ids, point_ids): (Vec<uuid::Uuid>, Vec<uuid::Uuid>) =
+                        chunks.into_iter().unzip();
+                    let migrated_point_ids = point_ids
+                        .iter()
+                        .map(|point_id| get_migrated_point_id(migration_id, *point_id))
+                        .collect_vec();
+
+                    let written_point_ids =
+                        get_written_migrated_point_ids(qdrant_client, migration, &migrated_point_ids)
+                            .await?;
+                    let missing_count = migrated_point_ids
+                        .iter()
+                        .filter(|point_id| !written_point_ids.contains(point_id))
+                        .count();
+                    if missing_count > 0 {
+                        return Err(ServiceError::BadRequest(format!(
+                            "{} chunks have no migrated point in {}, keeping the previous model",
+                            missing_count, migration.to_collection
+                        )));
+                    }
+
+                    diesel::sql_query(
+                        "UPDATE chunk_metadata SET qdrant_point_id = m.qdrant_point_id FROM unnest($1::uuid[], $2::uuid[]) AS m(id, qdrant_point_id) WHERE chunk_metadata.id = m.id",
+                    )
+                    .bind::<sql_types::Array<sql_types::Uuid>, _>(&chunk_ids)
+                    .bind::<sql_types::Array<sql_types::Uuid>, _>(&migrated_point_ids)
+                    .execute(conn)
+                    .await?;
+
+                    last_chunk_id = match chunk_ids.last() {
+                        Some(chunk_id) => *chunk_id,
+                        None => break,
+                    };
+                    old_point_ids.extend(point_ids);
+
+                    if chunk_ids.len() < EMBEDDING_MIGRATION_SWITCH_PAGE_SIZE as usize {
+                        break;
+                    }
+                }
+
+                let dataset_config = DatasetConfiguration::from_json(server_configuration)
+                    .with_embedding_model_options(to_model)
+                    .with_distance_metric(to_distance_metric);
+
+                diesel::update(datasets_columns::datasets.filter(datasets_columns::id.eq(dataset_id)))
+                    .set((
+                        datasets_columns::server_configuration.eq(dataset_config.to_json()),
+                        datasets_columns::updated_at.eq(diesel::dsl::now),
+                    ))
+                    .execute(conn)
+                    .await?;
+
+                Ok(old_point_ids)
+            }
+            .scope_boxed()
+        })
+        .await
+        .map_err(|err| {
+            log::error!("Failed to switch dataset to migrated points {:?}", err);
+            err
+        })?;
+
+    // The chunks no longer point at the old points, so they drop out of search results even before they are deleted.
+    if let Some(shadow_dataset_id) = migration.shadow_dataset_id {
+        let dataset_id_payload: Payload =
+            HashMap::from([("dataset_id", Value::from(migration.dataset_id.to_string()))]).into();
+
+        qdrant_client
+            .set_payload(
+                SetPayloadPointsBuilder::new(migration.to_collection.clone(), dataset_id_payload)
+                    .points_selector(Filter::must([Condition::matches(
+                        "dataset_id",
+                        shadow_dataset_id.to_string(),
+                    )]))
+                    .wait(true),
+            )
+            .await
+            .map_err(|err| {
+                log::error!("Failed to set dataset_id on migrated points {:?}", err);
+                ServiceError::BadRequest("Failed to set dataset_id on migrated points".to_string())
+            })?;
+    }
+
+    for old_point_ids in old_point_ids.chunks(1000) {
+        delete_points_from_qdrant(old_point_ids.to_vec(), migration.from_collection.clone())
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Builds the migrated point for `point` from its freshly computed dense vector, or with its vectors unchanged when the migration only moves the dataset to another collection. Sparse and BM25 vectors are carried over as they do not depend on the embedding model.
+pub fn get_migrated_point(
+    migration: &EmbeddingMigration,
+    point: RetrievedPoint,
+    dense_vector: Option<Vec<f32>>,
+) -> Option<PointStruct> {
+    let qdrant_point_id = match point.id?.point_id_options? {
+        qdrant_client::qdrant::point_id::PointIdOptions::Uuid(id) => {
+            uuid::Uuid::parse_str(&id).ok()?
+        }
+        qdrant_client::qdrant::point_id::PointIdOptions::Num(_) => return None,
+    };
+
+    let mut vectors: HashMap<String, Vector> = match point.vectors.and_then(|v| v.vectors_options) {
+        Some(VectorsOptions::Vectors(named_vectors)) => named_vectors.vectors,
+        _ => HashMap::new(),
+    };
+    if let Some(dense_vector) = dense_vector {
+        vectors.remove(&get_dense_vector_name(&migration.from_model));
+        vectors.insert(
+            get_dense_vector_name(&migration.to_model),
+            Vector::from(dense_vector),
+        );
+    }
+
+    let mut payload = point.payload;
+    if let Some(shadow_dataset_id) = migration.shadow_dataset_id {
+        payload.insert(
+            "dataset_id".to_string(),
+            Value::from(shadow_dataset_id.to_string()),
+        );
+    }
+
+    Some(PointStruct::new(
+        get_migrated_point_id(migration.migration_id, qdrant_point_id).to_string(),
+        vectors,
+        Payload::from(payload),
+    ))
+}
+
+/// Embeds `points` with the migration's target model and returns them ready to be written to its target collection. Points are only copied when the model stays the same.
+#[tracing::instrument(skip(points))]
+pub async fn get_migrated_points_query(
+    migration: &EmbeddingMigration,
+    points: Vec<RetrievedPoint>,
+) -> Result<Vec<PointStruct>, ServiceError> {
+    if migration.from_model == migration.to_model {
+        return Ok(points
+            .into_iter()
+            .filter_map(|point| get_migrated_point(migration, point, None))
+            .collect());
+    }
+
+    let points = points
+        .into_iter()
+        .filter_map(|point| match point.payload.get("content") {
+            Some(Value {
+                kind: Some(qdrant_client::qdrant::value::Kind::StringValue(content)),
+            }) => Some((content.clone(), point)),
+            _ => None,
+        })
+        .collect_vec();
+
+    if points.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let dense_vectors = get_dense_vectors(
+        points
+            .iter()
+            .map(|(content, _)| (content.clone(), None))
+            .collect(),
+        "doc",
+        DatasetConfiguration::default().with_embedding_model_options(migration.to_model.clone()),
+        reqwest::Client::new(),
+    )
+    .await?;
+
+    Ok(points
+        .into_iter()
+        .zip(dense_vectors)
+        .filter_map(|((_, point), dense_vector)| {
+            get_migrated_point(migration, point, Some(dense_vector))
+        })
+        .collect())
+}
+
+/// Rebuilds the payloads of the given points from their chunks, groups and tags in Postgres. Points without a chunk are left out.
+#[tracing::instrument(skip(pool))]
+pub async fn get_refreshed_payloads_query(
+    qdrant_point_ids: Vec<uuid::Uuid>,
+    pool: web::Data<Pool>,
+) -> Result<HashMap<uuid::Uuid, QdrantPayload>, ServiceError> {
+    use crate::data::schema::chunk_group_bookmarks::dsl as chunk_group_bookmarks_columns;
+
+    let chunk_metadatas = get_chunk_metadatas_from_point_ids(qdrant_point_ids, pool.clone())
+        .await?
+        .into_iter()
+        .map(ChunkMetadata::from)
+        .collect_vec();
+
+    let group_ids_by_chunk_id: HashMap<uuid::Uuid, Vec<uuid::Uuid>> = {
+        let mut conn = pool.get().await.map_err(|_| {
+            ServiceError::BadRequest("Could not get database connection".to_string())
+        })?;
+
+        chunk_group_bookmarks_columns::chunk_group_bookmarks
+            .filter(
+                chunk_group_bookmarks_columns::chunk_metadata_id
+                    .eq_any(chunk_metadatas.iter().map(|chunk| chunk.id).collect_vec()),
+            )
+            .select((
+                chunk_group_bookmarks_columns::chunk_metadata_id,
+                chunk_group_bookmarks_columns::group_id,
+            ))
+            .load::<(uuid::Uuid, uuid::Uuid)>(&mut conn)
+            .await
+            .map_err(|_| ServiceError::BadRequest("Failed to load group bookmarks".to_string()))?
+            .into_iter()
+            .into_group_map()
+    };
+
+    let group_tag_sets: HashMap<uuid::Uuid, Option<Vec<Option<String>>>> =
+        get_groups_from_group_ids_query(
+            group_ids_by_chunk_id
+                .values()
+                .flatten()
+                .copied()
+                .unique()
+                .collect(),
+            pool,
+        )
+        .await?
+        .into_iter()
+        .map(|group| (group.id, group.tag_set))
+        .collect();
+
+    Ok(chunk_metadatas
+        .into_iter()
+        .map(|chunk_metadata| {
+            let group_ids = group_ids_by_chunk_id.get(&chunk_metadata.id).cloned();
+            let group_tag_set = group_ids.as_ref().map(|group_ids| {
+                group_ids
+                    .iter()
+                    .filter_map(|group_id| group_tag_sets.get(group_id).cloned().flatten())
+                    .flatten()
+                    .dedup()
+                    .collect()
+            });
+
+            (
+                chunk_metadata.qdrant_point_id,
+                QdrantPayload::new(chunk_metadata, group_ids, None, group_tag_set),
+            )
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use qdrant_client::qdrant::{NamedVectors, PointId, Vectors};
+
+    #[test]
+    fn test_get_migrated_point() {
+        let migration_id = uuid::Uuid::new_v4();
+        let dataset_id = uuid::Uuid::new_v4();
+        let qdrant_point_id = uuid::Uuid::new_v4();
+
+        let migration = EmbeddingMigration {
+            migration_id,
+            dataset_id,
+            from_model: EmbeddingModelOptions {
+                base_url: "https://api.openai.com/v1".to_string(),
+                model_name: "text-embedding-3-small".to_string(),
+                size: 3,
+            },
+            to_model: EmbeddingModelOptions {
+                base_url: "https://api.openai.com/v1".to_string(),
+                model_name: "text-embedding-3-large".to_string(),
+                size: 2,
+            },
+            from_distance_metric: DistanceMetric::Cosine,
+            to_distance_metric: DistanceMetric::Cosine,
+            from_collection: "3_vectors".to_string(),
+            to_collection: "2_vectors".to_string(),
+            shadow_dataset_id: Some(migration_id),
+            state: EmbeddingMigrationState::Running,
+            total_points: 1,
+            migrated_points: 0,
+            error: None,
+            started_at: chrono::Utc::now().naive_local(),
+            updated_at: chrono::Utc::now().naive_local(),
+        };
+
+        let point = RetrievedPoint {
+            id: Some(PointId::from(qdrant_point_id.to_string())),
+            payload: HashMap::from([
+                (
+                    "dataset_id".to_string(),
+                    Value::from(dataset_id.to_string()),
+                ),
+                ("content".to_string(), Value::from("hello world")),
+            ]),
+            vectors: Some(Vectors {
+                vectors_options: Some(VectorsOptions::Vectors(NamedVectors {
+                    vectors: HashMap::from([
+                        ("3_vectors".to_string(), Vector::from(vec![0.1, 0.2, 0.3])),
+                        ("sparse_vectors".to_string(), Vector::from(vec![(4, 0.5)])),
+                    ]),
+                })),
+            }),
+            shard_key: None,
+            order_value: None,
+        };
+
+        let migrated_point = get_migrated_point(&migration, point, Some(vec![0.7, 0.8])).unwrap();
+
+        assert_eq!(
+            migrated_point.id,
+            Some(PointId::from(
+                get_migrated_point_id(migration_id, qdrant_point_id).to_string()
+            ))
+        );
+        assert_eq!(
+            get_migrated_point_id(migration_id, qdrant_point_id),
+            get_migrated_point_id(migration_id, qdrant_point_id)
+        );
+        assert_eq!(
+            migrated_point.payload.get("dataset_id"),
+            Some(&Value::from(migration_id.to_string()))
+        );
+
+        let vectors = match migrated_point.vectors.and_then(|v| v.vectors_options) {
+            Some(VectorsOptions::Vectors(named_vectors)) => named_vectors.vectors,
+            _ => panic!("Migrated point must have named vectors"),
+        };
+        assert_eq!(vectors.len(), 2);
+        assert_eq!(
+            vectors.get("2_vectors"),
+            Some(&Vector::from(vec![0.7, 0.8]))
+        );
+        assert_eq!(
+            vectors.get("sparse_vectors"),
+            Some(&Vector::from(vec![(4, 0.5)]))
+        );
+    }
+
+    #[test]
+    fn test_get_migrated_point_keeps_vectors_when_moving_collection() {
+        let migration_id = uuid::Uuid::new_v4();
+        let dataset_id = uuid::Uuid::new_v4();
+        let qdrant_point_id = uuid::Uuid::new_v4();
+        let model = EmbeddingModelOptions {
+            base_url: "https://api.openai.com/v1".to_string(),
+            model_name: "text-embedding-3-small".to_string(),
+            size: 3,
+        };
+
+        let migration = EmbeddingMigration {
+            migration_id,
+            dataset_id,
+            from_model: model.clone(),
+            to_model: model,
+            from_distance_metric: DistanceMetric::Cosine,
+            to_distance_metric: DistanceMetric::Dot,
+            from_collection: "3_vectors".to_string(),
+            to_collection: "3_vectors_dot".to_string(),
+            shadow_dataset_id: None,
+            state: EmbeddingMigrationState::Running,
+            total_points: 1,
+            migrated_points: 0,
+            error: None,
+            started_at: chrono::Utc::now().naive_local(),
+            updated_at: chrono::Utc::now().naive_local(),
+        };
+
+        let point_vectors = HashMap::from([
+            ("3_vectors".to_string(), Vector::from(vec![0.1, 0.2, 0.3])),
+            ("sparse_vectors".to_string(), Vector::from(vec![(4, 0.5)])),
+        ]);
+        let point = RetrievedPoint {
+            id: Some(PointId::from(qdrant_point_id.to_string())),
+            payload: HashMap::from([(
+                "dataset_id".to_string(),
+                Value::from(dataset_id.to_string()),
+            )]),
+            vectors: Some(Vectors {
+                vectors_options: Some(VectorsOptions::Vectors(NamedVectors {
+                    vectors: point_vectors.clone(),
+                })),
+            }),
+            shard_key: None,
+            order_value: None,
+        };
+
+        assert!(matches!(
+            get_point_migration_mode(&migration),
+            MigrationMode::MoveCollection { .. }
+        ));
+
+        let migrated_point = get_migrated_point(&migration, point, None).unwrap();
+
+        assert_eq!(
+            migrated_point.payload.get("dataset_id"),
+            Some(&Value::from(dataset_id.to_string()))
+        );
+        match migrated_point.vectors.and_then(|v| v.vectors_options) {
+            Some(VectorsOptions::Vectors(named_vectors)) => {
+                assert_eq!(named_vectors.vectors, point_vectors)
+            }
+            _ => panic!("Migrated point must have named vectors"),
+        }
+    }
+}
@@ -1,18 +1,27 @@
 pub mod analytics_operator;
 pub mod chunk_operator;
 pub mod clickhouse_operator;
+pub mod dataset_archive_operator;
 pub mod dataset_operator;
+pub mod dedup_operator;
 pub mod email_operator;
+pub mod evaluation_operator;
 pub mod event_operator;
+pub mod facet_operator;
 pub mod file_operator;
 pub mod group_operator;
 pub mod invitation_operator;
 pub mod message_operator;
+pub mod migration_operator;
 pub mod model_operator;
 pub mod organization_operator;
 pub mod parse_operator;
 pub mod qdrant_operator;
+pub mod rank_model_operator;
 pub mod search_operator;
+pub mod spelling_operator;
 pub mod stripe_operator;
+pub mod synonym_operator;
 pub mod topic_operator;
 pub mod user_operator;
+pub mod webhook_operator;
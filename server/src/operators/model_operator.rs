@@ -1,9 +1,13 @@
 use crate::{
-    data::models::{ChunkMetadataTypes, DatasetConfiguration, ScoreChunkDTO},
+    data::models::{
+        Bm25AnalyzerOptions, Bm25Language, Bm25Segmentation, ChunkMetadataTypes,
+        DatasetConfiguration, ScoreChunkDTO,
+    },
     errors::ServiceError,
     get_env,
     handlers::chunk_handler::{FullTextBoost, SemanticBoost},
 };
+use itertools::Itertools;
 use murmur3::murmur3_32;
 use openai_dive::v1::{
     helpers::format_response,
@@ -1015,21 +1019,194 @@ pub fn get_bm25_embeddings(
     avg_len: f32,
     b: f32,
     k: f32,
+    analyzer: &Bm25AnalyzerOptions,
 ) -> Vec<Vec<(u32, f32)>> {
-    term_frequency(tokenize_batch(chunks_and_boost), avg_len, b, k)
+    term_frequency(
+        tokenize_batch(chunks_and_boost, analyzer),
+        avg_len,
+        b,
+        k,
+        analyzer,
+    )
 }
 
-fn tokenize(text: String) -> Vec<String> {
-    let mut en_stem =
+/// Token counts used as BM25 document lengths, tokenized the same way as `get_bm25_embeddings`.
+pub fn get_bm25_doc_lengths(chunks: Vec<String>, analyzer: &Bm25AnalyzerOptions) -> Vec<usize> {
+    chunks
+        .into_iter()
+        .map(|chunk| tokenize(chunk, analyzer).len())
+        .collect()
+}
+
+/// Distinct unstemmed alphabetic terms of each chunk paired with the surface form they were folded from, tokenized like `get_bm25_embeddings` otherwise. The surface form equals the term unless the analyzer folds accents. Only word segmented analyzers produce terms as n-grams and bigrams are not words.
+pub fn get_vocabulary_terms(
+    chunks: Vec<String>,
+    analyzer: &Bm25AnalyzerOptions,
+) -> Vec<Vec<(String, String)>> {
+    if get_segmentation(analyzer) != Bm25Segmentation::Word {
+        return vec![vec![]; chunks.len()];
+    }
+
+    let unfolded_analyzer = Bm25AnalyzerOptions {
+        ascii_folding: false,
+        ..analyzer.clone()
+    };
+
+    chunks
+        .into_iter()
+        .map(|chunk| {
+            let terms = get_tokens(&chunk, analyzer, false);
+            // Folding maps every token to exactly one token, so the unfolded tokens line up with the terms
+            let surface_forms = if analyzer.ascii_folding {
+                get_tokens(&chunk, &unfolded_analyzer, false)
+            } else {
+                terms.clone()
+            };
+
+            terms
+                .into_iter()
+                .zip(surface_forms)
+                .filter(|(term, _)| term.chars().all(char::is_alphabetic))
+                .unique()
+                .collect()
+        })
+        .collect()
+}
+
+fn get_tantivy_language(language: Bm25Language) -> Option<tantivy::tokenizer::Language> {
+    match language {
+        Bm25Language::Arabic => Some(tantivy::tokenizer::Language::Arabic),
+        Bm25Language::Danish => Some(tantivy::tokenizer::Language::Danish),
+        Bm25Language::Dutch => Some(tantivy::tokenizer::Language::Dutch),
+        Bm25Language::English => Some(tantivy::tokenizer::Language::English),
+        Bm25Language::Finnish => Some(tantivy::tokenizer::Language::Finnish),
+        Bm25Language::French => Some(tantivy::tokenizer::Language::French),
+        Bm25Language::German => Some(tantivy::tokenizer::Language::German),
+        Bm25Language::Greek => Some(tantivy::tokenizer::Language::Greek),
+        Bm25Language::Hungarian => Some(tantivy::tokenizer::Language::Hungarian),
+        Bm25Language::Italian => Some(tantivy::tokenizer::Language::Italian),
+        Bm25Language::Norwegian => Some(tantivy::tokenizer::Language::Norwegian),
+        Bm25Language::Portuguese => Some(tantivy::tokenizer::Language::Portuguese),
+        Bm25Language::Romanian => Some(tantivy::tokenizer::Language::Romanian),
+        Bm25Language::Russian => Some(tantivy::tokenizer::Language::Russian),
+        Bm25Language::Spanish => Some(tantivy::tokenizer::Language::Spanish),
+        Bm25Language::Swedish => Some(tantivy::tokenizer::Language::Swedish),
+        Bm25Language::Tamil => Some(tantivy::tokenizer::Language::Tamil),
+        Bm25Language::Turkish => Some(tantivy::tokenizer::Language::Turkish),
+        Bm25Language::None
+        | Bm25Language::Chinese
+        | Bm25Language::Japanese
+        | Bm25Language::Korean => None,
+    }
+}
+
+fn get_segmentation(analyzer: &Bm25AnalyzerOptions) -> Bm25Segmentation {
+    match (analyzer.segmentation, analyzer.language) {
+        (
+            Bm25Segmentation::Word,
+            Bm25Language::Chinese | Bm25Language::Japanese | Bm25Language::Korean,
+        ) => Bm25Segmentation::Cjk,
+        (segmentation, _) => segmentation,
+    }
+}
+
+fn is_cjk_char(c: char) -> bool {
+    matches!(c,
+        '\u{1100}'..='\u{11FF}'
+        | '\u{2E80}'..='\u{2FDF}'
+        | '\u{3040}'..='\u{30FF}'
+        | '\u{3100}'..='\u{31FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7AF}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FF66}'..='\u{FF9F}'
+        | '\u{20000}'..='\u{2FA1F}'
+    )
+}
+
+/// Splits runs of CJK characters into overlapping bigrams while keeping the rest of the token intact.
+fn cjk_bigrams(token: &str) -> Vec<String> {
+    let mut terms = vec![];
+
+    for (is_cjk, group) in &token.chars().chunk_by(|c| is_cjk_char(*c)) {
+        let chars = group.collect::<Vec<char>>();
+        if !is_cjk || chars.len() == 1 {
+            terms.push(chars.into_iter().collect());
+        } else {
+            terms.extend(
+                chars
+                    .windows(2)
+                    .map(|window| window.iter().collect::<String>()),
+            );
+        }
+    }
+
+    terms
+}
+
+fn char_ngrams(token: &str, ngram_min: usize, ngram_max: usize) -> Vec<String> {
+    let chars = token.chars().collect::<Vec<char>>();
+    let ngram_min = ngram_min.max(1);
+
+    if chars.len() <= ngram_min {
+        return vec![token.to_string()];
+    }
+
+    (ngram_min..=ngram_max.max(ngram_min))
+        .flat_map(|n| {
+            chars
+                .windows(n)
+                .map(|window| window.iter().collect::<String>())
+                .collect::<Vec<String>>()
+        })
+        .collect()
+}
+
+/// Analyzed tokens of the text before segmentation. Stemming is optional so terms can be shown back to users.
+fn get_tokens(text: &str, analyzer: &Bm25AnalyzerOptions, stem: bool) -> Vec<String> {
+    let segmentation = get_segmentation(analyzer);
+
+    // CJK runs are not separated by whitespace, so long tokens are only dropped after they have been split into bigrams.
+    let mut builder = if segmentation == Bm25Segmentation::Cjk {
+        tantivy::tokenizer::TextAnalyzer::builder(tantivy::tokenizer::SimpleTokenizer::default())
+            .filter(tantivy::tokenizer::LowerCaser)
+            .dynamic()
+    } else {
         tantivy::tokenizer::TextAnalyzer::builder(tantivy::tokenizer::SimpleTokenizer::default())
             .filter(tantivy::tokenizer::RemoveLongFilter::limit(40))
             .filter(tantivy::tokenizer::LowerCaser)
-            .filter(tantivy::tokenizer::Stemmer::new(
-                tantivy::tokenizer::Language::English,
-            ))
-            .build();
+            .dynamic()
+    };
+
+    let tantivy_language = get_tantivy_language(analyzer.language);
+
+    if analyzer.remove_stop_words {
+        if let Some(stop_word_filter) =
+            tantivy_language.and_then(tantivy::tokenizer::StopWordFilter::new)
+        {
+            builder = builder.filter_dynamic(stop_word_filter);
+        }
+    }
+
+    if let Some(stop_words) = &analyzer.stop_words {
+        builder = builder.filter_dynamic(tantivy::tokenizer::StopWordFilter::remove(
+            stop_words.iter().map(|word| word.to_lowercase()),
+        ));
+    }
+
+    // Stop word lists keep their accents, so folding has to come after them
+    if analyzer.ascii_folding {
+        builder = builder.filter_dynamic(tantivy::tokenizer::AsciiFoldingFilter);
+    }
+
+    if let Some(language) = tantivy_language.filter(|_| stem) {
+        builder = builder.filter_dynamic(tantivy::tokenizer::Stemmer::new(language));
+    }
 
-    let mut stream = en_stem.token_stream(&text);
+    let mut text_analyzer = builder.build();
+
+    let mut stream = text_analyzer.token_stream(text);
     let mut tokens: Vec<String> = vec![];
     while stream.advance() {
         tokens.push(stream.token().text.clone());
@@ -1038,12 +1215,30 @@ fn tokenize(text: String) -> Vec<String> {
     tokens
 }
 
+fn tokenize(text: String, analyzer: &Bm25AnalyzerOptions) -> Vec<String> {
+    let tokens = get_tokens(&text, analyzer, true);
+
+    match get_segmentation(analyzer) {
+        Bm25Segmentation::Word => tokens,
+        Bm25Segmentation::Ngram => tokens
+            .iter()
+            .flat_map(|token| char_ngrams(token, analyzer.ngram_min, analyzer.ngram_max))
+            .collect(),
+        Bm25Segmentation::Cjk => tokens
+            .iter()
+            .flat_map(|token| cjk_bigrams(token))
+            .filter(|token| token.len() < 40)
+            .collect(),
+    }
+}
+
 pub fn tokenize_batch(
     chunks: Vec<(String, Option<FullTextBoost>)>,
+    analyzer: &Bm25AnalyzerOptions,
 ) -> Vec<(Vec<String>, Option<FullTextBoost>)> {
     chunks
         .into_iter()
-        .map(|(chunk, boost)| (tokenize(chunk), boost))
+        .map(|(chunk, boost)| (tokenize(chunk, analyzer), boost))
         .collect()
 }
 
@@ -1052,6 +1247,7 @@ pub fn term_frequency(
     avg_len: f32,
     b: f32,
     k: f32,
+    analyzer: &Bm25AnalyzerOptions,
 ) -> Vec<Vec<(u32, f32)>> {
     batched_tokens
         .iter()
@@ -1081,7 +1277,7 @@ pub fn term_frequency(
             }
 
             if let Some(fulltext_boost) = fulltext_boost_option {
-                let tokenized_phrase = tokenize(fulltext_boost.phrase.clone());
+                let tokenized_phrase = tokenize(fulltext_boost.phrase.clone(), analyzer);
                 for token in tokenized_phrase {
                     let token_id =
                         (murmur3_32(&mut Cursor::new(token), 0).unwrap() as i32).unsigned_abs();
@@ -1095,3 +1291,39 @@ pub fn term_frequency(
         })
         .collect()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn test_tokenize_with_analyzer_options() {
+        let german = Bm25AnalyzerOptions {
+            language: Bm25Language::German,
+            remove_stop_words: true,
+            ascii_folding: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            tokenize("Die Häuser".to_string(), &german),
+            vec!["haus".to_string()]
+        );
+        assert_eq!(
+            tokenize("Häuser für".to_string(), &german),
+            vec!["haus".to_string()]
+        );
+        assert_eq!(
+            get_vocabulary_terms(vec!["Die Häuser, die Häuser 2".to_string()], &german),
+            vec![vec![("hauser".to_string(), "häuser".to_string())]]
+        );
+
+        let japanese = Bm25AnalyzerOptions {
+            language: Bm25Language::Japanese,
+            ..Default::default()
+        };
+        assert_eq!(
+            tokenize("東京都".to_string(), &japanese),
+            vec!["東京".to_string(), "京都".to_string()]
+        );
+    }
+}
@@ -1,8 +1,10 @@
+use itertools::Itertools;
 use ndarray::Array2;
 use regex::Regex;
 use regex_split::RegexSplit;
-use scraper::Html;
+use scraper::{ElementRef, Html, Node};
 use std::cmp;
+use tiktoken_rs::cl100k_base_singleton;
 
 use crate::errors::ServiceError;
 
@@ -122,6 +124,385 @@ pub fn coarse_doc_chunker(
     coarse_remove_large_chunks(groups)
 }
 
+/// Share of a requested token budget which chunks are packed to. Budgets are measured with cl100k_base, which is not the tokenizer of the embedding model, so the rest is left as headroom for models that split the same text into more tokens.
+pub const TOKEN_BUDGET_SAFETY_MARGIN: f32 = 0.9;
+
+/// Number of cl100k_base tokens in `text`. The BPE lock is only held while `text` is encoded.
+pub fn count_tokens(text: &str) -> usize {
+    cl100k_base_singleton().lock().encode_ordinary(text).len()
+}
+
+/// Applies `TOKEN_BUDGET_SAFETY_MARGIN` to a requested `max_tokens_per_chunk`.
+pub fn token_budget_with_margin(max_tokens_per_chunk: usize) -> usize {
+    cmp::max(
+        (max_tokens_per_chunk as f32 * TOKEN_BUDGET_SAFETY_MARGIN) as usize,
+        1,
+    )
+}
+
+/// Cuts `split` into pieces of at most `max_tokens` tokens. Pieces end on whitespace where possible and on a character boundary otherwise, so multi-byte characters are never broken.
+fn split_to_token_pieces(split: &str, max_tokens: usize) -> Vec<(String, usize)> {
+    let split_tokens = count_tokens(split);
+    if split_tokens <= max_tokens {
+        return vec![(split.to_string(), split_tokens)];
+    }
+
+    let mut pieces: Vec<(String, usize)> = vec![];
+    for word in split.split_inclusive(char::is_whitespace) {
+        let word_tokens = count_tokens(word);
+        if word_tokens <= max_tokens {
+            pieces.push((word.to_string(), word_tokens));
+            continue;
+        }
+
+        let mut piece = String::new();
+        let mut piece_tokens = 0;
+        for character in word.chars() {
+            let mut candidate = piece.clone();
+            candidate.push(character);
+            let candidate_tokens = count_tokens(&candidate);
+
+            if candidate_tokens > max_tokens && !piece.is_empty() {
+                pieces.push((std::mem::take(&mut piece), piece_tokens));
+                piece.push(character);
+                piece_tokens = count_tokens(&piece);
+            } else {
+                piece = candidate;
+                piece_tokens = candidate_tokens;
+            }
+        }
+        if !piece.is_empty() {
+            pieces.push((piece, piece_tokens));
+        }
+    }
+
+    pieces
+}
+
+/// Packs `splits` into chunks of at most `max_tokens_per_chunk` tokens without breaking a split unless it is longer than the budget on its own, in which case it is cut on word or character boundaries. Each chunk after the first starts with the trailing pieces of the chunk before it which fit in `overlap_tokens` tokens.
+#[tracing::instrument(skip(splits))]
+pub fn token_budget_chunker(
+    splits: Vec<String>,
+    max_tokens_per_chunk: usize,
+    overlap_tokens: usize,
+) -> Vec<String> {
+    let max_tokens_per_chunk = cmp::max(max_tokens_per_chunk, 1);
+    let overlap_tokens = cmp::min(overlap_tokens, max_tokens_per_chunk - 1);
+
+    let mut chunks: Vec<String> = vec![];
+    let mut current_pieces: Vec<(String, usize)> = vec![];
+    let mut current_tokens = 0;
+    let mut has_new_pieces = false;
+
+    let mut flush = |current_pieces: &mut Vec<(String, usize)>, current_tokens: &mut usize| {
+        chunks.push(
+            current_pieces
+                .iter()
+                .map(|(piece, _)| piece.as_str())
+                .collect::<String>()
+                .trim()
+                .to_string(),
+        );
+
+        let mut overlap_len = 0;
+        let mut kept_tokens = 0;
+        for (_, piece_tokens) in current_pieces.iter().rev() {
+            if kept_tokens + piece_tokens > overlap_tokens {
+                break;
+            }
+            kept_tokens += piece_tokens;
+            overlap_len += 1;
+        }
+        current_pieces.drain(0..current_pieces.len() - overlap_len);
+        *current_tokens = kept_tokens;
+    };
+
+    for split in splits {
+        for (piece, piece_tokens) in split_to_token_pieces(&split, max_tokens_per_chunk) {
+            if current_tokens + piece_tokens > max_tokens_per_chunk {
+                if has_new_pieces {
+                    flush(&mut current_pieces, &mut current_tokens);
+                }
+
+                // The overlap has to give way when it does not leave room for the next piece.
+                while current_tokens + piece_tokens > max_tokens_per_chunk {
+                    let (_, dropped_tokens) = current_pieces.remove(0);
+                    current_tokens -= dropped_tokens;
+                }
+            }
+
+            current_pieces.push((piece, piece_tokens));
+            current_tokens += piece_tokens;
+            has_new_pieces = true;
+        }
+    }
+
+    if has_new_pieces {
+        flush(&mut current_pieces, &mut current_tokens);
+    }
+
+    chunks.retain(|chunk| !chunk.is_empty());
+    chunks
+}
+
+/// Same splitting as `coarse_doc_chunker`, but splits are grouped by token budget instead of by count.
+#[tracing::instrument]
+pub fn token_budget_doc_chunker(
+    document: String,
+    split_pattern: Option<Regex>,
+    max_tokens_per_chunk: usize,
+    overlap_tokens: usize,
+) -> Vec<String> {
+    let document_without_newlines = document.replace('\n', " ");
+    let dom = Html::parse_fragment(&document_without_newlines);
+    let clean_text = dom.root_element().text().collect::<String>();
+
+    let pattern = match split_pattern {
+        Some(pattern) => pattern,
+        None => Regex::new(r"[.!?\n]+").expect("Invalid regex"),
+    };
+
+    let splits: Vec<String> = pattern
+        .split_inclusive(&clean_text)
+        .map(|split| split.to_string())
+        .collect();
+
+    token_budget_chunker(splits, max_tokens_per_chunk, overlap_tokens)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeadingChunk {
+    /// Text of the h1-h6 headings enclosing the chunk, outermost first.
+    pub heading_path: Vec<String>,
+    pub chunk_html: String,
+}
+
+struct HeadingChunker {
+    target_chunk_len: usize,
+    heading_path: Vec<(usize, String)>,
+    blocks: Vec<String>,
+    blocks_len: usize,
+    has_content: bool,
+    chunks: Vec<HeadingChunk>,
+}
+
+impl HeadingChunker {
+    fn walk(&mut self, element: ElementRef) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) if !text.trim().is_empty() => {
+                    self.push_block(text.trim().to_string(), text.trim().chars().count());
+                }
+                Node::Element(child_element) => {
+                    let child_ref = match ElementRef::wrap(child) {
+                        Some(child_ref) => child_ref,
+                        None => continue,
+                    };
+                    let text_len = child_ref.text().map(|text| text.chars().count()).sum();
+
+                    match child_element.name() {
+                        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                            let level = child_element.name()[1..].parse::<usize>().unwrap_or(1);
+                            let heading = child_ref
+                                .text()
+                                .collect::<String>()
+                                .split_whitespace()
+                                .join(" ");
+
+                            self.flush();
+                            self.heading_path
+                                .retain(|(cur_level, _)| *cur_level < level);
+                            if !heading.is_empty() {
+                                self.heading_path.push((level, heading));
+                            }
+                            self.blocks.push(child_ref.html());
+                            self.blocks_len += text_len;
+                        }
+                        // Lists stay whole when they fit, otherwise each item is kept whole inside its own copy of the list tag.
+                        "ul" | "ol" if self.blocks_len + text_len > self.target_chunk_len => {
+                            for item in child_ref.children().filter_map(ElementRef::wrap) {
+                                let item_len = item.text().map(|text| text.chars().count()).sum();
+                                self.push_block(
+                                    format!("<{0}>{1}</{0}>", child_element.name(), item.html()),
+                                    item_len,
+                                );
+                            }
+                        }
+                        "table" | "ul" | "ol" | "li" | "p" | "pre" | "blockquote" | "dl" => {
+                            self.push_block(child_ref.html(), text_len);
+                        }
+                        "head" | "script" | "style" | "title" | "meta" => {}
+                        _ => self.walk(child_ref),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn push_block(&mut self, block_html: String, text_len: usize) {
+        if text_len == 0 {
+            return;
+        }
+
+        if self.has_content && self.blocks_len + text_len > self.target_chunk_len {
+            self.flush();
+        }
+
+        self.blocks.push(block_html);
+        self.blocks_len += text_len;
+        self.has_content = true;
+    }
+
+    fn flush(&mut self) {
+        // Sections made only of a heading are dropped; the heading still shows up in the path of the chunks below it.
+        if self.has_content {
+            self.chunks.push(HeadingChunk {
+                heading_path: self
+                    .heading_path
+                    .iter()
+                    .map(|(_, heading)| heading.clone())
+                    .collect(),
+                chunk_html: self.blocks.join("\n"),
+            });
+        }
+
+        self.blocks.clear();
+        self.blocks_len = 0;
+        self.has_content = false;
+    }
+}
+
+/// Splits an HTML document on h1-h6 boundaries, keeping tables, lists and list items whole. Sections longer than `target_chunk_len` characters are split between blocks and every piece keeps the section's heading path.
+#[tracing::instrument]
+pub fn heading_doc_chunker(document: String, target_chunk_len: usize) -> Vec<HeadingChunk> {
+    let dom = Html::parse_document(&document);
+
+    let mut chunker = HeadingChunker {
+        target_chunk_len,
+        heading_path: vec![],
+        blocks: vec![],
+        blocks_len: 0,
+        has_content: false,
+        chunks: vec![],
+    };
+
+    chunker.walk(dom.root_element());
+    chunker.flush();
+
+    chunker.chunks
+}
+
+/// File formats the file worker parses itself instead of sending them to Tika.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NativeFileFormat {
+    PlainText,
+    Markdown,
+    Csv,
+    Jsonl,
+}
+
+pub fn get_native_file_format(file_name: &str) -> Option<NativeFileFormat> {
+    let extension = file_name.rsplit_once('.')?.1.to_lowercase();
+
+    match extension.as_str() {
+        "txt" => Some(NativeFileFormat::PlainText),
+        "md" | "markdown" => Some(NativeFileFormat::Markdown),
+        "csv" => Some(NativeFileFormat::Csv),
+        "jsonl" | "ndjson" => Some(NativeFileFormat::Jsonl),
+        _ => None,
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Wraps each blank line separated paragraph in a `<p>` so plain text goes through the same chunkers as Tika output.
+pub fn convert_plain_text_to_html(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .split("\n\n")
+        .map(|paragraph| paragraph.trim())
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(|paragraph| format!("<p>{}</p>", escape_html(paragraph)))
+        .join("\n")
+}
+
+pub fn convert_markdown_to_html(markdown: &str) -> String {
+    let mut options = pulldown_cmark::Options::empty();
+    options.insert(pulldown_cmark::Options::ENABLE_TABLES);
+    options.insert(pulldown_cmark::Options::ENABLE_STRIKETHROUGH);
+
+    let parser = pulldown_cmark::Parser::new_ext(markdown, options);
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, parser);
+    html
+}
+
+/// Parses a CSV file with a header row into one JSON object per row keyed by column name.
+pub fn parse_csv_rows(
+    file_data: &[u8],
+) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, ServiceError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(file_data);
+
+    let headers = reader
+        .headers()
+        .map_err(|err| ServiceError::BadRequest(format!("Could not read CSV header: {}", err)))?
+        .clone();
+
+    reader
+        .records()
+        .enumerate()
+        .map(|(i, record)| {
+            let record = record.map_err(|err| {
+                ServiceError::BadRequest(format!("Could not parse CSV row {}: {}", i + 1, err))
+            })?;
+
+            Ok(headers
+                .iter()
+                .zip(record.iter())
+                .map(|(header, value)| {
+                    (
+                        header.to_string(),
+                        serde_json::Value::String(value.to_string()),
+                    )
+                })
+                .collect())
+        })
+        .collect()
+}
+
+/// Parses a JSON Lines file into one JSON object per non-empty line.
+pub fn parse_jsonl_rows(
+    file_data: &[u8],
+) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, ServiceError> {
+    String::from_utf8_lossy(file_data)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(
+            |(i, line)| match serde_json::from_str::<serde_json::Value>(line) {
+                Ok(serde_json::Value::Object(row)) => Ok(row),
+                Ok(_) => Err(ServiceError::BadRequest(format!(
+                    "JSONL line {} is not an object",
+                    i + 1
+                ))),
+                Err(err) => Err(ServiceError::BadRequest(format!(
+                    "Could not parse JSONL line {}: {}",
+                    i + 1,
+                    err
+                ))),
+            },
+        )
+        .collect()
+}
+
 #[tracing::instrument(skip(embeddings))]
 pub fn average_embeddings(embeddings: Vec<Vec<f32>>) -> Result<Vec<f32>, ServiceError> {
     let first_embedding_len = match embeddings.first() {
@@ -161,4 +542,102 @@ mod test {
         let result = average_embeddings(embeddings).unwrap();
         assert!(result == vec![2.0, 2.5, 1.0]);
     }
+
+    #[test]
+    pub fn test_token_budget_chunker() {
+        let splits = (0..40)
+            .map(|i| format!("Sentence number {} is here. ", i))
+            .collect::<Vec<String>>();
+
+        let chunks = token_budget_chunker(splits, 32, 8);
+
+        assert!(chunks.len() > 1);
+        for chunk in chunks.iter() {
+            assert!(count_tokens(chunk) <= 32);
+        }
+        for pair in chunks.windows(2) {
+            let previous_tail = pair[0].split_whitespace().last().unwrap();
+            assert!(pair[1].contains(previous_tail));
+        }
+
+        let long_split = vec!["word ".repeat(100)];
+        let chunks = token_budget_chunker(long_split, 30, 0);
+        assert!(chunks.len() > 1);
+        for chunk in chunks.iter() {
+            assert!(count_tokens(chunk) <= 30);
+        }
+        assert_eq!(
+            chunks.join(" ").split_whitespace().count(),
+            100,
+            "no words should be lost or duplicated without overlap"
+        );
+
+        let multi_byte_split = vec!["日本語のテキスト".repeat(20)];
+        let chunks = token_budget_chunker(multi_byte_split.clone(), 10, 0);
+        assert!(chunks.len() > 1);
+        for chunk in chunks.iter() {
+            assert!(count_tokens(chunk) <= 10);
+            assert!(!chunk.contains(char::REPLACEMENT_CHARACTER));
+        }
+        assert_eq!(chunks.concat(), multi_byte_split[0]);
+    }
+
+    #[test]
+    pub fn test_parse_native_files() {
+        assert_eq!(
+            get_native_file_format("catalog.CSV"),
+            Some(NativeFileFormat::Csv)
+        );
+        assert_eq!(get_native_file_format("report.pdf"), None);
+
+        let rows = parse_csv_rows(b"sku,name,price\nA1,\"Desk, oak\",120\n").unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["name"], "Desk, oak");
+
+        let rows =
+            parse_jsonl_rows(b"{\"sku\": \"A1\", \"price\": 120}\n\n{\"sku\": \"B2\"}\n").unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["price"], 120);
+        assert!(parse_jsonl_rows(b"[1, 2]").is_err());
+
+        let html = convert_markdown_to_html("# Title\n\nSome *text*.");
+        assert_eq!(html, "<h1>Title</h1>\n<p>Some <em>text</em>.</p>\n");
+    }
+
+    #[test]
+    pub fn test_heading_doc_chunker() {
+        let document = r#"<html><head><title>Doc</title></head><body>
+            <h1>Guide</h1>
+            <p>Intro paragraph.</p>
+            <h2>Install</h2>
+            <ul><li>Step one</li><li>Step two</li></ul>
+            <h2>Reference</h2>
+            <h3>Options</h3>
+            <table><tr><td>a</td><td>b</td></tr></table>
+            <h1>Appendix</h1>
+            <p>Notes.</p>
+        </body></html>"#;
+
+        let chunks = heading_doc_chunker(document.to_string(), 2000);
+
+        let heading_paths = chunks
+            .iter()
+            .map(|chunk| chunk.heading_path.join(" > "))
+            .collect::<Vec<String>>();
+        assert_eq!(
+            heading_paths,
+            vec![
+                "Guide",
+                "Guide > Install",
+                "Guide > Reference > Options",
+                "Appendix"
+            ]
+        );
+        assert!(chunks[1]
+            .chunk_html
+            .contains("<ul><li>Step one</li><li>Step two</li></ul>"));
+        assert!(chunks[2]
+            .chunk_html
+            .contains("<table><tbody><tr><td>a</td><td>b</td></tr></tbody></table>"));
+    }
 }
@@ -16,9 +16,11 @@ use itertools::Itertools;
 use qdrant_client::{
     qdrant::{
         group_id::Kind, payload_index_params::IndexParams, point_id::PointIdOptions,
-        quantization_config::Quantization, query, BinaryQuantization, CreateCollectionBuilder,
-        CreateFieldIndexCollectionBuilder, DeleteFieldIndexCollectionBuilder, DeletePointsBuilder,
-        Distance, FieldType, Filter, GetPointsBuilder, HnswConfigDiff, OrderBy, PayloadIndexParams,
+        quantization_config::Quantization, query, vectors::VectorsOptions,
+        with_payload_selector::SelectorOptions, BinaryQuantization, CountPointsBuilder,
+        CreateCollectionBuilder, CreateFieldIndexCollectionBuilder,
+        DeleteFieldIndexCollectionBuilder, DeletePointsBuilder, Distance, FieldType, Filter,
+        GetPointsBuilder, HnswConfigDiff, OrderBy, PayloadIncludeSelector, PayloadIndexParams,
         PointId, PointStruct, PrefetchQuery, QuantizationConfig, Query, QueryBatchPoints,
         QueryPoints, RecommendPointGroups, RecommendPoints, RecommendStrategy, ScrollPointsBuilder,
         SearchBatchPoints, SearchParams, SearchPointGroups, SearchPoints, SetPayloadPointsBuilder,
@@ -1460,6 +1462,7 @@ pub async fn scroll_qdrant_collection_ids(
     collection_name: String,
     offset_id: Option<String>,
     limit: Option<u32>,
+    filter: Option<Filter>,
 ) -> Result<(Vec<uuid::Uuid>, Option<String>), ServiceError> {
     let qdrant_client = get_qdrant_connection(
         Some(get_env!("QDRANT_URL", "QDRANT_URL should be set")),
@@ -1476,6 +1479,11 @@ pub async fn scroll_qdrant_collection_ids(
     if let Some(limit) = limit {
         scroll_points_params = scroll_points_params.limit(limit);
     };
+
+    if let Some(filter) = filter {
+        scroll_points_params = scroll_points_params.filter(filter);
+    };
+
     let qdrant_point_ids = qdrant_client
         .scroll(scroll_points_params.with_payload(false).with_vectors(false))
         .await
@@ -1509,6 +1517,84 @@ pub async fn scroll_qdrant_collection_ids(
     Ok((point_ids, offset))
 }
 
+fn get_search_points(
+    query: QdrantSearchQuery,
+    limit: u64,
+    with_payload: WithPayloadSelector,
+    qdrant_collection: &str,
+    dataset_config: &DatasetConfiguration,
+) -> Result<SearchPoints, ServiceError> {
+    match query.vector {
+        VectorType::SpladeSparse(vector) => {
+            let sparse_vector: Vector = vector.into();
+            Ok(SearchPoints {
+                collection_name: qdrant_collection.to_string(),
+                vector: sparse_vector.data,
+                sparse_indices: sparse_vector.indices,
+                vector_name: Some("sparse_vectors".to_string()),
+                limit,
+                score_threshold: query.score_threshold,
+                with_payload: Some(with_payload),
+                with_vectors: Some(WithVectorsSelector::from(false)),
+                filter: Some(query.filter.clone()),
+                timeout: Some(60),
+                params: None,
+                ..Default::default()
+            })
+        }
+        VectorType::BM25Sparse(vector) => {
+            let sparse_vector: Vector = vector.into();
+            Ok(SearchPoints {
+                collection_name: qdrant_collection.to_string(),
+                vector: sparse_vector.data,
+                sparse_indices: sparse_vector.indices,
+                vector_name: Some("bm25_vectors".to_string()),
+                limit,
+                score_threshold: query.score_threshold,
+                with_payload: Some(with_payload),
+                with_vectors: Some(WithVectorsSelector::from(false)),
+                filter: Some(query.filter.clone()),
+                timeout: Some(60),
+                params: None,
+                ..Default::default()
+            })
+        }
+        VectorType::Dense(embedding_vector) => {
+            let vector_name = match embedding_vector.len() {
+                384 => "384_vectors",
+                512 => "512_vectors",
+                768 => "768_vectors",
+                1024 => "1024_vectors",
+                3072 => "3072_vectors",
+                1536 => "1536_vectors",
+                _ => {
+                    return Err(ServiceError::BadRequest(
+                        "Invalid embedding vector size".to_string(),
+                    ))
+                }
+            };
+
+            Ok(SearchPoints {
+                collection_name: qdrant_collection.to_string(),
+                vector: embedding_vector,
+                vector_name: Some(vector_name.to_string()),
+                limit,
+                score_threshold: query.score_threshold,
+                with_payload: Some(with_payload),
+                with_vectors: Some(WithVectorsSelector::from(false)),
+                filter: Some(query.filter.clone()),
+                timeout: Some(60),
+                params: Some(SearchParams {
+                    exact: Some(false),
+                    indexed_only: Some(dataset_config.INDEXED_ONLY),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            })
+        }
+    }
+}
+
 pub async fn count_qdrant_query(
     limit: u64,
     queries: Vec<QdrantSearchQuery>,
@@ -1534,74 +1620,14 @@ pub async fn count_qdrant_query(
 
     let search_point_req_payloads: Vec<SearchPoints> = queries
         .into_iter()
-        .map(|query| match query.vector {
-            VectorType::SpladeSparse(vector) => {
-                let sparse_vector: Vector = vector.into();
-                Ok(SearchPoints {
-                    collection_name: qdrant_collection.to_string(),
-                    vector: sparse_vector.data,
-                    sparse_indices: sparse_vector.indices,
-                    vector_name: Some("sparse_vectors".to_string()),
-                    limit,
-                    score_threshold: query.score_threshold,
-                    with_payload: Some(WithPayloadSelector::from(false)),
-                    with_vectors: Some(WithVectorsSelector::from(false)),
-                    filter: Some(query.filter.clone()),
-                    timeout: Some(60),
-                    params: None,
-                    ..Default::default()
-                })
-            }
-            VectorType::BM25Sparse(vector) => {
-                let sparse_vector: Vector = vector.into();
-                Ok(SearchPoints {
-                    collection_name: qdrant_collection.to_string(),
-                    vector: sparse_vector.data,
-                    sparse_indices: sparse_vector.indices,
-                    vector_name: Some("bm25_vectors".to_string()),
-                    limit,
-                    score_threshold: query.score_threshold,
-                    with_payload: Some(WithPayloadSelector::from(false)),
-                    with_vectors: Some(WithVectorsSelector::from(false)),
-                    filter: Some(query.filter.clone()),
-                    timeout: Some(60),
-                    params: None,
-                    ..Default::default()
-                })
-            }
-            VectorType::Dense(embedding_vector) => {
-                let vector_name = match embedding_vector.len() {
-                    384 => "384_vectors",
-                    512 => "512_vectors",
-                    768 => "768_vectors",
-                    1024 => "1024_vectors",
-                    3072 => "3072_vectors",
-                    1536 => "1536_vectors",
-                    _ => {
-                        return Err(ServiceError::BadRequest(
-                            "Invalid embedding vector size".to_string(),
-                        ))
-                    }
-                };
-
-                Ok(SearchPoints {
-                    collection_name: qdrant_collection.to_string(),
-                    vector: embedding_vector,
-                    vector_name: Some(vector_name.to_string()),
-                    limit,
-                    score_threshold: query.score_threshold,
-                    with_payload: Some(WithPayloadSelector::from(false)),
-                    with_vectors: Some(WithVectorsSelector::from(false)),
-                    filter: Some(query.filter.clone()),
-                    timeout: Some(60),
-                    params: Some(SearchParams {
-                        exact: Some(false),
-                        indexed_only: Some(dataset_config.INDEXED_ONLY),
-                        ..Default::default()
-                    }),
-                    ..Default::default()
-                })
-            }
+        .map(|query| {
+            get_search_points(
+                query,
+                limit,
+                WithPayloadSelector::from(false),
+                &qdrant_collection,
+                &dataset_config,
+            )
         })
         .collect::<Result<Vec<SearchPoints>, ServiceError>>()?;
 
@@ -1702,6 +1728,114 @@ pub async fn update_group_tag_sets_in_qdrant_query(
     Ok(())
 }
 
+/// Exact number of points matching each of the filters.
+pub async fn count_filtered_points_query(
+    filters: Vec<Filter>,
+    dataset_config: DatasetConfiguration,
+) -> Result<Vec<u64>, ServiceError> {
+    if filters.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let qdrant_collection = get_qdrant_collection_from_dataset_config(&dataset_config);
+
+    let qdrant_client = get_qdrant_connection(
+        Some(get_env!("QDRANT_URL", "QDRANT_URL should be set")),
+        Some(get_env!("QDRANT_API_KEY", "QDRANT_API_KEY should be set")),
+    )
+    .await?;
+
+    let count_futures = filters.into_iter().map(|filter| {
+        qdrant_client.count(
+            CountPointsBuilder::new(qdrant_collection.clone())
+                .filter(filter)
+                .exact(true),
+        )
+    });
+
+    futures::future::join_all(count_futures)
+        .await
+        .into_iter()
+        .map(|count_response| {
+            count_response
+                .map(|count_response| {
+                    count_response
+                        .result
+                        .map(|result| result.count)
+                        .unwrap_or(0)
+                })
+                .map_err(|err| {
+                    log::error!("Failed to count points on Qdrant {:?}", err);
+                    ServiceError::BadRequest("Failed to count points on Qdrant".to_string())
+                })
+        })
+        .collect()
+}
+
+/// Returns the requested payload fields of up to `limit` points matching the filter, in point id order.
+pub async fn scroll_filtered_payloads_query(
+    limit: u64,
+    filter: Filter,
+    payload_fields: Vec<String>,
+    dataset_config: DatasetConfiguration,
+) -> Result<Vec<HashMap<String, serde_json::Value>>, ServiceError> {
+    let limit = limit.min(dataset_config.MAX_LIMIT);
+    let qdrant_collection = get_qdrant_collection_from_dataset_config(&dataset_config);
+
+    let qdrant_client = get_qdrant_connection(
+        Some(get_env!("QDRANT_URL", "QDRANT_URL should be set")),
+        Some(get_env!("QDRANT_API_KEY", "QDRANT_API_KEY should be set")),
+    )
+    .await?;
+
+    let with_payload = SelectorOptions::Include(PayloadIncludeSelector {
+        fields: payload_fields,
+    });
+
+    let mut payloads: Vec<HashMap<String, serde_json::Value>> = vec![];
+    let mut offset: Option<PointId> = None;
+
+    while (payloads.len() as u64) < limit {
+        let mut scroll_points_params = ScrollPointsBuilder::new(qdrant_collection.clone())
+            .filter(filter.clone())
+            .limit((limit - payloads.len() as u64).min(1000) as u32)
+            .with_payload(with_payload.clone())
+            .with_vectors(false);
+
+        if let Some(offset) = offset {
+            scroll_points_params = scroll_points_params.offset(offset);
+        }
+
+        let scroll_response = qdrant_client
+            .scroll(scroll_points_params)
+            .await
+            .map_err(|err| {
+                log::error!(
+                    "Failed to scroll points on Qdrant to get payloads {:?}",
+                    err
+                );
+                ServiceError::BadRequest(
+                    "Failed to scroll points on Qdrant to get payloads".to_string(),
+                )
+            })?;
+
+        payloads.extend(scroll_response.result.into_iter().map(|point| {
+            point
+                .payload
+                .into_iter()
+                .map(|(key, value)| (key, value.into()))
+                .collect::<HashMap<String, serde_json::Value>>()
+        }));
+
+        match scroll_response.next_page_offset {
+            Some(next_page_offset) => offset = Some(next_page_offset),
+            None => break,
+        }
+    }
+
+    Ok(payloads)
+}
+
 pub async fn scroll_dataset_points(
     limit: u64,
     qdrant_point_id: Option<uuid::Uuid>,
@@ -1759,3 +1893,53 @@ pub async fn scroll_dataset_points(
 
     Ok(point_ids)
 }
+
+/// Returns the named vectors stored for each of `point_ids`. Points which do not exist in the collection are left out.
+#[tracing::instrument]
+pub async fn get_point_vectors_query(
+    point_ids: Vec<uuid::Uuid>,
+    dataset_config: DatasetConfiguration,
+) -> Result<HashMap<uuid::Uuid, HashMap<String, Vector>>, ServiceError> {
+    let qdrant_collection = get_qdrant_collection_from_dataset_config(&dataset_config);
+
+    let qdrant_client = get_qdrant_connection(
+        Some(get_env!("QDRANT_URL", "QDRANT_URL should be set")),
+        Some(get_env!("QDRANT_API_KEY", "QDRANT_API_KEY should be set")),
+    )
+    .await?;
+
+    let points: Vec<PointId> = point_ids.iter().map(|x| x.to_string().into()).collect();
+
+    let data = qdrant_client
+        .get_points(
+            GetPointsBuilder::new(qdrant_collection, points)
+                .with_payload(false)
+                .with_vectors(true)
+                .build(),
+        )
+        .await
+        .map_err(|err| {
+            log::info!("Failed to fetch points from qdrant {:?}", err);
+            ServiceError::BadRequest("Failed to fetch points from qdrant".to_string())
+        })?;
+
+    let point_vectors = data
+        .result
+        .into_iter()
+        .filter_map(|point| {
+            let point_id = match point.id?.point_id_options? {
+                PointIdOptions::Uuid(id) => uuid::Uuid::parse_str(&id).ok()?,
+                PointIdOptions::Num(_) => return None,
+            };
+
+            let vectors = match point.vectors?.vectors_options? {
+                VectorsOptions::Vectors(named_vectors) => named_vectors.vectors,
+                VectorsOptions::Vector(_) => return None,
+            };
+
+            Some((point_id, vectors))
+        })
+        .collect();
+
+    Ok(point_vectors)
+}
@@ -0,0 +1,310 @@
+use crate::{
+    data::models::{DatasetRankModel, Pool, RankModel, ScoreChunkDTO, SearchMethod},
+    errors::ServiceError,
+    handlers::chunk_handler::SearchChunksReqPayload,
+};
+use actix_web::web;
+use diesel::prelude::*;
+use diesel::upsert::excluded;
+use diesel_async::RunQueryDsl;
+use std::collections::HashMap;
+
+#[tracing::instrument(skip(pool))]
+pub async fn upsert_rank_model_query(
+    rank_model: DatasetRankModel,
+    pool: web::Data<Pool>,
+) -> Result<DatasetRankModel, ServiceError> {
+    use crate::data::schema::rank_models::dsl as rank_models_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let upserted_rank_model: DatasetRankModel =
+        diesel::insert_into(rank_models_columns::rank_models)
+            .values(&rank_model)
+            .on_conflict(rank_models_columns::dataset_id)
+            .do_update()
+            .set((
+                rank_models_columns::model.eq(excluded(rank_models_columns::model)),
+                rank_models_columns::updated_at.eq(excluded(rank_models_columns::updated_at)),
+            ))
+            .get_result(&mut conn)
+            .await
+            .map_err(|_| ServiceError::BadRequest("Could not save rank model".to_string()))?;
+
+    Ok(upserted_rank_model)
+}
+
+#[tracing::instrument(skip(pool))]
+pub async fn get_rank_model_query(
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<Option<DatasetRankModel>, ServiceError> {
+    use crate::data::schema::rank_models::dsl as rank_models_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let rank_model: Option<DatasetRankModel> = rank_models_columns::rank_models
+        .filter(rank_models_columns::dataset_id.eq(dataset_id))
+        .first(&mut conn)
+        .await
+        .optional()
+        .map_err(|_| ServiceError::BadRequest("Could not get rank model".to_string()))?;
+
+    Ok(rank_model)
+}
+
+#[tracing::instrument(skip(pool))]
+pub async fn delete_rank_model_query(
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<(), ServiceError> {
+    use crate::data::schema::rank_models::dsl as rank_models_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let deleted_count = diesel::delete(
+        rank_models_columns::rank_models.filter(rank_models_columns::dataset_id.eq(dataset_id)),
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(|_| ServiceError::BadRequest("Could not delete rank model".to_string()))?;
+
+    if deleted_count == 0 {
+        return Err(ServiceError::NotFound(
+            "No rank model found for dataset".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Rescores the chunks of a search with a dataset's rank model. The per method retrieval scores of a hybrid search are keyed by qdrant point id. BM25 scores are only known if BM25 is enabled for the dataset.
+#[derive(Debug, Clone)]
+pub struct RankModelRescorer {
+    pub model: RankModel,
+    pub search_type: SearchMethod,
+    pub semantic_scores: HashMap<uuid::Uuid, f32>,
+    pub splade_scores: HashMap<uuid::Uuid, f32>,
+    pub bm25_scores: HashMap<uuid::Uuid, f32>,
+}
+
+impl RankModelRescorer {
+    pub fn new(model: RankModel, search_type: SearchMethod) -> Self {
+        RankModelRescorer {
+            model,
+            search_type,
+            semantic_scores: HashMap::new(),
+            splade_scores: HashMap::new(),
+            bm25_scores: HashMap::new(),
+        }
+    }
+
+    pub fn features(
+        &self,
+        chunk: &ScoreChunkDTO,
+        now: chrono::NaiveDateTime,
+    ) -> HashMap<String, f64> {
+        let mut features = HashMap::new();
+        let metadata = match chunk.metadata.get(0) {
+            Some(metadata) => metadata.metadata(),
+            None => return features,
+        };
+
+        features.insert("score".to_string(), chunk.score);
+
+        let get_score = |scores: &HashMap<uuid::Uuid, f32>| {
+            scores
+                .get(&metadata.qdrant_point_id)
+                .map(|score| *score as f64)
+        };
+        let (semantic_score, splade_score, bm25_score) = match self.search_type {
+            SearchMethod::Semantic => (Some(chunk.score), None, None),
+            SearchMethod::FullText => (None, Some(chunk.score), None),
+            SearchMethod::BM25 => (None, None, Some(chunk.score)),
+            SearchMethod::Hybrid => (
+                get_score(&self.semantic_scores),
+                get_score(&self.splade_scores),
+                get_score(&self.bm25_scores),
+            ),
+        };
+        let retrieval_scores = [
+            ("semantic_score", semantic_score),
+            ("splade_score", splade_score),
+            ("bm25_score", bm25_score),
+        ];
+        for (feature, score) in retrieval_scores {
+            if let Some(score) = score {
+                features.insert(feature.to_string(), score);
+            }
+        }
+
+        if let Some(time_stamp) = metadata.time_stamp {
+            let age_days = ((now - time_stamp).num_seconds() as f64 / 86400.0).max(0.0);
+            let half_life_days = self.model.recency_half_life_days.unwrap_or(30.0);
+            features.insert(
+                "recency".to_string(),
+                0.5f64.powf(age_days / half_life_days),
+            );
+        }
+
+        if let Some(num_value) = metadata.num_value {
+            features.insert("num_value".to_string(), num_value);
+        }
+
+        features.insert(
+            "weight".to_string(),
+            if metadata.weight == 0.0 {
+                1.0
+            } else {
+                metadata.weight
+            },
+        );
+
+        if let Some(ctr) = self
+            .model
+            .click_priors
+            .as_ref()
+            .and_then(|click_priors| click_priors.get(&metadata.id))
+        {
+            features.insert("ctr".to_string(), *ctr);
+        }
+
+        for tag in metadata.tag_set.unwrap_or_default().into_iter().flatten() {
+            features.insert(format!("tag:{}", tag), 1.0);
+        }
+
+        features
+    }
+
+    pub fn rescore(&self, chunks: Vec<ScoreChunkDTO>) -> Vec<ScoreChunkDTO> {
+        let now = chrono::Utc::now().naive_utc();
+
+        chunks
+            .into_iter()
+            .map(|mut chunk| {
+                chunk.score = self.model.score(&self.features(&chunk, now));
+                chunk
+            })
+            .collect()
+    }
+}
+
+/// Loads the rank model of the dataset when the search asks for it with `sort_options.use_rank_model`.
+pub async fn get_rank_model_rescorer(
+    data: &SearchChunksReqPayload,
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<Option<RankModelRescorer>, ServiceError> {
+    if !data
+        .sort_options
+        .as_ref()
+        .and_then(|sort_options| sort_options.use_rank_model)
+        .unwrap_or(false)
+    {
+        return Ok(None);
+    }
+
+    let rank_model = get_rank_model_query(dataset_id, pool)
+        .await?
+        .ok_or(ServiceError::BadRequest(
+            "use_rank_model was set but no rank model was uploaded for the dataset".to_string(),
+        ))?
+        .rank_model()?;
+
+    Ok(Some(RankModelRescorer::new(
+        rank_model,
+        data.search_type.clone(),
+    )))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::models::{RankScorer, RankTreeNode};
+
+    #[test]
+    fn test_linear_rank_model() {
+        let model = RankModel {
+            scorer: RankScorer::Linear {
+                weights: HashMap::from([
+                    ("semantic_score".to_string(), 2.0),
+                    ("tag:featured".to_string(), 0.5),
+                ]),
+                bias: Some(0.1),
+            },
+            recency_half_life_days: None,
+            click_priors: None,
+        };
+
+        let features = HashMap::from([
+            ("semantic_score".to_string(), 0.5),
+            ("tag:featured".to_string(), 1.0),
+            ("weight".to_string(), 3.0),
+        ]);
+
+        assert!((model.score(&features) - 1.6).abs() < 1e-9);
+        assert!(model.validate().is_ok());
+    }
+
+    #[test]
+    fn test_gradient_boosted_rank_model() {
+        let model = RankModel {
+            scorer: RankScorer::GradientBoosted {
+                trees: vec![RankTreeNode::Split {
+                    feature: "recency".to_string(),
+                    threshold: 0.5,
+                    left: Box::new(RankTreeNode::Leaf { leaf: -1.0 }),
+                    right: Box::new(RankTreeNode::Leaf { leaf: 1.0 }),
+                }],
+                base_score: Some(0.5),
+                learning_rate: Some(0.1),
+            },
+            recency_half_life_days: Some(7.0),
+            click_priors: None,
+        };
+
+        assert!((model.score(&HashMap::new()) - 0.4).abs() < 1e-9);
+        assert!((model.score(&HashMap::from([("recency".to_string(), 0.9)])) - 0.6).abs() < 1e-9);
+        assert!(model.validate().is_ok());
+    }
+
+    #[test]
+    fn test_rank_model_accepts_retrieval_score_features() {
+        let model = RankModel {
+            scorer: RankScorer::Linear {
+                weights: HashMap::from([
+                    ("splade_score".to_string(), 1.0),
+                    ("bm25_score".to_string(), 1.0),
+                ]),
+                bias: None,
+            },
+            recency_half_life_days: None,
+            click_priors: None,
+        };
+
+        assert!(model.validate().is_ok());
+    }
+
+    #[test]
+    fn test_rank_model_rejects_unknown_features() {
+        let model = RankModel {
+            scorer: RankScorer::Linear {
+                weights: HashMap::from([("popularity".to_string(), 1.0)]),
+                bias: None,
+            },
+            recency_half_life_days: None,
+            click_priors: None,
+        };
+
+        assert!(model.validate().is_err());
+    }
+}
@@ -3,8 +3,10 @@ use super::chunk_operator::{
     get_content_chunk_from_point_ids_query, get_highlights, get_highlights_with_exact_match,
     get_qdrant_ids_from_chunk_ids_query, get_slim_chunks_from_point_ids_query, HighlightStrategy,
 };
+use super::facet_operator::get_facets_query;
 use super::group_operator::{
-    get_group_ids_from_tracking_ids_query, get_groups_from_group_ids_query,
+    get_group_ids_for_chunks_query, get_group_ids_from_tracking_ids_query,
+    get_groups_from_group_ids_query,
 };
 use super::model_operator::{
     cross_encoder, get_bm25_embeddings, get_dense_vector, get_sparse_vector,
@@ -12,11 +14,15 @@ use super::model_operator::{
 use super::qdrant_operator::{
     count_qdrant_query, search_over_groups_query, GroupSearchResults, QdrantSearchQuery, VectorType,
 };
+use super::rank_model_operator::{get_rank_model_rescorer, RankModelRescorer};
+use super::synonym_operator::{expand_query_with_synonyms, get_cached_synonym_sets_query};
 use crate::data::models::{
     convert_to_date_time, ChunkGroup, ChunkGroupAndFileId, ChunkMetadata, ChunkMetadataTypes,
-    ConditionType, ContentChunkMetadata, Dataset, DatasetConfiguration, GeoInfoWithBias,
-    HasIDCondition, QdrantSortBy, QueryTypes, ReRankOptions, ScoreChunk, ScoreChunkDTO,
-    SearchMethod, SlimChunkMetadata, SortByField, SortBySearchType, SortOrder, UnifiedId,
+    CollapseOptions, ConditionType, ContentChunkMetadata, Dataset, DatasetConfiguration,
+    DecayFunction, DistanceMetric, FusionMethod, GeoInfoWithBias, HasIDCondition,
+    HybridSearchOptions, QdrantSortBy, QueryTypes, ReRankOptions, ScoreChunk, ScoreChunkDTO,
+    SearchCursor, SearchMethod, SlimChunkMetadata, SortByField, SortBySearchType, SortValue,
+    SynonymSet, UnifiedId,
 };
 use crate::handlers::chunk_handler::{
     AutocompleteReqPayload, ChunkFilter, CountChunkQueryResponseBody, CountChunksReqPayload,
@@ -27,7 +33,7 @@ use crate::handlers::group_handler::{
 };
 use crate::operators::qdrant_operator::search_qdrant_query;
 use crate::{
-    data::models::{get_range, FieldCondition, MatchCondition, Pool},
+    data::models::{get_range, FieldCondition, HighlightOptions, MatchCondition, Pool, RedisPool},
     errors::ServiceError,
 };
 use actix_web::web;
@@ -292,6 +298,7 @@ impl RetrievePointQuery {
         parsed_query: ParsedQueryTypes,
         dataset_id: uuid::Uuid,
         group_id: Option<uuid::Uuid>,
+        synonym_sets: &[SynonymSet],
         config: &DatasetConfiguration,
         pool: web::Data<Pool>,
     ) -> Result<QdrantSearchQuery, ServiceError> {
@@ -341,6 +348,7 @@ impl RetrievePointQuery {
                         let vector = get_qdrant_vector(
                             data.search_type,
                             ParsedQueryTypes::Single(parsed_query),
+                            synonym_sets,
                             config,
                         )
                         .await?;
@@ -368,6 +376,7 @@ impl RetrievePointQuery {
                         let vector = get_qdrant_vector(
                             data.search_type,
                             ParsedQueryTypes::Single(parsed_query),
+                            synonym_sets,
                             config,
                         )
                         .await?;
@@ -395,6 +404,7 @@ impl RetrievePointQuery {
                         let vector = get_qdrant_vector(
                             data.search_type,
                             ParsedQueryTypes::Single(parsed_query),
+                            synonym_sets,
                             config,
                         )
                         .await?;
@@ -1105,6 +1115,8 @@ impl From<GroupScoreChunk> for SearchOverGroupsResults {
 pub struct DeprecatedSearchOverGroupsResponseBody {
     pub group_chunks: Vec<GroupScoreChunk>,
     pub total_chunk_pages: i64,
+    /// Cursor to pass as search_after to fetch the next page. Not present when no groups remain.
+    pub search_after: Option<String>,
 }
 
 impl DeprecatedSearchOverGroupsResponseBody {
@@ -1117,6 +1129,7 @@ impl DeprecatedSearchOverGroupsResponseBody {
                 .map(|chunk| chunk.into())
                 .collect(),
             total_pages: self.total_chunk_pages,
+            search_after: self.search_after,
         }
     }
 }
@@ -1127,6 +1140,8 @@ pub struct SearchOverGroupsResponseBody {
     pub id: uuid::Uuid,
     pub results: Vec<SearchOverGroupsResults>,
     pub total_pages: i64,
+    /// Cursor to pass as search_after to fetch the next page. Not present when no groups remain.
+    pub search_after: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, ToSchema)]
@@ -1142,6 +1157,7 @@ pub enum SearchOverGroupsResponseTypes {
 pub async fn retrieve_chunks_for_groups(
     search_over_groups_query_result: SearchOverGroupsQueryResult,
     data: &SearchOverGroupsReqPayload,
+    synonym_sets: &[SynonymSet],
     pool: web::Data<Pool>,
 ) -> Result<DeprecatedSearchOverGroupsResponseBody, ServiceError> {
     let point_ids = search_over_groups_query_result
@@ -1171,6 +1187,12 @@ pub async fn retrieve_chunks_for_groups(
     )
     .await?;
 
+    let highlight_query = data
+        .query
+        .to_single_query()
+        .map(|query| expand_query_with_synonyms(&query, synonym_sets))
+        .unwrap_or_default();
+
     let group_chunks: Vec<GroupScoreChunk> = search_over_groups_query_result
         .search_results
         .iter()
@@ -1205,7 +1227,7 @@ pub async fn retrieve_chunks_for_groups(
                                 Some(HighlightStrategy::V1) => {
                                     get_highlights(
                                             chunk.clone().into(),
-                                            data.query.clone().to_single_query().expect("Should never be multi query"),
+                                            highlight_query.clone(),
                                             highlight_options.highlight_threshold,
                                             highlight_options.highlight_delimiters.clone().unwrap_or(vec![
                                                 ".".to_string(),
@@ -1224,7 +1246,7 @@ pub async fn retrieve_chunks_for_groups(
                                 _ => {
                                     get_highlights_with_exact_match(
                                             chunk.clone().into(),
-                                            data.query.clone().to_single_query().expect("Should never be multi query"),
+                                            highlight_query.clone(),
                                             highlight_options.highlight_threshold,
                                             highlight_options.highlight_delimiters.clone().unwrap_or(vec![
                                                 ".".to_string(),
@@ -1263,6 +1285,7 @@ pub async fn retrieve_chunks_for_groups(
                         metadata: vec![chunk],
                         highlights,
                         score: search_result.score.into(),
+                        inner_hits: None,
                     })
                 })
                 .sorted_by(|a, b| b.score.partial_cmp(&a.score).unwrap())
@@ -1289,6 +1312,7 @@ pub async fn retrieve_chunks_for_groups(
     Ok(DeprecatedSearchOverGroupsResponseBody {
         group_chunks,
         total_chunk_pages: search_over_groups_query_result.total_chunk_pages,
+        search_after: None,
     })
 }
 
@@ -1353,6 +1377,7 @@ pub async fn get_metadata_from_groups(
                         metadata: vec![chunk],
                         highlights: None,
                         score: search_result.score.into(),
+                        inner_hits: None,
                     })
                 })
                 .collect_vec();
@@ -1385,6 +1410,7 @@ pub async fn retrieve_chunks_from_point_ids(
     search_chunk_query_results: SearchChunkQueryResult,
     timer: Option<&mut Timer>,
     data: &SearchChunksReqPayload,
+    synonym_sets: &[SynonymSet],
     pool: web::Data<Pool>,
 ) -> Result<SearchChunkQueryResponseBody, actix_web::Error> {
     let parent_span = sentry::configure_scope(|scope| scope.get_span());
@@ -1429,6 +1455,12 @@ pub async fn retrieve_chunks_from_point_ids(
         None
     };
 
+    let highlight_query = data
+        .query
+        .to_single_query()
+        .map(|query| expand_query_with_synonyms(&query, synonym_sets))
+        .unwrap_or_default();
+
     let score_chunks: Vec<ScoreChunkDTO> = search_chunk_query_results
         .search_results
         .iter()
@@ -1462,7 +1494,7 @@ pub async fn retrieve_chunks_from_point_ids(
                                 Some(HighlightStrategy::V1) => {
                                     get_highlights(
                                             chunk.clone().into(),
-                                            data.query.clone().to_single_query().expect("Should never be multi query"),
+                                            highlight_query.clone(),
                                             highlight_options.highlight_threshold,
                                             highlight_options.highlight_delimiters.clone().unwrap_or(vec![
                                                 ".".to_string(),
@@ -1481,7 +1513,7 @@ pub async fn retrieve_chunks_from_point_ids(
                                 _ => {
                                     get_highlights_with_exact_match(
                                             chunk.clone().into(),
-                                            data.query.clone().to_single_query().expect("Should never be multi query"),
+                                            highlight_query.clone(),
                                             highlight_options.highlight_threshold,
                                             highlight_options.highlight_delimiters.clone().unwrap_or(vec![
                                                 ".".to_string(),
@@ -1519,6 +1551,7 @@ pub async fn retrieve_chunks_from_point_ids(
                 metadata: vec![chunk],
                 highlights,
                 score: search_result.score.into(),
+                inner_hits: None,
             })
         })
         .collect();
@@ -1530,19 +1563,635 @@ pub async fn retrieve_chunks_from_point_ids(
     Ok(SearchChunkQueryResponseBody {
         score_chunks,
         total_chunk_pages: search_chunk_query_results.total_chunk_pages,
+        facets: None,
+        search_after: None,
+        corrected_query: None,
+    })
+}
+
+/// Fuses several ranked result lists into a single ranking. Each list must be ordered best first and is paired
+/// with the weight applied to its contribution. Keys which appear in more than one list accumulate their scores.
+pub fn fuse_ranked_lists<K>(
+    ranked_lists: Vec<(Vec<(K, f32)>, f32)>,
+    fusion_method: &FusionMethod,
+    rrf_k: f32,
+) -> Vec<(K, f32)>
+where
+    K: Eq + std::hash::Hash + Clone,
+{
+    let mut fused_scores: Vec<(K, f32)> = Vec::new();
+    let mut fused_positions: HashMap<K, usize> = HashMap::new();
+
+    for (ranked_list, weight) in ranked_lists {
+        // Lists are ordered best first, so normalizing against the first and last scores works for
+        // both similarity and distance metrics.
+        let best_score = ranked_list.get(0).map(|(_, score)| *score).unwrap_or(0.0);
+        let worst_score = ranked_list.last().map(|(_, score)| *score).unwrap_or(0.0);
+
+        for (rank, (key, score)) in ranked_list.into_iter().enumerate() {
+            let contribution = match fusion_method {
+                FusionMethod::ReciprocalRankFusion => weight / (rrf_k + rank as f32 + 1.0),
+                _ => {
+                    let normalized_score = if best_score != worst_score {
+                        (score - worst_score) / (best_score - worst_score)
+                    } else {
+                        1.0
+                    };
+
+                    weight * normalized_score
+                }
+            };
+
+            match fused_positions.get(&key) {
+                Some(position) => fused_scores[*position].1 += contribution,
+                None => {
+                    fused_positions.insert(key.clone(), fused_scores.len());
+                    fused_scores.push((key, contribution));
+                }
+            }
+        }
+    }
+
+    fused_scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+
+    fused_scores
+}
+
+pub fn fuse_search_results(
+    semantic_results: Vec<SearchResult>,
+    fulltext_results: Vec<SearchResult>,
+    hybrid_options: &HybridSearchOptions,
+) -> Vec<SearchResult> {
+    let to_ranked_list = |results: Vec<SearchResult>| {
+        results
+            .into_iter()
+            .map(|result| (result.point_id, result.score))
+            .collect_vec()
+    };
+
+    fuse_ranked_lists(
+        vec![
+            (
+                to_ranked_list(semantic_results),
+                hybrid_options.semantic_weight.unwrap_or(1.0),
+            ),
+            (
+                to_ranked_list(fulltext_results),
+                hybrid_options.fulltext_weight.unwrap_or(1.0),
+            ),
+        ],
+        &hybrid_options.get_fusion_method(),
+        hybrid_options.rrf_k.unwrap_or(60.0),
+    )
+    .into_iter()
+    .map(|(point_id, score)| SearchResult { score, point_id })
+    .collect()
+}
+
+/// Retrieves the top `page * page_size` results of a list fused by a hybrid search. Fusion reorders the results of its lists, so the requested page can only be cut out of the fused results with [get_fused_page]. Total pages are still counted in pages of `page_size`.
+pub async fn retrieve_fused_list_query(
+    mut qdrant_search: QdrantSearchQuery,
+    page: u64,
+    page_size: u64,
+    get_total_pages: bool,
+    config: &DatasetConfiguration,
+) -> Result<SearchChunkQueryResult, ServiceError> {
+    qdrant_search.limit = page.max(1).saturating_mul(page_size);
+
+    let (search_results, count, batch_lengths) =
+        search_qdrant_query(1, vec![qdrant_search], config.clone(), get_total_pages).await?;
+
+    Ok(SearchChunkQueryResult {
+        search_results,
+        total_chunk_pages: (count as f64 / page_size as f64).ceil() as i64,
+        batch_lengths,
+    })
+}
+
+/// Retrieves one of the lists of a hybrid search. The cross encoder reranks the requested page of each list, the other fusion methods cut the page out of the fused lists.
+pub async fn retrieve_hybrid_list_query(
+    qdrant_search: QdrantSearchQuery,
+    fusion_method: &FusionMethod,
+    page: u64,
+    page_size: u64,
+    get_total_pages: bool,
+    config: &DatasetConfiguration,
+) -> Result<SearchChunkQueryResult, ServiceError> {
+    match fusion_method {
+        FusionMethod::CrossEncoder => {
+            retrieve_qdrant_points_query(vec![qdrant_search], page, get_total_pages, config).await
+        }
+        _ => {
+            retrieve_fused_list_query(qdrant_search, page, page_size, get_total_pages, config).await
+        }
+    }
+}
+
+/// Cuts the requested page out of results fused from lists retrieved with [retrieve_fused_list_query].
+pub fn get_fused_page<T>(fused_results: Vec<T>, page: u64, page_size: u64) -> Vec<T> {
+    fused_results
+        .into_iter()
+        .skip((page.max(1) - 1).saturating_mul(page_size) as usize)
+        .take(page_size as usize)
+        .collect()
+}
+
+/// Fuses the groups of both searches, each group is returned with its fused score. The hits of a group are fused the same way and cut to `group_size`.
+pub fn fuse_group_search_results(
+    semantic_results: Vec<GroupSearchResults>,
+    fulltext_results: Vec<GroupSearchResults>,
+    group_size: usize,
+    hybrid_options: &HybridSearchOptions,
+) -> Vec<(GroupSearchResults, f32)> {
+    let to_ranked_list = |results: &Vec<GroupSearchResults>| {
+        results
+            .iter()
+            .map(|group| {
+                (
+                    group.group_id,
+                    group.hits.get(0).map(|hit| hit.score).unwrap_or(0.0),
+                )
+            })
+            .collect_vec()
+    };
+
+    let fused_groups = fuse_ranked_lists(
+        vec![
+            (
+                to_ranked_list(&semantic_results),
+                hybrid_options.semantic_weight.unwrap_or(1.0),
+            ),
+            (
+                to_ranked_list(&fulltext_results),
+                hybrid_options.fulltext_weight.unwrap_or(1.0),
+            ),
+        ],
+        &hybrid_options.get_fusion_method(),
+        hybrid_options.rrf_k.unwrap_or(60.0),
+    );
+
+    let get_hits = |results: &Vec<GroupSearchResults>, group_id: uuid::Uuid| {
+        results
+            .iter()
+            .find(|group| group.group_id == group_id)
+            .map(|group| {
+                group
+                    .hits
+                    .iter()
+                    .map(|hit| (hit.point_id, hit.score))
+                    .collect_vec()
+            })
+            .unwrap_or_default()
+    };
+
+    fused_groups
+        .into_iter()
+        .map(|(group_id, score)| {
+            let hits = fuse_ranked_lists(
+                vec![
+                    (
+                        get_hits(&semantic_results, group_id),
+                        hybrid_options.semantic_weight.unwrap_or(1.0),
+                    ),
+                    (
+                        get_hits(&fulltext_results, group_id),
+                        hybrid_options.fulltext_weight.unwrap_or(1.0),
+                    ),
+                ],
+                &hybrid_options.get_fusion_method(),
+                hybrid_options.rrf_k.unwrap_or(60.0),
+            )
+            .into_iter()
+            .take(group_size)
+            .map(|(point_id, score)| SearchResult { score, point_id })
+            .collect_vec();
+
+            (GroupSearchResults { group_id, hits }, score)
+        })
+        .collect()
+}
+
+/// Qdrant can only order the candidates by a single payload field, so the first sort key picks them and every key is applied after retrieval.
+fn get_qdrant_sort_by(sort_by: &[SortByField]) -> Option<SortByField> {
+    sort_by
+        .first()
+        .filter(|sort_by| sort_by.is_payload_field() && sort_by.validate().is_ok())
+        .cloned()
+}
+
+/// Compares the sort key values of two chunks key by key, later keys only break the ties of earlier ones.
+pub fn compare_sort_values(
+    sort_by: &[SortByField],
+    a: &[Option<SortValue>],
+    b: &[Option<SortValue>],
+) -> std::cmp::Ordering {
+    sort_by
+        .iter()
+        .zip(a.iter().zip(b.iter()))
+        .map(|(sort_by, (a, b))| sort_by.compare(a, b))
+        .find(|ordering| ordering.is_ne())
+        .unwrap_or(std::cmp::Ordering::Equal)
+}
+
+/// Stable sort of the chunks by the sort keys, chunks which tie on every key keep their order. Unsupported keys are skipped.
+pub fn sort_chunks_by_fields(
+    chunks: Vec<ScoreChunkDTO>,
+    sort_by: &[SortByField],
+) -> Vec<ScoreChunkDTO> {
+    let sort_by = sort_by
+        .iter()
+        .filter(|sort_by| match sort_by.validate() {
+            Ok(()) => true,
+            Err(err) => {
+                log::error!("Invalid sort_by field: {}", err);
+                sentry::capture_message(
+                    &format!("Invalid sort_by field: {}", err),
+                    sentry::Level::Error,
+                );
+                false
+            }
+        })
+        .cloned()
+        .collect::<Vec<SortByField>>();
+
+    chunks
+        .into_iter()
+        .map(|chunk| {
+            let values = match chunk.metadata.get(0).map(|metadata| metadata.metadata()) {
+                Some(metadata) => sort_by
+                    .iter()
+                    .map(|sort_by| sort_by.sort_value(&metadata, Some(chunk.score)))
+                    .collect(),
+                None => vec![None; sort_by.len()],
+            };
+            (values, chunk)
+        })
+        .sorted_by(|(a, _), (b, _)| compare_sort_values(&sort_by, a, b))
+        .map(|(_, chunk)| chunk)
+        .collect()
+}
+
+/// Collapsed searches retrieve all of their candidates as the first page and cut the requested page out of the collapsed results. Searches continuing from a cursor retrieve the page at the cursor's offset, results ranked at or before the cursor's score and id are dropped from it by [paginate_after_cursor].
+fn get_page_and_limit(
+    data: &SearchChunksReqPayload,
+    search_cursor: Option<&SearchCursor>,
+    config: &DatasetConfiguration,
+) -> Result<(u64, u64), ServiceError> {
+    let page = data.page.unwrap_or(1);
+    let page_size = data.page_size.unwrap_or(10);
+
+    match (data.collapse.as_ref(), search_cursor) {
+        (Some(collapse), _) => {
+            collapse.validate()?;
+            Ok((
+                1,
+                collapse.get_candidate_limit(page, page_size, config.MAX_LIMIT),
+            ))
+        }
+        (None, Some(search_cursor)) => {
+            Ok((get_search_cursor_page(search_cursor, page_size), page_size))
+        }
+        (None, None) => Ok((page, page_size)),
+    }
+}
+
+/// Page starting at the cursor's offset. Offsets of cursors issued for another page_size are rounded down to a page start, the results already returned are dropped by [paginate_after_cursor].
+fn get_search_cursor_page(search_cursor: &SearchCursor, page_size: u64) -> u64 {
+    search_cursor.offset / page_size.max(1) + 1
+}
+
+/// Semantic scores of the euclidean and manhattan distance metrics are distances, so their best results have the lowest scores. Every other score ranks the best results first.
+fn scores_descending(search_type: &SearchMethod, config: &DatasetConfiguration) -> bool {
+    !(*search_type == SearchMethod::Semantic
+        && matches!(
+            config.DISTANCE_METRIC,
+            DistanceMetric::Euclidean | DistanceMetric::Manhattan
+        ))
+}
+
+/// Cursors order the results by score, so they can not be combined with sorting by fields or collapse.
+fn get_search_cursor(
+    data: &SearchChunksReqPayload,
+    sort_by: &[SortByField],
+) -> Result<Option<SearchCursor>, ServiceError> {
+    let search_cursor = data
+        .search_after
+        .as_deref()
+        .map(SearchCursor::decode)
+        .transpose()?;
+
+    if search_cursor.is_some() && (data.collapse.is_some() || !sort_by.is_empty()) {
+        return Err(ServiceError::BadRequest(
+            "search_after can not be combined with sort_by fields or collapse".to_string(),
+        ));
+    }
+
+    Ok(search_cursor)
+}
+
+/// Cuts the page following the cursor out of the results retrieved at its offset, ranked by descending or ascending score, and returns it with the cursor of its last result. Results with equal scores are ordered by id. Results ranked at or before the cursor were already returned and are dropped, which happens when results are added in front of the cursor. No cursor is returned when fewer results than page_size were retrieved as no results remain after them.
+pub fn paginate_after_cursor<T>(
+    results: Vec<T>,
+    search_cursor: Option<&SearchCursor>,
+    page_size: u64,
+    descending: bool,
+    get_key: impl Fn(&T) -> (f64, uuid::Uuid),
+) -> (Vec<T>, Option<SearchCursor>) {
+    let mut results = results
+        .into_iter()
+        .map(|result| (get_key(&result), result))
+        .collect_vec();
+
+    results.sort_by(|((a_score, a_id), _), ((b_score, b_id), _)| {
+        let score_ordering = if descending {
+            b_score.partial_cmp(a_score)
+        } else {
+            a_score.partial_cmp(b_score)
+        };
+
+        score_ordering
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a_id.cmp(b_id))
+    });
+
+    let retrieved = results.len() as u64;
+    let start = match search_cursor {
+        Some(search_cursor) => results
+            .iter()
+            .position(|((score, id), _)| search_cursor.precedes(*score, *id, descending))
+            .unwrap_or(results.len()),
+        None => 0,
+    };
+
+    let page = results
+        .into_iter()
+        .skip(start)
+        .take(page_size as usize)
+        .collect_vec();
+
+    let offset = search_cursor
+        .map(|search_cursor| {
+            (get_search_cursor_page(search_cursor, page_size) - 1).saturating_mul(page_size)
+        })
+        .unwrap_or(0);
+
+    let next_search_cursor = match page.last() {
+        Some(((score, id), _)) if retrieved >= page_size => Some(SearchCursor {
+            score: *score,
+            id: *id,
+            offset: offset + page_size,
+        }),
+        _ => None,
+    };
+
+    (
+        page.into_iter().map(|(_, result)| result).collect(),
+        next_search_cursor,
+    )
+}
+
+/// Chunk searches ordered by score are paginated by cursor when they start at the first page or continue from a cursor, other searches are returned as they are.
+fn paginate_chunks_after_cursor(
+    chunks: Vec<ScoreChunkDTO>,
+    data: &SearchChunksReqPayload,
+    search_cursor: Option<&SearchCursor>,
+    sort_by: &[SortByField],
+    descending: bool,
+) -> (Vec<ScoreChunkDTO>, Option<String>) {
+    if data.collapse.is_some()
+        || !sort_by.is_empty()
+        || (search_cursor.is_none() && data.page.unwrap_or(1) > 1)
+    {
+        return (chunks, None);
+    }
+
+    let (chunks, next_search_cursor) = paginate_after_cursor(
+        chunks,
+        search_cursor,
+        data.page_size.unwrap_or(10),
+        descending,
+        |chunk| {
+            (
+                chunk.score,
+                chunk
+                    .metadata
+                    .get(0)
+                    .map(|metadata| metadata.metadata().qdrant_point_id)
+                    .unwrap_or_default(),
+            )
+        },
+    );
+
+    (
+        chunks,
+        next_search_cursor.map(|search_cursor| search_cursor.encode()),
+    )
+}
+
+/// Group searches are paginated by cursor when they start at the first page or continue from a cursor, using the score each group was ranked by.
+fn paginate_groups_after_cursor(
+    groups: Vec<GroupScoreChunk>,
+    data: &SearchOverGroupsReqPayload,
+    search_cursor: Option<&SearchCursor>,
+    group_scores: &HashMap<uuid::Uuid, f64>,
+    descending: bool,
+) -> (Vec<GroupScoreChunk>, Option<String>) {
+    if search_cursor.is_none() && data.page.unwrap_or(1) > 1 {
+        return (groups, None);
+    }
+
+    let (groups, next_search_cursor) = paginate_after_cursor(
+        groups,
+        search_cursor,
+        data.page_size.unwrap_or(10),
+        descending,
+        |group| {
+            (
+                group_scores
+                    .get(&group.group_id)
+                    .copied()
+                    .unwrap_or_default(),
+                group.group_id,
+            )
+        },
+    );
+
+    (
+        groups,
+        next_search_cursor.map(|search_cursor| search_cursor.encode()),
+    )
+}
+
+/// Groups are ranked by the score of their best hit.
+fn get_group_scores(groups: &[GroupSearchResults]) -> HashMap<uuid::Uuid, f64> {
+    groups
+        .iter()
+        .map(|group| {
+            (
+                group.group_id,
+                group.hits.get(0).map(|hit| hit.score as f64).unwrap_or(0.0),
+            )
+        })
+        .collect()
+}
+
+/// Groups continuing from a cursor are retrieved from the page at the cursor's offset.
+fn get_group_search_cursor_page_and_limit(
+    data: &SearchOverGroupsReqPayload,
+) -> Result<(Option<SearchCursor>, u64, u64), ServiceError> {
+    let page_size = data.page_size.unwrap_or(10);
+    let search_cursor = data
+        .search_after
+        .as_deref()
+        .map(SearchCursor::decode)
+        .transpose()?;
+
+    Ok(match search_cursor {
+        Some(search_cursor) => (
+            Some(search_cursor),
+            get_search_cursor_page(&search_cursor, page_size),
+            page_size,
+        ),
+        None => (None, data.page.unwrap_or(1), page_size),
     })
 }
 
+/// Keeps the first chunk for each collapse key and counts the later chunks sharing any of its keys as its inner hits. The chunks have to be ordered best first. Chunks without keys are always kept.
+pub fn collapse_chunks(chunks: Vec<ScoreChunkDTO>, keys: Vec<Vec<String>>) -> Vec<ScoreChunkDTO> {
+    let mut collapsed_chunks: Vec<ScoreChunkDTO> = vec![];
+    let mut collapsed_chunk_for_key: HashMap<String, usize> = HashMap::new();
+
+    for (mut chunk, keys) in chunks.into_iter().zip(keys) {
+        match keys
+            .iter()
+            .find_map(|key| collapsed_chunk_for_key.get(key).copied())
+        {
+            Some(index) => {
+                let inner_hits = collapsed_chunks[index].inner_hits.get_or_insert(1);
+                *inner_hits += 1;
+            }
+            None => {
+                for key in keys {
+                    collapsed_chunk_for_key.insert(key, collapsed_chunks.len());
+                }
+                chunk.inner_hits = Some(1);
+                collapsed_chunks.push(chunk);
+            }
+        }
+    }
+
+    collapsed_chunks
+}
+
+/// Collapses the reranked chunks of a search and returns the requested page of them along with the total number of pages.
+#[tracing::instrument(skip(pool))]
+pub async fn collapse_chunks_query(
+    chunks: Vec<ScoreChunkDTO>,
+    collapse: &CollapseOptions,
+    page: u64,
+    page_size: u64,
+    pool: web::Data<Pool>,
+) -> Result<(Vec<ScoreChunkDTO>, i64), ServiceError> {
+    let metadatas = chunks
+        .iter()
+        .map(|chunk| chunk.metadata.get(0).map(|metadata| metadata.metadata()))
+        .collect_vec();
+
+    let group_ids_for_chunks = if collapse.field == "group_ids" {
+        get_group_ids_for_chunks_query(
+            metadatas
+                .iter()
+                .flatten()
+                .map(|metadata| metadata.id)
+                .collect(),
+            pool,
+        )
+        .await?
+    } else {
+        HashMap::new()
+    };
+
+    let keys = metadatas
+        .iter()
+        .map(|metadata| match metadata {
+            Some(metadata) => collapse.keys(
+                metadata,
+                group_ids_for_chunks
+                    .get(&metadata.id)
+                    .map(|group_ids| group_ids.as_slice())
+                    .unwrap_or_default(),
+            ),
+            None => vec![],
+        })
+        .collect();
+
+    let collapsed_chunks = collapse_chunks(chunks, keys);
+    let total_chunk_pages = (collapsed_chunks.len() as f64 / page_size.max(1) as f64).ceil() as i64;
+
+    Ok((
+        collapsed_chunks
+            .into_iter()
+            .skip((page.saturating_sub(1) * page_size) as usize)
+            .take(page_size as usize)
+            .collect(),
+        total_chunk_pages,
+    ))
+}
+
+/// Synonym sets only expand the text of sparse query vectors and highlights, so they are not loaded for searches which use neither.
+async fn get_search_synonym_sets(
+    dataset_id: uuid::Uuid,
+    search_type: &SearchMethod,
+    rerank_by: Option<&SortBySearchType>,
+    highlight_results: bool,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<Vec<SynonymSet>, ServiceError> {
+    let sparse_vector_used = *search_type != SearchMethod::Semantic
+        || rerank_by.is_some_and(|rerank_by| {
+            matches!(
+                rerank_by.rerank_type,
+                ReRankOptions::Fulltext | ReRankOptions::BM25
+            )
+        });
+
+    if !sparse_vector_used && !highlight_results {
+        return Ok(vec![]);
+    }
+
+    get_cached_synonym_sets_query(dataset_id, pool, redis_pool).await
+}
+
+fn highlights_requested(
+    highlight_options: &Option<HighlightOptions>,
+    slim_chunks: Option<bool>,
+    query: &QueryTypes,
+) -> bool {
+    highlight_options
+        .as_ref()
+        .is_some_and(|highlight_options| highlight_options.highlight_results.unwrap_or(true))
+        && !slim_chunks.unwrap_or(false)
+        && !matches!(query, QueryTypes::Multi(_))
+}
+
 #[tracing::instrument]
 pub fn rerank_chunks(
     chunks: Vec<ScoreChunkDTO>,
-    sort_by: Option<SortByField>,
+    sort_by: Vec<SortByField>,
     tag_weights: Option<HashMap<String, f32>>,
     use_weights: Option<bool>,
     query_location: Option<GeoInfoWithBias>,
+    decay_functions: Option<Vec<DecayFunction>>,
+    rank_model_rescorer: Option<&RankModelRescorer>,
 ) -> Vec<ScoreChunkDTO> {
+    let chunks = match rank_model_rescorer {
+        Some(rank_model_rescorer) => rank_model_rescorer.rescore(chunks),
+        None => chunks,
+    };
+
     let mut reranked_chunks = Vec::new();
-    if use_weights.unwrap_or(true) {
+    // A rank model already scores the weight as one of its features
+    if use_weights.unwrap_or(true) && rank_model_rescorer.is_none() {
         chunks.into_iter().for_each(|mut chunk| {
             if chunk.metadata[0].metadata().weight == 0.0 {
                 chunk.score *= 1.0;
@@ -1612,60 +2261,28 @@ pub fn rerank_chunks(
             })
             .collect::<Vec<ScoreChunkDTO>>();
     }
+
+    if let Some(decay_functions) = decay_functions {
+        let now = chrono::Utc::now().naive_utc();
+
+        reranked_chunks.iter_mut().for_each(|chunk| {
+            if let Some(metadata) = chunk.metadata.get(0).map(|metadata| metadata.metadata()) {
+                for decay_function in decay_functions.iter() {
+                    chunk.score *= decay_function
+                        .score_multiplier(&metadata, now)
+                        .unwrap_or(1.0);
+                }
+            }
+        });
+    }
     reranked_chunks.sort_by(|a, b| {
         b.score
             .partial_cmp(&a.score)
             .unwrap_or(std::cmp::Ordering::Equal)
     });
 
-    if let Some(sort_by) = sort_by {
-        match sort_by.field.as_str() {
-            "time_stamp" => {
-                if sort_by.direction.is_some_and(|dir| dir == SortOrder::Asc) {
-                    reranked_chunks.sort_by(|a, b| {
-                        a.metadata[0]
-                            .metadata()
-                            .time_stamp
-                            .partial_cmp(&b.metadata[0].metadata().time_stamp)
-                            .unwrap_or(std::cmp::Ordering::Equal)
-                    });
-                } else {
-                    reranked_chunks.sort_by(|a, b| {
-                        b.metadata[0]
-                            .metadata()
-                            .time_stamp
-                            .partial_cmp(&a.metadata[0].metadata().time_stamp)
-                            .unwrap_or(std::cmp::Ordering::Equal)
-                    });
-                }
-            }
-            "num_value" => {
-                if sort_by.direction.is_some_and(|dir| dir == SortOrder::Asc) {
-                    reranked_chunks.sort_by(|a, b| {
-                        a.metadata[0]
-                            .metadata()
-                            .num_value
-                            .partial_cmp(&b.metadata[0].metadata().num_value)
-                            .unwrap_or(std::cmp::Ordering::Equal)
-                    });
-                } else {
-                    reranked_chunks.sort_by(|a, b| {
-                        b.metadata[0]
-                            .metadata()
-                            .num_value
-                            .partial_cmp(&a.metadata[0].metadata().num_value)
-                            .unwrap_or(std::cmp::Ordering::Equal)
-                    });
-                }
-            }
-            _ => {
-                log::error!("Unsupported sort_by field: {}", sort_by.field);
-                sentry::capture_message(
-                    &format!("Unsupported sort_by field: {}", sort_by.field),
-                    sentry::Level::Error,
-                );
-            }
-        }
+    if !sort_by.is_empty() {
+        reranked_chunks = sort_chunks_by_fields(reranked_chunks, &sort_by);
     }
 
     reranked_chunks
@@ -1674,6 +2291,7 @@ pub fn rerank_chunks(
 async fn get_qdrant_vector(
     search_type: SearchMethod,
     parsed_query: ParsedQueryTypes,
+    synonym_sets: &[SynonymSet],
     config: &DatasetConfiguration,
 ) -> Result<VectorType, ServiceError> {
     match search_type {
@@ -1739,10 +2357,11 @@ async fn get_qdrant_vector(
             }
             let sparse_vectors = match parsed_query {
                 ParsedQueryTypes::Single(query) => get_bm25_embeddings(
-                    vec![(query.query.clone(), None)],
+                    vec![(expand_query_with_synonyms(&query.query, synonym_sets), None)],
                     config.BM25_AVG_LEN,
                     config.BM25_B,
                     config.BM25_K,
+                    &config.bm25_analyzer_options(),
                 ),
                 ParsedQueryTypes::Multi(_) => {
                     return Err(ServiceError::BadRequest(
@@ -1763,7 +2382,11 @@ async fn get_qdrant_vector(
 
             let sparse_vector = match parsed_query {
                 ParsedQueryTypes::Single(query) => {
-                    get_sparse_vector(query.query.clone(), "query").await?
+                    get_sparse_vector(
+                        expand_query_with_synonyms(&query.query, synonym_sets),
+                        "query",
+                    )
+                    .await?
                 }
                 ParsedQueryTypes::Multi(_) => {
                     return Err(ServiceError::BadRequest(
@@ -1780,11 +2403,12 @@ async fn get_qdrant_vector(
     }
 }
 
-#[tracing::instrument(skip(timer, pool))]
+#[tracing::instrument(skip(timer, pool, redis_pool))]
 pub async fn search_chunks_query(
     data: SearchChunksReqPayload,
     parsed_query: ParsedQueryTypes,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     dataset: Dataset,
     config: &DatasetConfiguration,
     timer: &mut Timer,
@@ -1801,19 +2425,39 @@ pub async fn search_chunks_query(
     };
     sentry::configure_scope(|scope| scope.set_span(Some(transaction.clone())));
 
+    let rank_model_rescorer = get_rank_model_rescorer(&data, dataset.id, pool.clone()).await?;
+
+    let (sort_by, rerank_by) = data
+        .sort_options
+        .as_ref()
+        .and_then(|d| d.sort_by.clone())
+        .map(QdrantSortBy::into_parts)
+        .unwrap_or_default();
+
+    let synonym_sets = get_search_synonym_sets(
+        dataset.id,
+        &data.search_type,
+        rerank_by.as_ref(),
+        highlights_requested(&data.highlight_options, data.slim_chunks, &data.query),
+        pool.clone(),
+        redis_pool,
+    )
+    .await?;
+
+    let search_cursor = get_search_cursor(&data, &sort_by)?;
+    let (page, limit) = get_page_and_limit(&data, search_cursor.as_ref(), config)?;
+
     timer.add("start to create dense embedding vector");
 
     timer.add("computed dense embedding");
 
-    let vector = get_qdrant_vector(data.clone().search_type, parsed_query.clone(), config).await?;
-
-    let (sort_by, rerank_by) = match data.sort_options.as_ref().map(|d| d.sort_by.clone()) {
-        Some(Some(sort_by)) => match sort_by {
-            QdrantSortBy::Field(field) => (Some(field.clone()), None),
-            QdrantSortBy::SearchType(search_type) => (None, Some(search_type)),
-        },
-        _ => (None, None),
-    };
+    let vector = get_qdrant_vector(
+        data.clone().search_type,
+        parsed_query.clone(),
+        &synonym_sets,
+        config,
+    )
+    .await?;
 
     let qdrant_query = RetrievePointQuery {
         vector,
@@ -1824,18 +2468,32 @@ pub async fn search_chunks_query(
         } else {
             data.score_threshold
         },
-        limit: data.page_size.unwrap_or(10),
-        sort_by: sort_by.clone(),
+        limit,
+        sort_by: get_qdrant_sort_by(&sort_by),
         rerank_by: rerank_by.clone(),
         filter: data.filters.clone(),
     }
-    .into_qdrant_query(parsed_query, dataset.id, None, config, pool.clone())
+    .into_qdrant_query(
+        parsed_query,
+        dataset.id,
+        None,
+        &synonym_sets,
+        config,
+        pool.clone(),
+    )
+    .await?;
+
+    let facets = get_facets_query(
+        data.facets.clone(),
+        qdrant_query.filter.clone(),
+        config.clone(),
+    )
     .await?;
 
     let search_chunk_query_results = retrieve_qdrant_points_query(
         vec![qdrant_query],
-        data.page.unwrap_or(1),
-        data.get_total_pages.unwrap_or(false),
+        page,
+        data.get_total_pages.unwrap_or(false) && data.collapse.is_none() && search_cursor.is_none(),
         config,
     )
     .await?;
@@ -1846,16 +2504,21 @@ pub async fn search_chunks_query(
         search_chunk_query_results,
         Some(timer),
         &data,
+        &synonym_sets,
         pool.clone(),
     )
     .await?;
 
+    // Cross encoder scores rank the best chunks first regardless of the distance metric
+    let cross_encoded =
+        rerank_by.clone().map(|r| r.rerank_type) == Some(ReRankOptions::CrossEncoder);
+
     let rerank_chunks_input = if let Some(rerank_by) = rerank_by {
         match rerank_by.rerank_type {
             ReRankOptions::CrossEncoder => {
                 let mut cross_encoder_results = cross_encoder(
                     data.query.clone().to_single_query()?,
-                    data.page_size.unwrap_or(10),
+                    limit,
                     result_chunks.score_chunks,
                     config,
                 )
@@ -1875,7 +2538,7 @@ pub async fn search_chunks_query(
 
     result_chunks.score_chunks = rerank_chunks(
         rerank_chunks_input,
-        sort_by,
+        sort_by.clone(),
         data.sort_options
             .as_ref()
             .map(|d| d.tag_weights.clone())
@@ -1888,8 +2551,34 @@ pub async fn search_chunks_query(
             .as_ref()
             .map(|d| d.location_bias)
             .unwrap_or_default(),
+        data.sort_options
+            .as_ref()
+            .map(|d| d.decay_functions.clone())
+            .unwrap_or_default(),
+        rank_model_rescorer.as_ref(),
+    );
+
+    if let Some(collapse) = data.collapse.as_ref() {
+        (result_chunks.score_chunks, result_chunks.total_chunk_pages) = collapse_chunks_query(
+            result_chunks.score_chunks,
+            collapse,
+            data.page.unwrap_or(1),
+            data.page_size.unwrap_or(10),
+            pool.clone(),
+        )
+        .await?;
+    }
+
+    (result_chunks.score_chunks, result_chunks.search_after) = paginate_chunks_after_cursor(
+        result_chunks.score_chunks,
+        &data,
+        search_cursor.as_ref(),
+        &sort_by,
+        cross_encoded || scores_descending(&data.search_type, config),
     );
 
+    result_chunks.facets = facets;
+
     timer.add("reranking");
     transaction.finish();
 
@@ -1897,11 +2586,12 @@ pub async fn search_chunks_query(
 }
 
 #[allow(clippy::too_many_arguments)]
-#[tracing::instrument(skip(timer, pool))]
+#[tracing::instrument(skip(timer, pool, redis_pool))]
 pub async fn search_hybrid_chunks(
     data: SearchChunksReqPayload,
     parsed_query: ParsedQuery,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     dataset: Dataset,
     config: &DatasetConfiguration,
     timer: &mut Timer,
@@ -1920,6 +2610,18 @@ pub async fn search_hybrid_chunks(
 
     let dataset_config = DatasetConfiguration::from_json(dataset.server_configuration.clone());
 
+    let mut rank_model_rescorer = get_rank_model_rescorer(&data, dataset.id, pool.clone()).await?;
+
+    let synonym_sets = get_search_synonym_sets(
+        dataset.id,
+        &SearchMethod::Hybrid,
+        None,
+        false,
+        pool.clone(),
+        redis_pool,
+    )
+    .await?;
+
     let dense_vector_future = get_dense_vector(
         data.query.clone().to_single_query()?,
         None,
@@ -1927,68 +2629,204 @@ pub async fn search_hybrid_chunks(
         dataset_config.clone(),
     );
 
-    let sparse_vector_future = get_sparse_vector(parsed_query.query.clone(), "query");
+    let sparse_vector_future = get_sparse_vector(
+        expand_query_with_synonyms(&parsed_query.query, &synonym_sets),
+        "query",
+    );
 
     let (dense_vector, sparse_vector) =
         futures::try_join!(dense_vector_future, sparse_vector_future)?;
 
     timer.add("computed sparse and dense embeddings");
 
-    let (sort_by, rerank_by) = match data.sort_options.as_ref().map(|d| d.sort_by.clone()) {
-        Some(Some(sort_by)) => match sort_by {
-            QdrantSortBy::Field(field) => (Some(field.clone()), None),
-            QdrantSortBy::SearchType(search_type) => (None, Some(search_type)),
-        },
-        _ => (None, None),
+    let (sort_by, rerank_by) = data
+        .sort_options
+        .as_ref()
+        .and_then(|d| d.sort_by.clone())
+        .map(QdrantSortBy::into_parts)
+        .unwrap_or_default();
+
+    let search_cursor = get_search_cursor(&data, &sort_by)?;
+    let (page, limit) = get_page_and_limit(&data, search_cursor.as_ref(), config)?;
+
+    let hybrid_options = data.hybrid_options.clone().unwrap_or_default();
+    let fusion_method = hybrid_options.get_fusion_method();
+
+    // Fused scores are ranks or normalized scores, so the threshold cuts each list before fusion
+    let list_score_threshold = match fusion_method {
+        FusionMethod::CrossEncoder => None,
+        _ => data.score_threshold,
     };
 
-    let qdrant_queries = vec![
-        RetrievePointQuery {
-            vector: VectorType::Dense(dense_vector),
-            score_threshold: None,
-            sort_by: sort_by.clone(),
-            rerank_by: rerank_by.clone(),
-            limit: data.page_size.unwrap_or(10),
-            filter: data.filters.clone(),
-        }
-        .into_qdrant_query(
-            ParsedQueryTypes::Single(parsed_query.clone()),
-            dataset.id,
-            None,
-            config,
-            pool.clone(),
-        )
-        .await?,
-        RetrievePointQuery {
-            vector: VectorType::SpladeSparse(sparse_vector),
-            score_threshold: None,
-            sort_by: sort_by.clone(),
-            rerank_by: rerank_by.clone(),
-            limit: data.page_size.unwrap_or(10),
-            filter: data.filters.clone(),
-        }
-        .into_qdrant_query(
-            ParsedQueryTypes::Single(parsed_query.clone()),
-            dataset.id,
-            None,
-            config,
-            pool.clone(),
-        )
-        .await?,
-    ];
+    let semantic_query = RetrievePointQuery {
+        vector: VectorType::Dense(dense_vector),
+        score_threshold: list_score_threshold,
+        sort_by: get_qdrant_sort_by(&sort_by),
+        rerank_by: rerank_by.clone(),
+        limit,
+        filter: data.filters.clone(),
+    }
+    .into_qdrant_query(
+        ParsedQueryTypes::Single(parsed_query.clone()),
+        dataset.id,
+        None,
+        &synonym_sets,
+        config,
+        pool.clone(),
+    )
+    .await?;
 
-    let search_chunk_query_results = retrieve_qdrant_points_query(
-        qdrant_queries,
-        data.page.unwrap_or(1),
-        data.get_total_pages.unwrap_or(false),
+    let fulltext_query = RetrievePointQuery {
+        vector: VectorType::SpladeSparse(sparse_vector),
+        score_threshold: list_score_threshold,
+        sort_by: get_qdrant_sort_by(&sort_by),
+        rerank_by: rerank_by.clone(),
+        limit,
+        filter: data.filters.clone(),
+    }
+    .into_qdrant_query(
+        ParsedQueryTypes::Single(parsed_query.clone()),
+        dataset.id,
+        None,
+        &synonym_sets,
         config,
+        pool.clone(),
+    )
+    .await?;
+
+    let facets = get_facets_query(
+        data.facets.clone(),
+        semantic_query.filter.clone(),
+        config.clone(),
     )
     .await?;
 
+    // Only retrieved to give the rank model a bm25_score feature, the hits are not fused into the results
+    let bm25_query = if rank_model_rescorer.is_some()
+        && dataset_config.BM25_ENABLED
+        && std::env::var("BM25_ACTIVE").unwrap_or("false".to_string()) == "true"
+    {
+        Some(
+            RetrievePointQuery {
+                vector: get_qdrant_vector(
+                    SearchMethod::BM25,
+                    ParsedQueryTypes::Single(parsed_query.clone()),
+                    &synonym_sets,
+                    config,
+                )
+                .await?,
+                score_threshold: None,
+                sort_by: get_qdrant_sort_by(&sort_by),
+                rerank_by: rerank_by.clone(),
+                limit,
+                filter: data.filters.clone(),
+            }
+            .into_qdrant_query(
+                ParsedQueryTypes::Single(parsed_query.clone()),
+                dataset.id,
+                None,
+                &synonym_sets,
+                config,
+                pool.clone(),
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+
+    let get_total_pages =
+        data.get_total_pages.unwrap_or(false) && data.collapse.is_none() && search_cursor.is_none();
+
+    let semantic_future = retrieve_hybrid_list_query(
+        semantic_query,
+        &fusion_method,
+        page,
+        limit,
+        get_total_pages,
+        config,
+    );
+    let fulltext_future = retrieve_hybrid_list_query(
+        fulltext_query,
+        &fusion_method,
+        page,
+        limit,
+        get_total_pages,
+        config,
+    );
+
+    let bm25_future = async {
+        match bm25_query {
+            Some(bm25_query) => {
+                retrieve_hybrid_list_query(bm25_query, &fusion_method, page, limit, false, config)
+                    .await
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    };
+
+    let (semantic_results, fulltext_results, bm25_results) =
+        futures::try_join!(semantic_future, fulltext_future, bm25_future)?;
+
+    if let Some(rank_model_rescorer) = rank_model_rescorer.as_mut() {
+        let to_score_map = |results: &Vec<SearchResult>| {
+            results
+                .iter()
+                .map(|result| (result.point_id, result.score))
+                .collect::<HashMap<uuid::Uuid, f32>>()
+        };
+        rank_model_rescorer.semantic_scores = to_score_map(&semantic_results.search_results);
+        rank_model_rescorer.splade_scores = to_score_map(&fulltext_results.search_results);
+        if let Some(bm25_results) = bm25_results.as_ref() {
+            rank_model_rescorer.bm25_scores = to_score_map(&bm25_results.search_results);
+        }
+    }
+
+    let total_chunk_pages = semantic_results
+        .total_chunk_pages
+        .max(fulltext_results.total_chunk_pages);
+
+    let search_chunk_query_results = match fusion_method {
+        FusionMethod::CrossEncoder => SearchChunkQueryResult {
+            batch_lengths: vec![
+                semantic_results.search_results.len(),
+                fulltext_results.search_results.len(),
+            ],
+            search_results: semantic_results
+                .search_results
+                .into_iter()
+                .chain(fulltext_results.search_results)
+                .unique_by(|result| result.point_id)
+                .collect(),
+            total_chunk_pages,
+        },
+        _ => {
+            let search_results = get_fused_page(
+                fuse_search_results(
+                    semantic_results.search_results,
+                    fulltext_results.search_results,
+                    &hybrid_options,
+                ),
+                page,
+                limit,
+            );
+
+            SearchChunkQueryResult {
+                batch_lengths: vec![search_results.len()],
+                search_results,
+                total_chunk_pages,
+            }
+        }
+    };
+
+    timer.add("fetched from qdrant");
+
     let result_chunks = retrieve_chunks_from_point_ids(
         search_chunk_query_results,
         Some(timer),
         &data,
+        &synonym_sets,
         pool.clone(),
     )
     .await?;
@@ -1997,21 +2835,28 @@ pub async fn search_hybrid_chunks(
 
     let mut reranked_chunks = {
         let mut reranked_chunks = {
-            let mut cross_encoder_results = cross_encoder(
-                data.query.clone().to_single_query()?,
-                data.page_size.unwrap_or(10),
-                result_chunks.score_chunks,
-                config,
-            )
-            .await?;
+            let fused_results = match fusion_method {
+                FusionMethod::CrossEncoder => {
+                    let mut cross_encoder_results = cross_encoder(
+                        data.query.clone().to_single_query()?,
+                        limit,
+                        result_chunks.score_chunks,
+                        config,
+                    )
+                    .await?;
+
+                    if let Some(score_threshold) = data.score_threshold {
+                        cross_encoder_results.retain(|chunk| chunk.score >= score_threshold.into());
+                    }
 
-            if let Some(score_threshold) = data.score_threshold {
-                cross_encoder_results.retain(|chunk| chunk.score >= score_threshold.into());
-            }
+                    cross_encoder_results
+                }
+                _ => result_chunks.score_chunks,
+            };
 
             rerank_chunks(
-                cross_encoder_results,
-                sort_by,
+                fused_results,
+                sort_by.clone(),
                 data.sort_options
                     .as_ref()
                     .map(|d| d.tag_weights.clone())
@@ -2024,16 +2869,49 @@ pub async fn search_hybrid_chunks(
                     .as_ref()
                     .map(|d| d.location_bias)
                     .unwrap_or_default(),
+                data.sort_options
+                    .as_ref()
+                    .map(|d| d.decay_functions.clone())
+                    .unwrap_or_default(),
+                rank_model_rescorer.as_ref(),
             )
         };
 
-        reranked_chunks.truncate(data.page_size.unwrap_or(10) as usize);
+        let mut search_after = None;
+        let total_chunk_pages = match data.collapse.as_ref() {
+            Some(collapse) => {
+                let total_chunk_pages;
+                (reranked_chunks, total_chunk_pages) = collapse_chunks_query(
+                    reranked_chunks,
+                    collapse,
+                    data.page.unwrap_or(1),
+                    data.page_size.unwrap_or(10),
+                    pool.clone(),
+                )
+                .await?;
+                total_chunk_pages
+            }
+            None => {
+                (reranked_chunks, search_after) = paginate_chunks_after_cursor(
+                    reranked_chunks,
+                    &data,
+                    search_cursor.as_ref(),
+                    &sort_by,
+                    true,
+                );
+                reranked_chunks.truncate(data.page_size.unwrap_or(10) as usize);
+                result_chunks.total_chunk_pages
+            }
+        };
 
         timer.add("reranking");
 
         SearchChunkQueryResponseBody {
             score_chunks: reranked_chunks,
-            total_chunk_pages: result_chunks.total_chunk_pages,
+            total_chunk_pages,
+            facets,
+            search_after,
+            corrected_query: None,
         }
     };
 
@@ -2057,6 +2935,7 @@ pub async fn search_hybrid_chunks(
                     .collect(),
                 highlights: score_chunk.highlights,
                 score: score_chunk.score,
+                inner_hits: score_chunk.inner_hits,
             })
             .collect();
     }
@@ -2066,24 +2945,43 @@ pub async fn search_hybrid_chunks(
 }
 
 #[allow(clippy::too_many_arguments)]
-#[tracing::instrument(skip(pool))]
+#[tracing::instrument(skip(pool, redis_pool))]
 pub async fn search_groups_query(
     data: SearchWithinGroupReqPayload,
     parsed_query: ParsedQueryTypes,
     group: ChunkGroupAndFileId,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     dataset: Dataset,
     config: &DatasetConfiguration,
 ) -> Result<SearchWithinGroupResults, actix_web::Error> {
-    let vector = get_qdrant_vector(data.clone().search_type, parsed_query.clone(), config).await?;
+    let rank_model_rescorer =
+        get_rank_model_rescorer(&data.clone().into(), dataset.id, pool.clone()).await?;
 
-    let (sort_by, rerank_by) = match data.sort_options.as_ref().map(|d| d.sort_by.clone()) {
-        Some(Some(sort_by)) => match sort_by {
-            QdrantSortBy::Field(field) => (Some(field.clone()), None),
-            QdrantSortBy::SearchType(search_type) => (None, Some(search_type)),
-        },
-        _ => (None, None),
-    };
+    let (sort_by, rerank_by) = data
+        .sort_options
+        .as_ref()
+        .and_then(|d| d.sort_by.clone())
+        .map(QdrantSortBy::into_parts)
+        .unwrap_or_default();
+
+    let synonym_sets = get_search_synonym_sets(
+        dataset.id,
+        &data.search_type,
+        rerank_by.as_ref(),
+        highlights_requested(&data.highlight_options, data.slim_chunks, &data.query),
+        pool.clone(),
+        redis_pool,
+    )
+    .await?;
+
+    let vector = get_qdrant_vector(
+        data.clone().search_type,
+        parsed_query.clone(),
+        &synonym_sets,
+        config,
+    )
+    .await?;
 
     let qdrant_query = RetrievePointQuery {
         vector,
@@ -2095,11 +2993,18 @@ pub async fn search_groups_query(
             data.score_threshold
         },
         limit: data.page_size.unwrap_or(10),
-        sort_by: sort_by.clone(),
+        sort_by: get_qdrant_sort_by(&sort_by),
         rerank_by: rerank_by.clone(),
         filter: data.filters.clone(),
     }
-    .into_qdrant_query(parsed_query, dataset.id, None, config, pool.clone())
+    .into_qdrant_query(
+        parsed_query,
+        dataset.id,
+        None,
+        &synonym_sets,
+        config,
+        pool.clone(),
+    )
     .await?;
 
     let search_semantic_chunk_query_results = retrieve_qdrant_points_query(
@@ -2114,6 +3019,7 @@ pub async fn search_groups_query(
         search_semantic_chunk_query_results,
         None,
         &web::Json(data.clone().into()),
+        &synonym_sets,
         pool.clone(),
     )
     .await?;
@@ -2156,6 +3062,11 @@ pub async fn search_groups_query(
             .as_ref()
             .map(|d| d.location_bias)
             .unwrap_or_default(),
+        data.sort_options
+            .as_ref()
+            .map(|d| d.decay_functions.clone())
+            .unwrap_or_default(),
+        rank_model_rescorer.as_ref(),
     );
 
     Ok(SearchWithinGroupResults {
@@ -2166,17 +3077,31 @@ pub async fn search_groups_query(
 }
 
 #[allow(clippy::too_many_arguments)]
-#[tracing::instrument(skip(pool))]
+#[tracing::instrument(skip(pool, redis_pool))]
 pub async fn search_hybrid_groups(
     data: SearchWithinGroupReqPayload,
     parsed_query: ParsedQuery,
     group: ChunkGroupAndFileId,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     dataset: Dataset,
     config: &DatasetConfiguration,
 ) -> Result<SearchWithinGroupResults, actix_web::Error> {
     let dataset_config = DatasetConfiguration::from_json(dataset.server_configuration.clone());
 
+    let rank_model_rescorer =
+        get_rank_model_rescorer(&data.clone().into(), dataset.id, pool.clone()).await?;
+
+    let synonym_sets = get_search_synonym_sets(
+        dataset.id,
+        &SearchMethod::Hybrid,
+        None,
+        false,
+        pool.clone(),
+        redis_pool,
+    )
+    .await?;
+
     let dense_vector_future = get_dense_vector(
         data.query.clone().to_single_query()?,
         None,
@@ -2184,24 +3109,26 @@ pub async fn search_hybrid_groups(
         dataset_config.clone(),
     );
 
-    let sparse_vector_future = get_sparse_vector(parsed_query.query.clone(), "query");
+    let sparse_vector_future = get_sparse_vector(
+        expand_query_with_synonyms(&parsed_query.query, &synonym_sets),
+        "query",
+    );
 
     let (dense_vector, sparse_vector) =
         futures::try_join!(dense_vector_future, sparse_vector_future)?;
 
-    let (sort_by, rerank_by) = match data.sort_options.as_ref().map(|d| d.sort_by.clone()) {
-        Some(Some(sort_by)) => match sort_by {
-            QdrantSortBy::Field(field) => (Some(field.clone()), None),
-            QdrantSortBy::SearchType(search_type) => (None, Some(search_type)),
-        },
-        _ => (None, None),
-    };
+    let (sort_by, rerank_by) = data
+        .sort_options
+        .as_ref()
+        .and_then(|d| d.sort_by.clone())
+        .map(QdrantSortBy::into_parts)
+        .unwrap_or_default();
 
     let qdrant_queries = vec![
         RetrievePointQuery {
             vector: VectorType::Dense(dense_vector),
             score_threshold: None,
-            sort_by: sort_by.clone(),
+            sort_by: get_qdrant_sort_by(&sort_by),
             rerank_by: rerank_by.clone(),
             limit: data.page_size.unwrap_or(10),
             filter: data.filters.clone(),
@@ -2210,6 +3137,7 @@ pub async fn search_hybrid_groups(
             ParsedQueryTypes::Single(parsed_query.clone()),
             dataset.id,
             Some(group.id),
+            &synonym_sets,
             config,
             pool.clone(),
         )
@@ -2217,7 +3145,7 @@ pub async fn search_hybrid_groups(
         RetrievePointQuery {
             vector: VectorType::SpladeSparse(sparse_vector),
             score_threshold: None,
-            sort_by: sort_by.clone(),
+            sort_by: get_qdrant_sort_by(&sort_by),
             rerank_by: rerank_by.clone(),
             limit: data.page_size.unwrap_or(10),
             filter: data.filters.clone(),
@@ -2226,6 +3154,7 @@ pub async fn search_hybrid_groups(
             ParsedQueryTypes::Single(parsed_query.clone()),
             dataset.id,
             Some(group.id),
+            &synonym_sets,
             config,
             pool.clone(),
         )
@@ -2251,6 +3180,7 @@ pub async fn search_hybrid_groups(
         qdrant_results,
         None,
         &web::Json(data.clone().into()),
+        &synonym_sets,
         pool.clone(),
     )
     .await?;
@@ -2288,6 +3218,11 @@ pub async fn search_hybrid_groups(
                     .as_ref()
                     .map(|d| d.location_bias)
                     .unwrap_or_default(),
+                data.sort_options
+                    .as_ref()
+                    .map(|d| d.decay_functions.clone())
+                    .unwrap_or_default(),
+                rank_model_rescorer.as_ref(),
             );
 
             score_chunks
@@ -2319,6 +3254,11 @@ pub async fn search_hybrid_groups(
                     .as_ref()
                     .map(|d| d.location_bias)
                     .unwrap_or_default(),
+                data.sort_options
+                    .as_ref()
+                    .map(|d| d.decay_functions.clone())
+                    .unwrap_or_default(),
+                rank_model_rescorer.as_ref(),
             )
         };
 
@@ -2329,6 +3269,9 @@ pub async fn search_hybrid_groups(
         SearchChunkQueryResponseBody {
             score_chunks: reranked_chunks,
             total_chunk_pages: result_chunks.total_chunk_pages,
+            facets: None,
+            search_after: None,
+            corrected_query: None,
         }
     };
 
@@ -2339,34 +3282,48 @@ pub async fn search_hybrid_groups(
     })
 }
 
-#[tracing::instrument(skip(timer, pool))]
+#[tracing::instrument(skip(timer, pool, redis_pool))]
 pub async fn semantic_search_over_groups(
     data: SearchOverGroupsReqPayload,
     parsed_query: ParsedQueryTypes,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     dataset: Dataset,
     config: &DatasetConfiguration,
     timer: &mut Timer,
 ) -> Result<DeprecatedSearchOverGroupsResponseBody, actix_web::Error> {
     let dataset_config = DatasetConfiguration::from_json(dataset.server_configuration.clone());
 
+    let synonym_sets = get_search_synonym_sets(
+        dataset.id,
+        &data.search_type,
+        None,
+        highlights_requested(&data.highlight_options, data.slim_chunks, &data.query),
+        pool.clone(),
+        redis_pool,
+    )
+    .await?;
+
     timer.add("start to create dense embedding vector");
 
     let embedding_vector = get_qdrant_vector(
         data.clone().search_type,
         parsed_query.clone(),
+        &synonym_sets,
         &dataset_config.clone(),
     )
     .await?;
 
     timer.add("computed dense embedding");
 
+    let (search_cursor, page, limit) = get_group_search_cursor_page_and_limit(&data)?;
+
     let search_over_groups_qdrant_result = retrieve_group_qdrant_points_query(
         embedding_vector,
-        data.page.unwrap_or(1),
-        data.get_total_pages.unwrap_or(false),
+        page,
+        data.get_total_pages.unwrap_or(false) && search_cursor.is_none(),
         data.filters.clone(),
-        data.page_size.unwrap_or(10),
+        limit,
         data.score_threshold,
         data.group_size.unwrap_or(3),
         parsed_query,
@@ -2381,6 +3338,7 @@ pub async fn semantic_search_over_groups(
     let mut result_chunks = retrieve_chunks_for_groups(
         search_over_groups_qdrant_result.clone(),
         &data,
+        &synonym_sets,
         pool.clone(),
     )
     .await?;
@@ -2397,6 +3355,15 @@ pub async fn semantic_search_over_groups(
         })
         .collect();
 
+    let group_scores = get_group_scores(&search_over_groups_qdrant_result.search_results);
+    (result_chunks.group_chunks, result_chunks.search_after) = paginate_groups_after_cursor(
+        result_chunks.group_chunks,
+        &data,
+        search_cursor.as_ref(),
+        &group_scores,
+        scores_descending(&data.search_type, config),
+    );
+
     timer.add("fetched from postgres");
 
     //TODO: rerank for groups
@@ -2404,32 +3371,46 @@ pub async fn semantic_search_over_groups(
     Ok(result_chunks)
 }
 
-#[tracing::instrument(skip(timer, pool))]
+#[tracing::instrument(skip(timer, pool, redis_pool))]
 pub async fn full_text_search_over_groups(
     data: SearchOverGroupsReqPayload,
     parsed_query: ParsedQueryTypes,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     dataset: Dataset,
     config: &DatasetConfiguration,
     timer: &mut Timer,
 ) -> Result<DeprecatedSearchOverGroupsResponseBody, actix_web::Error> {
+    let synonym_sets = get_search_synonym_sets(
+        dataset.id,
+        &data.search_type,
+        None,
+        highlights_requested(&data.highlight_options, data.slim_chunks, &data.query),
+        pool.clone(),
+        redis_pool,
+    )
+    .await?;
+
     timer.add("start to get sparse vector");
 
     let embedding_vector = get_qdrant_vector(
         data.clone().search_type,
         parsed_query.clone(),
+        &synonym_sets,
         &config.clone(),
     )
     .await?;
 
     timer.add("computed sparse vector");
 
+    let (search_cursor, page, limit) = get_group_search_cursor_page_and_limit(&data)?;
+
     let search_over_groups_qdrant_result = retrieve_group_qdrant_points_query(
         embedding_vector,
-        data.page.unwrap_or(1),
-        data.get_total_pages.unwrap_or(false),
+        page,
+        data.get_total_pages.unwrap_or(false) && search_cursor.is_none(),
         data.filters.clone(),
-        data.page_size.unwrap_or(10),
+        limit,
         data.score_threshold,
         data.group_size.unwrap_or(3),
         parsed_query,
@@ -2444,6 +3425,7 @@ pub async fn full_text_search_over_groups(
     let mut result_groups_with_chunk_hits = retrieve_chunks_for_groups(
         search_over_groups_qdrant_result.clone(),
         &data,
+        &synonym_sets,
         pool.clone(),
     )
     .await?;
@@ -2460,6 +3442,18 @@ pub async fn full_text_search_over_groups(
         })
         .collect();
 
+    let group_scores = get_group_scores(&search_over_groups_qdrant_result.search_results);
+    (
+        result_groups_with_chunk_hits.group_chunks,
+        result_groups_with_chunk_hits.search_after,
+    ) = paginate_groups_after_cursor(
+        result_groups_with_chunk_hits.group_chunks,
+        &data,
+        search_cursor.as_ref(),
+        &group_scores,
+        scores_descending(&data.search_type, config),
+    );
+
     timer.add("fetched from postgres");
 
     //TODO: rerank for groups
@@ -2527,17 +3521,28 @@ async fn cross_encoder_for_groups(
     Ok(group_results)
 }
 
-#[tracing::instrument(skip(timer, pool))]
+#[tracing::instrument(skip(timer, pool, redis_pool))]
 pub async fn hybrid_search_over_groups(
     data: SearchOverGroupsReqPayload,
     parsed_query: ParsedQuery,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     dataset: Dataset,
     config: &DatasetConfiguration,
     timer: &mut Timer,
 ) -> Result<DeprecatedSearchOverGroupsResponseBody, actix_web::Error> {
     let dataset_config = DatasetConfiguration::from_json(dataset.server_configuration.clone());
 
+    let synonym_sets = get_search_synonym_sets(
+        dataset.id,
+        &SearchMethod::Hybrid,
+        None,
+        false,
+        pool.clone(),
+        redis_pool,
+    )
+    .await?;
+
     timer.add("start to create dense embedding vector and sparse vector");
 
     let dense_embedding_vectors_future = get_dense_vector(
@@ -2547,8 +3552,10 @@ pub async fn hybrid_search_over_groups(
         dataset_config.clone(),
     );
 
-    let sparse_embedding_vector_future =
-        get_sparse_vector(data.query.clone().to_single_query()?, "query");
+    let sparse_embedding_vector_future = get_sparse_vector(
+        expand_query_with_synonyms(&data.query.clone().to_single_query()?, &synonym_sets),
+        "query",
+    );
 
     let (dense_vector, sparse_vector) = futures::try_join!(
         dense_embedding_vectors_future,
@@ -2557,13 +3564,24 @@ pub async fn hybrid_search_over_groups(
 
     timer.add("computed dense embedding");
 
+    let (search_cursor, page, limit) = get_group_search_cursor_page_and_limit(&data)?;
+
+    let hybrid_options = data.hybrid_options.clone().unwrap_or_default();
+    let fusion_method = hybrid_options.get_fusion_method();
+
+    // Fused scores are ranks or normalized scores, so the threshold cuts each list before fusion
+    let list_score_threshold = match fusion_method {
+        FusionMethod::CrossEncoder => None,
+        _ => data.score_threshold,
+    };
+
     let semantic_future = retrieve_group_qdrant_points_query(
         VectorType::Dense(dense_vector),
-        data.page.unwrap_or(1),
-        data.get_total_pages.unwrap_or(false),
+        page,
+        data.get_total_pages.unwrap_or(false) && search_cursor.is_none(),
         data.filters.clone(),
-        data.page_size.unwrap_or(10),
-        None,
+        limit,
+        list_score_threshold,
         data.group_size.unwrap_or(3),
         ParsedQueryTypes::Single(parsed_query.clone()),
         dataset.id,
@@ -2573,11 +3591,11 @@ pub async fn hybrid_search_over_groups(
 
     let full_text_future = retrieve_group_qdrant_points_query(
         VectorType::SpladeSparse(sparse_vector),
-        data.page.unwrap_or(1),
-        data.get_total_pages.unwrap_or(false),
+        page,
+        data.get_total_pages.unwrap_or(false) && search_cursor.is_none(),
         data.filters.clone(),
-        data.page_size.unwrap_or(10),
-        None,
+        limit,
+        list_score_threshold,
         data.group_size.unwrap_or(3),
         ParsedQueryTypes::Single(parsed_query.clone()),
         dataset.id,
@@ -2591,14 +3609,29 @@ pub async fn hybrid_search_over_groups(
 
     let full_text_results = full_text_results?;
 
-    let combined_results = semantic_results
-        .clone()
-        .search_results
-        .iter()
-        .zip(full_text_results.search_results.iter())
-        .flat_map(|(x, y)| vec![x.clone(), y.clone()])
-        .unique_by(|chunk| chunk.group_id)
-        .collect::<Vec<GroupSearchResults>>();
+    let mut group_scores = HashMap::new();
+    let combined_results = match fusion_method {
+        FusionMethod::CrossEncoder => semantic_results
+            .clone()
+            .search_results
+            .iter()
+            .zip(full_text_results.search_results.iter())
+            .flat_map(|(x, y)| vec![x.clone(), y.clone()])
+            .unique_by(|chunk| chunk.group_id)
+            .collect::<Vec<GroupSearchResults>>(),
+        _ => fuse_group_search_results(
+            semantic_results.search_results.clone(),
+            full_text_results.search_results.clone(),
+            data.group_size.unwrap_or(3) as usize,
+            &hybrid_options,
+        )
+        .into_iter()
+        .map(|(group, score)| {
+            group_scores.insert(group.group_id, score as f64);
+            group
+        })
+        .collect(),
+    };
 
     let combined_search_chunk_query_results = SearchOverGroupsQueryResult {
         search_results: combined_results,
@@ -2610,13 +3643,32 @@ pub async fn hybrid_search_over_groups(
     let combined_result_chunks = retrieve_chunks_for_groups(
         combined_search_chunk_query_results.clone(),
         &data,
+        &synonym_sets,
         pool.clone(),
     )
     .await?;
 
     timer.add("fetched from postgres");
 
-    let mut reranked_chunks = if combined_result_chunks.group_chunks.len() > 20 {
+    let mut reranked_chunks = if fusion_method != FusionMethod::CrossEncoder {
+        let fused_group_ids = combined_search_chunk_query_results
+            .search_results
+            .iter()
+            .map(|group| group.group_id)
+            .collect_vec();
+
+        combined_result_chunks
+            .group_chunks
+            .into_iter()
+            .sorted_by_key(|group| {
+                fused_group_ids
+                    .iter()
+                    .position(|group_id| *group_id == group.group_id)
+                    .unwrap_or(usize::MAX)
+            })
+            .take(limit as usize)
+            .collect::<Vec<GroupScoreChunk>>()
+    } else if combined_result_chunks.group_chunks.len() > 20 && search_cursor.is_none() {
         let split_results = combined_result_chunks
             .group_chunks
             .chunks(20)
@@ -2640,18 +3692,28 @@ pub async fn hybrid_search_over_groups(
             .cloned()
             .collect::<Vec<GroupScoreChunk>>()
     } else {
-        cross_encoder_for_groups(
+        let cross_encoder_results = cross_encoder_for_groups(
             data.query.clone().to_single_query()?,
-            data.page_size.unwrap_or(10),
+            limit,
             combined_result_chunks.group_chunks.clone(),
             config,
         )
-        .await?
+        .await?;
+
+        group_scores.extend(
+            cross_encoder_results
+                .iter()
+                .map(|group| (group.group_id, group.metadata[0].score)),
+        );
+
+        cross_encoder_results
     };
 
     timer.add("reranking");
 
-    if let Some(score_threshold) = data.score_threshold {
+    if let (FusionMethod::CrossEncoder, Some(score_threshold)) =
+        (&fusion_method, data.score_threshold)
+    {
         reranked_chunks.retain(|chunk| chunk.metadata[0].score >= score_threshold.into());
         reranked_chunks.iter_mut().for_each(|chunk| {
             chunk
@@ -2660,9 +3722,23 @@ pub async fn hybrid_search_over_groups(
         });
     }
 
+    // Groups past the first 20 are not cross encoded, so their scores can not be compared
+    let (group_chunks, search_after) = if group_scores.is_empty() {
+        (reranked_chunks, None)
+    } else {
+        paginate_groups_after_cursor(
+            reranked_chunks,
+            &data,
+            search_cursor.as_ref(),
+            &group_scores,
+            true,
+        )
+    };
+
     let result_chunks = DeprecatedSearchOverGroupsResponseBody {
-        group_chunks: reranked_chunks,
+        group_chunks,
         total_chunk_pages: combined_search_chunk_query_results.total_chunk_pages,
+        search_after,
     };
 
     //TODO: rerank for groups
@@ -2670,11 +3746,12 @@ pub async fn hybrid_search_over_groups(
     Ok(result_chunks)
 }
 
-#[tracing::instrument(skip(timer, pool))]
+#[tracing::instrument(skip(timer, pool, redis_pool))]
 pub async fn autocomplete_chunks_query(
     data: AutocompleteReqPayload,
     parsed_query: ParsedQuery,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     dataset: Dataset,
     config: &DatasetConfiguration,
     timer: &mut Timer,
@@ -2691,21 +3768,38 @@ pub async fn autocomplete_chunks_query(
     };
     sentry::configure_scope(|scope| scope.set_span(Some(transaction.clone())));
 
+    let rank_model_rescorer =
+        get_rank_model_rescorer(&data.clone().into(), dataset.id, pool.clone()).await?;
+
     timer.add("start to create dense embedding vector");
 
     timer.add("computed dense embedding");
 
-    let (sort_by, rerank_by) = match data.sort_options.as_ref().map(|d| d.sort_by.clone()) {
-        Some(Some(sort_by)) => match sort_by {
-            QdrantSortBy::Field(field) => (Some(field.clone()), None),
-            QdrantSortBy::SearchType(search_type) => (None, Some(search_type)),
-        },
-        _ => (None, None),
-    };
+    let (sort_by, rerank_by) = data
+        .sort_options
+        .as_ref()
+        .and_then(|d| d.sort_by.clone())
+        .map(QdrantSortBy::into_parts)
+        .unwrap_or_default();
+
+    let synonym_sets = get_search_synonym_sets(
+        dataset.id,
+        &data.search_type,
+        rerank_by.as_ref(),
+        highlights_requested(
+            &data.highlight_options,
+            data.slim_chunks,
+            &QueryTypes::Single(data.query.clone()),
+        ),
+        pool.clone(),
+        redis_pool,
+    )
+    .await?;
 
     let vector = get_qdrant_vector(
         data.clone().search_type,
         ParsedQueryTypes::Single(parsed_query.clone()),
+        &synonym_sets,
         config,
     )
     .await?;
@@ -2714,7 +3808,7 @@ pub async fn autocomplete_chunks_query(
         RetrievePointQuery {
             vector: vector.clone(),
             score_threshold: data.score_threshold,
-            sort_by: sort_by.clone(),
+            sort_by: get_qdrant_sort_by(&sort_by),
             rerank_by: rerank_by.clone(),
             limit: data.page_size.unwrap_or(10),
             filter: data.filters.clone(),
@@ -2723,6 +3817,7 @@ pub async fn autocomplete_chunks_query(
             ParsedQueryTypes::Single(parsed_query.clone()),
             dataset.id,
             None,
+            &synonym_sets,
             config,
             pool.clone(),
         )
@@ -2739,7 +3834,7 @@ pub async fn autocomplete_chunks_query(
             RetrievePointQuery {
                 vector,
                 score_threshold: data.score_threshold,
-                sort_by: sort_by.clone(),
+                sort_by: get_qdrant_sort_by(&sort_by),
                 rerank_by: rerank_by.clone(),
                 limit: data.page_size.unwrap_or(10),
                 filter: data.filters.clone(),
@@ -2748,6 +3843,7 @@ pub async fn autocomplete_chunks_query(
                 ParsedQueryTypes::Single(parsed_query.clone()),
                 dataset.id,
                 None,
+                &synonym_sets,
                 config,
                 pool.clone(),
             )
@@ -2764,6 +3860,7 @@ pub async fn autocomplete_chunks_query(
         search_chunk_query_results.clone(),
         None,
         &data.clone().into(),
+        &synonym_sets,
         pool.clone(),
     )
     .await?;
@@ -2791,6 +3888,11 @@ pub async fn autocomplete_chunks_query(
             .as_ref()
             .map(|d| d.location_bias)
             .unwrap_or_default(),
+        data.sort_options
+            .as_ref()
+            .map(|d| d.decay_functions.clone())
+            .unwrap_or_default(),
+        rank_model_rescorer.as_ref(),
     );
     reranked_chunks.extend(rerank_chunks(
         after_increase.to_vec(),
@@ -2807,6 +3909,11 @@ pub async fn autocomplete_chunks_query(
             .as_ref()
             .map(|d| d.location_bias)
             .unwrap_or_default(),
+        data.sort_options
+            .as_ref()
+            .map(|d| d.decay_functions.clone())
+            .unwrap_or_default(),
+        rank_model_rescorer.as_ref(),
     ));
 
     result_chunks.score_chunks = reranked_chunks;
@@ -2817,17 +3924,29 @@ pub async fn autocomplete_chunks_query(
     Ok(result_chunks)
 }
 
-#[tracing::instrument(skip(pool))]
+#[tracing::instrument(skip(pool, redis_pool))]
 pub async fn count_chunks_query(
     data: CountChunksReqPayload,
     parsed_query: ParsedQueryTypes,
     pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
     dataset: Dataset,
     config: &DatasetConfiguration,
 ) -> Result<CountChunkQueryResponseBody, actix_web::Error> {
+    let synonym_sets = get_search_synonym_sets(
+        dataset.id,
+        &data.search_type.clone().into(),
+        None,
+        false,
+        pool.clone(),
+        redis_pool,
+    )
+    .await?;
+
     let vector = get_qdrant_vector(
         data.clone().search_type.into(),
         parsed_query.clone(),
+        &synonym_sets,
         config,
     )
     .await?;
@@ -2840,7 +3959,14 @@ pub async fn count_chunks_query(
         limit: data.limit.unwrap_or(100000_u64),
         filter: data.filters.clone(),
     }
-    .into_qdrant_query(parsed_query, dataset.id, None, config, pool.clone())
+    .into_qdrant_query(
+        parsed_query,
+        dataset.id,
+        None,
+        &synonym_sets,
+        config,
+        pool.clone(),
+    )
     .await?;
 
     let count = count_qdrant_query(
@@ -2852,3 +3978,344 @@ pub async fn count_chunks_query(
 
     Ok(CountChunkQueryResponseBody { count })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::data::models::{DecayFunctionType, DecayOrigin, NullsOrder, SortOrder};
+    use serde_json::json;
+
+    #[test]
+    pub fn test_reciprocal_rank_fusion() {
+        let semantic = vec![("a", 0.9), ("b", 0.8), ("c", 0.7)];
+        let fulltext = vec![("c", 12.0), ("d", 8.0), ("a", 1.0)];
+
+        let fused = fuse_ranked_lists(
+            vec![(semantic, 1.0), (fulltext, 1.0)],
+            &FusionMethod::ReciprocalRankFusion,
+            60.0,
+        );
+
+        let keys = fused.iter().map(|(key, _)| *key).collect_vec();
+        assert_eq!(keys, vec!["a", "c", "b", "d"]);
+    }
+
+    #[test]
+    pub fn test_weighted_sum_fusion() {
+        let semantic = vec![("a", 0.9), ("b", 0.5)];
+        let fulltext = vec![("b", 20.0), ("a", 10.0)];
+
+        let fused = fuse_ranked_lists(
+            vec![(semantic, 1.0), (fulltext, 2.0)],
+            &FusionMethod::WeightedSum,
+            60.0,
+        );
+
+        assert_eq!(fused[0], ("b", 2.0));
+        assert_eq!(fused[1], ("a", 1.0));
+    }
+
+    #[test]
+    pub fn test_get_fused_page() {
+        let fused_results = (0..25).collect_vec();
+
+        assert_eq!(
+            get_fused_page(fused_results.clone(), 1, 10),
+            (0..10).collect_vec()
+        );
+        assert_eq!(
+            get_fused_page(fused_results.clone(), 3, 10),
+            (20..25).collect_vec()
+        );
+        assert!(get_fused_page(fused_results, 4, 10).is_empty());
+    }
+
+    #[test]
+    pub fn test_fuse_group_search_results() {
+        let group_id = uuid::Uuid::new_v4();
+        let point_ids = (0..3).map(|_| uuid::Uuid::new_v4()).collect_vec();
+        let hit = |point: usize, score: f32| SearchResult {
+            score,
+            point_id: point_ids[point],
+        };
+
+        let semantic = vec![GroupSearchResults {
+            group_id,
+            hits: vec![hit(0, 0.9), hit(1, 0.8)],
+        }];
+        let fulltext = vec![GroupSearchResults {
+            group_id,
+            hits: vec![hit(1, 14.0), hit(2, 3.0)],
+        }];
+
+        let fused = fuse_group_search_results(
+            semantic,
+            fulltext,
+            2,
+            &HybridSearchOptions {
+                fusion_method: Some(FusionMethod::ReciprocalRankFusion),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(fused.len(), 1);
+        assert_eq!(
+            fused[0].0.hits.iter().map(|hit| hit.point_id).collect_vec(),
+            vec![point_ids[1], point_ids[0]]
+        );
+    }
+
+    #[test]
+    pub fn test_decay_functions() {
+        let decay_function = |function| DecayFunction {
+            field: "num_value".to_string(),
+            function,
+            origin: Some(DecayOrigin::Number(100.0)),
+            scale: 10.0,
+            offset: Some(5.0),
+            decay: Some(0.5),
+            weight: None,
+        };
+
+        for function in [
+            DecayFunctionType::Gauss,
+            DecayFunctionType::Exp,
+            DecayFunctionType::Linear,
+        ] {
+            let decay_function = decay_function(function);
+            assert_eq!(decay_function.decay_at(3.0), 1.0);
+            assert!((decay_function.decay_at(-15.0) - 0.5).abs() < 1e-9);
+            assert!(decay_function.decay_at(20.0) < 0.5);
+        }
+
+        let chunk = ChunkMetadata {
+            num_value: Some(115.0),
+            ..Default::default()
+        };
+        let now = chrono::Utc::now().naive_utc();
+        let mut half_weight = decay_function(DecayFunctionType::Exp);
+        half_weight.weight = Some(0.5);
+        assert!((half_weight.score_multiplier(&chunk, now).unwrap() - 0.75).abs() < 1e-9);
+        assert_eq!(
+            half_weight
+                .score_multiplier(&ChunkMetadata::default(), now)
+                .unwrap(),
+            1.0
+        );
+
+        let mut missing_origin = decay_function(DecayFunctionType::Exp);
+        missing_origin.origin = None;
+        assert!(missing_origin.validate().is_err());
+    }
+
+    #[test]
+    pub fn test_sort_chunks_by_fields() {
+        let chunk = |num_value: Option<f64>, metadata: serde_json::Value, score: f64| {
+            let id = uuid::Uuid::new_v4();
+            let chunk = ChunkMetadata {
+                id,
+                num_value,
+                metadata: Some(metadata),
+                ..Default::default()
+            };
+            (
+                id,
+                ScoreChunkDTO {
+                    metadata: vec![ChunkMetadataTypes::Metadata(chunk.into())],
+                    highlights: None,
+                    score,
+                    inner_hits: None,
+                },
+            )
+        };
+        let (a, chunk_a) = chunk(Some(1.0), json!({ "brand": { "name": "b" } }), 0.9);
+        let (b, chunk_b) = chunk(Some(2.0), json!({ "brand": { "name": "a" } }), 0.5);
+        let (c, chunk_c) = chunk(Some(1.0), json!({ "brand": { "name": "a" } }), 0.1);
+        let (d, chunk_d) = chunk(None, json!({}), 0.7);
+        let chunks = vec![chunk_a, chunk_b, chunk_c, chunk_d];
+
+        let sort_by_field = |field: &str, direction, nulls| SortByField {
+            field: field.to_string(),
+            direction,
+            nulls,
+            prefetch_amount: None,
+        };
+        let ids = |chunks: Vec<ScoreChunkDTO>| {
+            chunks
+                .iter()
+                .map(|chunk| chunk.metadata[0].metadata().id)
+                .collect_vec()
+        };
+
+        let sorted = sort_chunks_by_fields(
+            chunks.clone(),
+            &[
+                sort_by_field("num_value", Some(SortOrder::Asc), None),
+                sort_by_field("metadata.brand.name", Some(SortOrder::Asc), None),
+            ],
+        );
+        assert_eq!(ids(sorted), vec![c, a, b, d]);
+
+        let sorted = sort_chunks_by_fields(
+            chunks.clone(),
+            &[
+                sort_by_field("num_value", None, Some(NullsOrder::First)),
+                sort_by_field("score", None, None),
+            ],
+        );
+        assert_eq!(ids(sorted), vec![d, b, a, c]);
+
+        let sorted = sort_chunks_by_fields(chunks, &[sort_by_field("link", None, None)]);
+        assert_eq!(ids(sorted), vec![a, b, c, d]);
+    }
+
+    #[test]
+    pub fn test_collapse_chunks() {
+        let collapse = CollapseOptions {
+            field: "tracking_id".to_string(),
+            tracking_id_delimiter: Some("_".to_string()),
+            candidate_limit: None,
+        };
+        let chunks = ["doc-1_0", "doc-2_0", "doc-1_1", "doc-1_2", "doc-3_0"]
+            .into_iter()
+            .map(|tracking_id| ChunkMetadata {
+                id: uuid::Uuid::new_v4(),
+                tracking_id: Some(tracking_id.to_string()),
+                ..Default::default()
+            })
+            .chain(std::iter::once(ChunkMetadata::default()))
+            .collect_vec();
+        let keys = chunks
+            .iter()
+            .map(|chunk| collapse.keys(chunk, &[]))
+            .collect_vec();
+        let score_chunks = chunks
+            .into_iter()
+            .map(|chunk| ScoreChunkDTO {
+                metadata: vec![ChunkMetadataTypes::Metadata(chunk.into())],
+                highlights: None,
+                score: 1.0,
+                inner_hits: None,
+            })
+            .collect_vec();
+
+        let collapsed = collapse_chunks(score_chunks, keys);
+
+        assert_eq!(
+            collapsed
+                .iter()
+                .map(|chunk| (chunk.metadata[0].metadata().tracking_id, chunk.inner_hits))
+                .collect_vec(),
+            vec![
+                (Some("doc-1_0".to_string()), Some(3)),
+                (Some("doc-2_0".to_string()), Some(1)),
+                (Some("doc-3_0".to_string()), Some(1)),
+                (None, Some(1)),
+            ]
+        );
+        assert_eq!(collapse.get_candidate_limit(2, 10, 100), 100);
+        assert_eq!(collapse.get_candidate_limit(2, 10, 1000), 200);
+        assert!(CollapseOptions {
+            field: "content".to_string(),
+            tracking_id_delimiter: None,
+            candidate_limit: None,
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    pub fn test_paginate_after_cursor() {
+        let ids = (0..5).map(|_| uuid::Uuid::new_v4()).sorted().collect_vec();
+        let results = vec![
+            (0.9, ids[3]),
+            (0.7, ids[4]),
+            (0.7, ids[1]),
+            (0.5, ids[0]),
+            (0.3, ids[2]),
+        ];
+
+        let (first_page, search_cursor) =
+            paginate_after_cursor(results.clone(), None, 2, true, |r| *r);
+        assert_eq!(first_page, vec![(0.9, ids[3]), (0.7, ids[1])]);
+
+        let search_cursor =
+            SearchCursor::decode(&search_cursor.expect("Page is full").encode()).unwrap();
+        assert_eq!(search_cursor.offset, 2);
+
+        let (second_page, next_search_cursor) = paginate_after_cursor(
+            vec![(0.5, ids[0]), (0.7, ids[4])],
+            Some(&search_cursor),
+            2,
+            true,
+            |r| *r,
+        );
+        assert_eq!(second_page, vec![(0.7, ids[4]), (0.5, ids[0])]);
+        assert_eq!(next_search_cursor.expect("Page is full").offset, 4);
+
+        // A new result ranked before the cursor shifts the page at its offset, the result which was already returned is dropped
+        let (second_page, search_cursor) = paginate_after_cursor(
+            vec![(0.7, ids[1]), (0.7, ids[4])],
+            Some(&search_cursor),
+            2,
+            true,
+            |r| *r,
+        );
+        assert_eq!(second_page, vec![(0.7, ids[4])]);
+
+        let (third_page, search_cursor) = paginate_after_cursor(
+            vec![(0.5, ids[0]), (0.3, ids[2])],
+            search_cursor.as_ref(),
+            2,
+            true,
+            |r| *r,
+        );
+        assert_eq!(third_page, vec![(0.5, ids[0]), (0.3, ids[2])]);
+
+        let (last_page, search_cursor) =
+            paginate_after_cursor(vec![], search_cursor.as_ref(), 2, true, |r| *r);
+        assert!(last_page.is_empty());
+        assert!(search_cursor.is_none());
+        assert!(SearchCursor::decode("not a cursor").is_err());
+
+        // Distances rank the closest results first, even when every result is equally close
+        let distances = vec![(0.2, ids[0]), (0.2, ids[1]), (0.2, ids[2])];
+        let (first_page, search_cursor) =
+            paginate_after_cursor(distances.clone(), None, 2, false, |r| *r);
+        assert_eq!(first_page, vec![(0.2, ids[0]), (0.2, ids[1])]);
+        let (last_page, search_cursor) = paginate_after_cursor(
+            distances[2..].to_vec(),
+            search_cursor.as_ref(),
+            2,
+            false,
+            |r| *r,
+        );
+        assert_eq!(last_page, vec![(0.2, ids[2])]);
+        assert!(search_cursor.is_none());
+
+        let distances = vec![(0.5, ids[0]), (0.1, ids[1]), (0.3, ids[2])];
+        let (first_page, _) = paginate_after_cursor(distances, None, 2, false, |r| *r);
+        assert_eq!(first_page, vec![(0.1, ids[1]), (0.3, ids[2])]);
+    }
+
+    #[test]
+    pub fn test_search_cursor_signature() {
+        use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+
+        let search_cursor = SearchCursor {
+            score: 0.5,
+            id: uuid::Uuid::new_v4(),
+            offset: 10,
+        };
+        let encoded = search_cursor.encode();
+        assert_eq!(SearchCursor::decode(&encoded).unwrap(), search_cursor);
+
+        let decoded = String::from_utf8(URL_SAFE_NO_PAD.decode(&encoded).unwrap()).unwrap();
+        let tampered = URL_SAFE_NO_PAD.encode(decoded.replacen("10:", "1000000:", 1));
+        assert!(SearchCursor::decode(&tampered).is_err());
+
+        let unsigned =
+            URL_SAFE_NO_PAD.encode(format!("10:{:x}:{}", 0.5_f64.to_bits(), search_cursor.id));
+        assert!(SearchCursor::decode(&unsigned).is_err());
+    }
+}
@@ -0,0 +1,504 @@
+use super::model_operator::get_vocabulary_terms;
+use crate::{
+    data::models::{Bm25AnalyzerOptions, DatasetConfiguration, RedisPool},
+    errors::ServiceError,
+};
+use actix_web::web;
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// Terms shorter than this are too ambiguous to correct.
+const MIN_CORRECTABLE_TERM_LENGTH: usize = 3;
+
+pub fn get_vocabulary_key(dataset_id: uuid::Uuid) -> String {
+    format!("bm25_vocab:{}", dataset_id)
+}
+
+pub fn get_vocabulary_forms_key(dataset_id: uuid::Uuid, term: &str) -> String {
+    format!("bm25_vocab_forms:{}:{}", dataset_id, term)
+}
+
+/// Adds `sign` times the number of chunks each term occurs in to the vocabulary. When the analyzer folds accents, the surface forms of each term are counted under the term as well so corrections can be shown the way the dataset writes them.
+async fn add_to_vocabulary(
+    dataset_id: uuid::Uuid,
+    vocabulary_terms: Vec<Vec<(String, String)>>,
+    count_surface_forms: bool,
+    sign: i64,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    let term_counts = vocabulary_terms
+        .iter()
+        .flat_map(|terms| terms.iter().map(|(term, _)| term).unique())
+        .counts();
+    if term_counts.is_empty() {
+        return Ok(());
+    }
+
+    let form_counts = if count_surface_forms {
+        vocabulary_terms.iter().flatten().counts()
+    } else {
+        HashMap::new()
+    };
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let vocabulary_key = get_vocabulary_key(dataset_id);
+    let mut pipe = redis::pipe();
+    for (term, count) in term_counts {
+        pipe.cmd("ZINCRBY")
+            .arg(&vocabulary_key)
+            .arg(sign * count as i64)
+            .arg(term)
+            .ignore();
+    }
+
+    let mut forms_keys = vec![];
+    for ((term, form), count) in form_counts {
+        let forms_key = get_vocabulary_forms_key(dataset_id, term);
+        pipe.cmd("ZINCRBY")
+            .arg(&forms_key)
+            .arg(sign * count as i64)
+            .arg(form)
+            .ignore();
+        forms_keys.push(forms_key);
+    }
+
+    // Terms and forms which no longer occur in any chunk must not be suggested
+    if sign < 0 {
+        for key in std::iter::once(vocabulary_key).chain(forms_keys.into_iter().unique()) {
+            pipe.cmd("ZREMRANGEBYSCORE")
+                .arg(key)
+                .arg("-inf")
+                .arg(0)
+                .ignore();
+        }
+    }
+
+    pipe.query_async::<_, ()>(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(())
+}
+
+/// Adds the terms of newly uploaded chunks to the dataset's vocabulary. The vocabulary counts the number of chunks each term occurs in.
+#[tracing::instrument(skip(vocabulary_terms, redis_pool))]
+pub async fn increment_vocabulary_query(
+    dataset_id: uuid::Uuid,
+    vocabulary_terms: Vec<Vec<(String, String)>>,
+    count_surface_forms: bool,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    add_to_vocabulary(
+        dataset_id,
+        vocabulary_terms,
+        count_surface_forms,
+        1,
+        redis_pool,
+    )
+    .await
+}
+
+/// Takes the terms of deleted chunks or replaced chunk contents back out of the dataset's vocabulary.
+#[tracing::instrument(skip(vocabulary_terms, redis_pool))]
+pub async fn decrement_vocabulary_query(
+    dataset_id: uuid::Uuid,
+    vocabulary_terms: Vec<Vec<(String, String)>>,
+    count_surface_forms: bool,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    add_to_vocabulary(
+        dataset_id,
+        vocabulary_terms,
+        count_surface_forms,
+        -1,
+        redis_pool,
+    )
+    .await
+}
+
+/// Number of chunks each term occurs in, terms missing from the vocabulary are left out.
+#[tracing::instrument(skip(terms, redis_pool))]
+pub async fn get_term_frequencies_query(
+    dataset_id: uuid::Uuid,
+    terms: Vec<String>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<HashMap<String, u64>, ServiceError> {
+    if terms.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let vocabulary_key = get_vocabulary_key(dataset_id);
+    let mut pipe = redis::pipe();
+    for term in terms.iter() {
+        pipe.cmd("ZSCORE").arg(&vocabulary_key).arg(term);
+    }
+
+    let frequencies: Vec<Option<f64>> = pipe
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(terms
+        .into_iter()
+        .zip(frequencies)
+        .filter_map(|(term, frequency)| {
+            frequency
+                .filter(|frequency| *frequency > 0.0)
+                .map(|frequency| (term, frequency as u64))
+        })
+        .collect())
+}
+
+/// Most frequent surface form of each term, terms without counted surface forms are left out.
+#[tracing::instrument(skip(terms, redis_pool))]
+pub async fn get_surface_forms_query(
+    dataset_id: uuid::Uuid,
+    terms: Vec<String>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<HashMap<String, String>, ServiceError> {
+    if terms.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let mut pipe = redis::pipe();
+    for term in terms.iter() {
+        pipe.cmd("ZREVRANGE")
+            .arg(get_vocabulary_forms_key(dataset_id, term))
+            .arg(0)
+            .arg(0);
+    }
+
+    let forms: Vec<Vec<String>> = pipe
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(terms
+        .into_iter()
+        .zip(forms)
+        .filter_map(|(term, mut forms)| forms.pop().map(|form| (term, form)))
+        .collect())
+}
+
+#[tracing::instrument(skip(redis_pool))]
+pub async fn clear_vocabulary_query(
+    dataset_id: uuid::Uuid,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    redis::cmd("DEL")
+        .arg(get_vocabulary_key(dataset_id))
+        .query_async::<_, ()>(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let mut cursor: u64 = 0;
+    loop {
+        let (next_cursor, keys): (u64, Vec<String>) = redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("MATCH")
+            .arg(get_vocabulary_forms_key(dataset_id, "*"))
+            .arg("COUNT")
+            .arg(1000)
+            .query_async(&mut *redis_conn)
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+        if !keys.is_empty() {
+            redis::cmd("DEL")
+                .arg(keys)
+                .query_async::<_, ()>(&mut *redis_conn)
+                .await
+                .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+        }
+
+        cursor = next_cursor;
+        if cursor == 0 {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// All terms one deletion, transposition, substitution or insertion away from the term. Letters are taken from the English alphabet and the term itself.
+pub fn get_edit_candidates(term: &str) -> Vec<String> {
+    let chars = term.chars().collect_vec();
+    let alphabet = ('a'..='z')
+        .chain(chars.iter().copied())
+        .unique()
+        .collect_vec();
+    let mut candidates = vec![];
+
+    for i in 0..=chars.len() {
+        let (left, right) = chars.split_at(i);
+
+        if !right.is_empty() {
+            candidates.push(left.iter().chain(&right[1..]).collect::<String>());
+        }
+
+        if right.len() > 1 {
+            candidates.push(
+                left.iter()
+                    .chain([right[1], right[0]].iter())
+                    .chain(&right[2..])
+                    .collect::<String>(),
+            );
+        }
+
+        for letter in alphabet.iter() {
+            if !right.is_empty() && right[0] != *letter {
+                candidates.push(
+                    left.iter()
+                        .chain(std::iter::once(letter))
+                        .chain(&right[1..])
+                        .collect::<String>(),
+                );
+            }
+
+            candidates.push(
+                left.iter()
+                    .chain(std::iter::once(letter))
+                    .chain(right)
+                    .collect::<String>(),
+            );
+        }
+    }
+
+    candidates
+        .into_iter()
+        .filter(|candidate| candidate != term && !candidate.is_empty())
+        .unique()
+        .collect()
+}
+
+/// The most frequent candidate of a term missing from the vocabulary. Ties go to the alphabetically first candidate so corrections are stable.
+pub fn get_best_correction(
+    candidates: &[String],
+    frequencies: &HashMap<String, u64>,
+) -> Option<String> {
+    candidates
+        .iter()
+        .filter_map(|candidate| {
+            frequencies
+                .get(candidate)
+                .map(|frequency| (candidate, *frequency))
+        })
+        .max_by(|(a, a_frequency), (b, b_frequency)| {
+            a_frequency.cmp(b_frequency).then_with(|| b.cmp(a))
+        })
+        .map(|(candidate, _)| candidate.clone())
+}
+
+/// Words of the query which can be corrected, paired with their position in the query split on spaces and their vocabulary term. Quoted phrases and negated words are left as they are.
+fn get_correctable_words(
+    query: &str,
+    analyzer: &Bm25AnalyzerOptions,
+) -> Vec<(usize, String, String)> {
+    let mut in_quote = false;
+    let mut correctable_words = vec![];
+
+    for (position, word) in query.split(' ').enumerate() {
+        let quoted = in_quote || word.starts_with('"');
+        if word.matches('"').count() % 2 == 1 {
+            in_quote = !in_quote;
+        }
+        if quoted || word.starts_with('-') {
+            continue;
+        }
+
+        let core = word.trim_matches(|c: char| !c.is_alphanumeric());
+        if core.chars().count() < MIN_CORRECTABLE_TERM_LENGTH {
+            continue;
+        }
+
+        if let Some((term, _)) = get_vocabulary_terms(vec![core.to_string()], analyzer)
+            .pop()
+            .filter(|terms| terms.len() == 1)
+            .and_then(|mut terms| terms.pop())
+        {
+            correctable_words.push((position, core.to_string(), term));
+        }
+    }
+
+    correctable_words
+}
+
+/// Replaces the corrected words of the query, keeping the punctuation around them and capitalizing corrections of capitalized words.
+pub fn apply_corrections(query: &str, corrections: &HashMap<usize, (String, String)>) -> String {
+    query
+        .split(' ')
+        .enumerate()
+        .map(|(position, word)| match corrections.get(&position) {
+            Some((core, correction)) => {
+                let correction = if core.starts_with(char::is_uppercase) {
+                    let mut chars = correction.chars();
+                    chars
+                        .next()
+                        .map(|first| first.to_uppercase().chain(chars).collect())
+                        .unwrap_or_default()
+                } else {
+                    correction.clone()
+                };
+                word.replacen(core.as_str(), &correction, 1)
+            }
+            None => word.to_string(),
+        })
+        .join(" ")
+}
+
+/// Corrects the query terms which do not occur in the dataset's vocabulary to the most frequent vocabulary term within one edit, written in the term's most frequent surface form. Returns None when the vocabulary is not kept for the dataset or nothing was corrected.
+#[tracing::instrument(skip(dataset_config, redis_pool))]
+pub async fn get_corrected_query(
+    query: &str,
+    dataset_id: uuid::Uuid,
+    dataset_config: &DatasetConfiguration,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<Option<String>, ServiceError> {
+    if !dataset_config.BM25_ENABLED
+        || std::env::var("BM25_ACTIVE").unwrap_or("false".to_string()) != "true"
+    {
+        return Ok(None);
+    }
+
+    let analyzer = dataset_config.bm25_analyzer_options();
+    let correctable_words = get_correctable_words(query, &analyzer);
+    if correctable_words.is_empty() {
+        return Ok(None);
+    }
+
+    let known_terms = get_term_frequencies_query(
+        dataset_id,
+        correctable_words
+            .iter()
+            .map(|(_, _, term)| term.clone())
+            .unique()
+            .collect(),
+        redis_pool.clone(),
+    )
+    .await?;
+
+    let unknown_words = correctable_words
+        .into_iter()
+        .filter(|(_, _, term)| !known_terms.contains_key(term))
+        .map(|(position, core, term)| (position, core, get_edit_candidates(&term)))
+        .collect_vec();
+    if unknown_words.is_empty() {
+        return Ok(None);
+    }
+
+    let candidate_frequencies = get_term_frequencies_query(
+        dataset_id,
+        unknown_words
+            .iter()
+            .flat_map(|(_, _, candidates)| candidates.clone())
+            .unique()
+            .collect(),
+        redis_pool.clone(),
+    )
+    .await?;
+
+    let mut corrections = unknown_words
+        .into_iter()
+        .filter_map(|(position, core, candidates)| {
+            get_best_correction(&candidates, &candidate_frequencies)
+                .map(|correction| (position, (core, correction)))
+        })
+        .collect::<HashMap<usize, (String, String)>>();
+    if corrections.is_empty() {
+        return Ok(None);
+    }
+
+    if analyzer.ascii_folding {
+        let surface_forms = get_surface_forms_query(
+            dataset_id,
+            corrections
+                .values()
+                .map(|(_, correction)| correction.clone())
+                .unique()
+                .collect(),
+            redis_pool,
+        )
+        .await?;
+
+        for (_, correction) in corrections.values_mut() {
+            if let Some(surface_form) = surface_forms.get(correction) {
+                *correction = surface_form.clone();
+            }
+        }
+    }
+
+    Ok(Some(apply_corrections(query, &corrections)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_edit_candidates() {
+        let candidates = get_edit_candidates("teh");
+
+        assert!(candidates.contains(&"the".to_string()));
+        assert!(candidates.contains(&"te".to_string()));
+        assert!(candidates.contains(&"tech".to_string()));
+        assert!(candidates.contains(&"ten".to_string()));
+        assert!(!candidates.contains(&"teh".to_string()));
+    }
+
+    #[test]
+    fn test_correct_query() {
+        let analyzer = Bm25AnalyzerOptions::default();
+        let query = "Wireles \"hedphones\" -hedphones with noise cancelation";
+
+        let correctable_words = get_correctable_words(query, &analyzer);
+        assert_eq!(
+            correctable_words
+                .iter()
+                .map(|(position, _, term)| (*position, term.as_str()))
+                .collect_vec(),
+            vec![
+                (0, "wireles"),
+                (3, "with"),
+                (4, "noise"),
+                (5, "cancelation")
+            ]
+        );
+
+        let frequencies = HashMap::from([
+            ("wireless".to_string(), 12),
+            ("cancellation".to_string(), 3),
+            ("cancelations".to_string(), 1),
+        ]);
+        let corrections = [(0, "Wireles", "wireles"), (5, "cancelation", "cancelation")]
+            .into_iter()
+            .filter_map(|(position, core, term)| {
+                get_best_correction(&get_edit_candidates(term), &frequencies)
+                    .map(|correction| (position, (core.to_string(), correction)))
+            })
+            .collect::<HashMap<usize, (String, String)>>();
+
+        assert_eq!(
+            apply_corrections(query, &corrections),
+            "Wireless \"hedphones\" -hedphones with noise cancellation"
+        );
+    }
+}
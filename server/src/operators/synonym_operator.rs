@@ -0,0 +1,284 @@
+use crate::{
+    data::models::{Pool, RedisPool, SynonymSet},
+    errors::ServiceError,
+};
+use actix_web::web;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use itertools::Itertools;
+
+#[tracing::instrument(skip(pool))]
+pub async fn create_synonym_set_query(
+    synonym_set: SynonymSet,
+    pool: web::Data<Pool>,
+) -> Result<SynonymSet, ServiceError> {
+    use crate::data::schema::synonym_sets::dsl as synonym_sets_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let created_synonym_set: SynonymSet = diesel::insert_into(synonym_sets_columns::synonym_sets)
+        .values(&synonym_set)
+        .get_result(&mut conn)
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not create synonym set".to_string()))?;
+
+    Ok(created_synonym_set)
+}
+
+#[tracing::instrument(skip(pool))]
+pub async fn get_synonym_sets_for_dataset_query(
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<Vec<SynonymSet>, ServiceError> {
+    use crate::data::schema::synonym_sets::dsl as synonym_sets_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let synonym_sets: Vec<SynonymSet> = synonym_sets_columns::synonym_sets
+        .filter(synonym_sets_columns::dataset_id.eq(dataset_id))
+        .order(synonym_sets_columns::created_at.asc())
+        .load(&mut conn)
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get synonym sets".to_string()))?;
+
+    Ok(synonym_sets)
+}
+
+/// Bounds how long a cache entry written by a search that raced a synonym set change can stay stale.
+const SYNONYM_SETS_CACHE_TTL_SECS: u64 = 15 * 60;
+
+pub fn get_synonym_sets_cache_key(dataset_id: uuid::Uuid) -> String {
+    format!("synonym_sets:{}", dataset_id)
+}
+
+/// Gets the synonym sets of the dataset from redis, loading and caching them from postgres on a miss. Every change to the dataset's synonym sets clears the cache.
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn get_cached_synonym_sets_query(
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<Vec<SynonymSet>, ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let cached_synonym_sets: Option<String> = redis::cmd("GET")
+        .arg(get_synonym_sets_cache_key(dataset_id))
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    if let Some(synonym_sets) = cached_synonym_sets
+        .and_then(|cached_synonym_sets| serde_json::from_str(&cached_synonym_sets).ok())
+    {
+        return Ok(synonym_sets);
+    }
+
+    let synonym_sets = get_synonym_sets_for_dataset_query(dataset_id, pool).await?;
+
+    let serialized_synonym_sets = serde_json::to_string(&synonym_sets)
+        .map_err(|_| ServiceError::BadRequest("Failed to serialize synonym sets".to_string()))?;
+
+    redis::cmd("SET")
+        .arg(get_synonym_sets_cache_key(dataset_id))
+        .arg(serialized_synonym_sets)
+        .arg("EX")
+        .arg(SYNONYM_SETS_CACHE_TTL_SECS)
+        .query_async::<_, ()>(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(synonym_sets)
+}
+
+#[tracing::instrument(skip(redis_pool))]
+pub async fn clear_synonym_sets_cache_query(
+    dataset_id: uuid::Uuid,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    redis::cmd("DEL")
+        .arg(get_synonym_sets_cache_key(dataset_id))
+        .query_async::<_, ()>(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(())
+}
+
+#[tracing::instrument(skip(pool))]
+pub async fn get_synonym_set_by_id_query(
+    synonym_set_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<SynonymSet, ServiceError> {
+    use crate::data::schema::synonym_sets::dsl as synonym_sets_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let synonym_set: Option<SynonymSet> = synonym_sets_columns::synonym_sets
+        .filter(synonym_sets_columns::id.eq(synonym_set_id))
+        .filter(synonym_sets_columns::dataset_id.eq(dataset_id))
+        .first(&mut conn)
+        .await
+        .optional()
+        .map_err(|_| ServiceError::BadRequest("Could not get synonym set".to_string()))?;
+
+    synonym_set.ok_or(ServiceError::NotFound(
+        "Synonym set with specified id not found".to_string(),
+    ))
+}
+
+#[tracing::instrument(skip(pool))]
+pub async fn update_synonym_set_query(
+    synonym_set_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    root: Option<String>,
+    synonyms: Vec<String>,
+    pool: web::Data<Pool>,
+) -> Result<SynonymSet, ServiceError> {
+    use crate::data::schema::synonym_sets::dsl as synonym_sets_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let updated_synonym_set: Option<SynonymSet> = diesel::update(
+        synonym_sets_columns::synonym_sets
+            .filter(synonym_sets_columns::id.eq(synonym_set_id))
+            .filter(synonym_sets_columns::dataset_id.eq(dataset_id)),
+    )
+    .set((
+        synonym_sets_columns::root.eq(root),
+        synonym_sets_columns::synonyms.eq(synonyms.into_iter().map(Some).collect_vec()),
+        synonym_sets_columns::updated_at.eq(chrono::Utc::now().naive_local()),
+    ))
+    .get_result(&mut conn)
+    .await
+    .optional()
+    .map_err(|_| ServiceError::BadRequest("Could not update synonym set".to_string()))?;
+
+    updated_synonym_set.ok_or(ServiceError::NotFound(
+        "Synonym set with specified id not found".to_string(),
+    ))
+}
+
+#[tracing::instrument(skip(pool))]
+pub async fn delete_synonym_set_query(
+    synonym_set_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<(), ServiceError> {
+    use crate::data::schema::synonym_sets::dsl as synonym_sets_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let deleted_count = diesel::delete(
+        synonym_sets_columns::synonym_sets
+            .filter(synonym_sets_columns::id.eq(synonym_set_id))
+            .filter(synonym_sets_columns::dataset_id.eq(dataset_id)),
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(|_| ServiceError::BadRequest("Could not delete synonym set".to_string()))?;
+
+    if deleted_count == 0 {
+        return Err(ServiceError::NotFound(
+            "Synonym set with specified id not found".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn get_words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+fn contains_phrase(words: &[String], phrase: &[String]) -> bool {
+    !phrase.is_empty() && words.windows(phrase.len()).any(|window| window == phrase)
+}
+
+/// Appends the synonyms of the terms and phrases the query contains to it, so sparse query vectors and highlights also match chunks which use a synonym. Matching ignores case and punctuation, negated words do not expand the query.
+pub fn expand_query_with_synonyms(query: &str, synonym_sets: &[SynonymSet]) -> String {
+    let query_words = query
+        .split_whitespace()
+        .filter(|word| !word.starts_with('-'))
+        .flat_map(get_words)
+        .collect_vec();
+
+    let expansions = synonym_sets
+        .iter()
+        .filter(|synonym_set| {
+            synonym_set
+                .triggers()
+                .iter()
+                .any(|trigger| contains_phrase(&query_words, &get_words(trigger)))
+        })
+        .flat_map(|synonym_set| synonym_set.synonyms.iter().flatten())
+        .filter(|synonym| !contains_phrase(&query_words, &get_words(synonym)))
+        .unique_by(|synonym| get_words(synonym))
+        .cloned()
+        .collect_vec();
+
+    if expansions.is_empty() {
+        return query.to_string();
+    }
+
+    format!("{} {}", query, expansions.join(" "))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_expand_query_with_synonyms() {
+        let synonym_sets = vec![
+            SynonymSet::from_details(
+                uuid::Uuid::nil(),
+                None,
+                vec!["laptop".to_string(), "notebook".to_string()],
+            ),
+            SynonymSet::from_details(
+                uuid::Uuid::nil(),
+                Some("SSD".to_string()),
+                vec!["solid state drive".to_string(), "flash storage".to_string()],
+            ),
+        ];
+
+        assert_eq!(
+            expand_query_with_synonyms("Cheap Notebook with SSD!", &synonym_sets),
+            "Cheap Notebook with SSD! laptop solid state drive flash storage"
+        );
+        assert_eq!(
+            expand_query_with_synonyms("solid state drive for my laptop", &synonym_sets),
+            "solid state drive for my laptop notebook"
+        );
+        assert_eq!(
+            expand_query_with_synonyms("tablet -laptop", &synonym_sets),
+            "tablet -laptop"
+        );
+    }
+}
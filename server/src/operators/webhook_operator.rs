@@ -0,0 +1,374 @@
+use crate::{
+    data::models::{Pool, RedisPool, Webhook, WorkerEvent},
+    errors::ServiceError,
+};
+use actix_web::web;
+use diesel::prelude::*;
+use diesel_async::RunQueryDsl;
+use hmac::{Hmac, Mac};
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::Sha256;
+use std::net::{IpAddr, SocketAddr};
+
+/// Redis set of the datasets which have at least one webhook, checked before an event is queued for delivery.
+pub const WEBHOOK_DATASETS_KEY: &str = "webhook_datasets";
+
+pub fn generate_webhook_secret() -> String {
+    let secret: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect();
+
+    format!("whsec-{}", secret)
+}
+
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn create_webhook_query(
+    webhook: Webhook,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<Webhook, ServiceError> {
+    use crate::data::schema::webhooks::dsl as webhooks_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let created_webhook: Webhook = diesel::insert_into(webhooks_columns::webhooks)
+        .values(&webhook)
+        .get_result(&mut conn)
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not create webhook".to_string()))?;
+
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    redis::cmd("SADD")
+        .arg(WEBHOOK_DATASETS_KEY)
+        .arg(created_webhook.dataset_id.to_string())
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(created_webhook)
+}
+
+#[tracing::instrument(skip(pool))]
+pub async fn get_webhooks_for_dataset_query(
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<Vec<Webhook>, ServiceError> {
+    use crate::data::schema::webhooks::dsl as webhooks_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let webhooks: Vec<Webhook> = webhooks_columns::webhooks
+        .filter(webhooks_columns::dataset_id.eq(dataset_id))
+        .order(webhooks_columns::created_at.asc())
+        .load(&mut conn)
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get webhooks".to_string()))?;
+
+    Ok(webhooks)
+}
+
+#[tracing::instrument(skip(pool))]
+pub async fn get_webhook_by_id_query(
+    webhook_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+) -> Result<Option<Webhook>, ServiceError> {
+    use crate::data::schema::webhooks::dsl as webhooks_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let webhook: Option<Webhook> = webhooks_columns::webhooks
+        .filter(webhooks_columns::id.eq(webhook_id))
+        .first(&mut conn)
+        .await
+        .optional()
+        .map_err(|_| ServiceError::BadRequest("Could not get webhook".to_string()))?;
+
+    Ok(webhook)
+}
+
+#[tracing::instrument(skip(pool, redis_pool))]
+pub async fn delete_webhook_query(
+    webhook_id: uuid::Uuid,
+    dataset_id: uuid::Uuid,
+    pool: web::Data<Pool>,
+    redis_pool: web::Data<RedisPool>,
+) -> Result<(), ServiceError> {
+    use crate::data::schema::webhooks::dsl as webhooks_columns;
+
+    let mut conn = pool
+        .get()
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not get database connection".to_string()))?;
+
+    let deleted_count = diesel::delete(
+        webhooks_columns::webhooks
+            .filter(webhooks_columns::id.eq(webhook_id))
+            .filter(webhooks_columns::dataset_id.eq(dataset_id)),
+    )
+    .execute(&mut conn)
+    .await
+    .map_err(|_| ServiceError::BadRequest("Could not delete webhook".to_string()))?;
+
+    if deleted_count == 0 {
+        return Err(ServiceError::NotFound(
+            "Webhook with specified id not found".to_string(),
+        ));
+    }
+
+    let remaining_count: i64 = webhooks_columns::webhooks
+        .filter(webhooks_columns::dataset_id.eq(dataset_id))
+        .count()
+        .get_result(&mut conn)
+        .await
+        .map_err(|_| ServiceError::BadRequest("Could not count webhooks".to_string()))?;
+
+    if remaining_count == 0 {
+        let mut redis_conn = redis_pool
+            .get()
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+        redis::cmd("SREM")
+            .arg(WEBHOOK_DATASETS_KEY)
+            .arg(dataset_id.to_string())
+            .query_async(&mut *redis_conn)
+            .await
+            .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+    }
+
+    Ok(())
+}
+
+/// Pushes a worker event to the `webhook_events` queue if its dataset has any webhooks.
+pub async fn queue_webhook_event_query(
+    event: WorkerEvent,
+    redis_pool: &RedisPool,
+) -> Result<(), ServiceError> {
+    let mut redis_conn = redis_pool
+        .get()
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    let has_webhooks: bool = redis::cmd("SISMEMBER")
+        .arg(WEBHOOK_DATASETS_KEY)
+        .arg(event.dataset_id.to_string())
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    if !has_webhooks {
+        return Ok(());
+    }
+
+    let serialized_event = serde_json::to_string(&event)
+        .map_err(|_| ServiceError::BadRequest("Could not serialize webhook event".to_string()))?;
+
+    redis::cmd("lpush")
+        .arg("webhook_events")
+        .arg(serialized_event)
+        .query_async(&mut *redis_conn)
+        .await
+        .map_err(|err| ServiceError::BadRequest(err.to_string()))?;
+
+    Ok(())
+}
+
+/// Parses a webhook url, which has to be http or https.
+pub fn parse_webhook_url(url: &str) -> Result<reqwest::Url, ServiceError> {
+    let url = reqwest::Url::parse(url)
+        .map_err(|_| ServiceError::BadRequest("Invalid webhook url".to_string()))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(ServiceError::BadRequest(
+            "Webhook url must be http or https".to_string(),
+        ));
+    }
+    if url.host_str().is_none() {
+        return Err(ServiceError::BadRequest(
+            "Webhook url must have a host".to_string(),
+        ));
+    }
+
+    Ok(url)
+}
+
+/// Whether webhooks may be delivered to the address. Loopback, private, link-local, unique-local and other non-global addresses are rejected so webhooks can not reach internal services or cloud metadata endpoints.
+pub fn is_public_webhook_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let octets = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || octets[0] == 0
+                // Shared address space used for carrier-grade NAT, 100.64.0.0/10.
+                || (octets[0] == 100 && (octets[1] & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(ip) = ip.to_ipv4_mapped() {
+                return is_public_webhook_ip(IpAddr::V4(ip));
+            }
+
+            let segments = ip.segments();
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local addresses, fc00::/7.
+                || (segments[0] & 0xfe00) == 0xfc00
+                // Link-local addresses, fe80::/10.
+                || (segments[0] & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// Resolves the host of a webhook url and checks that every address it resolves to is public. Deliveries connect to the returned addresses so the host can not be rebound to an internal address after the check.
+pub async fn resolve_webhook_url(url: &reqwest::Url) -> Result<Vec<SocketAddr>, ServiceError> {
+    let host = url
+        .host_str()
+        .ok_or(ServiceError::BadRequest(
+            "Webhook url must have a host".to_string(),
+        ))?
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let port = url.port_or_known_default().unwrap_or(443);
+
+    let addrs = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|err| {
+            ServiceError::BadRequest(format!("Could not resolve webhook host: {}", err))
+        })?
+        .collect::<Vec<SocketAddr>>();
+
+    if addrs.is_empty() {
+        return Err(ServiceError::BadRequest(
+            "Webhook host did not resolve to any address".to_string(),
+        ));
+    }
+    if addrs.iter().any(|addr| !is_public_webhook_ip(addr.ip())) {
+        return Err(ServiceError::BadRequest(
+            "Webhook url must not resolve to a loopback, private or link-local address".to_string(),
+        ));
+    }
+
+    Ok(addrs)
+}
+
+/// Hex encoded HMAC-SHA256 of `{timestamp}.{body}` keyed with the webhook's secret.
+pub fn sign_webhook_payload(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC can take a key of any size");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[tracing::instrument(skip(webhook))]
+pub async fn send_webhook_query(
+    webhook: &Webhook,
+    event: &WorkerEvent,
+) -> Result<(), ServiceError> {
+    let body = serde_json::to_string(event)
+        .map_err(|_| ServiceError::BadRequest("Could not serialize webhook event".to_string()))?;
+    let timestamp = chrono::Utc::now().timestamp();
+    let signature = sign_webhook_payload(&webhook.secret, timestamp, &body);
+
+    let url = parse_webhook_url(&webhook.url)?;
+    let addrs = resolve_webhook_url(&url).await?;
+
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .resolve_to_addrs(url.host_str().unwrap_or_default(), &addrs)
+        .build()
+        .map_err(|err| {
+            ServiceError::BadRequest(format!("Could not build webhook client: {}", err))
+        })?;
+
+    let response = client
+        .post(url)
+        .timeout(std::time::Duration::from_secs(10))
+        .header("Content-Type", "application/json")
+        .header("X-Trieve-Webhook-Id", webhook.id.to_string())
+        .header("X-Trieve-Event-Id", event.id.to_string())
+        .header("X-Trieve-Timestamp", timestamp.to_string())
+        .header("X-Trieve-Signature", format!("sha256={}", signature))
+        .body(body)
+        .send()
+        .await
+        .map_err(|err| ServiceError::BadRequest(format!("Could not send webhook: {}", err)))?;
+
+    if !response.status().is_success() {
+        return Err(ServiceError::BadRequest(format!(
+            "Webhook endpoint responded with status {}",
+            response.status()
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sign_webhook_payload() {
+        let signature = sign_webhook_payload("secret", 1700000000, "{\"id\":1}");
+
+        assert_eq!(
+            signature,
+            "3dd1b9aef568d75f6790a84bd2e5dfa1f44409eef3cbdbd3f10b837376100c11"
+        );
+        assert_ne!(
+            signature,
+            sign_webhook_payload("other", 1700000000, "{\"id\":1}")
+        );
+        assert_ne!(
+            signature,
+            sign_webhook_payload("secret", 1700000001, "{\"id\":1}")
+        );
+    }
+
+    #[test]
+    fn test_webhook_url_checks() {
+        assert!(parse_webhook_url("https://example.com/hook").is_ok());
+        assert!(parse_webhook_url("ftp://example.com/hook").is_err());
+        assert!(parse_webhook_url("file:///etc/passwd").is_err());
+
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_webhook_ip(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["93.184.216.34", "2606:2800:220:1:248:1893:25c8:1946"] {
+            assert!(is_public_webhook_ip(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+}